@@ -315,6 +315,7 @@ pub(crate) fn create_doc_export_prefs_from_args(
         with_pattern: !no_pattern,
         optimize_printing,
         page_order,
+        with_pdf_text_layer: true,
     };
 
     Ok(prefs)
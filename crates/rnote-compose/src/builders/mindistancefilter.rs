@@ -0,0 +1,43 @@
+// Imports
+use crate::PenEvent;
+use crate::penpath::Element;
+
+/// Filters out pen input elements that are too close to the last accepted element.
+///
+/// Avoids many near-duplicate elements when the pen is nearly stationary.
+#[derive(Debug, Clone)]
+pub(crate) struct MinDistanceFilter {
+    /// The minimum distance to the last accepted element, in surface coordinates.
+    min_distance: f64,
+    last_accepted: na::Vector2<f64>,
+}
+
+impl MinDistanceFilter {
+    pub(crate) fn new(start: &Element) -> Self {
+        Self {
+            min_distance: 0.0,
+            last_accepted: start.pos,
+        }
+    }
+
+    pub(crate) fn set_min_distance(&mut self, min_distance: f64) {
+        self.min_distance = min_distance.max(0.0);
+    }
+
+    /// Whether the event should be handled by the builder.
+    ///
+    /// Only down events can be rejected, all other events are always accepted, so that the final up element
+    /// always ends the path.
+    pub(crate) fn accept(&mut self, event: &PenEvent) -> bool {
+        match event {
+            PenEvent::Down { element, .. } => {
+                if (element.pos - self.last_accepted).magnitude() < self.min_distance {
+                    return false;
+                }
+                self.last_accepted = element.pos;
+                true
+            }
+            _ => true,
+        }
+    }
+}
@@ -9,6 +9,7 @@ mod ellipsebuilder;
 mod fociellipsebuilder;
 mod gridbuilder;
 mod linebuilder;
+mod mindistancefilter;
 mod penpathcurvedbuilder;
 mod penpathmodeledbuilder;
 mod penpathsimplebuilder;
@@ -17,6 +18,8 @@ mod polylinebuilder;
 mod quadbezbuilder;
 mod quadrantcoordsystem2dbuilder;
 mod rectanglebuilder;
+mod stabilizer;
+mod velocitytracker;
 
 // Re-exports
 pub use arrowbuilder::ArrowBuilder;
@@ -35,6 +38,7 @@ pub use polylinebuilder::PolylineBuilder;
 pub use quadbezbuilder::QuadBezBuilder;
 pub use quadrantcoordsystem2dbuilder::QuadrantCoordSystem2DBuilder;
 pub use rectanglebuilder::RectangleBuilder;
+pub use stabilizer::{Stabilizer, StabilizerMode};
 
 // Imports
 use anyhow::Context;
@@ -1,5 +1,7 @@
 // Imports
 use super::buildable::{Buildable, BuilderCreator, BuilderProgress};
+use super::mindistancefilter::MinDistanceFilter;
+use super::velocitytracker::VelocityTracker;
 use crate::PenEvent;
 use crate::eventresult::EventPropagation;
 use crate::penpath::{Element, Segment};
@@ -25,14 +27,18 @@ pub struct PenPathCurvedBuilder {
     buffer: Vec<Element>,
     /// the index of the current first unprocessed buffer element.
     i: usize,
+    velocity_tracker: VelocityTracker,
+    distance_filter: MinDistanceFilter,
 }
 
 impl BuilderCreator for PenPathCurvedBuilder {
-    fn start(element: Element, _now: Instant) -> Self {
+    fn start(element: Element, now: Instant) -> Self {
         Self {
             state: PenPathCurvedBuilderState::Start,
             buffer: vec![element],
             i: 0,
+            velocity_tracker: VelocityTracker::new(&element, now),
+            distance_filter: MinDistanceFilter::new(&element),
         }
     }
 }
@@ -43,12 +49,20 @@ impl Buildable for PenPathCurvedBuilder {
     fn handle_event(
         &mut self,
         event: PenEvent,
-        _now: Instant,
+        now: Instant,
         _constraints: Constraints,
     ) -> EventResult<BuilderProgress<Self::Emit>> {
+        if !self.distance_filter.accept(&event) {
+            return EventResult {
+                handled: true,
+                propagate: EventPropagation::Stop,
+                progress: BuilderProgress::InProgress,
+            };
+        }
+
         let progress = match (&mut self.state, event) {
             (PenPathCurvedBuilderState::Start, PenEvent::Down { element, .. }) => {
-                self.buffer.push(element);
+                self.buffer.push(self.velocity_tracker.update(element, now));
 
                 match self.try_build_segments_start() {
                     Some(segments) => {
@@ -61,7 +75,7 @@ impl Buildable for PenPathCurvedBuilder {
                 }
             }
             (PenPathCurvedBuilderState::During, PenEvent::Down { element, .. }) => {
-                self.buffer.push(element);
+                self.buffer.push(self.velocity_tracker.update(element, now));
 
                 match self.try_build_segments_during() {
                     Some(shapes) => BuilderProgress::EmitContinue(shapes),
@@ -69,7 +83,7 @@ impl Buildable for PenPathCurvedBuilder {
                 }
             }
             (_, PenEvent::Up { element, .. }) => {
-                self.buffer.push(element);
+                self.buffer.push(self.velocity_tracker.update(element, now));
 
                 let segments = self.try_build_segments_end();
                 self.reset();
@@ -137,6 +151,19 @@ impl Buildable for PenPathCurvedBuilder {
 }
 
 impl PenPathCurvedBuilder {
+    /// Sets the minimum distance of new input elements to the last accepted element, closer elements are dropped.
+    pub fn with_min_segment_distance(mut self, min_segment_distance: f64) -> Self {
+        self.distance_filter.set_min_distance(min_segment_distance);
+        self
+    }
+
+    /// Enables or disables simulating the pressure from the pen velocity for pressure insensitive input.
+    pub fn with_simulated_pressure(mut self, simulate_pressure: bool) -> Self {
+        self.velocity_tracker
+            .set_simulate_pressure(simulate_pressure);
+        self
+    }
+
     fn try_build_segments_start(&mut self) -> Option<Vec<Segment>> {
         if self.buffer.len().saturating_sub(1) > self.i {
             let segment = Segment::LineTo {
@@ -1,5 +1,7 @@
 // Imports
 use super::buildable::{Buildable, BuilderCreator, BuilderProgress};
+use super::mindistancefilter::MinDistanceFilter;
+use super::velocitytracker::VelocityTracker;
 use crate::PenEvent;
 use crate::eventresult::EventPropagation;
 use crate::penpath::{Element, Segment};
@@ -27,6 +29,8 @@ pub struct PenPathModeledBuilder {
     last_element: Element,
     last_element_time: Instant,
     stroke_modeler: StrokeModeler,
+    velocity_tracker: VelocityTracker,
+    distance_filter: MinDistanceFilter,
 }
 
 impl std::fmt::Debug for PenPathModeledBuilder {
@@ -38,6 +42,8 @@ impl std::fmt::Debug for PenPathModeledBuilder {
             .field("last_element", &self.last_element)
             .field("last_element_time", &self.last_element_time)
             .field("stroke_modeler", &"{.. no debug impl ..}")
+            .field("velocity_tracker", &self.velocity_tracker)
+            .field("distance_filter", &self.distance_filter)
             .finish()
     }
 }
@@ -52,6 +58,8 @@ impl BuilderCreator for PenPathModeledBuilder {
             last_element: element,
             last_element_time: now,
             stroke_modeler: StrokeModeler::default(),
+            velocity_tracker: VelocityTracker::new(&element, now),
+            distance_filter: MinDistanceFilter::new(&element),
         };
 
         builder.restart(element, now);
@@ -69,6 +77,14 @@ impl Buildable for PenPathModeledBuilder {
         now: Instant,
         _constraints: Constraints,
     ) -> EventResult<BuilderProgress<Self::Emit>> {
+        if !self.distance_filter.accept(&event) {
+            return EventResult {
+                handled: true,
+                propagate: EventPropagation::Stop,
+                progress: BuilderProgress::InProgress,
+            };
+        }
+
         let progress = match event {
             PenEvent::Down { element, .. } => {
                 // kDown is already fed into the modeler when the builder was instantiated (with start())
@@ -139,6 +155,19 @@ static MODELER_PARAMS: Lazy<ModelerParams> = Lazy::new(|| ModelerParams {
 });
 
 impl PenPathModeledBuilder {
+    /// Sets the minimum distance of new input elements to the last accepted element, closer elements are dropped.
+    pub fn with_min_segment_distance(mut self, min_segment_distance: f64) -> Self {
+        self.distance_filter.set_min_distance(min_segment_distance);
+        self
+    }
+
+    /// Enables or disables simulating the pressure from the pen velocity for pressure insensitive input.
+    pub fn with_simulated_pressure(mut self, simulate_pressure: bool) -> Self {
+        self.velocity_tracker
+            .set_simulate_pressure(simulate_pressure);
+        self
+    }
+
     fn try_build_segments(&mut self) -> Option<Vec<Segment>> {
         if self.buffer.is_empty() {
             return None;
@@ -165,6 +194,7 @@ impl PenPathModeledBuilder {
         event_type: ModelerInputEventType,
         now: Instant,
     ) {
+        let element = self.velocity_tracker.update(element, now);
         let modeler_input = ModelerInput {
             event_type,
             pos: (element.pos[0], element.pos[1]),
@@ -177,6 +207,8 @@ impl PenPathModeledBuilder {
                 let (x, y) = r.pos;
                 let pressure = r.pressure;
                 Element::new(na::vector![x, y], pressure)
+                    .with_tilt(element.tilt)
+                    .with_velocity(element.velocity)
             })),
             Err(e) => {
                 match e {
@@ -227,6 +259,8 @@ impl PenPathModeledBuilder {
                         let (x, y) = r.pos;
                         let pressure = r.pressure;
                         Element::new(na::vector![x, y], pressure)
+                            .with_tilt(element.tilt)
+                            .with_velocity(element.velocity)
                     })
                     .collect::<Vec<Element>>(),
                 Err(e) => {
@@ -259,6 +293,8 @@ impl PenPathModeledBuilder {
                     let (x, y) = r.pos;
                     let pressure = r.pressure;
                     Element::new(na::vector![x, y], pressure)
+                        .with_tilt(element.tilt)
+                        .with_velocity(element.velocity)
                 }));
             }
             Err(e) => {
@@ -1,5 +1,7 @@
 // Imports
 use super::buildable::{Buildable, BuilderCreator, BuilderProgress};
+use super::mindistancefilter::MinDistanceFilter;
+use super::velocitytracker::VelocityTracker;
 use crate::PenEvent;
 use crate::eventresult::EventPropagation;
 use crate::penpath::{Element, Segment};
@@ -16,13 +18,24 @@ use std::time::Instant;
 pub struct PenPathSimpleBuilder {
     /// Buffered elements, which are filled up by new pen events and used to try to build path segments.
     buffer: VecDeque<Element>,
+    /// Whether the segments are interpolated with Catmull-Rom splines while drawing.
+    ///
+    /// The emitted segments lag behind the pen by one segment, because the next element is needed for the interpolation.
+    live_smoothing: bool,
+    velocity_tracker: VelocityTracker,
+    distance_filter: MinDistanceFilter,
 }
 
 impl BuilderCreator for PenPathSimpleBuilder {
-    fn start(element: Element, _now: Instant) -> Self {
+    fn start(element: Element, now: Instant) -> Self {
         let buffer = VecDeque::from_iter([element]);
 
-        Self { buffer }
+        Self {
+            buffer,
+            live_smoothing: false,
+            velocity_tracker: VelocityTracker::new(&element, now),
+            distance_filter: MinDistanceFilter::new(&element),
+        }
     }
 }
 
@@ -32,19 +45,29 @@ impl Buildable for PenPathSimpleBuilder {
     fn handle_event(
         &mut self,
         event: PenEvent,
-        _now: Instant,
+        now: Instant,
         _constraints: Constraints,
     ) -> EventResult<BuilderProgress<Self::Emit>> {
+        if !self.distance_filter.accept(&event) {
+            return EventResult {
+                handled: true,
+                propagate: EventPropagation::Stop,
+                progress: BuilderProgress::InProgress,
+            };
+        }
+
         let progress = match event {
             PenEvent::Down { element, .. } => {
-                self.buffer.push_back(element);
+                self.buffer
+                    .push_back(self.velocity_tracker.update(element, now));
 
                 BuilderProgress::EmitContinue(self.build_segments())
             }
             PenEvent::Up { element, .. } => {
-                self.buffer.push_back(element);
+                self.buffer
+                    .push_back(self.velocity_tracker.update(element, now));
 
-                let segments = self.build_segments();
+                let segments = self.build_segments_end();
                 self.reset();
 
                 BuilderProgress::Finished(segments)
@@ -66,7 +89,7 @@ impl Buildable for PenPathSimpleBuilder {
     }
 
     fn bounds(&self, style: &Style, _zoom: f64) -> Option<Aabb> {
-        let pen_path = PenPath::try_from_elements(self.buffer.iter().copied())?;
+        let pen_path = PenPath::try_from_elements(self.pending_elements())?;
 
         Some(pen_path.composed_bounds(style))
     }
@@ -74,7 +97,7 @@ impl Buildable for PenPathSimpleBuilder {
     fn draw_styled(&self, cx: &mut piet_cairo::CairoRenderContext, style: &Style, _zoom: f64) {
         cx.save().unwrap();
 
-        if let Some(pen_path) = PenPath::try_from_elements(self.buffer.iter().copied()) {
+        if let Some(pen_path) = PenPath::try_from_elements(self.pending_elements()) {
             pen_path.draw_composed(cx, style);
         }
 
@@ -83,14 +106,137 @@ impl Buildable for PenPathSimpleBuilder {
 }
 
 impl PenPathSimpleBuilder {
+    /// Sets the minimum distance of new input elements to the last accepted element, closer elements are dropped.
+    pub fn with_min_segment_distance(mut self, min_segment_distance: f64) -> Self {
+        self.distance_filter.set_min_distance(min_segment_distance);
+        self
+    }
+
+    /// Enables or disables simulating the pressure from the pen velocity for pressure insensitive input.
+    pub fn with_simulated_pressure(mut self, simulate_pressure: bool) -> Self {
+        self.velocity_tracker
+            .set_simulate_pressure(simulate_pressure);
+        self
+    }
+
+    /// Enables or disables live smoothing of the built segments.
+    pub fn with_live_smoothing(mut self, live_smoothing: bool) -> Self {
+        if live_smoothing && !self.live_smoothing {
+            // The start is duplicated as the first control element of the spline.
+            if let Some(start) = self.buffer.front().copied() {
+                self.buffer.push_front(start);
+            }
+        }
+        self.live_smoothing = live_smoothing;
+        self
+    }
+
     fn build_segments(&mut self) -> Vec<Segment> {
+        if self.live_smoothing {
+            let mut segments = vec![];
+            // Keeps the last emitted element as start for next segment and its predecessor for the interpolation
+            while self.buffer.len() >= 4 {
+                segments.push(self.build_smoothed_segment());
+                self.buffer.pop_front();
+            }
+            segments
+        } else {
+            self.buffer
+                .drain(..)
+                .map(|el| Segment::LineTo { end: el })
+                .collect()
+        }
+    }
+
+    fn build_segments_end(&mut self) -> Vec<Segment> {
+        if self.live_smoothing {
+            let mut segments = vec![];
+            while self.buffer.len() >= 3 {
+                segments.push(self.build_smoothed_segment());
+                self.buffer.pop_front();
+            }
+            segments
+        } else {
+            self.build_segments()
+        }
+    }
+
+    /// Builds the Catmull-Rom spline segment between the second and third buffered element.
+    ///
+    /// Expects at least three buffered elements, the last element is duplicated if there is no fourth.
+    fn build_smoothed_segment(&self) -> Segment {
+        let p0 = self.buffer[0];
+        let p1 = self.buffer[1];
+        let p2 = self.buffer[2];
+        let p3 = self.buffer.get(3).copied().unwrap_or(p2);
+
+        Segment::CubBezTo {
+            cp1: p1.pos + (p2.pos - p0.pos) / 6.0,
+            cp2: p2.pos - (p3.pos - p1.pos) / 6.0,
+            end: p2,
+        }
+    }
+
+    /// The elements that are not yet emitted as segments, including the start of the next segment.
+    fn pending_elements(&self) -> impl Iterator<Item = Element> + '_ {
         self.buffer
-            .drain(..)
-            .map(|el| Segment::LineTo { end: el })
-            .collect()
+            .iter()
+            .skip(usize::from(self.live_smoothing))
+            .copied()
     }
 
     fn reset(&mut self) {
         self.buffer.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::smooth::SmoothOptions;
+    use std::time::Duration;
+
+    #[test]
+    fn simulated_pressure_from_speed() {
+        let start_time = Instant::now();
+        let new_element = |x: f64| {
+            Element::new(na::vector![x, 0.0], Element::PRESSURE_DEFAULT)
+                .with_pressure_insensitive(true)
+        };
+        let mut builder =
+            PenPathSimpleBuilder::start(new_element(0.0), start_time).with_simulated_pressure(true);
+
+        // Slow movement first, then fast movement with a larger spacing in the same time steps
+        let mut x = 0.0;
+        let mut segments = vec![];
+        for i in 1..=20 {
+            x += if i <= 10 { 1.0 } else { 20.0 };
+            let now = start_time + Duration::from_millis(i * 10);
+            let event = PenEvent::Down {
+                element: new_element(x),
+                modifier_keys: Default::default(),
+            };
+            if let BuilderProgress::EmitContinue(emitted) = builder
+                .handle_event(event, now, Constraints::default())
+                .progress
+            {
+                segments.extend(emitted);
+            }
+        }
+
+        let options = SmoothOptions::default();
+        let widths = segments
+            .iter()
+            .map(|seg| options.element_width(&seg.end()))
+            .collect::<Vec<f64>>();
+
+        // The start is emitted together with the first element
+        assert_eq!(widths.len(), 21);
+        assert!(widths[10] > widths[20]);
+        assert!(
+            widths
+                .iter()
+                .any(|w| (w - options.element_width(&new_element(0.0))).abs() > f64::EPSILON)
+        );
+    }
+}
@@ -0,0 +1,147 @@
+// Imports
+use crate::penpath::Element;
+use crate::shapes::Line;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// The mode of a stabilizer, smoothing the pen input by letting the stabilized point trail behind the pen.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "stabilizer_mode")]
+pub enum StabilizerMode {
+    /// A virtual point is pulled toward the pen on a string with the given length.
+    ///
+    /// It only moves when the pen is farther away than the string length.
+    #[serde(rename = "pull")]
+    Pull(f64),
+    /// The stabilized point is the average of the last N input elements.
+    #[serde(rename = "average")]
+    Average(usize),
+}
+
+impl StabilizerMode {
+    /// The minimum pull string length.
+    pub const PULL_LEN_MIN: f64 = 1.0;
+    /// The maximum pull string length.
+    pub const PULL_LEN_MAX: f64 = 500.0;
+    /// The minimum number of averaged input elements.
+    pub const AVERAGE_LEN_MIN: usize = 2;
+    /// The maximum number of averaged input elements.
+    pub const AVERAGE_LEN_MAX: usize = 64;
+}
+
+/// A stabilizer for pen input elements.
+#[derive(Debug, Clone)]
+pub struct Stabilizer {
+    mode: StabilizerMode,
+    /// The current stabilized element.
+    stabilized: Element,
+    /// The latest input element.
+    last_input: Element,
+    /// The buffered latest input elements, only used in average mode.
+    window: VecDeque<Element>,
+}
+
+impl Stabilizer {
+    /// A new stabilizer, starting at the given element.
+    pub fn new(mode: StabilizerMode, start: Element) -> Self {
+        let mode = match mode {
+            StabilizerMode::Pull(len) => StabilizerMode::Pull(
+                len.clamp(StabilizerMode::PULL_LEN_MIN, StabilizerMode::PULL_LEN_MAX),
+            ),
+            StabilizerMode::Average(n) => StabilizerMode::Average(n.clamp(
+                StabilizerMode::AVERAGE_LEN_MIN,
+                StabilizerMode::AVERAGE_LEN_MAX,
+            )),
+        };
+
+        Self {
+            mode,
+            stabilized: start,
+            last_input: start,
+            window: VecDeque::from_iter([start]),
+        }
+    }
+
+    /// The stabilizer mode.
+    pub fn mode(&self) -> StabilizerMode {
+        self.mode
+    }
+
+    /// Feed a new input element and returns the new stabilized element.
+    ///
+    /// Returns None when the stabilized element did not move.
+    pub fn update(&mut self, element: Element) -> Option<Element> {
+        self.last_input = element;
+
+        match self.mode {
+            StabilizerMode::Pull(len) => {
+                let offset = element.pos - self.stabilized.pos;
+                let dist = offset.magnitude();
+                if dist <= len {
+                    return None;
+                }
+                self.stabilized = Element {
+                    pos: self.stabilized.pos + offset * ((dist - len) / dist),
+                    ..element
+                };
+            }
+            StabilizerMode::Average(n) => {
+                self.window.push_back(element);
+                while self.window.len() > n {
+                    self.window.pop_front();
+                }
+                self.stabilized = Element {
+                    pos: self.window_average_pos(),
+                    ..element
+                };
+            }
+        }
+
+        Some(self.stabilized)
+    }
+
+    /// Flushes the remaining lag, returning the elements that lead from the stabilized element to the given end.
+    ///
+    /// The last returned element is always the end, so that the stroke ends where the pen was lifted.
+    pub fn flush(&mut self, end: Element) -> Vec<Element> {
+        self.last_input = end;
+
+        let mut elements = match self.mode {
+            StabilizerMode::Pull(_) => vec![],
+            StabilizerMode::Average(_) => {
+                self.window.push_back(end);
+                let mut elements = Vec::with_capacity(self.window.len());
+                // Catch up by averaging over the shrinking window
+                while self.window.len() > 1 {
+                    self.window.pop_front();
+                    elements.push(Element {
+                        pos: self.window_average_pos(),
+                        ..end
+                    });
+                }
+                elements.pop();
+                elements
+            }
+        };
+        elements.push(end);
+        self.stabilized = end;
+
+        elements
+    }
+
+    /// The line from the stabilized element trailing behind to the latest input element.
+    pub fn trailing_line(&self) -> Line {
+        Line {
+            start: self.stabilized.pos,
+            end: self.last_input.pos,
+        }
+    }
+
+    fn window_average_pos(&self) -> na::Vector2<f64> {
+        self.window
+            .iter()
+            .map(|el| el.pos)
+            .sum::<na::Vector2<f64>>()
+            / self.window.len().max(1) as f64
+    }
+}
@@ -0,0 +1,75 @@
+// Imports
+use crate::penpath::Element;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// Tracks the pen velocity from the positions and timestamps of consecutive elements.
+///
+/// The velocity is averaged over a small window of the latest elements, so that input jitter does not cause flicker.
+#[derive(Debug, Clone)]
+pub(crate) struct VelocityTracker {
+    window: VecDeque<(na::Vector2<f64>, Instant)>,
+    /// Whether a synthetic pressure is derived from the velocity for pressure insensitive elements.
+    simulate_pressure: bool,
+}
+
+impl VelocityTracker {
+    /// The number of elements the velocity is averaged over.
+    const WINDOW_LEN: usize = 5;
+    /// The velocity at which the simulated pressure is halved, in surface coordinates per second.
+    const SIMULATED_PRESSURE_VELOCITY_REFERENCE: f64 = 800.0;
+    /// The minimum simulated pressure.
+    const SIMULATED_PRESSURE_MIN: f64 = 0.1;
+
+    pub(crate) fn new(start: &Element, now: Instant) -> Self {
+        Self {
+            window: VecDeque::from_iter([(start.pos, now)]),
+            simulate_pressure: false,
+        }
+    }
+
+    pub(crate) fn set_simulate_pressure(&mut self, simulate_pressure: bool) {
+        self.simulate_pressure = simulate_pressure;
+    }
+
+    /// Pushes the element into the window and returns it with the smoothed velocity set.
+    ///
+    /// When simulating pressure, pressure insensitive elements get a pressure that decreases with the velocity.
+    /// Elements with genuine pressure are never modified.
+    pub(crate) fn update(&mut self, element: Element, now: Instant) -> Element {
+        self.window.push_back((element.pos, now));
+        while self.window.len() > Self::WINDOW_LEN {
+            self.window.pop_front();
+        }
+
+        let velocity = self.velocity();
+        let mut element = element.with_velocity(velocity);
+        if self.simulate_pressure
+            && element.pressure_insensitive
+            && let Some(velocity) = velocity
+        {
+            element.set_pressure_clamped(
+                (1.0 / (1.0 + velocity / Self::SIMULATED_PRESSURE_VELOCITY_REFERENCE))
+                    .max(Self::SIMULATED_PRESSURE_MIN),
+            );
+        }
+        element
+    }
+
+    /// The current smoothed velocity, or None if it can't be computed yet.
+    pub(crate) fn velocity(&self) -> Option<f64> {
+        let (first, last) = (self.window.front()?, self.window.back()?);
+        let dt = last.1.duration_since(first.1).as_secs_f64();
+        if dt <= 0.0 {
+            return None;
+        }
+        let dist = self
+            .window
+            .iter()
+            .zip(self.window.iter().skip(1))
+            .map(|((prev, _), (next, _))| (next - prev).magnitude())
+            .sum::<f64>();
+
+        Some(dist / dt)
+    }
+}
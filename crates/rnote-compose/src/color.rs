@@ -123,6 +123,17 @@ impl Color {
             && approx::relative_eq!(self.a as f32, other.a as f32)
     }
 
+    /// Linearly interpolate between the color and another color. `t` is clamped to the range [0.0 - 1.0].
+    pub fn lerp(self, other: Self, t: f64) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        Self::new(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+            self.a + (other.a - self.a) * t,
+        )
+    }
+
     /// The luma value, ranging [0.0 - 1.0].
     ///
     /// see: <https://en.wikipedia.org/wiki/Luma_(video)>
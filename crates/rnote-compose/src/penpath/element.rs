@@ -13,6 +13,33 @@ pub struct Element {
     #[serde(rename = "pressure", with = "crate::serialize::f64_dp3")]
     /// The pen pressure. The valid range is [0.0, 1.0].
     pub pressure: f64,
+    #[serde(rename = "tilt", skip_serializing_if = "Option::is_none", default)]
+    /// The pen tilt in x and y direction, when it could be retrieved from the input.
+    ///
+    /// The valid range for each component is [-1.0, 1.0].
+    pub tilt: Option<na::Vector2<f64>>,
+    #[serde(rename = "velocity", skip_serializing_if = "Option::is_none", default)]
+    /// The smoothed pen velocity in surface coordinates per second, when it could be computed from the input timing.
+    pub velocity: Option<f64>,
+    #[serde(skip)]
+    /// Whether the element comes from an input device without pressure support, then the pressure holds the
+    /// default fallback value.
+    pub pressure_insensitive: bool,
+    #[serde(skip)]
+    /// The kind of input device the element comes from.
+    pub source: InputSource,
+}
+
+/// The kind of input device an element comes from.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum InputSource {
+    /// A stylus. Elements that are not created from input are treated as coming from a pen.
+    #[default]
+    Pen,
+    /// A touchscreen.
+    Touch,
+    /// A mouse or touchpad.
+    Mouse,
 }
 
 impl Default for Element {
@@ -30,10 +57,15 @@ impl Transformable for Element {
         let mut isometry = na::Isometry2::identity();
         isometry.append_rotation_wrt_point_mut(&na::UnitComplex::new(angle), &center);
         self.pos = isometry.transform_point(&self.pos.into()).coords;
+        self.tilt = self.tilt.map(|tilt| na::Rotation2::new(angle) * tilt);
     }
 
     fn scale(&mut self, scale: na::Vector2<f64>) {
         self.pos = self.pos.component_mul(&scale);
+        // Keeps the velocity relative to the scaled stroke
+        self.velocity = self
+            .velocity
+            .map(|velocity| velocity * (scale[0].abs() + scale[1].abs()) * 0.5);
     }
 }
 
@@ -46,9 +78,37 @@ impl Element {
         Self {
             pos,
             pressure: pressure.clamp(0.0, 1.0),
+            tilt: None,
+            velocity: None,
+            pressure_insensitive: false,
+            source: InputSource::default(),
         }
     }
 
+    /// Sets the tilt, with components clamped to the range [-1.0 - 1.0].
+    pub fn with_tilt(mut self, tilt: Option<na::Vector2<f64>>) -> Self {
+        self.tilt = tilt.map(|tilt| tilt.map(|c| c.clamp(-1.0, 1.0)));
+        self
+    }
+
+    /// Sets the velocity, clamped to be non-negative.
+    pub fn with_velocity(mut self, velocity: Option<f64>) -> Self {
+        self.velocity = velocity.map(|velocity| velocity.max(0.0));
+        self
+    }
+
+    /// Flags the element as coming from an input device without pressure support.
+    pub fn with_pressure_insensitive(mut self, pressure_insensitive: bool) -> Self {
+        self.pressure_insensitive = pressure_insensitive;
+        self
+    }
+
+    /// Sets the kind of input device the element comes from.
+    pub fn with_source(mut self, source: InputSource) -> Self {
+        self.source = source;
+        self
+    }
+
     /// Sets the pressure, clamped to the range [0.0 - 1.0].
     pub fn set_pressure_clamped(&mut self, pressure: f64) {
         self.pressure = pressure.clamp(0.0, 1.0);
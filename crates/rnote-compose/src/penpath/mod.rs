@@ -3,7 +3,7 @@ mod element;
 mod segment;
 
 // Re-exports
-pub use element::Element;
+pub use element::{Element, InputSource};
 pub use segment::Segment;
 
 // Imports
@@ -33,7 +33,7 @@ impl Shapeable for PenPath {
         let mut prev = self.start;
         for seg in self.segments.iter() {
             match seg {
-                Segment::LineTo { end } => {
+                Segment::LineTo { end } | Segment::MoveTo { end } => {
                     bounds.take_point(end.pos.into());
 
                     prev = *end;
@@ -101,6 +101,9 @@ impl Transformable for PenPath {
 }
 
 impl PenPath {
+    /// The accuracy the arc length is calculated with.
+    const LENGTH_ACCURACY: f64 = 0.1;
+
     /// A new pen path
     pub fn new(start: Element) -> Self {
         Self {
@@ -123,6 +126,7 @@ impl PenPath {
 
         elements.extend(self.segments.into_iter().map(|seg| match seg {
             Segment::LineTo { end } => end,
+            Segment::MoveTo { end } => end,
             Segment::QuadBezTo { end, .. } => end,
             Segment::CubBezTo { end, .. } => end,
         }));
@@ -130,6 +134,57 @@ impl PenPath {
         elements
     }
 
+    /// The elements of the path, the start followed by the segment ends.
+    pub fn elements(&self) -> impl Iterator<Item = Element> + '_ {
+        std::iter::once(self.start).chain(self.segments.iter().map(|seg| seg.end()))
+    }
+
+    /// The element at the index, where index 0 is the start and index `i` the end of the segment `i - 1`.
+    fn element_mut(&mut self, index: usize) -> Option<&mut Element> {
+        if index == 0 {
+            return Some(&mut self.start);
+        }
+        self.segments.get_mut(index - 1).map(|seg| match seg {
+            Segment::LineTo { end }
+            | Segment::MoveTo { end }
+            | Segment::QuadBezTo { end, .. }
+            | Segment::CubBezTo { end, .. } => end,
+        })
+    }
+
+    /// Moves the element at the index by the offset.
+    ///
+    /// The adjacent control points are moved along, so that the curvature around the element is preserved.
+    /// Quadratic control points are shared by two elements and are moved by half the offset.
+    pub fn translate_element(&mut self, index: usize, offset: na::Vector2<f64>) {
+        let Some(element) = self.element_mut(index) else {
+            return;
+        };
+        element.pos += offset;
+
+        if let Some(incoming) = index.checked_sub(1).and_then(|i| self.segments.get_mut(i)) {
+            match incoming {
+                Segment::LineTo { .. } | Segment::MoveTo { .. } => {}
+                Segment::QuadBezTo { cp, .. } => *cp += offset * 0.5,
+                Segment::CubBezTo { cp2, .. } => *cp2 += offset,
+            }
+        }
+        if let Some(outgoing) = self.segments.get_mut(index) {
+            match outgoing {
+                Segment::LineTo { .. } | Segment::MoveTo { .. } => {}
+                Segment::QuadBezTo { cp, .. } => *cp += offset * 0.5,
+                Segment::CubBezTo { cp1, .. } => *cp1 += offset,
+            }
+        }
+    }
+
+    /// Sets the pressure of the element at the index, clamped between 0.0 and 1.0.
+    pub fn set_element_pressure(&mut self, index: usize, pressure: f64) {
+        if let Some(element) = self.element_mut(index) {
+            element.pressure = pressure.clamp(0.0, 1.0);
+        }
+    }
+
     /// Try to create a pen path from the elements. the first element will be the start
     pub fn try_from_elements(elements_iter: impl IntoIterator<Item = Element>) -> Option<Self> {
         let mut elements_iter = elements_iter.into_iter();
@@ -142,6 +197,374 @@ impl PenPath {
         Some(Self { start, segments })
     }
 
+    /// Simplifies the path with the Douglas-Peucker algorithm, removing segment ends with a distance
+    /// below `tolerance` from the simplified path.
+    ///
+    /// The control points of curve segments are included in the distance check, so that curves are only merged
+    /// when they are flat within the tolerance. Curve segments that are kept retain their control points.
+    /// The start and the end of the path are never moved and the kept elements retain their pressure.
+    /// Consecutive segments that get merged are replaced by a single line segment.
+    pub fn simplify(&mut self, tolerance: f64) {
+        if self.segments.len() < 2 || tolerance <= 0.0 {
+            return;
+        }
+
+        let positions = std::iter::once(self.start.pos)
+            .chain(self.segments.iter().map(|seg| seg.end().pos))
+            .collect::<Vec<na::Vector2<f64>>>();
+        let mut keep = vec![false; positions.len()];
+        keep[0] = true;
+        keep[positions.len() - 1] = true;
+        // Gaps must be preserved, so both ends of move-to segments are kept
+        for (i, seg) in self.segments.iter().enumerate() {
+            if matches!(seg, Segment::MoveTo { .. }) {
+                keep[i] = true;
+                keep[i + 1] = true;
+            }
+        }
+
+        // Every range between the initially kept positions is simplified on its own
+        let mut ranges = keep
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &kept)| kept.then_some(i))
+            .collect::<Vec<usize>>()
+            .windows(2)
+            .map(|w| (w[0], w[1]))
+            .collect::<Vec<(usize, usize)>>();
+        while let Some((first, last)) = ranges.pop() {
+            if last - first < 2 {
+                continue;
+            }
+            let line = Line {
+                start: positions[first],
+                end: positions[last],
+            };
+            // The segment at index i spans from position i to i + 1.
+            // When one of its control points is too far away, the range is split at one of its ends
+            let control_points = (first..last).flat_map(|i| {
+                let split = if i + 1 < last { i + 1 } else { i };
+                let cps = match self.segments[i] {
+                    Segment::LineTo { .. } | Segment::MoveTo { .. } => [None, None],
+                    Segment::QuadBezTo { cp, .. } => [Some(cp), None],
+                    Segment::CubBezTo { cp1, cp2, .. } => [Some(cp1), Some(cp2)],
+                };
+                cps.into_iter().flatten().map(move |cp| (split, cp))
+            });
+            let Some((i, dist)) = (first + 1..last)
+                .map(|i| (i, positions[i]))
+                .chain(control_points)
+                .map(|(i, pos)| (i, line.distance_to_point(pos)))
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            else {
+                continue;
+            };
+
+            if dist > tolerance {
+                keep[i] = true;
+                ranges.push((first, i));
+                ranges.push((i, last));
+            }
+        }
+
+        let mut last_kept = 0;
+        let mut segments = Vec::with_capacity(self.segments.len());
+        for (i, seg) in self.segments.iter().enumerate() {
+            // The segment ends at position index i + 1
+            let pos_i = i + 1;
+            if !keep[pos_i] {
+                continue;
+            }
+            if pos_i - last_kept == 1 {
+                segments.push(*seg);
+            } else {
+                segments.push(Segment::LineTo { end: seg.end() });
+            }
+            last_kept = pos_i;
+        }
+
+        self.segments = segments;
+    }
+
+    /// Smooths the pressures of the elements with a centered moving average over the given window of elements.
+    ///
+    /// The positions are not touched. A window of 0 or 1 keeps the pressures.
+    pub fn smooth_pressures(&mut self, window: usize) {
+        if window <= 1 || self.segments.is_empty() {
+            return;
+        }
+        let pressures = std::iter::once(self.start.pressure)
+            .chain(self.segments.iter().map(|seg| seg.end().pressure))
+            .collect::<Vec<f64>>();
+        let half_before = (window - 1) / 2;
+        let half_after = window / 2;
+        let smoothed = (0..pressures.len())
+            .map(|i| {
+                let window = &pressures
+                    [i.saturating_sub(half_before)..(i + half_after + 1).min(pressures.len())];
+                window.iter().sum::<f64>() / window.len() as f64
+            })
+            .collect::<Vec<f64>>();
+
+        self.start.pressure = smoothed[0];
+        for (seg, pressure) in self.segments.iter_mut().zip(smoothed.into_iter().skip(1)) {
+            match seg {
+                Segment::LineTo { end }
+                | Segment::MoveTo { end }
+                | Segment::QuadBezTo { end, .. }
+                | Segment::CubBezTo { end, .. } => end.pressure = pressure,
+            }
+        }
+    }
+
+    /// Replaces the path with a straight line from its start to its end.
+    ///
+    /// The elements are kept and moved onto the line at their relative distance along the original path,
+    /// so that the pressure profile and with it the tapering of the path is preserved.
+    pub fn straighten(&mut self) {
+        let start = self.start.pos;
+        let end = self
+            .segments
+            .last()
+            .map(|seg| seg.end().pos)
+            .unwrap_or(start);
+        let distances = self
+            .segments
+            .iter()
+            .scan(start, |prev, seg| {
+                let pos = seg.end().pos;
+                let distance = (pos - *prev).magnitude();
+                *prev = pos;
+                Some(distance)
+            })
+            .scan(0.0, |length, distance| {
+                *length += distance;
+                Some(*length)
+            })
+            .collect::<Vec<f64>>();
+        let Some(&length) = distances.last() else {
+            return;
+        };
+        if length <= 0.0 {
+            return;
+        }
+
+        self.segments = self
+            .segments
+            .iter()
+            .zip(distances)
+            .map(|(seg, distance)| {
+                let mut end_element = seg.end();
+                end_element.pos = start + (end - start) * (distance / length);
+                // Gaps are preserved
+                match seg {
+                    Segment::MoveTo { .. } => Segment::MoveTo { end: end_element },
+                    _ => Segment::LineTo { end: end_element },
+                }
+            })
+            .collect();
+    }
+
+    /// The unit direction the elements of the path predominantly extend along, being their principal axis.
+    ///
+    /// Points from the start towards the end of the path. None when all elements are at the same position.
+    pub fn dominant_direction(&self) -> Option<na::Vector2<f64>> {
+        let positions = self
+            .elements()
+            .map(|element| element.pos)
+            .collect::<Vec<na::Vector2<f64>>>();
+        let mean = positions.iter().sum::<na::Vector2<f64>>() / positions.len() as f64;
+        let (sxx, syy, sxy) = positions
+            .iter()
+            .fold((0.0, 0.0, 0.0), |(sxx, syy, sxy), pos| {
+                let d = pos - mean;
+                (sxx + d[0] * d[0], syy + d[1] * d[1], sxy + d[0] * d[1])
+            });
+        if sxx + syy <= f64::EPSILON {
+            return None;
+        }
+
+        let angle = 0.5 * (2.0 * sxy).atan2(sxx - syy);
+        let direction = na::vector![angle.cos(), angle.sin()];
+        if direction.dot(&(self.end().pos - self.start.pos)) < 0.0 {
+            Some(-direction)
+        } else {
+            Some(direction)
+        }
+    }
+
+    /// A parallel path offset from the centerline of this path by the given distance.
+    ///
+    /// Positive distances offset to the outside of the path as determined by its winding direction,
+    /// negative distances to the inside. The elements keep their pressure and the segment types are kept.
+    /// Where the offset path would fold back on itself the elements are clamped to the previous offset element.
+    pub fn offset(&self, distance: f64) -> Self {
+        let elements = self.clone().into_elements();
+        let positions = elements
+            .iter()
+            .map(|el| el.pos)
+            .collect::<Vec<na::Vector2<f64>>>();
+        // Whether the element is connected to its predecessor, gaps split the path
+        let connected = std::iter::once(false)
+            .chain(
+                self.segments
+                    .iter()
+                    .map(|seg| !matches!(seg, Segment::MoveTo { .. })),
+            )
+            .collect::<Vec<bool>>();
+        // The shoelace sum decides which side of the path is the outside
+        let winding = positions
+            .iter()
+            .zip(positions.iter().cycle().skip(1))
+            .map(|(a, b)| a[0] * b[1] - b[0] * a[1])
+            .sum::<f64>()
+            .signum();
+        let winding = if winding == 0.0 { 1.0 } else { winding };
+
+        let mut shifts = Vec::with_capacity(positions.len());
+        let mut offset_positions: Vec<na::Vector2<f64>> = Vec::with_capacity(positions.len());
+        for i in 0..positions.len() {
+            let incoming = connected[i]
+                .then(|| (positions[i] - positions[i - 1]).try_normalize(f64::EPSILON))
+                .flatten();
+            let outgoing = connected
+                .get(i + 1)
+                .copied()
+                .unwrap_or(false)
+                .then(|| (positions[i + 1] - positions[i]).try_normalize(f64::EPSILON))
+                .flatten();
+            let tangent = match (incoming, outgoing) {
+                (Some(incoming), Some(outgoing)) => (incoming + outgoing)
+                    .try_normalize(f64::EPSILON)
+                    .unwrap_or(outgoing),
+                (Some(tangent), None) | (None, Some(tangent)) => tangent,
+                (None, None) => na::Vector2::zeros(),
+            };
+            let mut offset_pos =
+                positions[i] + na::vector![tangent[1], -tangent[0]] * winding * distance;
+            if connected[i] {
+                let prev_offset_pos = offset_positions[i - 1];
+                if (offset_pos - prev_offset_pos).dot(&(positions[i] - positions[i - 1])) < 0.0 {
+                    offset_pos = prev_offset_pos;
+                }
+            }
+            shifts.push(offset_pos - positions[i]);
+            offset_positions.push(offset_pos);
+        }
+
+        let mut start = self.start;
+        start.pos = offset_positions[0];
+        let segments = self
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(i, seg)| {
+                // Control points are moved by the mean shift of the segment ends
+                let cp_shift = (shifts[i] + shifts[i + 1]) * 0.5;
+                let mut end = seg.end();
+                end.pos = offset_positions[i + 1];
+                match seg {
+                    Segment::LineTo { .. } => Segment::LineTo { end },
+                    Segment::MoveTo { .. } => Segment::MoveTo { end },
+                    Segment::QuadBezTo { cp, .. } => Segment::QuadBezTo {
+                        cp: cp + cp_shift,
+                        end,
+                    },
+                    Segment::CubBezTo { cp1, cp2, .. } => Segment::CubBezTo {
+                        cp1: cp1 + cp_shift,
+                        cp2: cp2 + cp_shift,
+                        end,
+                    },
+                }
+            })
+            .collect();
+
+        Self { start, segments }
+    }
+
+    /// The last element of the path.
+    pub fn end(&self) -> Element {
+        self.segments
+            .last()
+            .map(|seg| seg.end())
+            .unwrap_or(self.start)
+    }
+
+    /// The path running in the opposite direction, from its end to its start. The shape and the gaps are kept.
+    pub fn reversed(&self) -> Self {
+        let ends = std::iter::once(self.start)
+            .chain(self.segments.iter().map(|seg| seg.end()))
+            .collect::<Vec<Element>>();
+        let segments = self
+            .segments
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, seg)| {
+                // The segment at index i starts at the end of index i - 1
+                let end = ends[i];
+                match seg {
+                    Segment::LineTo { .. } => Segment::LineTo { end },
+                    Segment::MoveTo { .. } => Segment::MoveTo { end },
+                    Segment::QuadBezTo { cp, .. } => Segment::QuadBezTo { cp: *cp, end },
+                    Segment::CubBezTo { cp1, cp2, .. } => Segment::CubBezTo {
+                        cp1: *cp2,
+                        cp2: *cp1,
+                        end,
+                    },
+                }
+            })
+            .collect();
+
+        Self {
+            start: self.end(),
+            segments,
+        }
+    }
+
+    /// Append the other path, connected from the end of this path to the start of the other one
+    /// with a cubic bridging segment that continues the direction of both paths.
+    pub fn join_smooth(&mut self, other: PenPath) {
+        let from = self.end();
+        let to = other.start;
+        let distance = (to.pos - from.pos).magnitude();
+        let chord = (to.pos - from.pos)
+            .try_normalize(f64::EPSILON)
+            .unwrap_or_else(na::Vector2::zeros);
+        let end_tangent = match self.segments.last() {
+            Some(Segment::LineTo { end }) => Some(end.pos - self.end_before_last().pos),
+            Some(Segment::QuadBezTo { cp, end }) => Some(end.pos - cp),
+            Some(Segment::CubBezTo { cp2, end, .. }) => Some(end.pos - cp2),
+            Some(Segment::MoveTo { .. }) | None => None,
+        }
+        .and_then(|tangent| tangent.try_normalize(f64::EPSILON))
+        .unwrap_or(chord);
+        let start_tangent = match other.segments.first() {
+            Some(Segment::LineTo { end }) => Some(end.pos - to.pos),
+            Some(Segment::QuadBezTo { cp, .. }) => Some(cp - to.pos),
+            Some(Segment::CubBezTo { cp1, .. }) => Some(cp1 - to.pos),
+            Some(Segment::MoveTo { .. }) | None => None,
+        }
+        .and_then(|tangent| tangent.try_normalize(f64::EPSILON))
+        .unwrap_or(chord);
+
+        // The element pressures are interpolated along the bridge when it is drawn
+        self.segments.push(Segment::CubBezTo {
+            cp1: from.pos + end_tangent * distance / 3.0,
+            cp2: to.pos - start_tangent * distance / 3.0,
+            end: to,
+        });
+        self.segments.extend(other.segments);
+    }
+
+    /// The element where the last segment starts.
+    fn end_before_last(&self) -> Element {
+        match self.segments.len() {
+            0 | 1 => self.start,
+            n => self.segments[n - 2].end(),
+        }
+    }
+
     /// Checks whether bounds collide with the path. If it does, it returns the indices of the colliding segments
     ///
     /// `loosened` loosens the segments hitboxes by the value
@@ -188,6 +611,11 @@ impl PenPath {
                     ));
                     prev = *end;
                 }
+                Segment::MoveTo { end } => {
+                    // Nothing is drawn in a gap
+                    hitboxes.push((Some(i), vec![]));
+                    prev = *end;
+                }
                 Segment::QuadBezTo { cp, end } => {
                     let quadbez = QuadraticBezier {
                         start: prev.pos,
@@ -235,6 +663,46 @@ impl PenPath {
         hitboxes
     }
 
+    /// Snaps the path to the pixel grid at the image scale when it is a straight horizontal or vertical line.
+    ///
+    /// See [crate::utils::pixel_snap_axis_aligned]. Paths with curve segments are left anti-aliased.
+    /// Returns whether the path was snapped.
+    pub fn pixel_snap_axis_aligned(
+        &mut self,
+        width: f64,
+        image_scale: f64,
+        tolerance: f64,
+    ) -> bool {
+        if self
+            .segments
+            .iter()
+            .any(|seg| matches!(seg, Segment::QuadBezTo { .. } | Segment::CubBezTo { .. }))
+        {
+            return false;
+        }
+        let Some((axis, coord)) = crate::utils::pixel_snap_axis_aligned(
+            self.elements().map(|el| el.pos),
+            width,
+            image_scale,
+            tolerance,
+        ) else {
+            return false;
+        };
+
+        self.start.pos[axis] = coord;
+        for seg in self.segments.iter_mut() {
+            if let Segment::LineTo { end } | Segment::MoveTo { end } = seg {
+                end.pos[axis] = coord;
+            }
+        }
+        true
+    }
+
+    /// The arc length of the path, in the coordinates of its elements.
+    pub fn length(&self) -> f64 {
+        self.outline_path().perimeter(Self::LENGTH_ACCURACY)
+    }
+
     /// Convert to [kurbo::BezPath], flattened to the given precision.
     pub fn to_kurbo_flattened(&self, tolerance: f64) -> kurbo::BezPath {
         let elements = self.to_kurbo_el_iter();
@@ -249,6 +717,7 @@ impl PenPath {
         std::iter::once(kurbo::PathEl::MoveTo(self.start.pos.to_kurbo_point())).chain(
             self.segments.iter().map(|s| match s {
                 Segment::LineTo { end } => kurbo::PathEl::LineTo(end.pos.to_kurbo_point()),
+                Segment::MoveTo { end } => kurbo::PathEl::MoveTo(end.pos.to_kurbo_point()),
                 Segment::QuadBezTo { cp, end } => {
                     kurbo::PathEl::QuadTo(cp.to_kurbo_point(), end.pos.to_kurbo_point())
                 }
@@ -283,3 +752,134 @@ pub(crate) fn no_subsegments_for_segment_len(len: f64) -> i32 {
         MAX_SUBSEGMENT_ELEMENTS
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::PressureCurve;
+    use crate::style::smooth::SmoothOptions;
+
+    #[test]
+    fn smoothed_pressures_even_out_widths() {
+        let elements = (0..20).map(|i| {
+            let pressure = if i % 2 == 0 { 0.2 } else { 0.8 };
+            Element::new(na::vector![f64::from(i) * 5.0, 0.0], pressure)
+        });
+        let mut path = PenPath::try_from_elements(elements).unwrap();
+        let positions = path.clone().into_elements().into_iter().map(|el| el.pos);
+        let options = SmoothOptions {
+            pressure_curve: PressureCurve::Linear,
+            ..Default::default()
+        };
+        let widths = |path: &PenPath| {
+            path.clone()
+                .into_elements()
+                .iter()
+                .map(|el| options.element_width(el))
+                .collect::<Vec<f64>>()
+        };
+        let max_width_jump = |widths: &[f64]| {
+            widths
+                .windows(2)
+                .map(|w| (w[1] - w[0]).abs())
+                .fold(0.0, f64::max)
+        };
+
+        path.smooth_pressures(1);
+        assert!((max_width_jump(&widths(&path)) - 0.6 * options.stroke_width).abs() < 1e-9);
+
+        path.smooth_pressures(4);
+        let smoothed_widths = widths(&path);
+        // The interior elements all average two high and two low pressures
+        for width in &smoothed_widths[2..smoothed_widths.len() - 2] {
+            assert!((width - 0.5 * options.stroke_width).abs() < 1e-9);
+        }
+        assert!(max_width_jump(&smoothed_widths) < 0.2 * options.stroke_width);
+        // The positions are kept
+        assert!(
+            path.into_elements()
+                .into_iter()
+                .map(|el| el.pos)
+                .eq(positions)
+        );
+    }
+
+    #[test]
+    fn simplified_curves_stay_within_tolerance() {
+        use kurbo::{ParamCurve, ParamCurveNearest};
+
+        let radius = 50.0;
+        let n_arcs = 8;
+        let n_waves = 4;
+        let n_line = 10;
+        let half_angle = std::f64::consts::PI / f64::from(2 * n_arcs);
+        let pressure = |i: u32| 0.2 + 0.03 * f64::from(i);
+        let on_arc = |angle: f64| na::vector![angle.cos(), angle.sin()] * radius;
+        // A half circle made of quadratic curves
+        let start = Element::new(on_arc(0.0), pressure(0));
+        let arcs = (1..=n_arcs).map(|i| Segment::QuadBezTo {
+            cp: on_arc(f64::from(2 * i - 1) * half_angle) / half_angle.cos(),
+            end: Element::new(on_arc(f64::from(2 * i) * half_angle), pressure(i)),
+        });
+        // Waves with all ends on a line, which only deviate from it through their control points
+        let waves = (1..=n_waves).map(|i| Segment::QuadBezTo {
+            cp: na::vector![
+                -radius - (f64::from(i) - 0.5) * 10.0,
+                if i % 2 == 0 { 10.0 } else { -10.0 }
+            ],
+            end: Element::new(
+                na::vector![-radius - f64::from(i) * 10.0, 0.0],
+                pressure(n_arcs + i),
+            ),
+        });
+        let line = (1..=n_line).map(|i| Segment::LineTo {
+            end: Element::new(
+                na::vector![
+                    -radius - f64::from(n_waves) * 10.0 - f64::from(i) * 5.0,
+                    0.0
+                ],
+                pressure(n_arcs + n_waves + i),
+            ),
+        });
+        let path = PenPath::new_w_segments(start, arcs.chain(waves).chain(line));
+        let tolerance = 0.5;
+
+        let mut simplified = path.clone();
+        simplified.simplify(tolerance);
+
+        assert_eq!(simplified.start.pos, path.start.pos);
+        assert_eq!(simplified.start.pressure, path.start.pressure);
+        let (end, simplified_end) = (
+            path.segments.last().unwrap().end(),
+            simplified.segments.last().unwrap().end(),
+        );
+        assert_eq!(simplified_end.pos, end.pos);
+        assert_eq!(simplified_end.pressure, end.pressure);
+        // The curves are kept, the densely sampled line is merged into a single segment
+        let n_curves = (n_arcs + n_waves) as usize;
+        assert_eq!(
+            simplified
+                .segments
+                .iter()
+                .filter(|seg| matches!(seg, Segment::QuadBezTo { .. }))
+                .count(),
+            n_curves
+        );
+        assert_eq!(simplified.segments.len(), n_curves + 1);
+
+        let simplified_outline = simplified.outline_path();
+        let max_distance = path
+            .outline_path()
+            .segments()
+            .flat_map(|seg| (0..=20).map(move |t| seg.eval(f64::from(t) / 20.0)))
+            .map(|point| {
+                simplified_outline
+                    .segments()
+                    .map(|seg| seg.nearest(point, 1e-6).distance_sq)
+                    .fold(f64::INFINITY, f64::min)
+                    .sqrt()
+            })
+            .fold(0.0, f64::max);
+        assert!(max_distance <= tolerance);
+    }
+}
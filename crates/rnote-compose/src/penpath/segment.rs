@@ -14,6 +14,13 @@ pub enum Segment {
         /// The line end.
         end: Element,
     },
+    #[serde(rename = "moveto")]
+    /// A move-to segment, lifting the pen. Nothing is drawn between the previous segment end and its end.
+    MoveTo {
+        #[serde(rename = "end")]
+        /// The move end.
+        end: Element,
+    },
     #[serde(rename = "quadbezto", alias = "quadbez")]
     /// A quadratic-bezier-to segment.
     QuadBezTo {
@@ -42,7 +49,7 @@ pub enum Segment {
 impl Transformable for Segment {
     fn translate(&mut self, offset: na::Vector2<f64>) {
         match self {
-            Self::LineTo { end } => {
+            Self::LineTo { end } | Self::MoveTo { end } => {
                 end.pos += offset;
             }
             Self::QuadBezTo { cp, end } => {
@@ -62,7 +69,7 @@ impl Transformable for Segment {
         isometry.append_rotation_wrt_point_mut(&na::UnitComplex::new(angle), &center);
 
         match self {
-            Self::LineTo { end } => {
+            Self::LineTo { end } | Self::MoveTo { end } => {
                 end.pos = isometry.transform_point(&end.pos.into()).coords;
             }
             Self::QuadBezTo { cp, end } => {
@@ -79,7 +86,7 @@ impl Transformable for Segment {
 
     fn scale(&mut self, scale: na::Vector2<f64>) {
         match self {
-            Self::LineTo { end } => {
+            Self::LineTo { end } | Self::MoveTo { end } => {
                 end.pos = end.pos.component_mul(&scale);
             }
             Self::QuadBezTo { cp, end } => {
@@ -102,6 +109,7 @@ impl Segment {
     pub fn end(&self) -> Element {
         match self {
             Segment::LineTo { end, .. } => *end,
+            Segment::MoveTo { end } => *end,
             Segment::QuadBezTo { end, .. } => *end,
             Segment::CubBezTo { end, .. } => *end,
         }
@@ -77,6 +77,18 @@ impl Line {
         }
     }
 
+    /// The shortest distance from the point to the line.
+    pub fn distance_to_point(&self, point: na::Vector2<f64>) -> f64 {
+        let vec = self.end - self.start;
+        let magn_squared = vec.magnitude_squared();
+        if magn_squared <= 0.0 {
+            return (point - self.start).magnitude();
+        }
+        let t = ((point - self.start).dot(&vec) / magn_squared).clamp(0.0, 1.0);
+
+        (point - (self.start + vec * t)).magnitude()
+    }
+
     /// Split itself given the number of splits.
     pub fn split(&self, n_splits: i32) -> Vec<Self> {
         (0..n_splits)
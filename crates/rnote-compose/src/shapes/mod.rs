@@ -13,6 +13,8 @@ pub mod polygon;
 pub mod polyline;
 /// Polyline
 pub mod quadbez;
+/// Recognizing shapes from roughly drawn paths
+pub mod recognition;
 /// Rectangle
 pub mod rectangle;
 /// Shape
@@ -28,6 +30,7 @@ pub use line::Line;
 pub use polygon::Polygon;
 pub use polyline::Polyline;
 pub use quadbez::QuadraticBezier;
+pub use recognition::recognize_shape;
 pub use rectangle::Rectangle;
 pub use shape::Shape;
 pub use shapeable::Shapeable;
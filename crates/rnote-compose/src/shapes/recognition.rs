@@ -0,0 +1,194 @@
+// Imports
+use super::{Ellipse, Line, Polygon, Rectangle, Shape};
+use crate::PenPath;
+use crate::Transform;
+use p2d::bounding_volume::Aabb;
+
+/// Paths with a smaller bounds diagonal are not recognized.
+const MIN_DIAGONAL: f64 = 10.0;
+/// The maximum gap between the start and the end of a closed path, relative to the bounds diagonal.
+const CLOSED_GAP_RATIO: f64 = 0.2;
+/// The maximum deviation of a line from the straight connection, relative to its length.
+const LINE_MAX_DEVIATION_RATIO: f64 = 0.06;
+/// The tolerance for detecting corners, relative to the bounds diagonal.
+const CORNER_TOLERANCE_RATIO: f64 = 0.08;
+/// The minimum area of a rectangle relative to the area of its bounds.
+const RECTANGLE_MIN_FILL_RATIO: f64 = 0.85;
+/// The maximum mean relative deviation of the points from the fitted ellipse.
+const ELLIPSE_MAX_DEVIATION: f64 = 0.1;
+
+/// Recognizes the primitive shape that was intended by the roughly drawn path.
+///
+/// Recognizes lines, axis-aligned rectangles, triangles and axis-aligned ellipses.
+/// Returns None when no shape is recognized with enough confidence.
+pub fn recognize_shape(path: &PenPath) -> Option<Shape> {
+    let points = flattened_points(path);
+    if points.len() < 3 {
+        return None;
+    }
+    let bounds = Aabb::from_points(points.iter().map(|p| (*p).into()));
+    let diagonal = bounds.extents().magnitude();
+    if diagonal < MIN_DIAGONAL {
+        return None;
+    }
+    let first = points[0];
+    let last = points[points.len() - 1];
+
+    if (last - first).magnitude() > diagonal * CLOSED_GAP_RATIO {
+        return recognize_line(&points);
+    }
+
+    let corners = closed_corners(&points, diagonal * CORNER_TOLERANCE_RATIO);
+    match corners.len() {
+        3 => {
+            let mut triangle = Polygon::new(corners[0]);
+            triangle.extend(corners[1..].iter().copied());
+            return Some(Shape::Polygon(triangle));
+        }
+        4 if polygon_area(&corners) >= bounds.volume() * RECTANGLE_MIN_FILL_RATIO => {
+            return Some(Shape::Rectangle(Rectangle::from_corners(
+                bounds.mins.coords,
+                bounds.maxs.coords,
+            )));
+        }
+        _ => {}
+    }
+
+    recognize_ellipse(&points, bounds)
+}
+
+/// The points of the path, flattened with a tolerance small enough to keep the drawn shape.
+fn flattened_points(path: &PenPath) -> Vec<na::Vector2<f64>> {
+    path.to_kurbo_flattened(0.5)
+        .elements()
+        .iter()
+        .filter_map(|el| match el {
+            kurbo::PathEl::MoveTo(p) | kurbo::PathEl::LineTo(p) => Some(na::vector![p.x, p.y]),
+            _ => None,
+        })
+        .collect()
+}
+
+fn recognize_line(points: &[na::Vector2<f64>]) -> Option<Shape> {
+    let start = points[0];
+    let end = points[points.len() - 1];
+    let length = (end - start).magnitude();
+    let max_deviation = points
+        .iter()
+        .map(|&p| distance_to_line(p, start, end))
+        .fold(0.0, f64::max);
+
+    (max_deviation <= length * LINE_MAX_DEVIATION_RATIO).then(|| Shape::Line(Line::new(start, end)))
+}
+
+fn recognize_ellipse(points: &[na::Vector2<f64>], bounds: Aabb) -> Option<Shape> {
+    let center = bounds.center().coords;
+    let radii = bounds.half_extents();
+    if radii[0] <= 0.0 || radii[1] <= 0.0 {
+        return None;
+    }
+    // The points of a perfect ellipse all have the normalized distance 1.0 to the center
+    let mean_deviation = points
+        .iter()
+        .map(|p| ((p - center).component_div(&radii).magnitude() - 1.0).abs())
+        .sum::<f64>()
+        / points.len() as f64;
+
+    (mean_deviation <= ELLIPSE_MAX_DEVIATION).then(|| {
+        Shape::Ellipse(Ellipse {
+            radii,
+            transform: Transform::new_w_isometry(na::Isometry2::new(center, 0.0)),
+        })
+    })
+}
+
+/// The corners of the closed path, where the path changes its direction by more than the tolerance.
+fn closed_corners(points: &[na::Vector2<f64>], tolerance: f64) -> Vec<na::Vector2<f64>> {
+    // Split the loop at the point farthest from the start, which is always a corner candidate
+    let start = points[0];
+    let split = points
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| {
+            (**a - start)
+                .magnitude()
+                .total_cmp(&(**b - start).magnitude())
+        })
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+    if split == 0 {
+        return vec![];
+    }
+    let mut second_half = points[split..].to_vec();
+    second_half.push(start);
+
+    let mut corners = vec![];
+    douglas_peucker(&points[..=split], tolerance, &mut corners);
+    douglas_peucker(&second_half, tolerance, &mut corners);
+
+    // The start is usually not a corner, so remove all vertices that lie on the connection of their neighbours
+    loop {
+        let n = corners.len();
+        if n < 3 {
+            return corners;
+        }
+        let Some(straight) = (0..n).find(|&i| {
+            distance_to_line(corners[i], corners[(i + n - 1) % n], corners[(i + 1) % n]) < tolerance
+        }) else {
+            return corners;
+        };
+        corners.remove(straight);
+    }
+}
+
+/// Simplifies the polyline, pushing all kept points except the last one.
+fn douglas_peucker(
+    points: &[na::Vector2<f64>],
+    tolerance: f64,
+    simplified: &mut Vec<na::Vector2<f64>>,
+) {
+    let (Some(&first), Some(&last)) = (points.first(), points.last()) else {
+        return;
+    };
+    let farthest = points
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| (i, distance_to_line(p, first, last)))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b));
+
+    match farthest {
+        Some((i, distance)) if distance > tolerance && i > 0 && i < points.len() - 1 => {
+            douglas_peucker(&points[..=i], tolerance, simplified);
+            douglas_peucker(&points[i..], tolerance, simplified);
+        }
+        _ => simplified.push(first),
+    }
+}
+
+/// The distance of the point to the line segment between start and end.
+fn distance_to_line(
+    point: na::Vector2<f64>,
+    start: na::Vector2<f64>,
+    end: na::Vector2<f64>,
+) -> f64 {
+    let line = end - start;
+    let length_squared = line.magnitude_squared();
+    if length_squared == 0.0 {
+        return (point - start).magnitude();
+    }
+    let t = ((point - start).dot(&line) / length_squared).clamp(0.0, 1.0);
+    (point - (start + line * t)).magnitude()
+}
+
+/// The area of the polygon using the shoelace formula.
+fn polygon_area(vertices: &[na::Vector2<f64>]) -> f64 {
+    let n = vertices.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (vertices[i], vertices[(i + 1) % n]);
+            a[0] * b[1] - b[0] * a[1]
+        })
+        .sum::<f64>()
+        .abs()
+        * 0.5
+}
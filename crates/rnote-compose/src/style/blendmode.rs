@@ -0,0 +1,45 @@
+// Imports
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+/// The blend mode a stroke is composited with onto the content underneath.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    Serialize,
+    Deserialize,
+    num_derive::FromPrimitive,
+    num_derive::ToPrimitive,
+)]
+#[serde(rename = "blend_mode")]
+pub enum BlendMode {
+    /// Normal alpha blending.
+    #[default]
+    #[serde(rename = "normal")]
+    Normal = 0,
+    /// Multiplies the colors, overlapping strokes build up darker colors.
+    #[serde(rename = "multiply")]
+    Multiply,
+    /// Inverse of multiply, overlapping strokes build up lighter colors.
+    #[serde(rename = "screen")]
+    Screen,
+    /// Multiplies dark and screens light colors of the content underneath.
+    #[serde(rename = "overlay")]
+    Overlay,
+    /// Keeps the darker of the colors, overlapping strokes of the same color never get darker than the color itself.
+    #[serde(rename = "darken")]
+    Darken,
+}
+
+impl TryFrom<u32> for BlendMode {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u32(value)
+            .with_context(|| format!("BlendMode try_from::<u32>() for value {value} failed"))
+    }
+}
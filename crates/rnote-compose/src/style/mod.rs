@@ -1,4 +1,6 @@
 // Modules
+/// Blend modes
+mod blendmode;
 /// Composer
 mod composer;
 /// Draw indicators
@@ -14,13 +16,13 @@ pub mod textured;
 use self::rough::RoughOptions;
 use self::smooth::SmoothOptions;
 use self::textured::TexturedOptions;
+pub use blendmode::BlendMode;
 
 // Imports
 use crate::shapes::{
     Arrow, CubicBezier, Ellipse, Line, Polygon, Polyline, QuadraticBezier, Rectangle,
 };
 use crate::{Color, PenPath, Shape};
-use anyhow::Context;
 pub use composer::Composer;
 use serde::{Deserialize, Serialize};
 
@@ -30,7 +32,7 @@ use serde::{Deserialize, Serialize};
 pub enum Style {
     /// A smooth style
     #[serde(rename = "smooth")]
-    Smooth(SmoothOptions),
+    Smooth(Box<SmoothOptions>),
     /// A rough style
     #[serde(rename = "rough")]
     Rough(RoughOptions),
@@ -41,7 +43,7 @@ pub enum Style {
 
 impl Default for Style {
     fn default() -> Self {
-        Self::Smooth(SmoothOptions::default())
+        Self::Smooth(Box::default())
     }
 }
 
@@ -58,16 +60,41 @@ impl Style {
     /// Set the stroke width. Available on all styles.
     pub fn set_stroke_width(&mut self, stroke_width: f64) {
         match self {
-            Style::Smooth(options) => options.stroke_width = stroke_width,
+            Style::Smooth(options) => {
+                options.stroke_width = stroke_width;
+                // The dash pattern depends on the width
+                options.update_piet_stroke_style();
+            }
             Style::Rough(options) => options.stroke_width = stroke_width,
             Style::Textured(options) => options.stroke_width = stroke_width,
         }
     }
 
+    /// Scales the style, so that shapes drawn with it appear scaled by the given factor.
+    pub fn scale(&mut self, scale: f64) {
+        self.set_stroke_width(self.stroke_width() * scale);
+        match self {
+            Style::Smooth(options) => {
+                options.fill_style.scale(scale);
+                options.scale_dashes(scale);
+                options.taper_start *= scale;
+                options.taper_end *= scale;
+                if let Some(nib) = &mut options.calligraphy {
+                    nib.nib_width *= scale;
+                }
+            }
+            Style::Rough(_) => {}
+            Style::Textured(options) => options.scale_dots(scale),
+        }
+    }
+
     /// The margins for bounds which contain the shape.
     pub fn bounds_margin(&self) -> f64 {
         match self {
-            Style::Smooth(options) => options.stroke_width,
+            Style::Smooth(options) => options
+                .calligraphy
+                .map(|nib| options.stroke_width.max(nib.nib_width))
+                .unwrap_or(options.stroke_width),
             Style::Rough(options) => options.stroke_width + RoughOptions::ROUGH_BOUNDS_MARGIN,
             Style::Textured(options) => options.stroke_width,
         }
@@ -91,6 +118,43 @@ impl Style {
         }
     }
 
+    /// The blend mode the style is composited with. Available on all styles.
+    pub fn blend_mode(&self) -> BlendMode {
+        match self {
+            Style::Smooth(options) => options.blend_mode,
+            Style::Rough(options) => options.blend_mode,
+            Style::Textured(options) => options.blend_mode,
+        }
+    }
+
+    /// Set the blend mode. Available on all styles.
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        match self {
+            Style::Smooth(options) => options.blend_mode = blend_mode,
+            Style::Rough(options) => options.blend_mode = blend_mode,
+            Style::Textured(options) => options.blend_mode = blend_mode,
+        }
+    }
+
+    /// The opacity the whole style is composited with. Available on all styles.
+    pub fn opacity(&self) -> f64 {
+        match self {
+            Style::Smooth(options) => options.opacity,
+            Style::Rough(options) => options.opacity,
+            Style::Textured(options) => options.opacity,
+        }
+    }
+
+    /// Set the opacity, clamped to the range [0.0 - 1.0]. Available on all styles.
+    pub fn set_opacity(&mut self, opacity: f64) {
+        let opacity = opacity.clamp(0.0, 1.0);
+        match self {
+            Style::Smooth(options) => options.opacity = opacity,
+            Style::Rough(options) => options.opacity = opacity,
+            Style::Textured(options) => options.opacity = opacity,
+        }
+    }
+
     /// Get the fill color of the style.
     pub fn fill_color(&self) -> Option<Color> {
         match self {
@@ -122,7 +186,7 @@ impl Style {
 impl Composer<Style> for Line {
     fn composed_bounds(&self, options: &Style) -> p2d::bounding_volume::Aabb {
         match options {
-            Style::Smooth(options) => self.composed_bounds(options),
+            Style::Smooth(options) => self.composed_bounds(options.as_ref()),
             Style::Rough(options) => self.composed_bounds(options),
             Style::Textured(options) => self.composed_bounds(options),
         }
@@ -130,7 +194,7 @@ impl Composer<Style> for Line {
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &Style) {
         match options {
-            Style::Smooth(options) => self.draw_composed(cx, options),
+            Style::Smooth(options) => self.draw_composed(cx, options.as_ref()),
             Style::Rough(options) => self.draw_composed(cx, options),
             Style::Textured(options) => self.draw_composed(cx, options),
         }
@@ -140,7 +204,7 @@ impl Composer<Style> for Line {
 impl Composer<Style> for Arrow {
     fn composed_bounds(&self, options: &Style) -> p2d::bounding_volume::Aabb {
         match options {
-            Style::Smooth(options) => self.composed_bounds(options),
+            Style::Smooth(options) => self.composed_bounds(options.as_ref()),
             Style::Rough(options) => self.composed_bounds(options),
             Style::Textured(_options) => unimplemented!(),
         }
@@ -148,7 +212,7 @@ impl Composer<Style> for Arrow {
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &Style) {
         match options {
-            Style::Smooth(options) => self.draw_composed(cx, options),
+            Style::Smooth(options) => self.draw_composed(cx, options.as_ref()),
             Style::Rough(options) => self.draw_composed(cx, options),
             Style::Textured(_options) => unimplemented!(),
         }
@@ -158,7 +222,7 @@ impl Composer<Style> for Arrow {
 impl Composer<Style> for Rectangle {
     fn composed_bounds(&self, options: &Style) -> p2d::bounding_volume::Aabb {
         match options {
-            Style::Smooth(options) => self.composed_bounds(options),
+            Style::Smooth(options) => self.composed_bounds(options.as_ref()),
             Style::Rough(options) => self.composed_bounds(options),
             Style::Textured(_options) => unimplemented!(),
         }
@@ -166,7 +230,7 @@ impl Composer<Style> for Rectangle {
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &Style) {
         match options {
-            Style::Smooth(options) => self.draw_composed(cx, options),
+            Style::Smooth(options) => self.draw_composed(cx, options.as_ref()),
             Style::Rough(options) => self.draw_composed(cx, options),
             Style::Textured(_options) => unimplemented!(),
         }
@@ -176,7 +240,7 @@ impl Composer<Style> for Rectangle {
 impl Composer<Style> for Ellipse {
     fn composed_bounds(&self, options: &Style) -> p2d::bounding_volume::Aabb {
         match options {
-            Style::Smooth(options) => self.composed_bounds(options),
+            Style::Smooth(options) => self.composed_bounds(options.as_ref()),
             Style::Rough(options) => self.composed_bounds(options),
             Style::Textured(_options) => unimplemented!(),
         }
@@ -184,7 +248,7 @@ impl Composer<Style> for Ellipse {
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &Style) {
         match options {
-            Style::Smooth(options) => self.draw_composed(cx, options),
+            Style::Smooth(options) => self.draw_composed(cx, options.as_ref()),
             Style::Rough(options) => self.draw_composed(cx, options),
             Style::Textured(_options) => unimplemented!(),
         }
@@ -194,7 +258,7 @@ impl Composer<Style> for Ellipse {
 impl Composer<Style> for QuadraticBezier {
     fn composed_bounds(&self, options: &Style) -> p2d::bounding_volume::Aabb {
         match options {
-            Style::Smooth(options) => self.composed_bounds(options),
+            Style::Smooth(options) => self.composed_bounds(options.as_ref()),
             Style::Rough(options) => self.composed_bounds(options),
             Style::Textured(_options) => unimplemented!(),
         }
@@ -202,7 +266,7 @@ impl Composer<Style> for QuadraticBezier {
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &Style) {
         match options {
-            Style::Smooth(options) => self.draw_composed(cx, options),
+            Style::Smooth(options) => self.draw_composed(cx, options.as_ref()),
             Style::Rough(options) => self.draw_composed(cx, options),
             Style::Textured(_options) => unimplemented!(),
         }
@@ -212,7 +276,7 @@ impl Composer<Style> for QuadraticBezier {
 impl Composer<Style> for CubicBezier {
     fn composed_bounds(&self, options: &Style) -> p2d::bounding_volume::Aabb {
         match options {
-            Style::Smooth(options) => self.composed_bounds(options),
+            Style::Smooth(options) => self.composed_bounds(options.as_ref()),
             Style::Rough(options) => self.composed_bounds(options),
             Style::Textured(_options) => unimplemented!(),
         }
@@ -220,7 +284,7 @@ impl Composer<Style> for CubicBezier {
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &Style) {
         match options {
-            Style::Smooth(options) => self.draw_composed(cx, options),
+            Style::Smooth(options) => self.draw_composed(cx, options.as_ref()),
             Style::Rough(options) => self.draw_composed(cx, options),
             Style::Textured(_options) => unimplemented!(),
         }
@@ -230,7 +294,7 @@ impl Composer<Style> for CubicBezier {
 impl Composer<Style> for Polyline {
     fn composed_bounds(&self, options: &Style) -> p2d::bounding_volume::Aabb {
         match options {
-            Style::Smooth(options) => self.composed_bounds(options),
+            Style::Smooth(options) => self.composed_bounds(options.as_ref()),
             Style::Rough(options) => self.composed_bounds(options),
             Style::Textured(_options) => unimplemented!(),
         }
@@ -238,7 +302,7 @@ impl Composer<Style> for Polyline {
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &Style) {
         match options {
-            Style::Smooth(options) => self.draw_composed(cx, options),
+            Style::Smooth(options) => self.draw_composed(cx, options.as_ref()),
             Style::Rough(options) => self.draw_composed(cx, options),
             Style::Textured(_options) => unimplemented!(),
         }
@@ -248,7 +312,7 @@ impl Composer<Style> for Polyline {
 impl Composer<Style> for Polygon {
     fn composed_bounds(&self, options: &Style) -> p2d::bounding_volume::Aabb {
         match options {
-            Style::Smooth(options) => self.composed_bounds(options),
+            Style::Smooth(options) => self.composed_bounds(options.as_ref()),
             Style::Rough(options) => self.composed_bounds(options),
             Style::Textured(_options) => unimplemented!(),
         }
@@ -256,7 +320,7 @@ impl Composer<Style> for Polygon {
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &Style) {
         match options {
-            Style::Smooth(options) => self.draw_composed(cx, options),
+            Style::Smooth(options) => self.draw_composed(cx, options.as_ref()),
             Style::Rough(options) => self.draw_composed(cx, options),
             Style::Textured(_options) => unimplemented!(),
         }
@@ -266,7 +330,7 @@ impl Composer<Style> for Polygon {
 impl Composer<Style> for PenPath {
     fn composed_bounds(&self, options: &Style) -> p2d::bounding_volume::Aabb {
         match options {
-            Style::Smooth(options) => self.composed_bounds(options),
+            Style::Smooth(options) => self.composed_bounds(options.as_ref()),
             Style::Rough(_) => unimplemented!(),
             Style::Textured(options) => self.composed_bounds(options),
         }
@@ -274,7 +338,7 @@ impl Composer<Style> for PenPath {
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &Style) {
         match options {
-            Style::Smooth(options) => self.draw_composed(cx, options),
+            Style::Smooth(options) => self.draw_composed(cx, options.as_ref()),
             Style::Rough(_) => unimplemented!(),
             Style::Textured(options) => self.draw_composed(cx, options),
         }
@@ -310,21 +374,12 @@ impl Composer<Style> for Shape {
 }
 
 /// The pressure curve used by some styles.
-#[derive(
-    Debug,
-    Clone,
-    Copy,
-    Default,
-    Serialize,
-    Deserialize,
-    num_derive::FromPrimitive,
-    num_derive::ToPrimitive,
-)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename = "pressure_curve")]
 pub enum PressureCurve {
     /// Constant.
     #[serde(rename = "const")]
-    Const = 0,
+    Const,
     /// Linear.
     #[default]
     #[serde(rename = "linear")]
@@ -341,21 +396,66 @@ pub enum PressureCurve {
     /// Cubic polynomial.
     #[serde(rename = "pow3")]
     Pow3,
+    /// Custom curve, linearly interpolated between the given `(pressure, width factor)` control points.
+    ///
+    /// The control points are expected to be sorted by ascending pressure.
+    /// Pressures outside of the control points range are mapped to the factor of the nearest point,
+    /// the resulting factors are clamped to the range [0.0 - 1.0].
+    #[serde(rename = "custom")]
+    Custom(Vec<(f64, f64)>),
 }
 
 impl PressureCurve {
+    /// The minimum pressure and width factor the curve is applied with.
+    ///
+    /// Avoids zero-width degenerate segments for elements without any pressure.
+    pub const PRESSURE_MIN: f64 = 0.01;
+
     /// Apply the pressure curve to a width and the given pressure.
     ///
     /// Expects pressure to be between range [0.0 - 1.0].
     pub fn apply(&self, width: f64, pressure: f64) -> f64 {
-        match self {
-            Self::Const => width,
-            Self::Linear => width * pressure,
-            Self::Sqrt => width * pressure.sqrt(),
-            Self::Cbrt => width * pressure.cbrt(),
-            Self::Pow2 => width * pressure.powi(2),
-            Self::Pow3 => width * pressure.powi(3),
+        let pressure = pressure.clamp(Self::PRESSURE_MIN, 1.0);
+
+        let factor = match self {
+            Self::Const => 1.0,
+            Self::Linear => pressure,
+            Self::Sqrt => pressure.sqrt(),
+            Self::Cbrt => pressure.cbrt(),
+            Self::Pow2 => pressure.powi(2),
+            Self::Pow3 => pressure.powi(3),
+            Self::Custom(control_points) => Self::sample_control_points(control_points, pressure),
+        };
+
+        width * factor.clamp(Self::PRESSURE_MIN, 1.0)
+    }
+
+    fn sample_control_points(control_points: &[(f64, f64)], pressure: f64) -> f64 {
+        let (Some(first), Some(last)) = (control_points.first(), control_points.last()) else {
+            // Fall back to linear if there are no control points
+            return pressure;
+        };
+
+        if pressure <= first.0 {
+            return first.1;
         }
+        if pressure >= last.0 {
+            return last.1;
+        }
+
+        control_points
+            .windows(2)
+            .find_map(|w| {
+                let ((p0, f0), (p1, f1)) = (w[0], w[1]);
+                if !(p0..=p1).contains(&pressure) {
+                    return None;
+                }
+                if p1 - p0 <= 0.0 {
+                    return Some(f1);
+                }
+                Some(f0 + (f1 - f0) * (pressure - p0) / (p1 - p0))
+            })
+            .unwrap_or(pressure)
     }
 }
 
@@ -363,7 +463,35 @@ impl TryFrom<u32> for PressureCurve {
     type Error = anyhow::Error;
 
     fn try_from(value: u32) -> Result<Self, Self::Error> {
-        num_traits::FromPrimitive::from_u32(value)
-            .with_context(|| format!("PressureCurve try_from::<u32>() for value {value} failed"))
+        match value {
+            0 => Ok(Self::Const),
+            1 => Ok(Self::Linear),
+            2 => Ok(Self::Sqrt),
+            3 => Ok(Self::Cbrt),
+            4 => Ok(Self::Pow2),
+            5 => Ok(Self::Pow3),
+            _ => Err(anyhow::anyhow!(
+                "PressureCurve try_from::<u32>() for value {value} failed"
+            )),
+        }
+    }
+}
+
+impl num_traits::ToPrimitive for PressureCurve {
+    fn to_i64(&self) -> Option<i64> {
+        self.to_u64().map(|v| v as i64)
+    }
+
+    /// The custom curve does not have a primitive representation.
+    fn to_u64(&self) -> Option<u64> {
+        match self {
+            Self::Const => Some(0),
+            Self::Linear => Some(1),
+            Self::Sqrt => Some(2),
+            Self::Cbrt => Some(3),
+            Self::Pow2 => Some(4),
+            Self::Pow3 => Some(5),
+            Self::Custom(_) => None,
+        }
     }
 }
@@ -1,5 +1,6 @@
 // Imports
 use crate::Color;
+use crate::style::BlendMode;
 use anyhow::Context;
 use serde::{Deserialize, Serialize};
 
@@ -25,6 +26,12 @@ pub struct RoughOptions {
     /// An optional seed to generate reproducible shapes.
     #[serde(rename = "seed")]
     pub seed: Option<u64>,
+    /// The blend mode the shape is composited with.
+    #[serde(rename = "blend_mode")]
+    pub blend_mode: BlendMode,
+    /// The opacity of the whole shape, independent of the alpha of the colors.
+    #[serde(rename = "opacity", with = "crate::serialize::f64_dp3")]
+    pub opacity: f64,
 }
 
 impl Default for RoughOptions {
@@ -37,6 +44,8 @@ impl Default for RoughOptions {
             // Default hachure angle (in rad). is -41 degrees
             hachure_angle: -0.715585,
             seed: None,
+            blend_mode: BlendMode::default(),
+            opacity: 1.0,
         }
     }
 }
@@ -2,16 +2,16 @@
 mod smoothoptions;
 
 // Re-exports
-pub use smoothoptions::{LineCap, LineStyle, SmoothOptions};
+pub use smoothoptions::{CalligraphyNib, FillStyle, LineCap, LineJoin, LineStyle, SmoothOptions};
 
 // Imports
 use super::Composer;
-use crate::PenPath;
 use crate::ext::Vector2Ext;
 use crate::penpath::{self, Segment};
 use crate::shapes::{
     Arrow, CubicBezier, Ellipse, Line, Polygon, Polyline, QuadraticBezier, Rectangle, Shapeable,
 };
+use crate::{Color, PenPath};
 use kurbo::Shape;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 
@@ -70,8 +70,7 @@ impl Composer<SmoothOptions> for Rectangle {
         let shape = self.outline_path();
 
         if let Some(fill_color) = options.fill_color {
-            let fill_brush = cx.solid_brush(fill_color.into());
-            cx.fill(shape.clone(), &fill_brush);
+            draw_fill(cx, &shape, fill_color, &options.fill_style);
         }
 
         if let Some(stroke_color) = options.stroke_color {
@@ -97,8 +96,7 @@ impl Composer<SmoothOptions> for Ellipse {
         let ellipse = self.outline_path();
 
         if let Some(fill_color) = options.fill_color {
-            let fill_brush = cx.solid_brush(fill_color.into());
-            cx.fill(&ellipse, &fill_brush);
+            draw_fill(cx, &ellipse, fill_color, &options.fill_style);
         }
 
         if let Some(stroke_color) = options.stroke_color {
@@ -124,8 +122,7 @@ impl Composer<SmoothOptions> for QuadraticBezier {
         let quadbez = self.outline_path();
 
         if let Some(fill_color) = options.fill_color {
-            let fill_brush = cx.solid_brush(fill_color.into());
-            cx.fill(&quadbez, &fill_brush);
+            draw_fill(cx, &quadbez, fill_color, &options.fill_style);
         }
 
         if let Some(stroke_color) = options.stroke_color {
@@ -151,8 +148,7 @@ impl Composer<SmoothOptions> for CubicBezier {
         let cubbez = self.outline_path();
 
         if let Some(fill_color) = options.fill_color {
-            let fill_brush = cx.solid_brush(fill_color.into());
-            cx.fill(&cubbez, &fill_brush);
+            draw_fill(cx, &cubbez, fill_color, &options.fill_style);
         }
 
         if let Some(stroke_color) = options.stroke_color {
@@ -187,16 +183,11 @@ impl Composer<SmoothOptions> for Polyline {
                 &Into::<piet::Color>::into(color),
             );
         } else {
-            let style = options
-                .piet_stroke_style
-                .clone()
-                .line_cap(piet::LineCap::Butt)
-                .line_join(piet::LineJoin::Bevel);
             cx.stroke_styled(
                 self.outline_path(),
                 &Into::<piet::Color>::into(color),
                 options.stroke_width,
-                &style,
+                &options.piet_stroke_style,
             );
         }
     }
@@ -223,19 +214,13 @@ impl Composer<SmoothOptions> for Polygon {
         } else {
             let outline_path = self.outline_path();
             if let Some(fill_color) = options.fill_color {
-                cx.fill(&outline_path, &Into::<piet::Color>::into(fill_color));
+                draw_fill(cx, &outline_path, fill_color, &options.fill_style);
             }
-            let style = options
-                .piet_stroke_style
-                .clone()
-                .line_cap(piet::LineCap::Butt)
-                .line_join(piet::LineJoin::Bevel);
-
             cx.stroke_styled(
                 &outline_path,
                 &Into::<piet::Color>::into(color),
                 options.stroke_width,
-                &style,
+                &options.piet_stroke_style,
             );
         }
     }
@@ -243,7 +228,9 @@ impl Composer<SmoothOptions> for Polygon {
 
 impl Composer<SmoothOptions> for PenPath {
     fn composed_bounds(&self, options: &SmoothOptions) -> Aabb {
-        self.bounds().loosened(options.stroke_width * 0.5)
+        let nib_width = options.calligraphy.map(|nib| nib.nib_width).unwrap_or(0.0);
+        self.bounds()
+            .loosened(options.stroke_width.max(nib_width) * 0.5)
     }
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &SmoothOptions) {
@@ -252,31 +239,68 @@ impl Composer<SmoothOptions> for PenPath {
         };
 
         let mut full_path = kurbo::BezPath::new();
+        // The segment paths with their arc length, only collected when drawing with a color gradient
+        let mut gradient_seg_paths = Vec::new();
         let mut single_pos = true;
         let mut prev = self.start;
 
         cx.save().unwrap();
 
-        for seg in self.segments.iter() {
+        // The variable width outline can't be dashed directly, so it is clipped to the dashes of the centerline instead
+        if let Some(dash_pattern) = options.custom_dash_pattern() {
+            let max_width = std::iter::once(self.start)
+                .chain(self.segments.iter().map(|seg| seg.end()))
+                .map(|element| options.element_width(&element))
+                .chain(options.calligraphy.map(|nib| nib.nib_width))
+                .fold(0.0, f64::max);
+            let dashes = kurbo::stroke(
+                self.to_kurbo_flattened(0.25),
+                &kurbo::Stroke::new(max_width)
+                    .with_caps(kurbo::Cap::Butt)
+                    .with_dashes(options.dash_offset, dash_pattern.iter().copied()),
+                &kurbo::StrokeOpts::default(),
+                0.25,
+            );
+            cx.clip(dashes);
+        }
+
+        // The arc lengths of the segments, for tapering the width along the path
+        let seg_lens = std::iter::once(self.start)
+            .chain(self.segments.iter().map(|seg| seg.end()))
+            .zip(self.segments.iter())
+            .map(|(seg_start, seg)| segment_len(seg_start.pos, seg))
+            .collect::<Vec<f64>>();
+        let total_len = seg_lens.iter().sum::<f64>();
+        let mut current_len = 0.0;
+
+        for (seg, &seg_len) in self.segments.iter().zip(seg_lens.iter()) {
+            let (taper_start, taper_end) = (
+                options.taper_factor(current_len, total_len),
+                options.taper_factor(current_len + seg_len, total_len),
+            );
+            current_len += seg_len;
+
+            if let Segment::MoveTo { end } = seg {
+                prev = *end;
+                continue;
+            }
             if seg.end().pos == self.start.pos {
                 continue;
             } else {
                 single_pos = false;
             }
 
-            let bez_path = {
+            let (bez_path, seg_len) = {
                 match seg {
+                    // Gaps are skipped above
+                    Segment::MoveTo { .. } => unreachable!(),
                     Segment::LineTo { end } => {
                         let (width_start, width_end) = (
-                            options
-                                .pressure_curve
-                                .apply(options.stroke_width, prev.pressure),
-                            options
-                                .pressure_curve
-                                .apply(options.stroke_width, end.pressure),
+                            options.element_width(&prev) * taper_start,
+                            options.element_width(end) * taper_end,
                         );
 
-                        let bez_path = compose_lines_variable_width(
+                        let bez_path = compose_segment_lines(
                             &[Line {
                                 start: prev.pos,
                                 end: end.pos,
@@ -287,16 +311,12 @@ impl Composer<SmoothOptions> for PenPath {
                         );
 
                         prev = *end;
-                        bez_path
+                        (bez_path, seg_len)
                     }
                     Segment::QuadBezTo { cp, end } => {
                         let (width_start, width_end) = (
-                            options
-                                .pressure_curve
-                                .apply(options.stroke_width, prev.pressure),
-                            options
-                                .pressure_curve
-                                .apply(options.stroke_width, end.pressure),
+                            options.element_width(&prev) * taper_start,
+                            options.element_width(end) * taper_end,
                         );
 
                         let quadbez = QuadraticBezier {
@@ -304,25 +324,18 @@ impl Composer<SmoothOptions> for PenPath {
                             cp: *cp,
                             end: end.pos,
                         };
-                        let n_splits = penpath::no_subsegments_for_segment_len(
-                            quadbez.outline_path().perimeter(0.25),
-                        )
-                        .max(2);
+                        let n_splits = penpath::no_subsegments_for_segment_len(seg_len).max(2);
                         let lines = quadbez.approx_with_lines(n_splits);
                         let bez_path =
-                            compose_lines_variable_width(&lines, width_start, width_end, options);
+                            compose_segment_lines(&lines, width_start, width_end, options);
 
                         prev = *end;
-                        bez_path
+                        (bez_path, seg_len)
                     }
                     Segment::CubBezTo { cp1, cp2, end } => {
                         let (width_start, width_end) = (
-                            options
-                                .pressure_curve
-                                .apply(options.stroke_width, prev.pressure),
-                            options
-                                .pressure_curve
-                                .apply(options.stroke_width, end.pressure),
+                            options.element_width(&prev) * taper_start,
+                            options.element_width(end) * taper_end,
                         );
 
                         let cubbez = CubicBezier {
@@ -331,16 +344,13 @@ impl Composer<SmoothOptions> for PenPath {
                             cp2: *cp2,
                             end: end.pos,
                         };
-                        let n_splits = penpath::no_subsegments_for_segment_len(
-                            cubbez.outline_path().perimeter(0.25),
-                        )
-                        .max(2);
+                        let n_splits = penpath::no_subsegments_for_segment_len(seg_len).max(2);
                         let lines = cubbez.approx_with_lines(n_splits);
                         let bez_path =
-                            compose_lines_variable_width(&lines, width_start, width_end, options);
+                            compose_segment_lines(&lines, width_start, width_end, options);
 
                         prev = *end;
-                        bez_path
+                        (bez_path, seg_len)
                     }
                 }
             };
@@ -349,20 +359,52 @@ impl Composer<SmoothOptions> for PenPath {
             //let stroke_brush = cx.solid_brush(piet::Color::RED);
             //cx.stroke(bez_path.clone(), &stroke_brush, 0.2);
 
-            full_path.extend(bez_path);
+            if options.color_gradient.is_some() {
+                gradient_seg_paths.push((bez_path, seg_len));
+            } else {
+                full_path.extend(bez_path);
+            }
         }
 
-        cx.fill(full_path, &Into::<piet::Color>::into(color));
+        if let Some((start_color, end_color)) = options.color_gradient {
+            // Each segment is filled with the color at the center of its arc length
+            let total_len = gradient_seg_paths.iter().map(|(_, len)| len).sum::<f64>();
+            let mut current_len = 0.0;
+
+            for (bez_path, seg_len) in gradient_seg_paths {
+                let t = if total_len > 0.0 {
+                    (current_len + seg_len * 0.5) / total_len
+                } else {
+                    0.0
+                };
+                cx.fill(
+                    bez_path,
+                    &Into::<piet::Color>::into(start_color.lerp(end_color, t)),
+                );
+                current_len += seg_len;
+            }
+        } else {
+            cx.fill(full_path, &Into::<piet::Color>::into(color));
+        }
 
         // Single element/position strokes need special treatment to be rendered
         if single_pos {
-            let start_width = options
-                .pressure_curve
-                .apply(options.stroke_width, self.start.pressure);
-            cx.fill(
-                kurbo::Circle::new(self.start.pos.to_kurbo_point(), start_width * 0.5),
-                &Into::<piet::Color>::into(color),
-            );
+            let color = options
+                .color_gradient
+                .map(|(start_color, _)| start_color)
+                .unwrap_or(color);
+            if let Some(nib) = options.calligraphy {
+                cx.fill(
+                    polygon_path(&nib.corners(self.start.pos)),
+                    &Into::<piet::Color>::into(color),
+                );
+            } else {
+                let start_width = options.element_width(&self.start);
+                cx.fill(
+                    kurbo::Circle::new(self.start.pos.to_kurbo_point(), start_width * 0.5),
+                    &Into::<piet::Color>::into(color),
+                );
+            }
         }
 
         cx.restore().unwrap();
@@ -397,6 +439,149 @@ impl Composer<SmoothOptions> for crate::Shape {
     }
 }
 
+/// The arc length of the segment starting at the given position. Gaps have no length.
+fn segment_len(start: na::Vector2<f64>, seg: &Segment) -> f64 {
+    match seg {
+        Segment::MoveTo { .. } => 0.0,
+        Segment::LineTo { end } => (end.pos - start).magnitude(),
+        Segment::QuadBezTo { cp, end } => QuadraticBezier {
+            start,
+            cp: *cp,
+            end: end.pos,
+        }
+        .outline_path()
+        .perimeter(0.25),
+        Segment::CubBezTo { cp1, cp2, end } => CubicBezier {
+            start,
+            cp1: *cp1,
+            cp2: *cp2,
+            end: end.pos,
+        }
+        .outline_path()
+        .perimeter(0.25),
+    }
+}
+
+/// Fills the shape with the fill color, applying the fill style.
+fn draw_fill(
+    cx: &mut impl piet::RenderContext,
+    shape: &impl kurbo::Shape,
+    fill_color: Color,
+    fill_style: &FillStyle,
+) {
+    let (spacing, line_width) = match fill_style {
+        FillStyle::Solid => {
+            cx.fill(shape, &Into::<piet::Color>::into(fill_color));
+            return;
+        }
+        FillStyle::Hatch {
+            spacing,
+            line_width,
+            ..
+        }
+        | FillStyle::CrossHatch {
+            spacing,
+            line_width,
+            ..
+        } => (spacing.max(FillStyle::HATCH_SPACING_MIN), *line_width),
+    };
+    let bounds = shape.bounding_box();
+    let center = bounds.center().to_vec2();
+    // Lines through this radius around the center cover the shape for every angle
+    let radius = 0.5 * bounds.size().to_vec2().hypot();
+    let mut hatch_path = kurbo::BezPath::new();
+
+    for angle in fill_style.hatch_angles() {
+        let dir = kurbo::Vec2::from_angle(angle);
+        let normal = kurbo::Vec2::new(-dir.y, dir.x);
+        let mut offset = -radius;
+        while offset <= radius {
+            let base = center + normal * offset;
+            hatch_path.move_to((base - dir * radius).to_point());
+            hatch_path.line_to((base + dir * radius).to_point());
+            offset += spacing;
+        }
+    }
+
+    cx.save().unwrap();
+    cx.clip(shape.to_path(0.1));
+    cx.stroke(
+        hatch_path,
+        &Into::<piet::Color>::into(fill_color),
+        line_width,
+    );
+    cx.restore().unwrap();
+}
+
+/// Composes the outline of the lines of a pen path segment,
+/// swept with the calligraphy nib when it is set. Must be drawn with only a fill.
+fn compose_segment_lines(
+    lines: &[Line],
+    start_width: f64,
+    end_width: f64,
+    options: &SmoothOptions,
+) -> kurbo::BezPath {
+    let Some(nib) = options.calligraphy else {
+        return compose_lines_variable_width(lines, start_width, end_width, options);
+    };
+
+    // Every line is swept with the nib separately. All outlines have the same orientation,
+    // so that they are united when filled with the non-zero fill rule.
+    lines
+        .iter()
+        .flat_map(|line| {
+            let mut corners = nib.corners(line.start).to_vec();
+            corners.extend(nib.corners(line.end));
+            polygon_path(&convex_hull(corners))
+        })
+        .collect()
+}
+
+/// The convex hull of the points in counter-clockwise order, with Andrew's monotone chain algorithm.
+fn convex_hull(mut points: Vec<na::Vector2<f64>>) -> Vec<na::Vector2<f64>> {
+    points.sort_by(|a, b| a[0].total_cmp(&b[0]).then(a[1].total_cmp(&b[1])));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    let cross = |o: na::Vector2<f64>, a: na::Vector2<f64>, b: na::Vector2<f64>| {
+        (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0])
+    };
+
+    let mut hull: Vec<na::Vector2<f64>> = Vec::with_capacity(points.len() * 2);
+    for pass in [points.clone(), points.into_iter().rev().collect()] {
+        let lower_len = hull.len();
+        for point in pass {
+            while hull.len() >= lower_len + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        // The last point of each chain is the first point of the other one
+        hull.pop();
+    }
+
+    hull
+}
+
+/// A closed path through the vertices of the polygon.
+fn polygon_path(vertices: &[na::Vector2<f64>]) -> kurbo::BezPath {
+    let mut bez_path = kurbo::BezPath::new();
+    let mut vertices = vertices.iter();
+    let Some(first) = vertices.next() else {
+        return bez_path;
+    };
+    bez_path.move_to(first.to_kurbo_point());
+    for vertex in vertices {
+        bez_path.line_to(vertex.to_kurbo_point());
+    }
+    bez_path.close_path();
+
+    bez_path
+}
+
 /// Composes lines with variable width. Must be drawn with only a fill.
 fn compose_lines_variable_width(
     lines: &[Line],
@@ -1,6 +1,7 @@
 // Imports
 use crate::Color;
-use crate::style::PressureCurve;
+use crate::penpath::Element;
+use crate::style::{BlendMode, PressureCurve};
 use anyhow::Context;
 use num_derive::{FromPrimitive, ToPrimitive};
 use serde::{Deserialize, Serialize};
@@ -22,16 +23,70 @@ pub struct SmoothOptions {
     /// Fill color. When set to None, the fill is not drawn.
     #[serde(rename = "fill_color")]
     pub fill_color: Option<Color>,
+    /// Fill style, how the fill color is applied to closed shapes.
+    #[serde(rename = "fill_style")]
+    pub fill_style: FillStyle,
     /// Pressure curve.
     #[serde(rename = "pressure_curve")]
     pub pressure_curve: PressureCurve,
+    /// The input pressure that is mapped to no pressure, calibrating styluses that report pressure on the lightest touch.
+    #[serde(rename = "pressure_min", with = "crate::serialize::f64_dp3")]
+    pub pressure_min: f64,
+    /// The input pressure that is mapped to the full pressure, calibrating styluses that max out early.
+    #[serde(rename = "pressure_max", with = "crate::serialize::f64_dp3")]
+    pub pressure_max: f64,
     /// Line style.
     #[serde(rename = "line_style")]
     pub line_style: LineStyle,
     /// Line cap.
     #[serde(rename = "line_cap")]
     pub line_cap: LineCap,
-    /// The inner piet::StrokeStyle, computed using the stroke_width, line_style, and line_cap.
+    /// The join at the corners of the line.
+    #[serde(rename = "line_join")]
+    pub line_join: LineJoin,
+    /// Custom alternating dash and gap lengths, overriding the dashes of the line style. Drawn solid when None.
+    ///
+    /// Also applied to pen paths.
+    #[serde(rename = "dash_pattern")]
+    pub dash_pattern: Option<Vec<f64>>,
+    /// The offset into the custom dash pattern at which the line starts.
+    #[serde(rename = "dash_offset", with = "crate::serialize::f64_dp3")]
+    pub dash_offset: f64,
+    /// Optional color gradient from a start to an end color along the path length, replacing the stroke color.
+    ///
+    /// Only applied to pen paths.
+    #[serde(rename = "color_gradient")]
+    pub color_gradient: Option<(Color, Color)>,
+    /// The blend mode the stroke is composited with onto the content underneath.
+    ///
+    /// Applied by the renderer when compositing.
+    #[serde(rename = "blend_mode")]
+    pub blend_mode: BlendMode,
+    /// The opacity of the whole stroke, independent of the alpha of the colors.
+    ///
+    /// Applied by the renderer when compositing, like the blend mode.
+    #[serde(rename = "opacity", with = "crate::serialize::f64_dp3")]
+    pub opacity: f64,
+    /// How strongly the pen velocity thins the stroke width. When 0.0, the width only depends on the pressure.
+    ///
+    /// Only applied to pen paths with elements that carry a velocity.
+    #[serde(rename = "velocity_influence", with = "crate::serialize::f64_dp3")]
+    pub velocity_influence: f64,
+    /// The length over which the width ramps up from zero at the start of pen paths. Not tapered when 0.0.
+    #[serde(rename = "taper_start", with = "crate::serialize::f64_dp3")]
+    pub taper_start: f64,
+    /// The length over which the width ramps down to zero at the end of pen paths. Not tapered when 0.0.
+    #[serde(rename = "taper_end", with = "crate::serialize::f64_dp3")]
+    pub taper_end: f64,
+    /// The number of elements the pressures of pen paths are averaged over when the stroke is finished,
+    /// evening out jittering widths without changing the positions. A window of 1 disables it.
+    #[serde(rename = "pressure_smoothing_window")]
+    pub pressure_smoothing_window: usize,
+    /// Draw pen paths like a broad-edge pen with this nib, the width then follows the direction of the path
+    /// instead of the pressure.
+    #[serde(rename = "calligraphy")]
+    pub calligraphy: Option<CalligraphyNib>,
+    /// The inner piet::StrokeStyle, computed using the stroke_width, line_style, line_cap and line_join.
     #[serde(skip)]
     pub piet_stroke_style: piet::StrokeStyle,
 }
@@ -41,14 +96,36 @@ impl Default for SmoothOptions {
         let stroke_width: f64 = 2.0;
         let line_style = LineStyle::default();
         let line_cap = LineCap::default();
+        let line_join = LineJoin::default();
         Self {
             stroke_width,
             stroke_color: Some(Color::BLACK),
             fill_color: None,
+            fill_style: FillStyle::default(),
             pressure_curve: PressureCurve::default(),
+            pressure_min: 0.0,
+            pressure_max: 1.0,
             line_style,
             line_cap,
-            piet_stroke_style: Self::compute_piet_stroke_style(stroke_width, line_style, line_cap),
+            line_join,
+            dash_pattern: None,
+            dash_offset: 0.0,
+            color_gradient: None,
+            blend_mode: BlendMode::default(),
+            opacity: 1.0,
+            velocity_influence: 0.0,
+            taper_start: 0.0,
+            taper_end: 0.0,
+            pressure_smoothing_window: 1,
+            calligraphy: None,
+            piet_stroke_style: Self::compute_piet_stroke_style(
+                stroke_width,
+                line_style,
+                line_cap,
+                line_join,
+                None,
+                0.0,
+            ),
         }
     }
 }
@@ -56,37 +133,138 @@ impl Default for SmoothOptions {
 impl SmoothOptions {
     /// The ratio between the length of a dash and the width of the stroke
     const DASH_LENGTH_TO_WIDTH_RATIO: f64 = f64::consts::E;
+    /// The minimum velocity influence
+    pub const VELOCITY_INFLUENCE_MIN: f64 = 0.0;
+    /// The maximum velocity influence
+    pub const VELOCITY_INFLUENCE_MAX: f64 = 1.0;
+    /// The velocity at which the width is reduced by half the velocity influence, in surface coordinates per second.
+    const VELOCITY_REFERENCE: f64 = 1000.0;
+
+    /// Remaps the input pressure from the calibrated range between the pressure min and max to 0.0..=1.0.
+    ///
+    /// Pressures below the min map to 0.0, above the max to 1.0 and linear in between.
+    pub fn calibrated_pressure(&self, pressure: f64) -> f64 {
+        if self.pressure_max <= self.pressure_min {
+            return if pressure < self.pressure_min {
+                0.0
+            } else {
+                1.0
+            };
+        }
+        ((pressure - self.pressure_min) / (self.pressure_max - self.pressure_min)).clamp(0.0, 1.0)
+    }
+
+    /// The stroke width for the input pressure, calibrated and with the pressure curve applied.
+    pub fn pressure_width(&self, pressure: f64) -> f64 {
+        self.pressure_curve
+            .apply(self.stroke_width, self.calibrated_pressure(pressure))
+    }
+
+    /// The stroke width at the given element, with the pressure calibration, the pressure curve
+    /// and the velocity influence applied.
+    pub fn element_width(&self, element: &Element) -> f64 {
+        let width = self.pressure_width(element.pressure);
+
+        match element.velocity {
+            Some(velocity) if self.velocity_influence > 0.0 => {
+                let influence = self
+                    .velocity_influence
+                    .clamp(Self::VELOCITY_INFLUENCE_MIN, Self::VELOCITY_INFLUENCE_MAX);
+                width / (1.0 + influence * velocity / Self::VELOCITY_REFERENCE)
+            }
+            _ => width,
+        }
+    }
+
+    /// The factor the width is tapered with at the given arc length of a path with the total length.
+    ///
+    /// Multiplied with the element width, independent of the pressure.
+    pub fn taper_factor(&self, len: f64, total_len: f64) -> f64 {
+        let ramp = |len: f64, taper: f64| {
+            if taper > 0.0 {
+                (len / taper).clamp(0.0, 1.0)
+            } else {
+                1.0
+            }
+        };
+        ramp(len, self.taper_start) * ramp(total_len - len, self.taper_end)
+    }
+
+    /// The custom dash pattern, if it is set and valid.
+    ///
+    /// A valid pattern has only finite, non-negative lengths and is not entirely zero.
+    pub fn custom_dash_pattern(&self) -> Option<&[f64]> {
+        Self::valid_dash_pattern(self.dash_pattern.as_deref())
+    }
+
+    fn valid_dash_pattern(dash_pattern: Option<&[f64]>) -> Option<&[f64]> {
+        dash_pattern.filter(|pattern| {
+            pattern.iter().all(|len| len.is_finite() && *len >= 0.0)
+                && pattern.iter().sum::<f64>() > 0.0
+        })
+    }
+
+    /// Scales the custom dash pattern, so that the dashes appear scaled by the given factor.
+    pub fn scale_dashes(&mut self, scale: f64) {
+        if let Some(dash_pattern) = self.dash_pattern.as_mut() {
+            dash_pattern
+                .iter_mut()
+                .for_each(|len| len.mul_assign(scale));
+        }
+        self.dash_offset *= scale;
+        self.update_piet_stroke_style();
+    }
 
     fn compute_piet_stroke_style(
         stroke_width: f64,
         line_style: LineStyle,
         line_cap: LineCap,
+        line_join: LineJoin,
+        custom_dash_pattern: Option<&[f64]>,
+        dash_offset: f64,
     ) -> piet::StrokeStyle {
+        if let Some(custom_dash_pattern) = Self::valid_dash_pattern(custom_dash_pattern) {
+            let mut stroke_style = piet::StrokeStyle::new();
+            stroke_style.set_dash_pattern(custom_dash_pattern.to_vec());
+            stroke_style.set_dash_offset(dash_offset);
+            stroke_style.set_line_cap(line_cap.into());
+            stroke_style.set_line_join(line_join.into());
+            return stroke_style;
+        }
         let mut dash_pattern = line_style.as_unscaled_vector();
         match line_cap {
             LineCap::Straight => dash_pattern
                 .iter_mut()
                 .for_each(|e| e.mul_assign(stroke_width * Self::DASH_LENGTH_TO_WIDTH_RATIO)),
-            LineCap::Rounded => dash_pattern.iter_mut().enumerate().for_each(|(idx, e)| {
-                if !line_style.is_dotted() {
-                    e.mul_assign(stroke_width * Self::DASH_LENGTH_TO_WIDTH_RATIO);
-                }
-                // If the stroke has a rounded linecap, a half-disk with radius equal to the stroke width is added both ends of a stroke, this increases the length of each line by the width of the stroke, and is not taken into account by DashStroke, it has to be manually accounted for
-                if idx % 2 == 1 {
-                    e.add_assign(2.0 * stroke_width)
-                }
-            }),
+            LineCap::Rounded | LineCap::Square => {
+                dash_pattern.iter_mut().enumerate().for_each(|(idx, e)| {
+                    if !line_style.is_dotted() {
+                        e.mul_assign(stroke_width * Self::DASH_LENGTH_TO_WIDTH_RATIO);
+                    }
+                    // If the stroke has a rounded or square linecap, a half-disk with radius equal to the stroke width is added both ends of a stroke, this increases the length of each line by the width of the stroke, and is not taken into account by DashStroke, it has to be manually accounted for
+                    if idx % 2 == 1 {
+                        e.add_assign(2.0 * stroke_width)
+                    }
+                })
+            }
         };
         let mut stroke_style = piet::StrokeStyle::new();
         stroke_style.set_dash_pattern(dash_pattern);
         stroke_style.set_line_cap(line_cap.into());
+        stroke_style.set_line_join(line_join.into());
         stroke_style
     }
 
     /// Updates the inner piet::Strokestyle
     pub fn update_piet_stroke_style(&mut self) {
-        self.piet_stroke_style =
-            Self::compute_piet_stroke_style(self.stroke_width, self.line_style, self.line_cap);
+        self.piet_stroke_style = Self::compute_piet_stroke_style(
+            self.stroke_width,
+            self.line_style,
+            self.line_cap,
+            self.line_join,
+            self.dash_pattern.as_deref(),
+            self.dash_offset,
+        );
     }
 
     /// Updates the line cap
@@ -99,6 +277,12 @@ impl SmoothOptions {
         self.update_piet_stroke_style();
     }
 
+    /// Updates the line join
+    pub fn update_line_join(&mut self, line_join: LineJoin) {
+        self.line_join = line_join;
+        self.update_piet_stroke_style();
+    }
+
     /// Updates the line style
     pub fn update_line_style(&mut self, line_style: LineStyle) {
         // Dotted style requires a round LineCap
@@ -124,12 +308,40 @@ impl<'de> Deserialize<'de> for SmoothOptions {
             pub stroke_color: Option<Color>,
             #[serde(rename = "fill_color")]
             pub fill_color: Option<Color>,
+            #[serde(rename = "fill_style")]
+            pub fill_style: FillStyle,
             #[serde(rename = "pressure_curve")]
             pub pressure_curve: PressureCurve,
+            #[serde(rename = "pressure_min", with = "crate::serialize::f64_dp3")]
+            pub pressure_min: f64,
+            #[serde(rename = "pressure_max", with = "crate::serialize::f64_dp3")]
+            pub pressure_max: f64,
             #[serde(rename = "line_style")]
             pub line_style: LineStyle,
             #[serde(rename = "line_cap")]
             pub line_cap: LineCap,
+            #[serde(rename = "line_join")]
+            pub line_join: LineJoin,
+            #[serde(rename = "dash_pattern")]
+            pub dash_pattern: Option<Vec<f64>>,
+            #[serde(rename = "dash_offset", with = "crate::serialize::f64_dp3")]
+            pub dash_offset: f64,
+            #[serde(rename = "color_gradient")]
+            pub color_gradient: Option<(Color, Color)>,
+            #[serde(rename = "blend_mode")]
+            pub blend_mode: BlendMode,
+            #[serde(rename = "opacity", with = "crate::serialize::f64_dp3")]
+            pub opacity: f64,
+            #[serde(rename = "velocity_influence", with = "crate::serialize::f64_dp3")]
+            pub velocity_influence: f64,
+            #[serde(rename = "taper_start", with = "crate::serialize::f64_dp3")]
+            pub taper_start: f64,
+            #[serde(rename = "taper_end", with = "crate::serialize::f64_dp3")]
+            pub taper_end: f64,
+            #[serde(rename = "pressure_smoothing_window")]
+            pub pressure_smoothing_window: usize,
+            #[serde(rename = "calligraphy")]
+            pub calligraphy: Option<CalligraphyNib>,
         }
 
         impl From<SmoothOptions> for SmoothOptionsPrecursor {
@@ -138,9 +350,23 @@ impl<'de> Deserialize<'de> for SmoothOptions {
                     stroke_width: value.stroke_width,
                     stroke_color: value.stroke_color,
                     fill_color: value.fill_color,
+                    fill_style: value.fill_style,
                     pressure_curve: value.pressure_curve,
+                    pressure_min: value.pressure_min,
+                    pressure_max: value.pressure_max,
                     line_style: value.line_style,
                     line_cap: value.line_cap,
+                    line_join: value.line_join,
+                    dash_pattern: value.dash_pattern,
+                    dash_offset: value.dash_offset,
+                    color_gradient: value.color_gradient,
+                    blend_mode: value.blend_mode,
+                    opacity: value.opacity,
+                    velocity_influence: value.velocity_influence,
+                    taper_start: value.taper_start,
+                    taper_end: value.taper_end,
+                    pressure_smoothing_window: value.pressure_smoothing_window,
+                    calligraphy: value.calligraphy,
                 }
             }
         }
@@ -153,22 +379,82 @@ impl<'de> Deserialize<'de> for SmoothOptions {
 
         let precursor = SmoothOptionsPrecursor::deserialize(deserializer)?;
 
+        let piet_stroke_style = Self::compute_piet_stroke_style(
+            precursor.stroke_width,
+            precursor.line_style,
+            precursor.line_cap,
+            precursor.line_join,
+            precursor.dash_pattern.as_deref(),
+            precursor.dash_offset,
+        );
+
         Ok(SmoothOptions {
             stroke_width: precursor.stroke_width,
             stroke_color: precursor.stroke_color,
             fill_color: precursor.fill_color,
+            fill_style: precursor.fill_style,
             pressure_curve: precursor.pressure_curve,
+            pressure_min: precursor.pressure_min,
+            pressure_max: precursor.pressure_max,
             line_style: precursor.line_style,
             line_cap: precursor.line_cap,
-            piet_stroke_style: Self::compute_piet_stroke_style(
-                precursor.stroke_width,
-                precursor.line_style,
-                precursor.line_cap,
-            ),
+            line_join: precursor.line_join,
+            dash_pattern: precursor.dash_pattern,
+            dash_offset: precursor.dash_offset,
+            color_gradient: precursor.color_gradient,
+            blend_mode: precursor.blend_mode,
+            opacity: precursor.opacity,
+            velocity_influence: precursor.velocity_influence,
+            taper_start: precursor.taper_start,
+            taper_end: precursor.taper_end,
+            pressure_smoothing_window: precursor.pressure_smoothing_window,
+            calligraphy: precursor.calligraphy,
+            piet_stroke_style,
         })
     }
 }
 
+/// The broad-edge nib of calligraphy strokes.
+///
+/// Drawn as a thin rectangle swept along the path, so the stroke is widest perpendicular to the nib
+/// and thinnest along it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename = "calligraphy_nib")]
+pub struct CalligraphyNib {
+    /// The angle of the nib edge in radians, relative to the horizontal axis.
+    #[serde(rename = "nib_angle", with = "crate::serialize::f64_dp3")]
+    pub nib_angle: f64,
+    /// The width of the nib edge.
+    #[serde(rename = "nib_width", with = "crate::serialize::f64_dp3")]
+    pub nib_width: f64,
+}
+
+impl Default for CalligraphyNib {
+    fn default() -> Self {
+        Self {
+            nib_angle: std::f64::consts::FRAC_PI_4,
+            nib_width: 8.0,
+        }
+    }
+}
+
+impl CalligraphyNib {
+    /// The thickness of the nib relative to its width, so that strokes along the nib remain visible as hairlines.
+    const THICKNESS_RATIO: f64 = 0.08;
+
+    /// The corners of the nib placed at the position, in counter-clockwise order.
+    pub fn corners(&self, pos: na::Vector2<f64>) -> [na::Vector2<f64>; 4] {
+        let edge = na::vector![self.nib_angle.cos(), self.nib_angle.sin()] * self.nib_width * 0.5;
+        let thickness = na::vector![-edge[1], edge[0]] * Self::THICKNESS_RATIO;
+        [
+            pos - edge - thickness,
+            pos + edge - thickness,
+            pos + edge + thickness,
+            pos - edge + thickness,
+        ]
+    }
+}
+
 /// Line cap present at the start and end of a line
 #[derive(
     Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, FromPrimitive, ToPrimitive,
@@ -182,6 +468,9 @@ pub enum LineCap {
     /// Rounded line cap
     #[serde(rename = "rounded")]
     Rounded,
+    /// Square line cap, extending the line by half its width
+    #[serde(rename = "square")]
+    Square,
 }
 
 impl TryFrom<u32> for LineCap {
@@ -198,6 +487,46 @@ impl From<LineCap> for piet::LineCap {
         match value {
             LineCap::Straight => piet::LineCap::Butt,
             LineCap::Rounded => piet::LineCap::Round,
+            LineCap::Square => piet::LineCap::Square,
+        }
+    }
+}
+
+/// Line join present at the corners of a line
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, FromPrimitive, ToPrimitive,
+)]
+#[serde(rename = "line_join")]
+pub enum LineJoin {
+    /// Sharp line join, beveled when exceeding the miter limit
+    #[default]
+    #[serde(rename = "miter")]
+    Miter,
+    /// Rounded line join
+    #[serde(rename = "round")]
+    Round,
+    /// Beveled line join
+    #[serde(rename = "bevel")]
+    Bevel,
+}
+
+impl TryFrom<u32> for LineJoin {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u32(value)
+            .with_context(|| format!("LineJoin try_from::<u32>() for value {value} failed"))
+    }
+}
+
+impl From<LineJoin> for piet::LineJoin {
+    fn from(value: LineJoin) -> Self {
+        match value {
+            LineJoin::Miter => piet::LineJoin::Miter {
+                limit: piet::LineJoin::DEFAULT_MITER_LIMIT,
+            },
+            LineJoin::Round => piet::LineJoin::Round,
+            LineJoin::Bevel => piet::LineJoin::Bevel,
         }
     }
 }
@@ -257,3 +586,73 @@ impl TryFrom<u32> for LineStyle {
             .with_context(|| format!("LineStyle try_from::<u32>() for value {value} failed"))
     }
 }
+
+/// How the fill color is applied to closed shapes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "fill_style")]
+pub enum FillStyle {
+    /// Solid fill
+    #[default]
+    #[serde(rename = "solid")]
+    Solid,
+    /// Parallel lines clipped to the shape outline
+    #[serde(rename = "hatch")]
+    Hatch {
+        /// The angle of the lines (in radians).
+        #[serde(rename = "angle", with = "crate::serialize::f64_dp3")]
+        angle: f64,
+        /// The distance between the lines.
+        #[serde(rename = "spacing", with = "crate::serialize::f64_dp3")]
+        spacing: f64,
+        /// The width of the lines.
+        #[serde(rename = "line_width", with = "crate::serialize::f64_dp3")]
+        line_width: f64,
+    },
+    /// Two hatch passes, the second one orthogonal to the given angle
+    #[serde(rename = "cross_hatch")]
+    CrossHatch {
+        /// The angle of the lines of the first pass (in radians).
+        #[serde(rename = "angle", with = "crate::serialize::f64_dp3")]
+        angle: f64,
+        /// The distance between the lines.
+        #[serde(rename = "spacing", with = "crate::serialize::f64_dp3")]
+        spacing: f64,
+        /// The width of the lines.
+        #[serde(rename = "line_width", with = "crate::serialize::f64_dp3")]
+        line_width: f64,
+    },
+}
+
+impl FillStyle {
+    /// The minimum distance between hatch lines, preventing excessive amounts of lines.
+    pub const HATCH_SPACING_MIN: f64 = 0.5;
+
+    /// Scales the hatch spacing and line width, so that the fill appears scaled by the given factor.
+    pub fn scale(&mut self, scale: f64) {
+        match self {
+            Self::Solid => {}
+            Self::Hatch {
+                spacing,
+                line_width,
+                ..
+            }
+            | Self::CrossHatch {
+                spacing,
+                line_width,
+                ..
+            } => {
+                *spacing *= scale;
+                *line_width *= scale;
+            }
+        }
+    }
+
+    /// The angles of the hatch passes. Empty for a solid fill.
+    pub(super) fn hatch_angles(&self) -> Vec<f64> {
+        match self {
+            Self::Solid => vec![],
+            Self::Hatch { angle, .. } => vec![*angle],
+            Self::CrossHatch { angle, .. } => vec![*angle, *angle + f64::consts::FRAC_PI_2],
+        }
+    }
+}
@@ -1,20 +1,23 @@
 // Modules
 mod textureddotsdistribution;
 mod texturedoptions;
+mod tiptexture;
 
 // Re-exports
 pub use textureddotsdistribution::TexturedDotsDistribution;
 pub use texturedoptions::TexturedOptions;
+pub use tiptexture::TipTexture;
 
 // Imports
 use super::Composer;
-use crate::PenPath;
 use crate::ext::Vector2Ext;
-use crate::penpath::Segment;
+use crate::penpath::{Element, Segment};
 use crate::shapes::{Line, Shapeable};
-use kurbo::Shape;
+use crate::{Color, PenPath};
+use kurbo::{ParamCurve, ParamCurveArclen, ParamCurveDeriv, Shape};
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rand_distr::{Distribution, Uniform};
+use tracing::error;
 
 impl Composer<TexturedOptions> for Line {
     fn composed_bounds(&self, options: &TexturedOptions) -> Aabb {
@@ -22,7 +25,11 @@ impl Composer<TexturedOptions> for Line {
     }
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &TexturedOptions) {
-        let bez_path = compose_textured_line_path(self, options);
+        let bez_path = compose_textured_curve_path(
+            line_to_cubbez(self.start.to_kurbo_point(), self.end.to_kurbo_point()),
+            options,
+            None,
+        );
 
         if let Some(fill_color) = options.stroke_color {
             let fill_brush = cx.solid_brush(fill_color.into());
@@ -33,139 +40,299 @@ impl Composer<TexturedOptions> for Line {
 
 impl Composer<TexturedOptions> for PenPath {
     fn composed_bounds(&self, options: &TexturedOptions) -> Aabb {
-        self.bounds().loosened(options.stroke_width)
+        // Stamps of elongated tip textures extend further
+        let tip_texture_extent = options
+            .tip_texture
+            .as_ref()
+            .map(|tip_texture| tip_texture.aspect_ratio().max(1.0))
+            .unwrap_or(1.0);
+        self.bounds()
+            .loosened(options.stroke_width * tip_texture_extent)
     }
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &TexturedOptions) {
         let Some(color) = options.stroke_color else {
             return;
         };
+        if let Some(tip_texture) = &options.tip_texture {
+            draw_tip_texture_stamps(cx, self, options, tip_texture, color);
+            return;
+        }
 
-        let mut full_path = kurbo::BezPath::new();
-        let mut options = options.clone();
-        let mut prev = self.start;
+        cx.fill(
+            compose_textured_pen_path(self, options),
+            &Into::<piet::Color>::into(color),
+        );
+    }
+}
 
-        for seg in self.segments.iter() {
-            if seg.end().pos == self.start.pos {
-                options.advance_seed();
-                continue;
-            }
+/// Compose the textured dots of the pen path, which are the closed subpaths of the returned path.
+///
+/// The stroke color of the options is not considered.
+pub fn compose_textured_pen_path(path: &PenPath, options: &TexturedOptions) -> kurbo::BezPath {
+    let mut full_path = kurbo::BezPath::new();
+    let mut options = options.clone();
+    let mut prev = path.start;
+
+    for seg in path.segments.iter() {
+        if let Segment::MoveTo { end } = seg {
+            prev = *end;
+            options.advance_seed();
+            continue;
+        }
+        if seg.end().pos == path.start.pos {
+            options.advance_seed();
+            continue;
+        }
 
-            let bez_path = match seg {
-                Segment::LineTo { end } => {
-                    let line = Line {
-                        start: prev.pos,
-                        end: end.pos,
-                    };
-
-                    let mut options = options.clone();
-
-                    options.stroke_width = options
-                        .pressure_curve
-                        .apply(options.stroke_width, (prev.pressure + end.pressure) * 0.5);
-
-                    let bez_path = compose_textured_line_path(&line, &options);
-                    prev = *end;
-                    bez_path
-                }
-                Segment::QuadBezTo { end, .. } => {
-                    let line = Line {
-                        start: prev.pos,
-                        end: end.pos,
-                    };
-
-                    let mut options = options.clone();
-
-                    options.stroke_width = options
-                        .pressure_curve
-                        .apply(options.stroke_width, (prev.pressure + end.pressure) * 0.5);
-
-                    let bez_path = compose_textured_line_path(&line, &options);
-                    prev = *end;
-                    bez_path
-                }
-                Segment::CubBezTo { end, .. } => {
-                    let line = Line {
-                        start: prev.pos,
-                        end: end.pos,
-                    };
-
-                    let mut options = options.clone();
-
-                    options.stroke_width = options
-                        .pressure_curve
-                        .apply(options.stroke_width, (prev.pressure + end.pressure) * 0.5);
-
-                    let bez_path = compose_textured_line_path(&line, &options);
-                    prev = *end;
-                    bez_path
-                }
-            };
+        let Some((curve, end)) = segment_curve(&prev, seg) else {
+            continue;
+        };
 
-            full_path.extend(bez_path);
-            options.advance_seed();
+        let mut seg_options = options.clone();
+        seg_options.stroke_width = options
+            .pressure_curve
+            .apply(options.stroke_width, (prev.pressure + end.pressure) * 0.5);
+
+        full_path.extend(compose_textured_curve_path(
+            curve,
+            &seg_options,
+            segment_tilt(&prev, &end),
+        ));
+        prev = end;
+        options.advance_seed();
+    }
+
+    full_path
+}
+
+/// Stamp the tip texture along the pen path, rotated to follow the tangent.
+///
+/// The stamps are evenly spaced by the arc length, their size follows the pressure.
+fn draw_tip_texture_stamps(
+    cx: &mut impl piet::RenderContext,
+    path: &PenPath,
+    options: &TexturedOptions,
+    tip_texture: &TipTexture,
+    color: Color,
+) {
+    const ARCLEN_ACCURACY: f64 = 0.01;
+    const STAMP_SPACING_MIN: f64 = 0.1;
+
+    let image = match cx.make_image(
+        tip_texture.pixel_width() as usize,
+        tip_texture.pixel_height() as usize,
+        &tip_texture.tinted_data(color),
+        piet::ImageFormat::RgbaSeparate,
+    ) {
+        Ok(image) => image,
+        Err(e) => {
+            error!("Creating image for the tip texture failed, Err: {e:?}");
+            return;
         }
+    };
+    let spacing = (options.stroke_width * TipTexture::STAMP_SPACING).max(STAMP_SPACING_MIN);
+    // The arc length to the first stamp of the current segment,
+    // carried over from the previous segment so that the spacing stays even across segments.
+    let mut offset = 0.0;
+    let mut prev = path.start;
+
+    for seg in path.segments.iter() {
+        let Some((curve, end)) = segment_curve(&prev, seg) else {
+            // Every new subpath after a gap starts with a stamp
+            prev = seg.end();
+            offset = 0.0;
+            continue;
+        };
+        let length = curve.arclen(ARCLEN_ACCURACY);
+        let deriv = curve.deriv();
+        let chord = curve.p3 - curve.p0;
+
+        let mut s = offset;
+        while s < length {
+            let t = curve.inv_arclen(s, ARCLEN_ACCURACY);
+            let pressure = prev.pressure + (end.pressure - prev.pressure) * (s / length);
+            let size = options.pressure_curve.apply(options.stroke_width, pressure);
+            let tangent = deriv.eval(t).to_vec2();
+            // The derivative might vanish at degenerate control points
+            let angle = if tangent.hypot2() > 0.0 {
+                tangent.atan2()
+            } else {
+                chord.atan2()
+            };
+            let pos = curve.eval(t);
+
+            if let Err(e) = cx.with_save(|cx| {
+                cx.transform(
+                    kurbo::Affine::translate(pos.to_vec2()) * kurbo::Affine::rotate(angle),
+                );
+                cx.draw_image(
+                    &image,
+                    kurbo::Rect::from_center_size(
+                        kurbo::Point::ORIGIN,
+                        (size, size * tip_texture.aspect_ratio()),
+                    ),
+                    piet::InterpolationMode::Bilinear,
+                );
+                Ok(())
+            }) {
+                error!("Drawing tip texture stamp failed, Err: {e:?}");
+            }
+            s += spacing;
+        }
+
+        offset = s - length;
+        prev = end;
+    }
+}
+
+/// The segment as cubic curve starting at the previous element, together with its end element.
+///
+/// Is None for gaps.
+fn segment_curve(prev: &Element, seg: &Segment) -> Option<(kurbo::CubicBez, Element)> {
+    match seg {
+        Segment::MoveTo { .. } => None,
+        Segment::LineTo { end } => Some((
+            line_to_cubbez(prev.pos.to_kurbo_point(), end.pos.to_kurbo_point()),
+            *end,
+        )),
+        Segment::QuadBezTo { cp, end } => Some((
+            kurbo::QuadBez::new(
+                prev.pos.to_kurbo_point(),
+                cp.to_kurbo_point(),
+                end.pos.to_kurbo_point(),
+            )
+            .raise(),
+            *end,
+        )),
+        Segment::CubBezTo { cp1, cp2, end } => Some((
+            kurbo::CubicBez::new(
+                prev.pos.to_kurbo_point(),
+                cp1.to_kurbo_point(),
+                cp2.to_kurbo_point(),
+                end.pos.to_kurbo_point(),
+            ),
+            *end,
+        )),
+    }
+}
 
-        cx.fill(full_path, &Into::<piet::Color>::into(color));
+/// The mean tilt of a segment. Is None if the tilt is not available for both ends.
+fn segment_tilt(start: &Element, end: &Element) -> Option<na::Vector2<f64>> {
+    match (start.tilt, end.tilt) {
+        (Some(start_tilt), Some(end_tilt)) => Some((start_tilt + end_tilt) * 0.5),
+        (Some(tilt), None) | (None, Some(tilt)) => Some(tilt),
+        (None, None) => None,
     }
 }
 
-fn compose_textured_line_path(line: &Line, options: &TexturedOptions) -> kurbo::BezPath {
-    // Return early if line has no length, else Uniform::new() will panic for range with low >= high
-    if (line.end - line.start).magnitude() <= 0.0 {
+fn line_to_cubbez(start: kurbo::Point, end: kurbo::Point) -> kurbo::CubicBez {
+    kurbo::CubicBez::new(
+        start,
+        start.lerp(end, 1.0 / 3.0),
+        start.lerp(end, 2.0 / 3.0),
+        end,
+    )
+}
+
+/// Compose the dots along the curve.
+///
+/// The dots are distributed by the arc length of the curve, so that the density only depends on the geometry
+/// and the seed, and not on how the path is sampled or at which scale it is rendered.
+fn compose_textured_curve_path(
+    curve: kurbo::CubicBez,
+    options: &TexturedOptions,
+    tilt: Option<na::Vector2<f64>>,
+) -> kurbo::BezPath {
+    const ARCLEN_ACCURACY: f64 = 0.01;
+
+    let length = curve.arclen(ARCLEN_ACCURACY);
+    // Return early if curve has no length or width, else Uniform::new() will panic for range with low >= high
+    if length <= 0.0 || options.stroke_width <= 0.0 {
         return kurbo::BezPath::new();
     }
 
     let mut rng = crate::utils::new_rng_default_pcg64(options.seed);
 
-    let line_vec = line.end - line.start;
-    let line_rect = line.line_w_width_to_rect(options.stroke_width);
-
-    let area = 4.0 * line_rect.cuboid.half_extents[0] * line_rect.cuboid.half_extents[1];
+    // Tilting the pen widens the stroke and stretches the dots in the tilt direction.
+    // Without tilt the dots are oriented along the curve.
+    let tilt = tilt.filter(|tilt| tilt.magnitude() > 0.0);
+    let tilt_factor = tilt
+        .map(|tilt| (tilt.magnitude() * options.tilt_influence).clamp(0.0, 1.0))
+        .unwrap_or(0.0);
+    let stroke_width = options.stroke_width * (1.0 + tilt_factor);
 
     // Radii scale with the stroke width, with a weight.
+    // The width weight is applied to the unscaled width, so that the dots scale exactly with the dots scale.
+    let dots_scale = options.dots_scale.max(TexturedOptions::DOTS_SCALE_MIN);
     let dots_radii = TexturedOptions::DOTS_RADII_DEFAULT
-        * (1.0 + options.stroke_width * TexturedOptions::STROKE_WIDTH_RADII_WEIGHT);
+        * (1.0 + stroke_width / dots_scale * TexturedOptions::STROKE_WIDTH_RADII_WEIGHT)
+        * dots_scale;
+    let dots_radii = na::vector![dots_radii[0] * (1.0 + tilt_factor), dots_radii[1]];
 
     // Ranges for randomization
-    let range_x = -line_rect.cuboid.half_extents[0]..line_rect.cuboid.half_extents[0];
-    let range_y = -line_rect.cuboid.half_extents[1]..line_rect.cuboid.half_extents[1];
-    let range_dots_rot = -std::f64::consts::FRAC_PI_8..std::f64::consts::FRAC_PI_8;
+    let range_s = 0.0..length;
+    let range_y = -stroke_width * 0.5..stroke_width * 0.5;
     let range_dots_rx = dots_radii[0] * 0.8..dots_radii[0] * 1.25;
     let range_dots_ry = dots_radii[1] * 0.8..dots_radii[1] * 1.25;
 
-    let distr_x = Uniform::try_from(range_x).unwrap();
-    let distr_dots_rot = Uniform::try_from(range_dots_rot).unwrap();
+    let distr_unit = Uniform::try_from(0.0..1.0).unwrap();
+    let distr_s = Uniform::try_from(range_s).unwrap();
     let distr_dots_rx = Uniform::try_from(range_dots_rx).unwrap();
     let distr_dots_ry = Uniform::try_from(range_dots_ry).unwrap();
 
-    let n_dots = (area * 0.1 * options.density).round() as i32;
-
+    // The fractional part is rounded stochastically,
+    // else short segments of densely sampled paths would lose or gain dots.
+    let n_dots_exact = length * options.density;
+    let n_dots = n_dots_exact.floor() as usize
+        + usize::from(distr_unit.sample(&mut rng) < n_dots_exact.fract());
+
+    let rotation_jitter = options.rotation_jitter.clamp(
+        TexturedOptions::ROTATION_JITTER_MIN,
+        TexturedOptions::ROTATION_JITTER_MAX,
+    );
+    let deriv = curve.deriv();
+    let chord = curve.p3 - curve.p0;
     let mut bez_path = kurbo::BezPath::new();
 
     for _ in 0..n_dots {
-        let x_pos = distr_x.sample(&mut rng);
+        let s = distr_s.sample(&mut rng);
         let y_pos = options
             .distribution
             .sample_for_range_symmetrical_clipped(&mut rng, range_y.clone());
 
-        let pos = line_rect.transform.affine * na::point![x_pos, y_pos];
-
-        let rotation_angle = na::Rotation2::rotation_between(&na::Vector2::x(), &line_vec).angle()
-            + distr_dots_rot.sample(&mut rng);
+        let t = curve.inv_arclen(s, ARCLEN_ACCURACY);
+        let tangent = deriv.eval(t).to_vec2();
+        // The derivative might vanish at degenerate control points
+        let tangent = if tangent.hypot2() > 0.0 {
+            tangent
+        } else {
+            chord
+        };
+        let tangent_angle = tangent.atan2();
+        let normal = kurbo::Vec2::new(-tangent.y, tangent.x) / tangent.hypot();
+        let pos = curve.eval(t) + normal * y_pos;
+
+        let dots_angle = match tilt {
+            Some(tilt) => {
+                // The dots are symmetrical, so the angle difference can be wrapped to [-pi/2, pi/2]
+                let angle_diff = (tilt[1].atan2(tilt[0]) - tangent_angle
+                    + std::f64::consts::FRAC_PI_2)
+                    .rem_euclid(std::f64::consts::PI)
+                    - std::f64::consts::FRAC_PI_2;
+                tangent_angle + angle_diff * tilt_factor
+            }
+            None => tangent_angle,
+        };
+        // Always sampled, so that the other randomized values don't depend on the jitter
+        let rotation_angle =
+            dots_angle + (distr_unit.sample(&mut rng) * 2.0 - 1.0) * rotation_jitter;
         let radii = na::vector![
             distr_dots_rx.sample(&mut rng),
             distr_dots_ry.sample(&mut rng)
         ];
 
-        let ellipse = kurbo::Ellipse::new(
-            kurbo::Point {
-                x: pos[0],
-                y: pos[1],
-            },
-            radii.to_kurbo_vec(),
-            rotation_angle,
-        );
+        let ellipse = kurbo::Ellipse::new(pos, radii.to_kurbo_vec(), rotation_angle);
 
         bez_path.extend(ellipse.to_path(0.1));
     }
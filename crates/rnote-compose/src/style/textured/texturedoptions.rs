@@ -1,12 +1,13 @@
 // Imports
 use super::textureddotsdistribution::TexturedDotsDistribution;
+use super::tiptexture::TipTexture;
 use crate::Color;
-use crate::style::PressureCurve;
+use crate::style::{BlendMode, PressureCurve};
 use serde::{Deserialize, Serialize};
 
-/// Options for shapes that can be drawn in a textured style.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default, rename = "textured_options")]
+/// Options for shapes that can be drawn in a textured style. Ensure the precursor struct used in deserialization matches this one.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename = "textured_options")]
 pub struct TexturedOptions {
     /// An optional seed to generate reproducible shapes.
     #[serde(rename = "seed")]
@@ -17,8 +18,8 @@ pub struct TexturedOptions {
     /// Stroke color. When set to None, the stroke is not drawn.
     #[serde(rename = "stroke_color")]
     pub stroke_color: Option<Color>,
-    /// Amount of dots of the texture per 10x10 area.
-    #[serde(rename = "density", with = "crate::serialize::f64_dp3")]
+    /// Amount of dots of the texture per unit of path length.
+    #[serde(rename = "density_per_length", with = "crate::serialize::f64_dp3")]
     pub density: f64,
     /// Texture dots distribution type.
     #[serde(rename = "distribution")]
@@ -26,6 +27,30 @@ pub struct TexturedOptions {
     /// Pressure curve.
     #[serde(rename = "pressure_curve")]
     pub pressure_curve: PressureCurve,
+    /// How much the pen tilt influences the width of the stroke and the shape of the dots.
+    ///
+    /// Has no effect when the input does not report tilt.
+    #[serde(rename = "tilt_influence", with = "crate::serialize::f64_dp3")]
+    pub tilt_influence: f64,
+    /// The scale of the dots, relative to the dots size for the current stroke width.
+    ///
+    /// Is adjusted when the stroke is scaled, so that the texture scales along with it.
+    #[serde(rename = "dots_scale", with = "crate::serialize::f64_dp3")]
+    pub dots_scale: f64,
+    /// The maximum random rotation of the dots in radians, in both directions relative to the path tangent.
+    ///
+    /// Derived from the seed, so the rotations are reproducible. Zero keeps all dots aligned to the tangent.
+    #[serde(rename = "rotation_jitter", with = "crate::serialize::f64_dp3")]
+    pub rotation_jitter: f64,
+    /// A user supplied tip texture, stamped along the path. The procedural dots are drawn when None.
+    #[serde(rename = "tip_texture")]
+    pub tip_texture: Option<TipTexture>,
+    /// The blend mode the stroke is composited with.
+    #[serde(rename = "blend_mode")]
+    pub blend_mode: BlendMode,
+    /// The opacity of the whole stroke, independent of the alpha of the color.
+    #[serde(rename = "opacity", with = "crate::serialize::f64_dp3")]
+    pub opacity: f64,
 }
 
 impl Default for TexturedOptions {
@@ -33,10 +58,16 @@ impl Default for TexturedOptions {
         Self {
             seed: None,
             stroke_width: 6.0,
-            density: 5.0,
+            density: 3.0,
             stroke_color: Some(Color::BLACK),
             distribution: TexturedDotsDistribution::default(),
             pressure_curve: PressureCurve::default(),
+            tilt_influence: 0.5,
+            dots_scale: 1.0,
+            rotation_jitter: std::f64::consts::FRAC_PI_8,
+            tip_texture: None,
+            blend_mode: BlendMode::default(),
+            opacity: 1.0,
         }
     }
 }
@@ -50,9 +81,133 @@ impl TexturedOptions {
     pub const DENSITY_MIN: f64 = 0.1;
     /// Maximum dots density.
     pub const DENSITY_MAX: f64 = 100.0;
+    /// Minimum tilt influence.
+    pub const TILT_INFLUENCE_MIN: f64 = 0.0;
+    /// Maximum tilt influence.
+    pub const TILT_INFLUENCE_MAX: f64 = 1.0;
+
+    /// Minimum rotation jitter.
+    pub const ROTATION_JITTER_MIN: f64 = 0.0;
+    /// Maximum rotation jitter.
+    pub const ROTATION_JITTER_MAX: f64 = std::f64::consts::FRAC_PI_2;
+
+    /// Minimum dots scale.
+    pub const DOTS_SCALE_MIN: f64 = 0.01;
+    /// Maximum dots scale.
+    pub const DOTS_SCALE_MAX: f64 = 100.0;
+
+    /// Convert the density of dots per 10x10 area that was used before the density per length.
+    ///
+    /// The dots were distributed over the area of the path, which is its length times the stroke width.
+    pub fn density_per_length_from_area_density(density: f64, stroke_width: f64) -> f64 {
+        (density * stroke_width * 0.1).clamp(Self::DENSITY_MIN, Self::DENSITY_MAX)
+    }
 
     /// Advances the seed.
     pub fn advance_seed(&mut self) {
         self.seed = self.seed.map(crate::utils::seed_advance)
     }
+
+    /// Scales the dots and adjusts the density, so that the texture appears scaled by the given factor.
+    ///
+    /// Expects the stroke width to be scaled separately.
+    pub fn scale_dots(&mut self, scale: f64) {
+        if scale <= 0.0 {
+            return;
+        }
+        self.dots_scale =
+            (self.dots_scale * scale).clamp(Self::DOTS_SCALE_MIN, Self::DOTS_SCALE_MAX);
+        self.density = (self.density / scale).clamp(Self::DENSITY_MIN, Self::DENSITY_MAX);
+    }
+}
+
+impl<'de> Deserialize<'de> for TexturedOptions {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(default, rename = "textured_options")]
+        struct TexturedOptionsPrecursor {
+            #[serde(rename = "seed")]
+            pub seed: Option<u64>,
+            #[serde(rename = "stroke_width", with = "crate::serialize::f64_dp3")]
+            pub stroke_width: f64,
+            #[serde(rename = "stroke_color")]
+            pub stroke_color: Option<Color>,
+            #[serde(rename = "density_per_length")]
+            pub density_per_length: Option<f64>,
+            /// Replaced by the density per length, only read for compatibility with older configs and clipboard content.
+            #[serde(rename = "density")]
+            pub density: Option<f64>,
+            #[serde(rename = "distribution")]
+            pub distribution: TexturedDotsDistribution,
+            #[serde(rename = "pressure_curve")]
+            pub pressure_curve: PressureCurve,
+            #[serde(rename = "tilt_influence", with = "crate::serialize::f64_dp3")]
+            pub tilt_influence: f64,
+            #[serde(rename = "dots_scale", with = "crate::serialize::f64_dp3")]
+            pub dots_scale: f64,
+            #[serde(rename = "rotation_jitter", with = "crate::serialize::f64_dp3")]
+            pub rotation_jitter: f64,
+            #[serde(rename = "tip_texture")]
+            pub tip_texture: Option<TipTexture>,
+            #[serde(rename = "blend_mode")]
+            pub blend_mode: BlendMode,
+            #[serde(rename = "opacity", with = "crate::serialize::f64_dp3")]
+            pub opacity: f64,
+        }
+
+        impl From<TexturedOptions> for TexturedOptionsPrecursor {
+            fn from(value: TexturedOptions) -> Self {
+                Self {
+                    seed: value.seed,
+                    stroke_width: value.stroke_width,
+                    stroke_color: value.stroke_color,
+                    density_per_length: None,
+                    density: None,
+                    distribution: value.distribution,
+                    pressure_curve: value.pressure_curve,
+                    tilt_influence: value.tilt_influence,
+                    dots_scale: value.dots_scale,
+                    rotation_jitter: value.rotation_jitter,
+                    tip_texture: value.tip_texture,
+                    blend_mode: value.blend_mode,
+                    opacity: value.opacity,
+                }
+            }
+        }
+
+        impl Default for TexturedOptionsPrecursor {
+            fn default() -> Self {
+                TexturedOptions::default().into()
+            }
+        }
+
+        let precursor = TexturedOptionsPrecursor::deserialize(deserializer)?;
+
+        let density = precursor
+            .density_per_length
+            .or_else(|| {
+                precursor.density.map(|density| {
+                    Self::density_per_length_from_area_density(density, precursor.stroke_width)
+                })
+            })
+            .unwrap_or_else(|| Self::default().density);
+
+        Ok(TexturedOptions {
+            seed: precursor.seed,
+            stroke_width: precursor.stroke_width,
+            stroke_color: precursor.stroke_color,
+            density,
+            distribution: precursor.distribution,
+            pressure_curve: precursor.pressure_curve,
+            tilt_influence: precursor.tilt_influence,
+            dots_scale: precursor.dots_scale,
+            rotation_jitter: precursor.rotation_jitter,
+            tip_texture: precursor.tip_texture,
+            blend_mode: precursor.blend_mode,
+            opacity: precursor.opacity,
+        })
+    }
 }
@@ -0,0 +1,98 @@
+// Imports
+use crate::Color;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::sync::Arc;
+
+/// A user supplied tip texture, stamped along the path instead of the procedural dots.
+///
+/// The image is embedded, so that strokes render identically when loaded again.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename = "tip_texture")]
+pub struct TipTexture {
+    /// The image data in the RGBA8 format, not premultiplied.
+    ///
+    /// Is shared between clones, because the options are cloned frequently while composing.
+    /// Is (de)serialized with base64 encoding.
+    #[serde(
+        rename = "data",
+        serialize_with = "crate::serialize::sliceu8_base64::serialize",
+        deserialize_with = "deserialize_data"
+    )]
+    data: Arc<[u8]>,
+    #[serde(rename = "pixel_width")]
+    pixel_width: u32,
+    #[serde(rename = "pixel_height")]
+    pixel_height: u32,
+}
+
+impl std::fmt::Debug for TipTexture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TipTexture")
+            .field("data", &"{.. no debug impl ..}")
+            .field("pixel_width", &self.pixel_width)
+            .field("pixel_height", &self.pixel_height)
+            .finish()
+    }
+}
+
+impl TipTexture {
+    /// The spacing between the stamps, relative to the stroke width.
+    pub(super) const STAMP_SPACING: f64 = 0.25;
+
+    /// A new tip texture from RGBA8 data. Returns an error if the data does not match the size.
+    pub fn try_from_rgba8(
+        data: Vec<u8>,
+        pixel_width: u32,
+        pixel_height: u32,
+    ) -> anyhow::Result<Self> {
+        if pixel_width == 0
+            || pixel_height == 0
+            || data.len() as u64 != 4 * u64::from(pixel_width) * u64::from(pixel_height)
+        {
+            return Err(anyhow::anyhow!(
+                "Creating tip texture failed, invalid size or data."
+            ));
+        }
+
+        Ok(Self {
+            data: data.into(),
+            pixel_width,
+            pixel_height,
+        })
+    }
+
+    /// The image width.
+    pub fn pixel_width(&self) -> u32 {
+        self.pixel_width
+    }
+
+    /// The image height.
+    pub fn pixel_height(&self) -> u32 {
+        self.pixel_height
+    }
+
+    /// The ratio of the height to the width.
+    pub fn aspect_ratio(&self) -> f64 {
+        f64::from(self.pixel_height) / f64::from(self.pixel_width)
+    }
+
+    /// The image data tinted by the color, in the RGBA8 format, not premultiplied.
+    ///
+    /// The color channels of the image are multiplied with the color, so that white textures take on the color.
+    pub(super) fn tinted_data(&self, color: Color) -> Vec<u8> {
+        let factors = [color.r, color.g, color.b, color.a];
+
+        self.data
+            .chunks_exact(4)
+            .flat_map(|px| {
+                std::array::from_fn::<u8, 4, _>(|i| {
+                    (f64::from(px[i]) * factors[i].clamp(0.0, 1.0)).round() as u8
+                })
+            })
+            .collect()
+    }
+}
+
+fn deserialize_data<'de, D: Deserializer<'de>>(d: D) -> Result<Arc<[u8]>, D::Error> {
+    crate::serialize::sliceu8_base64::deserialize(d).map(Arc::from)
+}
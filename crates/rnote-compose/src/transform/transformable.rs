@@ -6,4 +6,34 @@ pub trait Transformable {
     fn rotate(&mut self, angle: f64, center: na::Point2<f64>);
     /// Scale by the given scale-factor.
     fn scale(&mut self, scale: na::Vector2<f64>);
+
+    /// Apply the affine transformation.
+    ///
+    /// The linear part is decomposed into a rotation, a (possibly mirroring) scale and another rotation,
+    /// so that any affine transformation including shearing can be expressed through the other methods.
+    fn apply_affine(&mut self, affine: &na::Affine2<f64>) {
+        let matrix = affine.matrix();
+        let linear = matrix.fixed_view::<2, 2>(0, 0).into_owned();
+        let translation = na::vector![matrix[(0, 2)], matrix[(1, 2)]];
+        let svd = linear.svd(true, true);
+        let (Some(mut u), Some(mut v_t)) = (svd.u, svd.v_t) else {
+            return;
+        };
+        let mut scale = svd.singular_values;
+
+        // Moves reflections of the orthogonal matrices into the scale, leaving pure rotations
+        if u.determinant() < 0.0 {
+            u.column_mut(1).neg_mut();
+            v_t.row_mut(1).neg_mut();
+        }
+        if v_t.determinant() < 0.0 {
+            v_t.row_mut(1).neg_mut();
+            scale[1] = -scale[1];
+        }
+
+        self.rotate(v_t[(1, 0)].atan2(v_t[(0, 0)]), na::Point2::origin());
+        self.scale(scale);
+        self.rotate(u[(1, 0)].atan2(u[(0, 0)]), na::Point2::origin());
+        self.translate(translation);
+    }
 }
@@ -118,3 +118,36 @@ pub fn seed_advance(seed: u64) -> u64 {
     let mut rng = rand_pcg::Pcg64::seed_from_u64(seed);
     rng.random()
 }
+
+/// The snapped coordinate of a horizontal or vertical line through the points, so that a line with the given width
+/// has its edges on the pixel grid at the image scale and is not blurred by anti-aliasing.
+///
+/// The points are considered axis-aligned when they deviate no more than the tolerance from a horizontal or vertical
+/// line through their center. Returns the index of the snapped coordinate (1 for horizontal, 0 for vertical lines)
+/// and its new value, or None when the points are not axis-aligned.
+pub fn pixel_snap_axis_aligned(
+    points: impl IntoIterator<Item = na::Vector2<f64>>,
+    width: f64,
+    image_scale: f64,
+    tolerance: f64,
+) -> Option<(usize, f64)> {
+    let (mins, maxs) = points.into_iter().fold(
+        (na::Vector2::repeat(f64::MAX), na::Vector2::repeat(f64::MIN)),
+        |(mins, maxs), point| (mins.inf(&point), maxs.sup(&point)),
+    );
+    let extents = maxs - mins;
+    let axis = if extents[1] <= tolerance && extents[0] > tolerance {
+        1
+    } else if extents[0] <= tolerance && extents[1] > tolerance {
+        0
+    } else {
+        return None;
+    };
+
+    let half_width_px = (width * image_scale).round().max(1.0) * 0.5;
+    let center_px = (mins[axis] + maxs[axis]) * 0.5 * image_scale;
+    Some((
+        axis,
+        ((center_px - half_width_px).round() + half_width_px) / image_scale,
+    ))
+}
@@ -0,0 +1,50 @@
+// Imports
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use rnote_compose::Style;
+use rnote_compose::penpath::{Element, Segment};
+use rnote_compose::shapes::Shapeable;
+use rnote_engine::strokes::{BrushStroke, Content};
+use std::hint::black_box;
+
+const N_SEGMENTS: usize = 50_000;
+
+fn element(i: usize) -> Element {
+    let t = i as f64 * 0.01;
+    Element::new(nalgebra::vector![t * 10.0, t.sin() * 100.0], 0.5)
+}
+
+/// A brush stroke built up segment by segment, like while drawing.
+fn drawn_brushstroke() -> BrushStroke {
+    let mut brushstroke = BrushStroke::new(element(0), Style::default());
+    for i in 1..=N_SEGMENTS {
+        brushstroke.push_segment(Segment::LineTo { end: element(i) });
+    }
+    brushstroke
+}
+
+fn finish_stroke(c: &mut Criterion) {
+    let mut group = c.benchmark_group("finish_brushstroke_50k_segments");
+
+    group.bench_function("full_geometry_update", |b| {
+        b.iter_batched(
+            drawn_brushstroke,
+            |mut brushstroke| {
+                brushstroke.update_geometry();
+                black_box(brushstroke.bounds())
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("incrementally_grown_bounds", |b| {
+        b.iter_batched(
+            drawn_brushstroke,
+            |brushstroke| black_box(brushstroke.bounds()),
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, finish_stroke);
+criterion_main!(benches);
@@ -0,0 +1,84 @@
+// Imports
+use criterion::{Criterion, criterion_group, criterion_main};
+use parry2d_f64::bounding_volume::Aabb;
+use rnote_compose::Style;
+use rnote_compose::penpath::{Element, Segment};
+use rnote_engine::Engine;
+use rnote_engine::engine::EngineSnapshot;
+use rnote_engine::store::{RenderCacheLimits, StrokeKey};
+use rnote_engine::strokes::{BrushStroke, Stroke};
+use slotmap::SlotMap;
+use std::sync::Arc;
+
+const N_STROKES: usize = 500;
+/// The strokes are laid out in a grid with this many columns.
+const N_COLUMNS: usize = 25;
+/// Zooming in and out again by a few steps, as when zooming with the mouse wheel.
+const ZOOM_STEPS: [f64; 6] = [1.0, 1.1, 1.21, 1.331, 1.21, 1.1];
+
+/// A brush stroke in the grid cell of the given index.
+fn brushstroke(i: usize) -> Stroke {
+    let origin = nalgebra::vector![(i % N_COLUMNS) as f64, (i / N_COLUMNS) as f64] * 50.0;
+    let mut brushstroke = BrushStroke::new(Element::new(origin, 0.5), Style::default());
+    for j in 1..=40 {
+        let t = j as f64;
+        brushstroke.push_segment(Segment::LineTo {
+            end: Element::new(origin + nalgebra::vector![t, (t * 0.2).sin() * 15.0], 0.5),
+        });
+    }
+    Stroke::BrushStroke(brushstroke)
+}
+
+fn engine_w_strokes() -> (Engine, Vec<StrokeKey>) {
+    let mut stroke_components = SlotMap::with_key();
+    for i in 0..N_STROKES {
+        stroke_components.insert(Arc::new(brushstroke(i)));
+    }
+    let keys = stroke_components.keys().collect::<Vec<StrokeKey>>();
+    let mut engine = Engine::default();
+    let _ = engine.load_snapshot(EngineSnapshot {
+        stroke_components: Arc::new(stroke_components),
+        ..Default::default()
+    });
+    (engine, keys)
+}
+
+fn zoom_steps(c: &mut Criterion) {
+    let viewport = Aabb::new(
+        nalgebra::point![-100.0, -100.0],
+        nalgebra::point![1500.0, 1500.0],
+    );
+    let mut group = c.benchmark_group("regenerate_rendering_zoom_steps_500_strokes");
+    group.sample_size(10);
+
+    let (mut engine, keys) = engine_w_strokes();
+    group.bench_function("render_cache", |b| {
+        b.iter(|| {
+            for image_scale in ZOOM_STEPS {
+                engine
+                    .store
+                    .regenerate_rendering_for_strokes(&keys, viewport, image_scale);
+            }
+        })
+    });
+
+    let (mut engine, keys) = engine_w_strokes();
+    engine.set_render_cache_limits(RenderCacheLimits {
+        max_entries: 0,
+        ..Default::default()
+    });
+    group.bench_function("no_render_cache", |b| {
+        b.iter(|| {
+            for image_scale in ZOOM_STEPS {
+                engine
+                    .store
+                    .regenerate_rendering_for_strokes(&keys, viewport, image_scale);
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, zoom_steps);
+criterion_main!(benches);
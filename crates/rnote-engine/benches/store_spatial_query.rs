@@ -0,0 +1,77 @@
+// Imports
+use criterion::{Criterion, criterion_group, criterion_main};
+use parry2d_f64::bounding_volume::{Aabb, BoundingVolume};
+use rnote_compose::Style;
+use rnote_compose::penpath::{Element, Segment};
+use rnote_compose::shapes::Shapeable;
+use rnote_engine::Engine;
+use rnote_engine::engine::EngineSnapshot;
+use rnote_engine::strokes::{BrushStroke, Stroke};
+use slotmap::SlotMap;
+use std::hint::black_box;
+use std::sync::Arc;
+
+const N_STROKES: usize = 10_000;
+/// The strokes are laid out in a grid with this many columns.
+const N_COLUMNS: usize = 100;
+
+/// A short brush stroke in the grid cell of the given index.
+fn brushstroke(i: usize) -> Stroke {
+    let origin = nalgebra::vector![(i % N_COLUMNS) as f64, (i / N_COLUMNS) as f64] * 50.0;
+    let mut brushstroke = BrushStroke::new(Element::new(origin, 0.5), Style::default());
+    for j in 1..=10 {
+        let t = j as f64;
+        brushstroke.push_segment(Segment::LineTo {
+            end: Element::new(
+                origin + nalgebra::vector![t * 3.0, (t * 0.5).sin() * 10.0],
+                0.5,
+            ),
+        });
+    }
+    Stroke::BrushStroke(brushstroke)
+}
+
+fn query_strokes(c: &mut Criterion) {
+    let mut stroke_components = SlotMap::with_key();
+    for i in 0..N_STROKES {
+        stroke_components.insert(Arc::new(brushstroke(i)));
+    }
+    // The linear scan baseline checks the bounds of every stroke
+    let stroke_bounds = stroke_components
+        .values()
+        .map(|stroke| stroke.bounds())
+        .collect::<Vec<Aabb>>();
+    let mut engine = Engine::default();
+    let _ = engine.load_snapshot(EngineSnapshot {
+        stroke_components: Arc::new(stroke_components),
+        ..Default::default()
+    });
+    let bounds = Aabb::new(
+        nalgebra::point![600.0, 600.0],
+        nalgebra::point![900.0, 900.0],
+    );
+
+    let mut group = c.benchmark_group("query_strokes_in_bounds_10k_strokes");
+
+    group.bench_function("spatial_index", |b| {
+        b.iter(|| black_box(engine.store.strokes_in_bounds(black_box(bounds), false)))
+    });
+    group.bench_function("spatial_index_exact", |b| {
+        b.iter(|| black_box(engine.store.strokes_in_bounds(black_box(bounds), true)))
+    });
+    group.bench_function("linear_scan", |b| {
+        b.iter(|| {
+            black_box(
+                stroke_bounds
+                    .iter()
+                    .filter(|stroke_bounds| stroke_bounds.intersects(black_box(&bounds)))
+                    .count(),
+            )
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, query_strokes);
+criterion_main!(benches);
@@ -7,6 +7,7 @@ use rodio::{Decoder, Source};
 use std::collections::HashMap;
 use std::fs::File;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::error;
 
@@ -18,6 +19,11 @@ pub struct AudioPlayer {
 
     sounds: HashMap<String, Buffered<Decoder<File>>>,
     brush_sound_task_handle: Option<crate::tasks::OneOffTaskHandle>,
+    /// The player of the currently looping brush sound.
+    brush_player: Option<Arc<rodio::Player>>,
+    /// The volume in the range [0.0, 1.0].
+    volume: f64,
+    enabled: bool,
 }
 
 impl std::fmt::Debug for AudioPlayer {
@@ -28,6 +34,9 @@ impl std::fmt::Debug for AudioPlayer {
             .field("typewriter_outputstream", &"{.. no debug impl ..}")
             .field("sounds", &"{.. no debug impl ..}")
             .field("brush_sound_task_handle", &self.brush_sound_task_handle)
+            .field("brush_player", &"{.. no debug impl ..}")
+            .field("volume", &self.volume)
+            .field("enabled", &self.enabled)
             .finish()
     }
 }
@@ -37,6 +46,8 @@ impl AudioPlayer {
     pub const N_SOUND_FILES_MARKER: usize = 15;
     pub const N_SOUND_FILES_TYPEWRITER: usize = 30;
     pub const SOUND_FILE_BRUSH_SEEK_TIMES_MS: [f64; 5] = [0., 910., 4129., 6000., 8560.];
+    pub const VOLUME_MIN: f64 = 0.0;
+    pub const VOLUME_MAX: f64 = 1.0;
 
     /// Create and initialize new audioplayer.
     /// `pkg_data_dir` is the app data directory which has a "sounds" subfolder containing the sound files
@@ -104,19 +115,63 @@ impl AudioPlayer {
 
             sounds,
             brush_sound_task_handle: None,
+            brush_player: None,
+            volume: Self::VOLUME_MAX,
+            enabled: true,
         })
     }
 
+    /// The volume of the sounds.
+    pub fn volume(&self) -> f64 {
+        self.volume
+    }
+
+    /// Sets the volume of the sounds, clamped to the range [0.0, 1.0].
+    ///
+    /// Takes effect on the next played sound. A currently looping brush sound is not restarted,
+    /// but continues with the new volume once it is triggered again.
+    pub fn set_volume(&mut self, volume: f64) {
+        self.volume = volume.clamp(Self::VOLUME_MIN, Self::VOLUME_MAX);
+    }
+
+    /// Whether sounds are played.
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enables or disables playing sounds. Disabling stops a currently looping brush sound.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+
+        if !enabled {
+            self.stop_brush_sound();
+        }
+    }
+
+    /// Stops a currently looping brush sound.
+    pub fn stop_brush_sound(&mut self) {
+        if let Some(brush_player) = self.brush_player.take() {
+            brush_player.stop();
+        }
+    }
+
     pub fn play_random_marker_sound(&self) {
+        if !self.enabled {
+            return;
+        }
         let mut rng = rand::rng();
         let marker_sound_index = rng.random_range(0..Self::N_SOUND_FILES_MARKER);
 
         let sink = rodio::Player::connect_new(self.marker_sink.mixer());
+        sink.set_volume(self.volume as f32);
         sink.append(self.sounds[&format!("marker_{marker_sound_index:02}")].clone());
         sink.detach();
     }
 
     pub fn trigger_random_brush_sound(&mut self) {
+        if !self.enabled {
+            return;
+        }
         let mut rng = rand::rng();
         let brush_sound_seek_time_index =
             rng.random_range(0..Self::SOUND_FILE_BRUSH_SEEK_TIMES_MS.len());
@@ -128,6 +183,8 @@ impl AudioPlayer {
                 if let Err(e) = handle.reset_timeout() {
                     error!("Resetting timeout on brush sound stop task failed, Err: {e:?}");
                     reinstall_task = true;
+                } else if let Some(brush_player) = self.brush_player.as_ref() {
+                    brush_player.set_volume(self.volume as f32);
                 }
             } else {
                 reinstall_task = true;
@@ -137,7 +194,8 @@ impl AudioPlayer {
         }
 
         if reinstall_task {
-            let sink = rodio::Player::connect_new(self.brush_sink.mixer());
+            let sink = Arc::new(rodio::Player::connect_new(self.brush_sink.mixer()));
+            sink.set_volume(self.volume as f32);
 
             sink.append(
                 self.sounds["brush"]
@@ -149,6 +207,7 @@ impl AudioPlayer {
                     )),
             );
 
+            self.brush_player = Some(Arc::clone(&sink));
             self.brush_sound_task_handle = Some(crate::tasks::OneOffTaskHandle::new(
                 move || {
                     sink.stop();
@@ -160,7 +219,11 @@ impl AudioPlayer {
 
     /// Play a typewriter sound that fits the given key type, or a generic sound when None.
     pub fn play_typewriter_key_sound(&self, keyboard_key: Option<KeyboardKey>) {
+        if !self.enabled {
+            return;
+        }
         let sink = rodio::Player::connect_new(self.typewriter_sink.mixer());
+        sink.set_volume(self.volume as f32);
 
         match keyboard_key {
             Some(KeyboardKey::CarriageReturn) | Some(KeyboardKey::Linefeed) => {
@@ -11,4 +11,7 @@ pub struct DocumentConfig {
     pub background: Background,
     #[serde(rename = "layout", alias = "expand_mode")]
     pub layout: Layout,
+    /// The tolerance for simplifying brush strokes when they are finished. Disabled when None.
+    #[serde(rename = "simplification_tolerance")]
+    pub simplification_tolerance: Option<f64>,
 }
@@ -337,6 +337,22 @@ impl Document {
         )
     }
 
+    /// Snap the position to the nearest intersection of the background pattern grid, regardless of `snap_positions`.
+    ///
+    /// Patterns without intersections are snapped to a grid with the pattern size.
+    pub(crate) fn snap_position_to_pattern_grid(&self, pos: na::Vector2<f64>) -> na::Vector2<f64> {
+        let pattern_size = self.config.background.pattern_size;
+
+        match self.config.background.pattern {
+            PatternStyle::IsometricGrid | PatternStyle::IsometricDots => {
+                snap_to_isometric_pattern(pos, pattern_size[1])
+            }
+            PatternStyle::None | PatternStyle::Lines | PatternStyle::Grid | PatternStyle::Dots => {
+                snap_to_grid(pos, pattern_size)
+            }
+        }
+    }
+
     /// Snap the position to the document and pattern grid when `snap_positions` is enabled.
     ///
     /// If not, the original coordinates are returned.
@@ -0,0 +1,138 @@
+// Imports
+use super::Engine;
+use crate::WidgetFlags;
+use crate::fileformats::FileFormatSaver;
+use crate::fileformats::rnoteformat::RnoteFile;
+use futures::channel::oneshot;
+use rnote_compose::penevent::PenProgress;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tracing::error;
+
+/// Crash recovery autosave preferences.
+///
+/// Independent of saving to the opened file, the document is saved to a file of its own in the target directory
+/// after it was modified and then left idle for the interval.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "crash_autosave_prefs")]
+pub struct CrashAutosavePrefs {
+    #[serde(rename = "enabled")]
+    pub enabled: bool,
+    /// The idle interval in seconds after the last modification, before the document is saved.
+    #[serde(rename = "idle_interval_secs")]
+    pub idle_interval_secs: u32,
+    /// The directory the documents are saved to. A directory in the user data directory when None.
+    #[serde(rename = "target_dir")]
+    pub target_dir: Option<PathBuf>,
+}
+
+impl Default for CrashAutosavePrefs {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_interval_secs: 10,
+            target_dir: None,
+        }
+    }
+}
+
+impl CrashAutosavePrefs {
+    pub const IDLE_INTERVAL_SECS_MIN: u32 = 1;
+    pub const IDLE_INTERVAL_SECS_MAX: u32 = 600;
+    const TARGET_DIR_DEFAULT: [&'static str; 2] = ["rnote", "crash-autosave"];
+
+    /// The directory the documents are saved to.
+    pub fn target_dir(&self) -> PathBuf {
+        self.target_dir.clone().unwrap_or_else(|| {
+            Self::TARGET_DIR_DEFAULT
+                .iter()
+                .fold(glib::user_data_dir(), |dir, name| dir.join(name))
+        })
+    }
+
+    /// The file the document with the given autosave id is saved to.
+    pub fn target_path(&self, autosave_id: u64) -> PathBuf {
+        self.target_dir()
+            .join(format!("crash-autosave-{autosave_id:016x}.rnote"))
+    }
+}
+
+impl Engine {
+    /// Save the document for crash recovery, when it was modified and then left idle for the configured interval.
+    ///
+    /// Expects the widget flags of every change to the engine, and must additionally be called periodically
+    /// (e.g. with default widget flags) so that an idle document gets saved.
+    /// Does not save while a pen is in progress, but keeps the modification pending.
+    ///
+    /// Returns a receiver for the result of the save with the target path when saving was started.
+    pub fn maybe_autosave(
+        &mut self,
+        widget_flags: &WidgetFlags,
+    ) -> Option<oneshot::Receiver<anyhow::Result<PathBuf>>> {
+        let now = Instant::now();
+        if widget_flags.store_modified {
+            self.autosave_last_modification = Some(now);
+        }
+        let prefs = self.config.read().crash_autosave_prefs.clone();
+        if !prefs.enabled {
+            return None;
+        }
+        let last_modification = self.autosave_last_modification?;
+        let idle_interval = Duration::from_secs(u64::from(prefs.idle_interval_secs.clamp(
+            CrashAutosavePrefs::IDLE_INTERVAL_SECS_MIN,
+            CrashAutosavePrefs::IDLE_INTERVAL_SECS_MAX,
+        )));
+        if now.duration_since(last_modification) < idle_interval
+            || self.penholder.current_pen_progress() == PenProgress::InProgress
+        {
+            return None;
+        }
+        self.autosave_last_modification = None;
+
+        let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<PathBuf>>();
+        let target_path = prefs.target_path(self.autosave_id);
+        let engine_snapshot = self.take_snapshot();
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<PathBuf> {
+                let file_name = target_path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let rnote_file = RnoteFile {
+                    engine_snapshot: ijson::to_value(&engine_snapshot)?,
+                };
+                let bytes = rnote_file.save_as_bytes(&file_name)?;
+
+                if let Some(target_dir) = target_path.parent() {
+                    std::fs::create_dir_all(target_dir)?;
+                }
+                // Written to a temporary file first, so that crashing while writing never leaves a truncated autosave.
+                // A leftover from such a crash is removed, and the file is created exclusively to never write
+                // through a file or symlink that was put in its place.
+                let mut tmp_path = target_path.clone().into_os_string();
+                tmp_path.push(".tmp");
+                match std::fs::remove_file(&tmp_path) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => {}
+                }
+                let mut tmp_file = std::fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&tmp_path)?;
+                tmp_file.write_all(&bytes)?;
+                tmp_file.sync_all()?;
+                drop(tmp_file);
+                std::fs::rename(&tmp_path, &target_path)?;
+                Ok(target_path)
+            };
+            if oneshot_sender.send(result()).is_err() {
+                error!(
+                    "Sending result to receiver failed while autosaving document. Receiver already dropped."
+                );
+            }
+        });
+        Some(oneshot_receiver)
+    }
+}
@@ -1,6 +1,7 @@
 // Imports
-use super::{ExportPrefs, ImportPrefs};
+use super::{CrashAutosavePrefs, ExportPrefs, ImportPrefs};
 use crate::pens::PensConfig;
+use rand::{RngExt, SeedableRng};
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
@@ -20,8 +21,48 @@ pub struct EngineConfig {
     pub optimize_epd: bool,
     #[serde(rename = "snap_positions")]
     pub snap_positions: bool,
+    /// The radius (in document coordinates) in which the start and end of new brush strokes snap
+    /// to the endpoints of existing brush strokes. Disabled when None.
+    #[serde(rename = "endpoint_snap_radius")]
+    pub endpoint_snap_radius: Option<f64>,
+    /// Derive the style seeds of new strokes from a counter instead of from entropy, making them reproducible.
+    #[serde(rename = "deterministic_seeding")]
+    pub deterministic_seeding: bool,
+    /// The counter the seeds are derived from when deterministic seeding is enabled.
+    #[serde(rename = "seed_counter")]
+    pub seed_counter: u64,
+    #[serde(rename = "crash_autosave_prefs")]
+    pub crash_autosave_prefs: CrashAutosavePrefs,
     #[serde(skip)]
     pub visual_debug: bool,
+    /// Overlay the elements of brush strokes as dots scaled and colored by their pressure, for debugging input.
+    #[serde(skip)]
+    pub pressure_debug: bool,
+}
+
+impl EngineConfig {
+    /// The seed of the master random number generator used for deterministic seeding.
+    pub const DETERMINISTIC_MASTER_SEED: u64 = 0x005e_ed0f_7e47_5eed;
+
+    /// A new seed for the styles of new strokes.
+    ///
+    /// When deterministic seeding is enabled the seed is the output of the master random number generator
+    /// at the position of the seed counter, which is then incremented.
+    pub(crate) fn new_style_seed(&mut self) -> u64 {
+        if self.deterministic_seeding {
+            let mut rng = rand_pcg::Pcg64::seed_from_u64(Self::DETERMINISTIC_MASTER_SEED);
+            rng.advance(u128::from(self.seed_counter));
+            self.seed_counter = self.seed_counter.wrapping_add(1);
+            rng.random()
+        } else {
+            rand_pcg::Pcg64::from_rng(&mut rand::rng()).random()
+        }
+    }
+
+    /// Resets the counter used for deterministic seeding.
+    pub fn reset_seed_counter(&mut self) {
+        self.seed_counter = 0;
+    }
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -56,6 +97,11 @@ impl EngineConfigShared {
         write.pen_sounds = config.pen_sounds;
         write.optimize_epd = config.optimize_epd;
         write.snap_positions = config.snap_positions;
+        write.endpoint_snap_radius = config.endpoint_snap_radius;
+        write.deterministic_seeding = config.deterministic_seeding;
+        write.seed_counter = config.seed_counter;
+        write.crash_autosave_prefs = config.crash_autosave_prefs;
         write.visual_debug = config.visual_debug;
+        write.pressure_debug = config.pressure_debug;
     }
 }
@@ -1,11 +1,16 @@
 // Imports
-use super::{Engine, StrokeContent};
+use super::{Engine, EngineSnapshot, StrokeContent};
+use crate::Camera;
 use crate::fileformats::rnoteformat::RnoteFile;
 use crate::fileformats::{FileFormatSaver, xoppformat};
+use crate::store::StrokeKey;
+use crate::store::metadata_comp::PDF_TEXT_LAYER_TAG;
 use anyhow::Context;
 use futures::channel::oneshot;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rayon::prelude::*;
 use rnote_compose::SplitOrder;
+use rnote_compose::ext::AabbExt;
 use rnote_compose::transform::Transformable;
 use serde::{Deserialize, Serialize};
 use std::io::Cursor;
@@ -86,6 +91,9 @@ pub struct DocExportPrefs {
     /// The page order when documents with layouts that expand in horizontal and vertical directions are cut into pages.
     #[serde(rename = "page_order")]
     pub page_order: SplitOrder,
+    /// Whether the invisible text layers of imported Pdf's should be exported, keeping their text searchable.
+    #[serde(rename = "with_pdf_text_layer")]
+    pub with_pdf_text_layer: bool,
 }
 
 impl Default for DocExportPrefs {
@@ -96,6 +104,7 @@ impl Default for DocExportPrefs {
             optimize_printing: false,
             export_format: DocExportFormat::default(),
             page_order: SplitOrder::default(),
+            with_pdf_text_layer: true,
         }
     }
 }
@@ -297,6 +306,95 @@ impl Default for SelectionExportPrefs {
     }
 }
 
+/// The area of the document that is rendered headless.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum HeadlessRenderArea {
+    /// The entire document.
+    #[default]
+    Document,
+    /// The bounds of the strokes only.
+    Content,
+}
+
+/// Preferences for rendering a document headless, without any surface, e.g. for thumbnails.
+#[derive(Debug, Clone, Copy)]
+pub struct HeadlessRenderPrefs {
+    /// The rendered area.
+    pub area: HeadlessRenderArea,
+    /// The dpi the document is rendered with.
+    pub dpi: f64,
+    /// The maximum width and height of the image in pixels. Lowers the dpi if the image would otherwise be larger.
+    pub max_size: Option<u32>,
+    /// Whether the background should be rendered.
+    pub with_background: bool,
+    /// Whether the background pattern should be rendered.
+    pub with_pattern: bool,
+    /// The margin around the rendered area.
+    pub margin: f64,
+}
+
+impl Default for HeadlessRenderPrefs {
+    fn default() -> Self {
+        Self {
+            area: HeadlessRenderArea::default(),
+            dpi: 96.0,
+            max_size: None,
+            with_background: true,
+            with_pattern: true,
+            margin: 0.0,
+        }
+    }
+}
+
+/// Preferences for exporting the region currently visible in the viewport.
+#[derive(Debug, Clone, Copy)]
+pub struct ViewportExportPrefs {
+    /// The dpi the viewport is rendered with.
+    pub dpi: f64,
+    /// Whether the background should be rendered.
+    pub with_background: bool,
+    /// Whether the background pattern should be rendered.
+    pub with_pattern: bool,
+}
+
+impl Default for ViewportExportPrefs {
+    fn default() -> Self {
+        Self {
+            dpi: 96.0,
+            with_background: true,
+            with_pattern: true,
+        }
+    }
+}
+
+/// Preferences for exporting the content split into pages of a fixed size as Pdf, independent of the document format.
+#[derive(Debug, Clone, Copy)]
+pub struct PagedPdfExportPrefs {
+    /// The size of the pages, in document coordinates.
+    pub page_size: na::Vector2<f64>,
+    /// The order the pages of the content are exported in.
+    pub page_order: SplitOrder,
+    /// Whether the background should be exported.
+    pub with_background: bool,
+    /// Whether the background pattern should be exported.
+    pub with_pattern: bool,
+    /// Whether the exported content should be optimized for printing.
+    pub optimize_printing: bool,
+}
+
+impl Default for PagedPdfExportPrefs {
+    fn default() -> Self {
+        Self {
+            page_size: crate::document::Format::default().size(),
+            page_order: SplitOrder::default(),
+            with_background: true,
+            with_pattern: true,
+            optimize_printing: false,
+        }
+    }
+}
+
 /// Export preferences.
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 #[serde(default, rename = "export_prefs")]
@@ -340,10 +438,14 @@ impl Engine {
     }
 
     pub fn extract_document_content(&self) -> StrokeContent {
+        self.extract_document_content_int(true)
+    }
+
+    fn extract_document_content_int(&self, with_pdf_text_layer: bool) -> StrokeContent {
         StrokeContent::default()
-            .with_strokes(
-                self.store
-                    .get_strokes_arc(&self.store.stroke_keys_as_rendered()),
+            .with_strokes_from_store(
+                &self.store,
+                &self.content_keys(self.store.stroke_keys_as_rendered(), with_pdf_text_layer),
             )
             .with_bounds(
                 self.bounds_w_content_extended()
@@ -353,15 +455,24 @@ impl Engine {
     }
 
     pub fn extract_pages_content(&self, page_order: SplitOrder) -> Vec<StrokeContent> {
+        self.extract_pages_content_int(page_order, true)
+    }
+
+    fn extract_pages_content_int(
+        &self,
+        page_order: SplitOrder,
+        with_pdf_text_layer: bool,
+    ) -> Vec<StrokeContent> {
         self.pages_bounds_w_content(page_order)
             .into_iter()
             .map(|bounds| {
                 StrokeContent::default()
-                    .with_strokes(
-                        self.store.get_strokes_arc(
-                            &self
-                                .store
+                    .with_strokes_from_store(
+                        &self.store,
+                        &self.content_keys(
+                            self.store
                                 .stroke_keys_as_rendered_intersecting_bounds(bounds),
+                            with_pdf_text_layer,
                         ),
                     )
                     .with_bounds(bounds)
@@ -370,6 +481,21 @@ impl Engine {
             .collect()
     }
 
+    /// Filters out the strokes of the text layers of imported Pdf's, if they should not be included.
+    fn content_keys(&self, keys: Vec<StrokeKey>, with_pdf_text_layer: bool) -> Vec<StrokeKey> {
+        if with_pdf_text_layer {
+            return keys;
+        }
+        keys.into_iter()
+            .filter(|&key| {
+                !self
+                    .store
+                    .get_stroke_tags(key)
+                    .is_some_and(|tags| tags.contains(PDF_TEXT_LAYER_TAG))
+            })
+            .collect()
+    }
+
     pub fn extract_selection_content(&self) -> Option<StrokeContent> {
         let selection_keys = self.store.selection_keys_as_rendered();
         if selection_keys.is_empty() {
@@ -377,7 +503,7 @@ impl Engine {
         }
         Some(
             StrokeContent::default()
-                .with_strokes(self.store.get_strokes_arc(&selection_keys))
+                .with_strokes_from_store(&self.store, &selection_keys)
                 .with_background(self.document.config.background),
         )
     }
@@ -391,7 +517,7 @@ impl Engine {
         let (keys, bounds) = self.store.thumbnail_keys_as_rendered(size * scale_factor);
         let bounds = bounds.unwrap_or_else(|| self.document.bounds());
         StrokeContent::default()
-            .with_strokes(self.store.get_strokes_arc(&keys))
+            .with_strokes_from_store(&self.store, &keys)
             .with_bounds(bounds)
             .with_background(self.document.config.background)
     }
@@ -429,7 +555,7 @@ impl Engine {
         let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
         let doc_export_prefs =
             doc_export_prefs_override.unwrap_or(self.config.read().export_prefs.doc_export_prefs);
-        let doc_content = self.extract_document_content();
+        let doc_content = self.extract_document_content_int(doc_export_prefs.with_pdf_text_layer);
 
         rayon::spawn(move || {
             let result = || -> anyhow::Result<Vec<u8>> {
@@ -472,7 +598,10 @@ impl Engine {
         let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
         let doc_export_prefs =
             doc_export_prefs_override.unwrap_or(self.config.read().export_prefs.doc_export_prefs);
-        let pages_content = self.extract_pages_content(doc_export_prefs.page_order);
+        let pages_content = self.extract_pages_content_int(
+            doc_export_prefs.page_order,
+            doc_export_prefs.with_pdf_text_layer,
+        );
         let format_size = self.document.config.format.size();
 
         rayon::spawn(move || {
@@ -548,7 +677,10 @@ impl Engine {
         let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
         let doc_export_prefs =
             doc_export_prefs_override.unwrap_or(self.config.read().export_prefs.doc_export_prefs);
-        let pages_content = self.extract_pages_content(doc_export_prefs.page_order);
+        let pages_content = self.extract_pages_content_int(
+            doc_export_prefs.page_order,
+            doc_export_prefs.with_pdf_text_layer,
+        );
         let document = self.document.clone();
 
         rayon::spawn(move || {
@@ -916,6 +1048,23 @@ impl Engine {
         oneshot_receiver
     }
 
+    /// Render the selection to a bitmap, e.g. for putting it on the clipboard for applications that only accept images.
+    ///
+    /// The image is cropped to the selection and has a transparent background.
+    /// Returns `Ok(None)` when nothing is selected.
+    pub fn render_selection_to_image(
+        &self,
+        image_scale: f64,
+    ) -> anyhow::Result<Option<image::RgbaImage>> {
+        let selection_keys = self.store.selection_keys_as_rendered();
+        if selection_keys.is_empty() {
+            return Ok(None);
+        }
+        self.store
+            .render_selection_to_image(&selection_keys, image_scale)
+            .map(Some)
+    }
+
     /// Generate a thumbnail PNG.
     ///
     /// # Arguments
@@ -985,4 +1134,326 @@ impl Engine {
 
         oneshot_receiver
     }
+
+    /// Render the document headless to an image.
+    ///
+    /// The strokes are drawn onto an offscreen cairo surface, so no surface or UI is required.
+    /// Returns an error for an area without any content.
+    pub fn render_headless(
+        &self,
+        prefs: HeadlessRenderPrefs,
+    ) -> oneshot::Receiver<anyhow::Result<image::RgbaImage>> {
+        let (oneshot_sender, oneshot_receiver) =
+            oneshot::channel::<anyhow::Result<image::RgbaImage>>();
+        let content = self.extract_headless_render_content(prefs.area);
+        let document_dpi = self.document.config.format.dpi();
+
+        rayon::spawn(move || {
+            let result = render_content_headless(&content, document_dpi, prefs);
+            if oneshot_sender.send(result).is_err() {
+                error!(
+                    "Sending result to receiver failed while rendering headless. Receiver already dropped."
+                );
+            }
+        });
+
+        oneshot_receiver
+    }
+
+    /// Export the document headless as Png bytes, ready to be written to a file.
+    pub fn export_doc_as_png_bytes(
+        &self,
+        prefs: HeadlessRenderPrefs,
+    ) -> oneshot::Receiver<anyhow::Result<Vec<u8>>> {
+        let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
+        let content = self.extract_headless_render_content(prefs.area);
+        let document_dpi = self.document.config.format.dpi();
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<Vec<u8>> {
+                let image = render_content_headless(&content, document_dpi, prefs)?;
+                let mut bytes: Cursor<Vec<u8>> = Cursor::new(Vec::new());
+                image.write_to(&mut bytes, image::ImageFormat::Png)?;
+                Ok(bytes.into_inner())
+            };
+            if oneshot_sender.send(result()).is_err() {
+                error!(
+                    "Sending result to receiver failed while exporting document as Png bytes. Receiver already dropped."
+                );
+            }
+        });
+
+        oneshot_receiver
+    }
+
+    /// Export exactly the region visible in the viewport of the camera as Png bytes, ready to be written to a file.
+    ///
+    /// The strokes are clipped to the viewport. The image is the size of the viewport at the dpi of the preferences,
+    /// independent of the zoom of the camera.
+    pub fn export_viewport_as_png(
+        &self,
+        camera: &Camera,
+        prefs: ViewportExportPrefs,
+    ) -> oneshot::Receiver<anyhow::Result<Vec<u8>>> {
+        let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
+        let viewport = camera.viewport();
+        let content = StrokeContent::default()
+            .with_strokes_from_store(
+                &self.store,
+                &self
+                    .store
+                    .stroke_keys_as_rendered_intersecting_bounds(viewport),
+            )
+            .with_bounds(viewport)
+            .with_background(self.document.config.background);
+        let image_scale = prefs.dpi / self.document.config.format.dpi();
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<Vec<u8>> {
+                let image = crate::Image::gen_with_cairo_exact_bounds(
+                    |cairo_cx| {
+                        content.draw_to_cairo(
+                            cairo_cx,
+                            prefs.with_background,
+                            prefs.with_pattern,
+                            false,
+                            0.0,
+                            image_scale,
+                        )
+                    },
+                    viewport,
+                    image_scale,
+                )?
+                .into_imgbuf()?;
+                let mut bytes: Cursor<Vec<u8>> = Cursor::new(Vec::new());
+                image.write_to(&mut bytes, image::ImageFormat::Png)?;
+                Ok(bytes.into_inner())
+            };
+            if oneshot_sender.send(result()).is_err() {
+                error!(
+                    "Sending result to receiver failed while exporting viewport as Png bytes. Receiver already dropped."
+                );
+            }
+        });
+
+        oneshot_receiver
+    }
+
+    /// The bounds of the pages with the given size that the content is split into.
+    ///
+    /// The pages start at the top left corner of the content, pages without any strokes are skipped.
+    pub fn content_pages_bounds(
+        &self,
+        page_size: na::Vector2<f64>,
+        page_order: SplitOrder,
+    ) -> Vec<Aabb> {
+        let strokes_bounds = self
+            .store
+            .strokes_bounds(&self.store.stroke_keys_as_rendered());
+        let Some(content_bounds) = strokes_bounds
+            .iter()
+            .copied()
+            .reduce(|acc, bounds| acc.merged(&bounds))
+        else {
+            return vec![];
+        };
+        let offset = content_bounds.mins.coords;
+
+        // Split relative to the content origin, so that the first page starts at the content
+        Aabb::new(content_bounds.mins - offset, content_bounds.maxs - offset)
+            .split_extended_origin_aligned(page_size, page_order)
+            .into_iter()
+            .map(|page_bounds| Aabb::new(page_bounds.mins + offset, page_bounds.maxs + offset))
+            .filter(|page_bounds| {
+                strokes_bounds.iter().any(|stroke_bounds| {
+                    stroke_bounds.intersects_w_tolerance(
+                        page_bounds,
+                        Self::STROKE_BOUNDS_INTERSECTION_TOLERANCE,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Export the content split into pages of a fixed size as Pdf.
+    ///
+    /// Strokes spanning multiple pages are clipped on each of them.
+    pub fn export_content_as_paged_pdf_bytes(
+        &self,
+        title: String,
+        prefs: PagedPdfExportPrefs,
+    ) -> oneshot::Receiver<anyhow::Result<Vec<u8>>> {
+        let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
+        let pages_content = self
+            .content_pages_bounds(prefs.page_size, prefs.page_order)
+            .into_iter()
+            .map(|bounds| {
+                StrokeContent::default()
+                    .with_strokes_from_store(
+                        &self.store,
+                        &self
+                            .store
+                            .stroke_keys_as_rendered_intersecting_bounds(bounds),
+                    )
+                    .with_bounds(bounds)
+                    .with_background(self.document.config.background)
+            })
+            .collect::<Vec<StrokeContent>>();
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<Vec<u8>> {
+                if pages_content.is_empty() {
+                    return Err(anyhow::anyhow!(
+                        "Exporting content as paged Pdf failed, no content to export."
+                    ));
+                }
+                let target_surface = cairo::PdfSurface::for_stream(
+                    prefs.page_size[0],
+                    prefs.page_size[1],
+                    Vec::<u8>::new(),
+                )
+                .context("Creating Pdf target surface failed.")?;
+
+                target_surface
+                    .set_metadata(cairo::PdfMetadata::Title, title.as_str())
+                    .context("Set pdf surface title metadata failed.")?;
+                target_surface
+                    .set_metadata(
+                        cairo::PdfMetadata::CreateDate,
+                        crate::utils::now_formatted_string().as_str(),
+                    )
+                    .context("Set pdf surface date metadata failed.")?;
+
+                // New scope to avoid errors when flushing
+                {
+                    let cairo_cx = cairo::Context::new(&target_surface)
+                        .context("Creating new cairo context for pdf target surface failed.")?;
+
+                    for (i, page_content) in pages_content.into_iter().enumerate() {
+                        let Some(page_bounds) = page_content.bounds() else {
+                            continue;
+                        };
+                        cairo_cx.save()?;
+                        cairo_cx.translate(-page_bounds.mins[0], -page_bounds.mins[1]);
+                        page_content.draw_to_cairo(
+                            &cairo_cx,
+                            prefs.with_background,
+                            prefs.with_pattern,
+                            prefs.optimize_printing,
+                            0.0,
+                            Engine::STROKE_EXPORT_IMAGE_SCALE,
+                        )?;
+                        cairo_cx.show_page().map_err(|e| {
+                            anyhow::anyhow!(
+                                "Showing page failed while exporting page {i} as paged pdf, Err: {e:?}"
+                            )
+                        })?;
+                        cairo_cx.restore()?;
+                    }
+                }
+                let data = *target_surface
+                    .finish_output_stream()
+                    .map_err(|e| anyhow::anyhow!("Finishing outputstream failed, Err: {e:?}"))?
+                    .downcast::<Vec<u8>>()
+                    .map_err(|e| {
+                        anyhow::anyhow!("Downcasting finished output stream failed, Err: {e:?}")
+                    })?;
+
+                Ok(data)
+            };
+
+            if oneshot_sender.send(result()).is_err() {
+                error!(
+                    "Sending result to receiver failed while exporting content as paged Pdf bytes. Receiver already dropped."
+                );
+            }
+        });
+
+        oneshot_receiver
+    }
+
+    fn extract_headless_render_content(&self, area: HeadlessRenderArea) -> StrokeContent {
+        match area {
+            HeadlessRenderArea::Document => self.extract_document_content(),
+            HeadlessRenderArea::Content => StrokeContent::default()
+                .with_strokes_from_store(&self.store, &self.store.stroke_keys_as_rendered())
+                .with_background(self.document.config.background),
+        }
+    }
+
+    /// Load the .rnote file bytes into a new engine and render it headless to an image.
+    pub async fn render_rnote_bytes_headless(
+        bytes: Vec<u8>,
+        prefs: HeadlessRenderPrefs,
+    ) -> anyhow::Result<image::RgbaImage> {
+        let mut engine = Engine::default();
+        let engine_snapshot = EngineSnapshot::load_from_rnote_bytes(bytes).await?;
+        // The widget flags are not needed without a surface
+        let _ = engine.load_snapshot(engine_snapshot);
+        engine.render_headless(prefs).await?
+    }
+}
+
+/// Render the content with the dpi of the preferences, relative to the document dpi.
+///
+/// Draws the strokes directly instead of generating a Svg first, so they look the same as on the canvas.
+fn render_content_headless(
+    content: &StrokeContent,
+    document_dpi: f64,
+    prefs: HeadlessRenderPrefs,
+) -> anyhow::Result<image::RgbaImage> {
+    let bounds = content
+        .bounds()
+        .context("Rendering headless failed, no content to render.")?
+        .loosened(prefs.margin);
+    let mut image_scale = prefs.dpi / document_dpi;
+    if let Some(max_size) = prefs.max_size {
+        let max_extent = bounds.extents().max() * image_scale;
+        if max_extent > f64::from(max_size) {
+            image_scale *= f64::from(max_size) / max_extent;
+        }
+    }
+    crate::Image::gen_with_cairo(
+        |cairo_cx| {
+            content.draw_to_cairo(
+                cairo_cx,
+                prefs.with_background,
+                prefs.with_pattern,
+                false,
+                prefs.margin,
+                image_scale,
+            )
+        },
+        bounds,
+        image_scale,
+    )?
+    .into_imgbuf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_viewport_as_png_size() {
+        let engine = Engine::default();
+        let camera = Camera::default()
+            .with_zoom(2.0)
+            .with_offset(na::vector![100.0, 50.0])
+            .with_size(na::vector![400.0, 300.0]);
+        let prefs = ViewportExportPrefs {
+            dpi: 192.0,
+            ..Default::default()
+        };
+
+        let bytes = futures::executor::block_on(engine.export_viewport_as_png(&camera, prefs))
+            .unwrap()
+            .unwrap();
+        let image = image::load_from_memory(&bytes).unwrap();
+
+        // Viewport extents of 200 x 150 in document coordinates
+        let image_scale = prefs.dpi / engine.document.config.format.dpi();
+        assert_eq!(image.width(), (200.0 * image_scale).round() as u32);
+        assert_eq!(image.height(), (150.0 * image_scale).round() as u32);
+    }
 }
@@ -6,7 +6,8 @@ use crate::pens::Pen;
 use crate::pens::PenStyle;
 use crate::store::StrokeKey;
 use crate::store::chrono_comp::StrokeLayer;
-use crate::strokes::{BitmapImage, Stroke, VectorImage};
+use crate::store::metadata_comp::PDF_TEXT_LAYER_TAG;
+use crate::strokes::{BitmapImage, Stroke, TextStroke, VectorImage};
 use crate::strokes::{Resize, resize::ImageSizeOption, resize::calculate_resize_ratio};
 use crate::{Engine, WidgetFlags};
 use futures::channel::oneshot;
@@ -96,6 +97,9 @@ pub struct PdfImportPrefs {
     /// Whether the document layout should be adjusted to the Pdf
     #[serde(rename = "adjust_document")]
     pub adjust_document: bool,
+    /// Whether the text of the Pdf should be imported as invisible, selectable text layer over the pages
+    #[serde(rename = "text_layer")]
+    pub text_layer: bool,
 }
 
 impl Default for PdfImportPrefs {
@@ -106,10 +110,20 @@ impl Default for PdfImportPrefs {
             page_spacing: PdfImportPageSpacing::default(),
             bitmap_scalefactor: 1.8,
             adjust_document: false,
+            text_layer: false,
         }
     }
 }
 
+/// The progress of a multi-page Pdf import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PdfImportProgress {
+    /// The number of imported pages.
+    pub imported: usize,
+    /// The total number of pages to import.
+    pub total: usize,
+}
+
 /// Xournal++ `.xopp` file import preferences.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(rename = "xopp_import_prefs")]
@@ -228,22 +242,35 @@ impl Engine {
         insert_pos: na::Vector2<f64>,
         page_range: Option<Range<usize>>,
         password: Option<String>,
+    ) -> oneshot::Receiver<anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>>> {
+        self.generate_pdf_pages_from_bytes_w_progress(
+            bytes,
+            insert_pos,
+            page_range,
+            password,
+            |_| {},
+        )
+    }
+
+    /// Generate image strokes for each page for the bytes, reporting the progress after every page.
+    ///
+    /// The progress callback is called from the worker thread.
+    ///
+    /// Note: `insert_pos` does not have an effect when the `adjust_document` import pref is set true.
+    #[allow(clippy::type_complexity)]
+    pub fn generate_pdf_pages_from_bytes_w_progress(
+        &self,
+        bytes: Vec<u8>,
+        insert_pos: na::Vector2<f64>,
+        page_range: Option<Range<usize>>,
+        password: Option<String>,
+        progress: impl Fn(PdfImportProgress) + Send + 'static,
     ) -> oneshot::Receiver<anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>>> {
         let (oneshot_sender, oneshot_receiver) =
             oneshot::channel::<anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>>>();
         let pdf_import_prefs = self.config.read().import_prefs.pdf_import_prefs;
         let format = self.document.config.format;
-        let insert_pos = if self
-            .config
-            .read()
-            .import_prefs
-            .pdf_import_prefs
-            .adjust_document
-        {
-            na::Vector2::<f64>::zeros()
-        } else {
-            insert_pos
-        };
+        let insert_pos = self.pdf_insert_pos(insert_pos);
 
         rayon::spawn(move || {
             let result = || -> anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>> {
@@ -256,6 +283,7 @@ impl Engine {
                             page_range,
                             &format,
                             password,
+                            progress,
                         )?
                         .into_iter()
                         .map(|s| (Stroke::BitmapImage(s), Some(StrokeLayer::Document)))
@@ -270,6 +298,7 @@ impl Engine {
                             page_range,
                             &format,
                             password,
+                            progress,
                         )?
                         .into_iter()
                         .map(|s| (Stroke::VectorImage(s), Some(StrokeLayer::Document)))
@@ -289,6 +318,59 @@ impl Engine {
         oneshot_receiver
     }
 
+    /// Generate the text layer for the pages for the bytes, positioned over the pages from
+    /// [`Engine::generate_pdf_pages_from_bytes()`] with the same arguments.
+    ///
+    /// The bytes are expected to be from a valid Pdf.
+    pub fn generate_pdf_text_layer_from_bytes(
+        &self,
+        bytes: Vec<u8>,
+        insert_pos: na::Vector2<f64>,
+        page_range: Option<Range<usize>>,
+        password: Option<String>,
+    ) -> oneshot::Receiver<anyhow::Result<Vec<TextStroke>>> {
+        let (oneshot_sender, oneshot_receiver) =
+            oneshot::channel::<anyhow::Result<Vec<TextStroke>>>();
+        let pdf_import_prefs = self.config.read().import_prefs.pdf_import_prefs;
+        let format = self.document.config.format;
+        let insert_pos = self.pdf_insert_pos(insert_pos);
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<Vec<TextStroke>> {
+                TextStroke::pdf_text_layer_from_pdf_bytes(
+                    &bytes,
+                    pdf_import_prefs,
+                    insert_pos,
+                    page_range,
+                    &format,
+                    password,
+                )
+            };
+
+            if oneshot_sender.send(result()).is_err() {
+                error!(
+                    "Sending result to receiver while generating Pdf text layer failed. Receiver already dropped"
+                );
+            }
+        });
+
+        oneshot_receiver
+    }
+
+    fn pdf_insert_pos(&self, insert_pos: na::Vector2<f64>) -> na::Vector2<f64> {
+        if self
+            .config
+            .read()
+            .import_prefs
+            .pdf_import_prefs
+            .adjust_document
+        {
+            na::Vector2::<f64>::zeros()
+        } else {
+            insert_pos
+        }
+    }
+
     /// Import the generated strokes into the store.
     pub fn import_generated_content(
         &mut self,
@@ -339,6 +421,47 @@ impl Engine {
         widget_flags
     }
 
+    /// Import the generated Pdf text layer into the store.
+    ///
+    /// Expected to be called right after importing the pages with [`Engine::import_generated_content()`],
+    /// since it is merged into the same history entry so that undoing removes both together.
+    pub fn import_pdf_text_layer(&mut self, textstrokes: Vec<TextStroke>) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if textstrokes.is_empty() {
+            return widget_flags;
+        }
+
+        let inserted = textstrokes
+            .into_iter()
+            .map(|textstroke| {
+                self.store
+                    .insert_stroke(Stroke::TextStroke(textstroke), Some(StrokeLayer::Document))
+            })
+            .collect::<Vec<StrokeKey>>();
+        widget_flags |= self
+            .store
+            .set_stroke_tag_keys(&inserted, PDF_TEXT_LAYER_TAG, true);
+
+        widget_flags |= self.store.update_latest_history_entry(Instant::now());
+        widget_flags.resize = true;
+        widget_flags.store_modified = true;
+
+        widget_flags
+    }
+
+    /// Enable or disable selecting the text layers of imported Pdf's.
+    ///
+    /// The text layers are invisible in any case, disabled text layers are locked against selecting and erasing.
+    pub fn set_pdf_text_layer_enabled(&mut self, enabled: bool) -> WidgetFlags {
+        self.store.set_locked_keys(
+            &self
+                .store
+                .stroke_keys_with_tag_unordered(PDF_TEXT_LAYER_TAG),
+            !enabled,
+        ) | self.current_pen_update_state()
+            | self.record(Instant::now())
+    }
+
     /// Insert text.
     pub fn insert_text(&mut self, text: String, pos: Option<na::Vector2<f64>>) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
@@ -1,5 +1,6 @@
 // Modules
 pub mod animation;
+pub mod autosave;
 pub mod config;
 pub mod export;
 pub mod import;
@@ -10,20 +11,26 @@ pub mod visual_debug;
 
 // Re-exports
 pub use animation::Animation;
+pub use autosave::CrashAutosavePrefs;
 pub use config::EngineConfig;
 pub use config::EngineConfigShared;
 pub use export::ExportPrefs;
 pub use import::ImportPrefs;
-pub use snapshot::EngineSnapshot;
+pub use snapshot::{EngineSnapshot, XoppImportProgress};
 pub use strokecontent::StrokeContent;
 
 // Imports
 use crate::Image;
 use crate::document::Layout;
+use crate::fileformats::rnoteformat;
 use crate::pens::PenMode;
+use crate::pens::pensconfig::brushconfig::BrushPreset;
 use crate::pens::{Pen, PenStyle};
-use crate::store::StrokeKey;
+use crate::store::render_cache::RenderedContent;
 use crate::store::render_comp::{self, RenderCompState};
+use crate::store::{
+    AlignMode, Axis, CoalesceKind, GroupId, LodConfig, RenderCacheLimits, StrokeKey,
+};
 use crate::strokes::content::GeneratedContentImages;
 use crate::strokes::textstroke::{TextAttribute, TextStyle};
 use crate::{Camera, Document, PenHolder, StrokeStore};
@@ -32,6 +39,7 @@ use futures::StreamExt;
 use futures::channel::mpsc::UnboundedReceiver;
 use futures::channel::{mpsc, oneshot};
 use p2d::bounding_volume::{Aabb, BoundingVolume};
+use rand::RngExt;
 use rnote_compose::eventresult::EventPropagation;
 use rnote_compose::ext::AabbExt;
 use rnote_compose::penevent::{PenEvent, ShortcutKey};
@@ -133,6 +141,8 @@ pub enum EngineTask {
         images: GeneratedContentImages,
         /// The image scale-factor the render task was using while generating the images.
         image_scale: f64,
+        /// The content the images were generated from, to cache the rendering.
+        content: RenderedContent,
     },
     /// Appends the images to the rendering of the given stroke.
     ///
@@ -144,8 +154,15 @@ pub enum EngineTask {
         /// The generated images
         images: GeneratedContentImages,
     },
+    /// Indicates that a batch rendering of all strokes is finished.
+    RenderBatchFinished {
+        /// The keys of the strokes that were not rendered, because the batch was cancelled or rendering failed.
+        unrendered_keys: Vec<StrokeKey>,
+    },
     /// Requests that the typewriter cursor should be blinked/toggled
     BlinkTypewriterCursor,
+    /// Requests that the brush stroke in progress is straightened, the pen being held still for the hold timeout.
+    StraightenHeldBrushStroke,
     /// Change the permanent zoom to the given value
     Zoom(f64),
     /// Indicates that the application is quitting. Sent to quit the handler which receives the tasks.
@@ -213,6 +230,17 @@ pub struct Engine {
     #[cfg(feature = "ui")]
     #[serde(skip)]
     origin_indicator_rendernode: Option<gtk4::gsk::RenderNode>,
+    // Crash recovery autosave
+    #[serde(skip)]
+    autosave_last_modification: Option<Instant>,
+    /// Identifies the document, so that every document has its own crash recovery autosave file.
+    #[serde(skip)]
+    autosave_id: u64,
+    /// The opacity image strokes are displayed with while tracing over them.
+    ///
+    /// Only a view setting, it is not saved in the document and doesn't affect exports.
+    #[serde(skip)]
+    trace_mode: Option<f64>,
 }
 
 impl Default for Engine {
@@ -237,6 +265,9 @@ impl Default for Engine {
             origin_indicator_image: None,
             #[cfg(feature = "ui")]
             origin_indicator_rendernode: None,
+            autosave_last_modification: None,
+            autosave_id: rand::rng().random(),
+            trace_mode: None,
         }
     }
 }
@@ -278,6 +309,34 @@ impl Engine {
         self.config.read().pen_sounds
     }
 
+    /// Sets the volume of the pen sounds in the range [0.0, 1.0], if pen sounds are enabled.
+    pub fn set_pen_sounds_volume(&mut self, volume: f64) {
+        #[cfg(feature = "ui")]
+        {
+            if let Some(audioplayer) = self.audioplayer.as_mut() {
+                audioplayer.set_volume(volume);
+            }
+        }
+        #[cfg(not(feature = "ui"))]
+        {
+            let _ = volume;
+        }
+    }
+
+    /// Mutes/unmutes the pen sounds, without releasing the audio player.
+    pub fn set_pen_sounds_muted(&mut self, muted: bool) {
+        #[cfg(feature = "ui")]
+        {
+            if let Some(audioplayer) = self.audioplayer.as_mut() {
+                audioplayer.set_enabled(!muted);
+            }
+        }
+        #[cfg(not(feature = "ui"))]
+        {
+            let _ = muted;
+        }
+    }
+
     /// Enables/disables the pen sounds.
     ///
     /// If pen sound should be enabled, the pkg data dir must be provided.
@@ -334,8 +393,14 @@ impl Engine {
             document: self.document.extract_snapshot_data(),
             camera: self.camera.extract_snapshot_data(),
             stroke_components: Arc::clone(&store_history_entry.stroke_components),
+            lock_components: Arc::clone(&store_history_entry.lock_components),
+            visibility_components: Arc::clone(&store_history_entry.visibility_components),
+            metadata_components: Arc::clone(&store_history_entry.metadata_components),
+            groups: Arc::clone(&store_history_entry.groups),
             chrono_components: Arc::clone(&store_history_entry.chrono_components),
             chrono_counter: store_history_entry.chrono_counter,
+            layers: Arc::clone(&store_history_entry.layers),
+            stroke_format_version: rnoteformat::STROKE_FORMAT_VERSION,
         }
     }
 
@@ -387,6 +452,73 @@ impl Engine {
         self.store.can_redo()
     }
 
+    /// The maximum number of entries in the undo history.
+    pub fn history_max_len(&self) -> usize {
+        self.store.history_max_len()
+    }
+
+    /// Set the maximum number of entries in the undo history to bound its memory usage.
+    ///
+    /// The oldest entries exceeding it are dropped.
+    pub fn set_history_max_len(&mut self, max_len: usize) -> WidgetFlags {
+        self.store.set_history_max_len(max_len)
+    }
+
+    /// Whether thin horizontal and vertical lines are snapped to the pixel grid when rendering.
+    pub fn pixel_snap_thin_lines(&self) -> bool {
+        self.store.pixel_snap_thin_lines()
+    }
+
+    /// Set whether thin horizontal and vertical lines are snapped to the pixel grid when rendering.
+    ///
+    /// Snapping makes lines in technical drawings crisper, curved strokes stay anti-aliased. Exports are unaffected.
+    pub fn set_pixel_snap_thin_lines(&mut self, pixel_snap_thin_lines: bool) -> WidgetFlags {
+        self.store.set_pixel_snap_thin_lines(pixel_snap_thin_lines);
+        self.store
+            .set_rendering_dirty_for_strokes(&self.store.stroke_keys_as_rendered());
+        self.update_content_rendering_current_viewport()
+    }
+
+    /// The level of detail strokes are rendered with when zoomed out.
+    pub fn lod_config(&self) -> LodConfig {
+        self.store.lod_config()
+    }
+
+    /// Set the level of detail strokes are rendered with when zoomed out.
+    pub fn set_lod_config(&mut self, lod_config: LodConfig) -> WidgetFlags {
+        self.store.set_lod_config(lod_config);
+        self.store
+            .set_rendering_dirty_for_strokes(&self.store.stroke_keys_as_rendered());
+        self.update_content_rendering_current_viewport()
+    }
+
+    /// The opacity image strokes are displayed with while tracing over them, or None when not tracing.
+    pub fn trace_mode(&self) -> Option<f64> {
+        self.trace_mode
+    }
+
+    /// Set the opacity image strokes are displayed with while tracing over them, or None to stop tracing.
+    ///
+    /// New strokes stay fully opaque, the images themselves are not modified.
+    pub fn set_trace_mode(&mut self, trace_mode: Option<f64>) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        self.trace_mode = trace_mode.map(|opacity| opacity.clamp(0.0, 1.0));
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
+    /// The limits of the cache for the rendering of unchanged strokes.
+    pub fn render_cache_limits(&self) -> RenderCacheLimits {
+        self.store.render_cache_limits()
+    }
+
+    /// Set the limits of the cache for the rendering of unchanged strokes.
+    ///
+    /// The least recently used renderings exceeding them are evicted.
+    pub fn set_render_cache_limits(&mut self, limits: RenderCacheLimits) {
+        self.store.set_render_cache_limits(limits);
+    }
+
     // Clears the entire engine.
     pub fn clear(&mut self) -> WidgetFlags {
         self.store.clear() | self.current_pen_update_state() | self.return_to_origin(None)
@@ -422,6 +554,7 @@ impl Engine {
                 key,
                 images,
                 image_scale,
+                content,
             } => {
                 if let Some(state) = self.store.render_comp_state(key) {
                     match state {
@@ -439,6 +572,7 @@ impl Engine {
                                 // Only when the image scale is roughly the same as when the render task was started,
                                 // the new images are considered valid and can replace the old.
                                 self.store.replace_rendering_with_images(key, images);
+                                self.store.cache_rendering(key, content, image_scale);
                             }
                             widget_flags.redraw = true;
                         }
@@ -453,12 +587,23 @@ impl Engine {
                 self.store.append_rendering_images(key, images);
                 widget_flags.redraw = true;
             }
+            EngineTask::RenderBatchFinished { unrendered_keys } => {
+                // Rendering the remaining strokes is left to the regular viewport rendering
+                self.store.set_rendering_dirty_for_strokes(&unrendered_keys);
+                widget_flags |= self.update_content_rendering_current_viewport();
+            }
             EngineTask::BlinkTypewriterCursor => {
                 if let Pen::Typewriter(typewriter) = self.penholder.current_pen_mut() {
                     typewriter.toggle_cursor_visibility();
                     widget_flags.redraw = true;
                 }
             }
+            EngineTask::StraightenHeldBrushStroke => {
+                if let Pen::Brush(brush) = self.penholder.current_pen_mut() {
+                    widget_flags |=
+                        brush.straighten_held_stroke(Instant::now(), &mut engine_view_mut!(self));
+                }
+            }
             EngineTask::Zoom(zoom) => {
                 widget_flags |= self.camera.zoom_temporarily_to(1.0) | self.camera.zoom_to(zoom);
 
@@ -819,6 +964,487 @@ impl Engine {
             | self.update_content_rendering_current_viewport()
     }
 
+    /// Recolor the selected strokes as a single undo step, keeping the seeds of textured styles.
+    pub fn recolor_selection(&mut self, color: Color) -> WidgetFlags {
+        self.store
+            .recolor_strokes(&self.store.selection_keys_as_rendered(), color)
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Set the opacity of the selected strokes as a single undo step.
+    pub fn set_selection_opacity(&mut self, opacity: f64) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        for key in self.store.selection_keys_as_rendered() {
+            widget_flags |= self.store.set_stroke_opacity(key, opacity);
+        }
+
+        // Changes made while dragging the opacity slider end up in a single history entry
+        widget_flags
+            | self
+                .store
+                .record_coalesced(Instant::now(), CoalesceKind::Opacity)
+    }
+
+    /// Scale the overall width of the selected brush strokes by the given factor as a single undo step.
+    pub fn scale_selection_stroke_widths(&mut self, factor: f64) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        for key in self.store.selection_keys_as_rendered() {
+            if let Ok(flags) = self.store.scale_stroke_width(key, factor) {
+                widget_flags |= flags;
+            }
+        }
+
+        widget_flags
+            | self.current_pen_update_state()
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Straighten the selected brush strokes into lines as a single undo step.
+    pub fn straighten_selection(&mut self) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        for key in self.store.selection_keys_as_rendered() {
+            if let Ok(flags) = self.store.straighten_stroke(key) {
+                widget_flags |= flags;
+            }
+        }
+
+        widget_flags
+            | self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Align the selected strokes to the edge or center of the selection bounds as a single undo step.
+    pub fn align_selection(&mut self, mode: AlignMode) -> WidgetFlags {
+        let selection_keys = self.store.selection_keys_as_rendered();
+        let widget_flags = self.store.align_strokes(&selection_keys, mode);
+        if !widget_flags.store_modified {
+            return widget_flags;
+        }
+
+        widget_flags
+            | self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Distribute the selected strokes with equal spacing along the axis as a single undo step.
+    pub fn distribute_selection(&mut self, axis: Axis) -> WidgetFlags {
+        let selection_keys = self.store.selection_keys_as_rendered();
+        let widget_flags = self.store.distribute_strokes(&selection_keys, axis);
+        if !widget_flags.store_modified {
+            return widget_flags;
+        }
+
+        widget_flags
+            | self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Insert a brush stroke parallel to the brush stroke for the key, offset by the distance.
+    ///
+    /// See [StrokeStore::offset_stroke].
+    pub fn offset_stroke(
+        &mut self,
+        key: StrokeKey,
+        distance: f64,
+        color: Option<Color>,
+    ) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if self.store.offset_stroke(key, distance, color).is_none() {
+            return widget_flags;
+        }
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+
+        widget_flags
+            | self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Join the two selected brush strokes into a single continuous stroke as a single undo step.
+    ///
+    /// Does nothing if not exactly two strokes are selected. See [StrokeStore::join_strokes].
+    pub fn join_selected_strokes(&mut self) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let &[a, b] = self.store.selection_keys_as_rendered().as_slice() else {
+            return widget_flags;
+        };
+        if self.store.join_strokes(a, b).is_none() {
+            return widget_flags;
+        }
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+        widget_flags.selection_changed = true;
+
+        widget_flags
+            | self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Remove the later brush strokes that duplicate earlier strokes within the tolerance, as a single undo step.
+    ///
+    /// See [StrokeStore::find_duplicate_strokes].
+    pub fn dedup_strokes(&mut self, tolerance: f64) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if self.store.dedup_strokes(tolerance).is_empty() {
+            return widget_flags;
+        }
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+
+        widget_flags
+            | self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// The distinct stroke colors of the most recently finished brush strokes, the most recent first.
+    pub fn recent_colors(&self) -> Vec<Color> {
+        self.config
+            .read()
+            .pens_config
+            .brush_config
+            .recent_colors
+            .iter()
+            .copied()
+            .collect()
+    }
+
+    /// The names of the saved brush presets.
+    pub fn brush_preset_names(&self) -> Vec<String> {
+        self.config
+            .read()
+            .pens_config
+            .brush_config
+            .presets
+            .iter()
+            .map(|preset| preset.name.clone())
+            .collect()
+    }
+
+    /// Save the current brush style and its options as a preset, replacing a preset with the same name.
+    pub fn save_brush_preset(&mut self, name: String) {
+        let mut config = self.config.write();
+        let preset = config.pens_config.brush_config.to_preset(name);
+        config.pens_config.brush_config.save_preset(preset);
+    }
+
+    /// Load the brush style and its options from the saved preset with the given name.
+    pub fn apply_brush_preset(&mut self, name: &str) -> WidgetFlags {
+        {
+            let mut config = self.config.write();
+            let brush_config = &mut config.pens_config.brush_config;
+            let Some(preset) = brush_config
+                .presets
+                .iter()
+                .find(|p| p.name == name)
+                .cloned()
+            else {
+                return WidgetFlags::default();
+            };
+            brush_config.load_preset(preset);
+        }
+        let mut widget_flags = self.reinstall_pen_current_style();
+        widget_flags.refresh_ui = true;
+        widget_flags
+    }
+
+    /// Remove the saved brush preset with the given name. Returns true when it was found.
+    pub fn remove_brush_preset(&mut self, name: &str) -> bool {
+        let presets = &mut self.config.write().pens_config.brush_config.presets;
+        let n_presets = presets.len();
+        presets.retain(|p| p.name != name);
+        presets.len() != n_presets
+    }
+
+    /// Import a brush preset from a file with its Json representation, replacing a preset with the same name.
+    ///
+    /// Returns the name of the imported preset.
+    pub fn import_brush_preset(&mut self, json: &str) -> anyhow::Result<String> {
+        let preset = BrushPreset::from_json(json)?;
+        let name = preset.name.clone();
+        self.config
+            .write()
+            .pens_config
+            .brush_config
+            .save_preset(preset);
+        Ok(name)
+    }
+
+    /// Export the saved brush preset with the given name to its Json representation, to be written to a file.
+    pub fn export_brush_preset(&self, name: &str) -> anyhow::Result<String> {
+        self.config
+            .read()
+            .pens_config
+            .brush_config
+            .presets
+            .iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| anyhow::anyhow!("No brush preset with name `{name}` saved."))?
+            .to_json()
+    }
+
+    /// Move the stroke to the front of its layer.
+    pub fn move_stroke_to_front(&mut self, key: StrokeKey) -> WidgetFlags {
+        self.store.move_stroke_to_front(key)
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Move the stroke to the back of its layer.
+    pub fn move_stroke_to_back(&mut self, key: StrokeKey) -> WidgetFlags {
+        self.store.move_stroke_to_back(key)
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Move the stroke one step forward in its layer.
+    pub fn move_stroke_forward(&mut self, key: StrokeKey) -> WidgetFlags {
+        self.store.move_stroke_forward(key)
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Move the stroke one step backward in its layer.
+    pub fn move_stroke_backward(&mut self, key: StrokeKey) -> WidgetFlags {
+        self.store.move_stroke_backward(key)
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Lock the selected strokes, so that they can't be selected or erased anymore.
+    pub fn lock_selection(&mut self) -> WidgetFlags {
+        self.store
+            .set_locked_keys(&self.store.selection_keys_as_rendered(), true)
+            | self.current_pen_update_state()
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Unlock all locked strokes.
+    pub fn unlock_all_strokes(&mut self) -> WidgetFlags {
+        self.store
+            .set_locked_keys(&self.store.locked_keys_unordered(), false)
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Show or hide the stroke. Hidden strokes stay in the document, but are not rendered or exported.
+    pub fn set_stroke_visible(&mut self, key: StrokeKey, visible: bool) -> WidgetFlags {
+        self.store.set_stroke_visible(key, visible)
+            | self.current_pen_update_state()
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Hide the selected strokes.
+    pub fn hide_selection(&mut self) -> WidgetFlags {
+        self.store
+            .set_stroke_visible_keys(&self.store.selection_keys_as_rendered(), false)
+            | self.current_pen_update_state()
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Show all strokes that were hidden by themselves. Hidden groups stay hidden.
+    pub fn show_all_hidden_strokes(&mut self) -> WidgetFlags {
+        self.store
+            .set_stroke_visible_keys(&self.store.invisible_keys_unordered(), true)
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Create a new layer above all other layers and make it the active layer, which new strokes are drawn into.
+    pub fn create_layer(&mut self, name: String) -> (u32, WidgetFlags) {
+        let (index, widget_flags) = self.store.create_layer(name);
+        (index, widget_flags | self.record(Instant::now()))
+    }
+
+    /// Delete the layer and its strokes.
+    pub fn delete_layer(&mut self, index: u32) -> WidgetFlags {
+        self.store.delete_layer(index)
+            | self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Move the layer to the new index in the layer stack, with index 0 being the bottom.
+    pub fn move_layer(&mut self, from: u32, to: u32) -> WidgetFlags {
+        self.store.move_layer(from, to)
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Set the layer that new strokes are drawn into.
+    pub fn set_active_layer(&mut self, index: u32) {
+        self.store.set_active_layer(index);
+    }
+
+    /// Move the selected strokes onto the layer.
+    pub fn move_selection_to_layer(&mut self, index: u32) -> WidgetFlags {
+        self.store
+            .move_strokes_to_layer(&self.store.selection_keys_as_rendered(), index)
+            | self.current_pen_update_state()
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    pub fn set_layer_name(&mut self, index: u32, name: String) -> WidgetFlags {
+        self.store.set_layer_name(index, name) | self.record(Instant::now())
+    }
+
+    /// Set the opacity the strokes of the layer are composited with.
+    pub fn set_layer_opacity(&mut self, index: u32, opacity: f64) -> WidgetFlags {
+        self.store.set_layer_opacity(index, opacity) | self.record(Instant::now())
+    }
+
+    /// Show or hide the strokes of the layer.
+    pub fn set_layer_visible(&mut self, index: u32, visible: bool) -> WidgetFlags {
+        self.store.set_layer_visible(index, visible)
+            | self.current_pen_update_state()
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Lock or unlock the strokes of the layer.
+    pub fn set_layer_locked(&mut self, index: u32, locked: bool) -> WidgetFlags {
+        self.store.set_layer_locked(index, locked)
+            | self.current_pen_update_state()
+            | self.record(Instant::now())
+    }
+
+    /// Group the selected strokes into a new group with the given name.
+    ///
+    /// Returns `None` when nothing is selected.
+    pub fn group_selection(&mut self, name: String) -> (Option<GroupId>, WidgetFlags) {
+        let selection = self.store.selection_keys_as_rendered();
+        if selection.is_empty() {
+            return (None, WidgetFlags::default());
+        }
+        let group = self.store.group_strokes(&selection, name);
+        let mut widget_flags = self.record(Instant::now());
+        widget_flags.store_modified = true;
+        (Some(group), widget_flags)
+    }
+
+    /// Remove the top level groups of the selected strokes. Nested groups are kept.
+    pub fn ungroup_selection(&mut self) -> WidgetFlags {
+        let mut groups = self
+            .store
+            .selection_keys_unordered()
+            .into_iter()
+            .filter_map(|key| self.store.stroke_top_level_group(key))
+            .collect::<Vec<GroupId>>();
+        groups.sort_unstable();
+        groups.dedup();
+        let mut widget_flags = WidgetFlags::default();
+        for group in groups {
+            widget_flags |= self.store.ungroup(group);
+        }
+        widget_flags | self.record(Instant::now())
+    }
+
+    /// Remove the group. Its members and nested groups are moved into the group it was nested in.
+    pub fn ungroup(&mut self, group: GroupId) -> WidgetFlags {
+        self.store.ungroup(group)
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    pub fn rename_group(&mut self, group: GroupId, name: String) -> WidgetFlags {
+        self.store.rename_group(group, name) | self.record(Instant::now())
+    }
+
+    /// Show or hide the strokes of the group.
+    pub fn set_group_hidden(&mut self, group: GroupId, hidden: bool) -> WidgetFlags {
+        self.store.set_group_hidden(group, hidden)
+            | self.current_pen_update_state()
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Lock or unlock the strokes of the group.
+    pub fn set_group_locked(&mut self, group: GroupId, locked: bool) -> WidgetFlags {
+        self.store.set_group_locked(group, locked)
+            | self.current_pen_update_state()
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Move the strokes of the group to the front of their layers.
+    pub fn move_group_to_front(&mut self, group: GroupId) -> WidgetFlags {
+        self.store.move_group_to_front(group)
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Move the strokes of the group to the back of their layers.
+    pub fn move_group_to_back(&mut self, group: GroupId) -> WidgetFlags {
+        self.store.move_group_to_back(group)
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
+    /// Attach the tag to the selected strokes.
+    pub fn tag_selection(&mut self, tag: &str) -> WidgetFlags {
+        self.store
+            .set_stroke_tag_keys(&self.store.selection_keys_as_rendered(), tag, true)
+            | self.record(Instant::now())
+    }
+
+    /// Remove the tag from the selected strokes.
+    pub fn untag_selection(&mut self, tag: &str) -> WidgetFlags {
+        self.store
+            .set_stroke_tag_keys(&self.store.selection_keys_as_rendered(), tag, false)
+            | self.record(Instant::now())
+    }
+
+    /// Select all strokes with the given tag, except locked strokes.
+    pub fn select_strokes_with_tag(&mut self, tag: &str) -> WidgetFlags {
+        let widget_flags = self.change_pen_style(PenStyle::Selector);
+        let select = self
+            .store
+            .stroke_keys_with_tag_unordered(tag)
+            .into_iter()
+            .filter(|&key| !self.store.locked(key).unwrap_or(false))
+            .collect::<Vec<StrokeKey>>();
+        self.store.set_selected_keys(&select, true);
+        widget_flags
+            | self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.record(Instant::now())
+            | self.update_rendering_current_viewport()
+    }
+
+    /// Apply the affine transformation to the strokes as a single undo step, e.g. for rotating the entire drawing.
+    pub fn transform_strokes(
+        &mut self,
+        keys: &[StrokeKey],
+        transform: na::Affine2<f64>,
+    ) -> WidgetFlags {
+        self.store.transform_strokes(keys, transform)
+            | self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.record(Instant::now())
+            | self.update_content_rendering_current_viewport()
+    }
+
     pub fn invert_selection_colors(&mut self) -> WidgetFlags {
         self.store
             .invert_color_brightness(&self.store.selection_keys_as_rendered())
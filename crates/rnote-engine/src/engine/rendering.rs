@@ -1,5 +1,6 @@
 // Imports
 use crate::Image;
+use crate::store::{RenderBatchHandle, RenderProgressCallback};
 use crate::{Engine, WidgetFlags};
 use p2d::bounding_volume::Aabb;
 use piet::RenderContext;
@@ -101,6 +102,22 @@ impl Engine {
         widget_flags
     }
 
+    /// Regenerate the rendering of all strokes for the current image scale in the background,
+    /// e.g. after loading a large document.
+    ///
+    /// The progress is reported with the callback, and the returned handle can cancel the rendering.
+    pub fn regenerate_all_rendering(
+        &mut self,
+        progress_cb: RenderProgressCallback,
+    ) -> RenderBatchHandle {
+        self.store.regenerate_all_rendering(
+            self.engine_tasks_tx(),
+            self.document.bounds(),
+            self.camera.image_scale(),
+            progress_cb,
+        )
+    }
+
     /// Update the content and background rendering for the current viewport.
     ///
     /// If the background pattern or zoom has changed, the background pattern needs to be regenerated first.
@@ -179,7 +196,7 @@ impl Engine {
         self.draw_format_borders_to_gtk_snapshot(snapshot)?;
         self.draw_origin_indicator_to_gtk_snapshot(snapshot)?;
         self.store
-            .draw_strokes_to_gtk_snapshot(snapshot, doc_bounds, viewport);
+            .draw_strokes_to_gtk_snapshot(snapshot, doc_bounds, viewport, self.trace_mode);
         snapshot.restore();
         /*
                let cairo_cx = snapshot.append_cairo(&graphene::Rect::from_p2d_aabb(surface_bounds));
@@ -204,6 +221,13 @@ impl Engine {
             visual_debug::draw_statistics_to_gtk_snapshot(snapshot, self, surface_bounds)?;
         }
 
+        if self.config.read().pressure_debug {
+            snapshot.save();
+            snapshot.transform(Some(&camera_transform));
+            visual_debug::draw_pressure_debug_to_gtk_snapshot(snapshot, self);
+            snapshot.restore();
+        }
+
         Ok(())
     }
 
@@ -2,7 +2,10 @@
 use crate::document::background;
 use crate::engine::import::XoppImportPrefs;
 use crate::fileformats::{FileFormatLoader, rnoteformat, xoppformat};
-use crate::store::{ChronoComponent, StrokeKey};
+use crate::store::{
+    ChronoComponent, GroupId, Layer, LockComponent, MetadataComponent, StrokeGroup, StrokeKey,
+    VisibilityComponent, layer_comp,
+};
 use crate::strokes::Stroke;
 use crate::{Camera, Document, Engine};
 use anyhow::Context;
@@ -10,8 +13,21 @@ use futures::channel::oneshot;
 use serde::{Deserialize, Serialize};
 use slotmap::{SecondaryMap, SlotMap};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tracing::error;
 
+/// The number of imported elements after which the progress is reported and cancellation is checked.
+const XOPP_IMPORT_BATCH_SIZE: usize = 256;
+
+/// The progress of a streamed Xournal++ file import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XoppImportProgress {
+    /// The number of imported strokes, images and texts.
+    pub imported: usize,
+    /// The total number of strokes, images and texts in the file.
+    pub total: usize,
+}
+
 /// Trait for types which hold configuration needed for engine snapshots
 pub trait Snapshotable {
     fn extract_snapshot_data(&self) -> Self;
@@ -27,10 +43,26 @@ pub struct EngineSnapshot {
     pub camera: Camera,
     #[serde(rename = "stroke_components")]
     pub stroke_components: Arc<SlotMap<StrokeKey, Arc<Stroke>>>,
+    #[serde(rename = "lock_components")]
+    pub lock_components: Arc<SecondaryMap<StrokeKey, Arc<LockComponent>>>,
+    #[serde(rename = "visibility_components")]
+    pub visibility_components: Arc<SecondaryMap<StrokeKey, Arc<VisibilityComponent>>>,
+    #[serde(rename = "metadata_components")]
+    pub metadata_components: Arc<SecondaryMap<StrokeKey, Arc<MetadataComponent>>>,
+    #[serde(rename = "groups")]
+    pub groups: Arc<SlotMap<GroupId, Arc<StrokeGroup>>>,
     #[serde(rename = "chrono_components")]
     pub chrono_components: Arc<SecondaryMap<StrokeKey, Arc<ChronoComponent>>>,
     #[serde(rename = "chrono_counter")]
     pub chrono_counter: u32,
+    /// Missing in documents from before layers were introduced, which then are loaded into a default layer.
+    #[serde(rename = "layers")]
+    pub layers: Arc<Vec<Layer>>,
+    /// The version of the serialized stroke format, see [rnoteformat::STROKE_FORMAT_VERSION].
+    ///
+    /// Is 0 for snapshots from before the stroke format was versioned.
+    #[serde(rename = "stroke_format_version", default)]
+    pub stroke_format_version: u32,
 }
 
 impl Default for EngineSnapshot {
@@ -39,8 +71,14 @@ impl Default for EngineSnapshot {
             document: Document::default(),
             camera: Camera::default(),
             stroke_components: Arc::new(SlotMap::with_key()),
+            lock_components: Arc::new(SecondaryMap::new()),
+            visibility_components: Arc::new(SecondaryMap::new()),
+            metadata_components: Arc::new(SecondaryMap::new()),
+            groups: Arc::new(SlotMap::with_key()),
             chrono_components: Arc::new(SecondaryMap::new()),
             chrono_counter: 0,
+            layers: Arc::new(layer_comp::default_layers()),
+            stroke_format_version: rnoteformat::STROKE_FORMAT_VERSION,
         }
     }
 }
@@ -54,8 +92,12 @@ impl EngineSnapshot {
 
         rayon::spawn(move || {
             let result = || -> anyhow::Result<Self> {
-                let rnote_file = rnoteformat::RnoteFile::load_from_bytes(&bytes)
+                let mut rnote_file = rnoteformat::RnoteFile::load_from_bytes(&bytes)
                     .context("loading RnoteFile from bytes failed.")?;
+                rnoteformat::strokeformat::migrate_snapshot_strokes(
+                    &mut rnote_file.engine_snapshot,
+                )
+                .context("migrating strokes to the current stroke format failed.")?;
                 Ok(ijson::from_value(&rnote_file.engine_snapshot)?)
             };
 
@@ -74,6 +116,28 @@ impl EngineSnapshot {
     pub async fn load_from_xopp_bytes(
         bytes: Vec<u8>,
         xopp_import_prefs: XoppImportPrefs,
+    ) -> anyhow::Result<Self> {
+        Self::load_from_xopp_bytes_streamed(
+            bytes,
+            xopp_import_prefs,
+            Arc::new(AtomicBool::new(false)),
+            |_| {},
+        )
+        .await
+    }
+
+    /// Loads from the bytes of a Xournal++ .xopp file, converting and inserting the content in batches.
+    ///
+    /// The progress callback is called from the worker thread after every batch.
+    /// When `cancelled` is set, the import is aborted after the current batch and an error is returned.
+    /// The current engine is never touched while importing, so an aborted import leaves it unchanged.
+    ///
+    /// To import this snapshot into the current engine, use [`Engine::load_snapshot()`].
+    pub async fn load_from_xopp_bytes_streamed(
+        bytes: Vec<u8>,
+        xopp_import_prefs: XoppImportPrefs,
+        cancelled: Arc<AtomicBool>,
+        progress: impl Fn(XoppImportProgress) + Send + 'static,
     ) -> anyhow::Result<Self> {
         let (snapshot_sender, snapshot_receiver) = oneshot::channel::<anyhow::Result<Self>>();
 
@@ -94,6 +158,24 @@ impl EngineSnapshot {
                         },
                     );
                 let no_pages = xopp_file.xopp_root.pages.len() as u32;
+                let total = xopp_file
+                    .xopp_root
+                    .pages
+                    .iter()
+                    .flat_map(|page| page.layers.iter())
+                    .map(|layer| layer.strokes.len() + layer.images.len() + layer.texts.len())
+                    .sum::<usize>();
+                let mut imported = 0;
+                let mut element_imported = || -> anyhow::Result<()> {
+                    imported += 1;
+                    if imported % XOPP_IMPORT_BATCH_SIZE == 0 || imported == total {
+                        if cancelled.load(Ordering::Relaxed) {
+                            return Err(anyhow::anyhow!("Importing Xopp file was cancelled."));
+                        }
+                        progress(XoppImportProgress { imported, total });
+                    }
+                    Ok(())
+                };
 
                 let mut engine = Engine::default();
 
@@ -163,6 +245,7 @@ impl EngineSnapshot {
                                     );
                                 }
                             }
+                            element_imported()?;
                         }
 
                         // import images
@@ -181,6 +264,7 @@ impl EngineSnapshot {
                                     );
                                 }
                             }
+                            element_imported()?;
                         }
 
                         for new_xopptext in layers.texts.into_iter() {
@@ -195,6 +279,7 @@ impl EngineSnapshot {
                                     );
                                 }
                             }
+                            element_imported()?;
                         }
                     }
 
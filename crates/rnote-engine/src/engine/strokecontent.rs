@@ -2,13 +2,20 @@
 use crate::Drawable;
 use crate::Svg;
 use crate::document::Background;
-use crate::strokes::Stroke;
+use crate::store::chrono_comp::StrokeLayer;
+use crate::store::layer_comp;
+use crate::store::{StrokeKey, StrokeStore};
+use crate::strokes::{Content, Stroke};
+use itertools::Itertools;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rnote_compose::shapes::Shapeable;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tracing::warn;
 
+/// A stroke together with the layer it is on and the layer opacity, if it is known.
+type LayeredStroke = (Arc<Stroke>, Option<(StrokeLayer, f64)>);
+
 /// Stroke content.
 ///
 /// Used when exporting and pasting/copying/cutting from/into the clipboard.
@@ -21,6 +28,12 @@ pub struct StrokeContent {
     pub bounds: Option<Aabb>,
     #[serde(rename = "background")]
     pub background: Option<Background>,
+    /// The layer each of the strokes is on together with its opacity, in the order of the strokes.
+    ///
+    /// Consecutive strokes on a translucent layer are composited together with the layer opacity.
+    /// Strokes without an entry are drawn opaque.
+    #[serde(rename = "stroke_layers")]
+    pub stroke_layers: Vec<(StrokeLayer, f64)>,
 }
 
 impl StrokeContent {
@@ -37,6 +50,17 @@ impl StrokeContent {
         self
     }
 
+    /// Take the strokes for the keys from the store, together with the layers they are on.
+    pub(crate) fn with_strokes_from_store(
+        mut self,
+        store: &StrokeStore,
+        keys: &[StrokeKey],
+    ) -> Self {
+        self.strokes = store.get_strokes_arc(keys);
+        self.stroke_layers = store.get_stroke_layers_with_opacity(keys);
+        self
+    }
+
     pub fn with_background(mut self, background: Background) -> Self {
         self.background = Some(background);
         self
@@ -76,19 +100,80 @@ impl StrokeContent {
         let Some(bounds_loosened) = self.bounds().map(|b| b.loosened(margin)) else {
             return Ok(None);
         };
-        let mut svg = Svg::gen_with_cairo(
-            |cairo_cx| {
-                self.draw_to_cairo(
-                    cairo_cx,
-                    draw_background,
-                    draw_pattern,
-                    optimize_printing,
-                    margin,
-                    1.0,
-                )
-            },
-            bounds_loosened,
-        )?;
+        let image_bounds = self.image_bounds();
+        let is_text = |(stroke, _): &(&Arc<Stroke>, Option<(StrokeLayer, f64)>)| {
+            matches!(stroke.as_ref(), Stroke::TextStroke(_))
+        };
+        let mut svg = Svg {
+            svg_data: String::new(),
+            bounds: bounds_loosened,
+        };
+        // The background is drawn together with the first strokes, or by itself if there are none to draw it with
+        if draw_background
+            && self
+                .layered_strokes()
+                .next()
+                .is_none_or(|layered_stroke| is_text(&layered_stroke))
+        {
+            svg.merge([Svg::gen_with_cairo(
+                |cairo_cx| {
+                    self.draw_to_cairo_int(
+                        cairo_cx,
+                        &[],
+                        &image_bounds,
+                        draw_background,
+                        draw_pattern,
+                        optimize_printing,
+                        margin,
+                        1.0,
+                    )
+                },
+                bounds_loosened,
+            )?]);
+        }
+        // Text strokes are exported as Svg text elements, the strokes in between are drawn with cairo in order.
+        for (i, (text_strokes, strokes)) in self
+            .layered_strokes()
+            .chunk_by(is_text)
+            .into_iter()
+            .enumerate()
+        {
+            let strokes = strokes
+                .map(|(stroke, layer)| (Arc::clone(stroke), layer))
+                .collect::<Vec<LayeredStroke>>();
+            if text_strokes {
+                for (stroke, layer) in strokes {
+                    let mut stroke = stroke.as_ref().clone();
+                    if optimize_printing && Self::darkened_for_printing(&stroke, &image_bounds) {
+                        stroke.set_to_darkest_color();
+                    }
+                    let mut stroke_svg = stroke.gen_svg()?;
+                    if let Some((_, layer_opacity)) = layer
+                        && layer_opacity < 1.0
+                    {
+                        stroke_svg.svg_data =
+                            format!("<g opacity=\"{layer_opacity}\">{}</g>", stroke_svg.svg_data);
+                    }
+                    svg.merge([stroke_svg]);
+                }
+            } else {
+                svg.merge([Svg::gen_with_cairo(
+                    |cairo_cx| {
+                        self.draw_to_cairo_int(
+                            cairo_cx,
+                            &strokes,
+                            &image_bounds,
+                            draw_background && i == 0,
+                            draw_pattern,
+                            optimize_printing,
+                            margin,
+                            1.0,
+                        )
+                    },
+                    bounds_loosened,
+                )?]);
+            }
+        }
         // The simplification also moves the bounds to mins: [0.0, 0.0], maxs: extents
         if let Err(e) = svg.simplify() {
             warn!("Simplifying Svg while generating StrokeContent Svg failed, Err: {e:?}");
@@ -104,6 +189,65 @@ impl StrokeContent {
         optimize_printing: bool,
         margin: f64,
         image_scale: f64,
+    ) -> anyhow::Result<()> {
+        self.draw_to_cairo_int(
+            cairo_cx,
+            &self
+                .layered_strokes()
+                .map(|(stroke, layer)| (Arc::clone(stroke), layer))
+                .collect::<Vec<LayeredStroke>>(),
+            &self.image_bounds(),
+            draw_background,
+            draw_pattern,
+            optimize_printing,
+            margin,
+            image_scale,
+        )
+    }
+
+    /// The strokes together with the layer they are on and its opacity, if it is known.
+    fn layered_strokes(&self) -> impl Iterator<Item = (&Arc<Stroke>, Option<(StrokeLayer, f64)>)> {
+        self.strokes
+            .iter()
+            .enumerate()
+            .map(|(i, stroke)| (stroke, self.stroke_layers.get(i).copied()))
+    }
+
+    /// The bounds of the images in the content.
+    fn image_bounds(&self) -> Vec<Aabb> {
+        self.strokes
+            .iter()
+            .filter_map(|stroke| match stroke.as_ref() {
+                Stroke::BitmapImage(image) => Some(image.rectangle.bounds()),
+                Stroke::VectorImage(image) => Some(image.rectangle.bounds()),
+                _ => None,
+            })
+            .collect::<Vec<Aabb>>()
+    }
+
+    /// Whether the stroke is drawn with its darkest color when optimizing for printing, which it is when it is not
+    /// contained in any of the images.
+    fn darkened_for_printing(stroke: &Stroke, image_bounds: &[Aabb]) -> bool {
+        // Using the stroke's bounds instead of hitboxes works for inclusion.
+        // If this is changed to intersection, all hitboxes must be checked individually.
+        let stroke_bounds = stroke.bounds();
+        image_bounds
+            .iter()
+            .all(|bounds| !bounds.contains(&stroke_bounds))
+    }
+
+    /// Draw the background and the given strokes of the content, clipped to its bounds.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_to_cairo_int(
+        &self,
+        cairo_cx: &cairo::Context,
+        strokes: &[LayeredStroke],
+        image_bounds: &[Aabb],
+        draw_background: bool,
+        draw_pattern: bool,
+        optimize_printing: bool,
+        margin: f64,
+        image_scale: f64,
     ) -> anyhow::Result<()> {
         let Some(bounds) = self.bounds() else {
             return Ok(());
@@ -133,35 +277,22 @@ impl StrokeContent {
         );
         cairo_cx.clip();
 
-        let image_bounds = self
-            .strokes
-            .iter()
-            .filter_map(|stroke| match stroke.as_ref() {
-                Stroke::BitmapImage(image) => Some(image.rectangle.bounds()),
-                Stroke::VectorImage(image) => Some(image.rectangle.bounds()),
-                _ => None,
-            })
-            .collect::<Vec<Aabb>>();
-
-        for stroke in self.strokes.iter() {
-            let stroke_bounds = stroke.bounds();
-
-            if optimize_printing
-                && image_bounds
-                    .iter()
-                    .all(|bounds| !bounds.contains(&stroke_bounds))
-            {
-                // Using the stroke's bounds instead of hitboxes works for inclusion.
-                // If this is changed to intersection, all hitboxes must be checked individually.
-
-                let mut darkest_color_stroke = stroke.as_ref().clone();
-                darkest_color_stroke.set_to_darkest_color();
+        layer_comp::draw_with_layer_opacity_to_cairo(
+            cairo_cx,
+            strokes
+                .iter()
+                .map(|(stroke, layer)| (stroke.as_ref(), *layer)),
+            |stroke| {
+                if optimize_printing && Self::darkened_for_printing(stroke, image_bounds) {
+                    let mut darkest_color_stroke = stroke.clone();
+                    darkest_color_stroke.set_to_darkest_color();
 
-                darkest_color_stroke.draw_to_cairo(cairo_cx, image_scale)?;
-            } else {
-                stroke.draw_to_cairo(cairo_cx, image_scale)?;
-            }
-        }
+                    darkest_color_stroke.draw_to_cairo(cairo_cx, image_scale)
+                } else {
+                    stroke.draw_to_cairo(cairo_cx, image_scale)
+                }
+            },
+        )?;
 
         cairo_cx.restore()?;
 
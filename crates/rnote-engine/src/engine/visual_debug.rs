@@ -43,6 +43,18 @@ pub const COLOR_SELECTOR_BOUNDS: Color = Color {
     b: 0.8,
     a: 1.0,
 };
+pub const COLOR_PRESSURE_LOW: Color = Color {
+    r: 0.0,
+    g: 0.4,
+    b: 1.0,
+    a: 0.8,
+};
+pub const COLOR_PRESSURE_HIGH: Color = Color {
+    r: 1.0,
+    g: 0.1,
+    b: 0.0,
+    a: 0.8,
+};
 pub const COLOR_DOC_BOUNDS: Color = Color {
     r: 0.8,
     g: 0.0,
@@ -234,3 +246,38 @@ pub(crate) fn draw_stroke_debug_to_gtk_snapshot(
 
     Ok(())
 }
+
+/// Draw the elements of the brush strokes in the viewport as dots, scaled and colored by their pressure.
+#[cfg(feature = "ui")]
+pub(crate) fn draw_pressure_debug_to_gtk_snapshot(
+    snapshot: &gtk4::Snapshot,
+    engine: &crate::Engine,
+) {
+    use crate::strokes::Stroke;
+
+    /// The dot width in surface coordinates for no pressure.
+    const DOT_WIDTH_MIN: f64 = 2.0;
+    /// The dot width in surface coordinates for full pressure.
+    const DOT_WIDTH_MAX: f64 = 10.0;
+
+    let total_zoom = engine.camera.total_zoom();
+    for key in engine
+        .store
+        .stroke_keys_as_rendered_intersecting_bounds(engine.camera.viewport())
+    {
+        let Some(Stroke::BrushStroke(brushstroke)) = engine.store.get_stroke_ref(key) else {
+            continue;
+        };
+        for element in brushstroke.path().elements() {
+            let pressure = element.pressure.clamp(0.0, 1.0);
+            let color = Color {
+                r: COLOR_PRESSURE_LOW.r + (COLOR_PRESSURE_HIGH.r - COLOR_PRESSURE_LOW.r) * pressure,
+                g: COLOR_PRESSURE_LOW.g + (COLOR_PRESSURE_HIGH.g - COLOR_PRESSURE_LOW.g) * pressure,
+                b: COLOR_PRESSURE_LOW.b + (COLOR_PRESSURE_HIGH.b - COLOR_PRESSURE_LOW.b) * pressure,
+                a: COLOR_PRESSURE_LOW.a,
+            };
+            let width = (DOT_WIDTH_MIN + (DOT_WIDTH_MAX - DOT_WIDTH_MIN) * pressure) / total_zoom;
+            draw_pos_to_gtk_snapshot(snapshot, element.pos, color, width);
+        }
+    }
+}
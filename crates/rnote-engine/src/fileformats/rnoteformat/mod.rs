@@ -11,6 +11,7 @@ pub(crate) mod maj0min5patch8;
 pub(crate) mod maj0min5patch9;
 pub(crate) mod maj0min6;
 pub(crate) mod maj0min9;
+pub mod strokeformat;
 
 // Imports
 use self::maj0min5patch8::RnoteFileMaj0Min5Patch8;
@@ -18,6 +19,7 @@ use self::maj0min5patch9::RnoteFileMaj0Min5Patch9;
 use self::maj0min6::RnoteFileMaj0Min6;
 use self::maj0min9::RnoteFileMaj0Min9;
 use self::maj0min13::RnoteFileMaj0Min13;
+pub use self::strokeformat::{STROKE_FORMAT_VERSION, migrate_stroke};
 
 use super::{FileFormatLoader, FileFormatSaver};
 use anyhow::Context;
@@ -0,0 +1,196 @@
+//! Versioning and migration of the serialized stroke format.
+//!
+//! Independent of the file format version, the strokes are tagged with the [STROKE_FORMAT_VERSION] they were
+//! serialized with. Strokes of older versions are upgraded to the current schema before they are deserialized,
+//! one migration step per version.
+//!
+//! Options that were added are filled with their defaults when deserializing, so a step is only needed when fields
+//! were renamed or changed their meaning.
+
+// Imports
+use anyhow::anyhow;
+use ijson::{IObject, IValue};
+use rnote_compose::style::textured::TexturedOptions;
+
+/// The current version of the serialized stroke format.
+pub const STROKE_FORMAT_VERSION: u32 = 1;
+
+/// The migration steps, where the step at index `i` upgrades strokes from version `i` to version `i + 1`.
+const MIGRATION_STEPS: [fn(&mut IValue) -> anyhow::Result<()>; STROKE_FORMAT_VERSION as usize] =
+    [migrate_v0_to_v1];
+
+/// Upgrade the serialized stroke from the given version to the current [STROKE_FORMAT_VERSION].
+///
+/// Fails for strokes of a newer version, which might contain data that can't be represented.
+pub fn migrate_stroke(value: &mut IValue, from_version: u32) -> anyhow::Result<()> {
+    if from_version > STROKE_FORMAT_VERSION {
+        return Err(anyhow!(
+            "Stroke has unsupported format version {from_version}, the latest supported version is {STROKE_FORMAT_VERSION}."
+        ));
+    }
+    for step in &MIGRATION_STEPS[from_version as usize..] {
+        step(value)?;
+    }
+    Ok(())
+}
+
+/// Upgrade all strokes of the serialized engine snapshot to the current [STROKE_FORMAT_VERSION] and update its tag.
+///
+/// Snapshots without the tag are from before the stroke format was versioned and have version 0.
+pub(crate) fn migrate_snapshot_strokes(engine_snapshot: &mut IValue) -> anyhow::Result<()> {
+    let engine_snapshot = engine_snapshot
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("engine snapshot is not a JSON object."))?;
+    let from_version = match engine_snapshot.get("stroke_format_version") {
+        Some(version) => version
+            .to_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| anyhow!("`stroke_format_version` is not an unsigned integer."))?,
+        None => 0,
+    };
+    if from_version == STROKE_FORMAT_VERSION {
+        return Ok(());
+    }
+
+    if let Some(stroke_components) = engine_snapshot.get_mut("stroke_components") {
+        // The slotmap is serialized as sequence of slots, where vacant slots have no value
+        let slots = stroke_components
+            .as_array_mut()
+            .ok_or_else(|| anyhow!("`stroke_components` is not a JSON array."))?;
+        for slot in slots.iter_mut() {
+            if let Some(stroke) = slot
+                .as_object_mut()
+                .and_then(|slot| slot.get_mut("value"))
+                .filter(|stroke| !stroke.is_null())
+            {
+                migrate_stroke(stroke, from_version)?;
+            }
+        }
+    }
+    engine_snapshot.insert("stroke_format_version", STROKE_FORMAT_VERSION);
+
+    Ok(())
+}
+
+/// The options object of the style of the stroke, together with the name of the style.
+fn stroke_style_options_mut(stroke: &mut IValue) -> Option<(String, &mut IObject)> {
+    // Strokes and styles are serialized as externally tagged enums
+    let (_, stroke) = stroke.as_object_mut()?.iter_mut().next()?;
+    let (style_name, options) = stroke
+        .as_object_mut()?
+        .get_mut("style")?
+        .as_object_mut()?
+        .iter_mut()
+        .next()?;
+    Some((style_name.to_string(), options.as_object_mut()?))
+}
+
+/// Version 1 replaced the density of textured strokes per 10x10 area with a density per unit of path length.
+fn migrate_v0_to_v1(stroke: &mut IValue) -> anyhow::Result<()> {
+    let Some((style_name, options)) = stroke_style_options_mut(stroke) else {
+        return Ok(());
+    };
+
+    if style_name == "textured"
+        && let Some(density) = options.remove("density")
+    {
+        let density = density
+            .to_f64()
+            .ok_or_else(|| anyhow!("textured `density` is not a number."))?;
+        let stroke_width = options
+            .get("stroke_width")
+            .and_then(|w| w.to_f64())
+            .unwrap_or_else(|| TexturedOptions::default().stroke_width);
+        options.insert(
+            "density_per_length",
+            TexturedOptions::density_per_length_from_area_density(density, stroke_width),
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stroke_w_style(style: serde_json::Value) -> IValue {
+        ijson::to_value(serde_json::json!({
+            "brushstroke": {
+                "path": { "start": { "pos": [0.0, 0.0], "pressure": 0.5 }, "segments": [] },
+                "style": style
+            }
+        }))
+        .unwrap()
+    }
+
+    fn style_options(stroke: &mut IValue) -> serde_json::Value {
+        let (_, options) = stroke_style_options_mut(stroke).unwrap();
+        ijson::from_value(&IValue::from(options.clone())).unwrap()
+    }
+
+    #[test]
+    fn migrate_v0_to_v1_textured_density() {
+        let mut stroke = stroke_w_style(
+            serde_json::json!({ "textured": { "stroke_width": 10.0, "density": 5.0 } }),
+        );
+        migrate_v0_to_v1(&mut stroke).unwrap();
+
+        let options = style_options(&mut stroke);
+        assert!(options.get("density").is_none());
+        assert_eq!(options["density_per_length"], serde_json::json!(5.0));
+    }
+
+    #[test]
+    fn migrate_v0_to_v1_other_styles_unchanged() {
+        let mut stroke = stroke_w_style(
+            serde_json::json!({ "smooth": { "stroke_width": 2.0, "density": 5.0 } }),
+        );
+        let original = stroke.clone();
+        migrate_v0_to_v1(&mut stroke).unwrap();
+
+        assert_eq!(stroke, original);
+    }
+
+    #[test]
+    fn migrate_stroke_current_version_unchanged() {
+        let mut stroke = stroke_w_style(
+            serde_json::json!({ "textured": { "stroke_width": 10.0, "density": 5.0 } }),
+        );
+        let original = stroke.clone();
+        migrate_stroke(&mut stroke, STROKE_FORMAT_VERSION).unwrap();
+
+        assert_eq!(stroke, original);
+        assert!(migrate_stroke(&mut stroke, STROKE_FORMAT_VERSION + 1).is_err());
+    }
+
+    #[test]
+    fn migrate_unversioned_snapshot() {
+        let mut snapshot = ijson::to_value(serde_json::json!({
+            "stroke_components": [
+                { "version": 0 },
+                {
+                    "value": {
+                        "brushstroke": {
+                            "path": {},
+                            "style": { "textured": { "stroke_width": 10.0, "density": 5.0 } }
+                        }
+                    },
+                    "version": 1
+                }
+            ]
+        }))
+        .unwrap();
+        migrate_snapshot_strokes(&mut snapshot).unwrap();
+
+        let snapshot = ijson::from_value::<serde_json::Value>(&snapshot).unwrap();
+        assert_eq!(
+            snapshot["stroke_format_version"],
+            serde_json::json!(STROKE_FORMAT_VERSION)
+        );
+        assert_eq!(
+            snapshot["stroke_components"][1]["value"]["brushstroke"]["style"]["textured"]["density_per_length"],
+            serde_json::json!(5.0)
+        );
+    }
+}
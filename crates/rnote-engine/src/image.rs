@@ -327,6 +327,22 @@ impl Image {
         bounds.loosen(1.0);
         bounds.assert_valid()?;
 
+        Self::gen_with_cairo_exact_bounds(draw_func, bounds, image_scale)
+    }
+
+    /// Generates an image of exactly the bounds with a provided closure that draws onto a [cairo::Context].
+    ///
+    /// The pixel size is the extents of the bounds times the image scale, rounded.
+    pub fn gen_with_cairo_exact_bounds<F>(
+        draw_func: F,
+        bounds: Aabb,
+        image_scale: f64,
+    ) -> anyhow::Result<Self>
+    where
+        F: FnOnce(&cairo::Context) -> anyhow::Result<()>,
+    {
+        bounds.assert_valid()?;
+
         let width_scaled = ((bounds.extents()[0]) * image_scale).round() as u32;
         let height_scaled = ((bounds.extents()[1]) * image_scale).round() as u32;
 
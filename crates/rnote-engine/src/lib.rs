@@ -41,7 +41,7 @@ pub use pens::PenHolder;
 pub use selectioncollision::SelectionCollision;
 pub use store::StrokeStore;
 pub use svg::Svg;
-pub use widgetflags::WidgetFlags;
+pub use widgetflags::{UserWarning, WidgetFlags};
 
 // Renames
 extern crate nalgebra as na;
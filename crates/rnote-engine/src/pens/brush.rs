@@ -1,25 +1,37 @@
 // Imports
 use super::PenBehaviour;
 use super::PenStyle;
+#[cfg(feature = "ui")]
 use super::pensconfig::brushconfig::BrushStyle;
-use crate::engine::{EngineView, EngineViewMut};
+use super::pensconfig::brushconfig::{BrushConfig, SymmetryConfig};
+use crate::engine::{EngineTask, EngineTaskSender, EngineView, EngineViewMut};
 use crate::store::StrokeKey;
+use crate::store::chrono_comp::StrokeLayer;
 use crate::strokes::BrushStroke;
+use crate::strokes::ShapeStroke;
 use crate::strokes::Stroke;
+use crate::tasks::OneOffTaskHandle;
 use crate::{DrawableOnDoc, WidgetFlags};
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use piet::RenderContext;
 use rnote_compose::Constraints;
 use rnote_compose::Style;
+use rnote_compose::Transform;
 use rnote_compose::builders::buildable::{Buildable, BuilderCreator, BuilderProgress};
 use rnote_compose::builders::{
     PenPathBuilderType, PenPathCurvedBuilder, PenPathModeledBuilder, PenPathSimpleBuilder,
+    Stabilizer,
 };
+use rnote_compose::color;
 use rnote_compose::eventresult::{EventPropagation, EventResult};
-use rnote_compose::penevent::{PenEvent, PenProgress};
-use rnote_compose::penpath::{Element, Segment};
-use std::time::Instant;
+use rnote_compose::penevent::{ModifierKey, PenEvent, PenProgress, PenState};
+use rnote_compose::penpath::{Element, InputSource, Segment};
+use rnote_compose::shapes::{Line, Shape, Shapeable, recognize_shape};
+use rnote_compose::style::indicators;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 enum BrushState {
     Idle,
@@ -27,18 +39,129 @@ enum BrushState {
         path_builder: Box<dyn Buildable<Emit = Segment>>,
         current_stroke_key: StrokeKey,
         preview_style: Style,
+        stabilizer: Option<Stabilizer>,
+        /// The straight line, while the line constraint is active.
+        line_constraint: Option<LineConstraint>,
+        /// The endpoint of another stroke the stroke end snaps to when the pen is lifted at the current position.
+        snap_indicator: Option<na::Vector2<f64>>,
+        /// The symmetric copies drawn in lockstep with the stroke, while symmetry drawing is enabled.
+        symmetry: Option<SymmetryCopies>,
+        /// The pen dwelling at a position, while hold to straighten is enabled.
+        hold: Option<HoldToStraighten>,
+        /// The last pen input, for the pressure indicator while it is enabled.
+        pen_tip: Option<Element>,
+        /// The kind of input device the stroke is drawn with.
+        source: InputSource,
     },
 }
 
+#[derive(Debug, Clone)]
+struct SymmetryCopies {
+    config: SymmetryConfig,
+    /// The stroke keys of the copies, ordered by the copy index.
+    keys: Vec<StrokeKey>,
+}
+
+impl SymmetryCopies {
+    /// The transforms from the drawn stroke onto the copies.
+    fn transforms(&self) -> impl Iterator<Item = Transform> + '_ {
+        (0..self.keys.len()).map(|index| {
+            let mut transform = Transform::default();
+            self.config.transform_to_copy(&mut transform, index);
+            transform
+        })
+    }
+
+    /// The bounds merged with the bounds of their copies.
+    fn merged_bounds(&self, bounds: Aabb) -> Aabb {
+        self.transforms().fold(bounds, |acc, transform| {
+            acc.merged(&transform.transform_aabb(bounds))
+        })
+    }
+}
+
+/// Tracks the pen dwelling at a position while drawing, to straighten the stroke when it is held still.
+#[derive(Debug)]
+struct HoldToStraighten {
+    /// The pen input the dwell started at.
+    anchor: Element,
+    /// The time the dwell started.
+    since: Instant,
+    /// Whether the stroke was already straightened for the current dwell.
+    straightened: bool,
+    /// Straightens the stroke when no pen input arrives while the pen is held still.
+    timeout_task: OneOffTaskHandle,
+}
+
+impl HoldToStraighten {
+    fn new(anchor: Element, now: Instant, timeout: Duration, tasks_tx: EngineTaskSender) -> Self {
+        let timeout_task = OneOffTaskHandle::new(
+            move || tasks_tx.send(EngineTask::StraightenHeldBrushStroke),
+            timeout,
+        );
+        Self {
+            anchor,
+            since: now,
+            straightened: false,
+            timeout_task,
+        }
+    }
+
+    /// Whether the pen is held still at the element long enough to straighten the stroke.
+    fn reached(&self, element: Element, now: Instant, timeout: Duration, radius: f64) -> bool {
+        !self.straightened
+            && (element.pos - self.anchor.pos).magnitude() <= radius
+            && now.duration_since(self.since) >= timeout
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LineConstraint {
+    /// The number of segments preceding the straight line.
+    start_index: usize,
+    /// The start of the line.
+    start: na::Vector2<f64>,
+    /// The current pen position. Differs from the line end when the line angle is snapped.
+    pen_pos: na::Vector2<f64>,
+    /// The direction of the previous stroke the line is kept parallel to, when hatching.
+    hatching_direction: Option<na::Vector2<f64>>,
+}
+
+impl LineConstraint {
+    /// The guide from the line start to the pen position.
+    fn guide_line(&self) -> Line {
+        Line {
+            start: self.start,
+            end: self.pen_pos,
+        }
+    }
+
+    /// The guide along the hatching direction through the line start, extending beyond the line by the extension.
+    fn hatching_guide(&self, extension: f64) -> Option<Line> {
+        let direction = self.hatching_direction?;
+        let t = (self.pen_pos - self.start).dot(&direction);
+        Some(Line {
+            start: self.start + direction * (t.min(0.0) - extension),
+            end: self.start + direction * (t.max(0.0) + extension),
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct Brush {
     state: BrushState,
+    /// The time of the last input from a pen, for rejecting touch input while the pen is active.
+    last_pen_input: Option<Instant>,
+    /// The last finished stroke, which is copied in stamp mode.
+    last_stroke_key: Option<StrokeKey>,
 }
 
 impl Default for Brush {
     fn default() -> Self {
         Self {
             state: BrushState::Idle,
+            last_pen_input: None,
+            last_stroke_key: None,
         }
     }
 }
@@ -56,6 +179,10 @@ impl PenBehaviour for Brush {
         PenStyle::Brush
     }
 
+    fn is_active(&self) -> bool {
+        matches!(self.state, BrushState::Drawing { .. })
+    }
+
     fn update_state(&mut self, _engine_view: &mut EngineViewMut) -> WidgetFlags {
         WidgetFlags::default()
     }
@@ -68,70 +195,156 @@ impl PenBehaviour for Brush {
     ) -> (EventResult<PenProgress>, WidgetFlags) {
         let mut widget_flags = WidgetFlags::default();
 
+        match &event {
+            PenEvent::Down { element, .. }
+            | PenEvent::Up { element, .. }
+            | PenEvent::Proximity { element, .. }
+                if element.source == InputSource::Pen =>
+            {
+                self.last_pen_input = Some(now);
+            }
+            PenEvent::Down { element, .. }
+                if element.source == InputSource::Touch
+                    && self.reject_touch(now, &engine_view.config.pens_config.brush_config) =>
+            {
+                // Left to the ui, which might use it for panning
+                let progress = match self.state {
+                    BrushState::Idle => PenProgress::Idle,
+                    BrushState::Drawing { .. } => PenProgress::InProgress,
+                };
+                return (
+                    EventResult {
+                        handled: false,
+                        propagate: EventPropagation::Proceed,
+                        progress,
+                    },
+                    widget_flags,
+                );
+            }
+            _ => {}
+        }
+
+        let stamp_key = self.stamp_key(engine_view);
+
         let event_result = match (&mut self.state, event) {
             (BrushState::Idle, PenEvent::Down { element, .. }) => {
-                if !element.filter_by_bounds(
-                    engine_view
-                        .document
-                        .bounds()
-                        .loosened(Self::INPUT_OVERSHOOT),
-                ) {
-                    #[cfg(feature = "ui")]
-                    {
-                        if engine_view.config.pens_config.brush_config.style == BrushStyle::Marker {
-                            play_marker_sound(engine_view);
-                        } else {
-                            trigger_brush_sound(engine_view);
-                        }
-                    }
+                if let Some(key) = stamp_key {
+                    widget_flags |= stamp_stroke(key, element, engine_view);
 
-                    engine_view
+                    EventResult {
+                        handled: true,
+                        propagate: EventPropagation::Stop,
+                        progress: PenProgress::Finished,
+                    }
+                } else {
+                    let input_overshoot = engine_view
                         .config
                         .pens_config
                         .brush_config
-                        .new_style_seeds();
+                        .input_overshoot
+                        .clamp(
+                            BrushConfig::INPUT_OVERSHOOT_MIN,
+                            BrushConfig::INPUT_OVERSHOOT_MAX,
+                        );
+                    if !element
+                        .filter_by_bounds(engine_view.document.bounds().loosened(input_overshoot))
+                    {
+                        #[cfg(feature = "ui")]
+                        {
+                            if matches!(
+                                engine_view.config.pens_config.brush_config.style,
+                                BrushStyle::Marker | BrushStyle::Highlighter
+                            ) {
+                                play_marker_sound(engine_view);
+                            } else {
+                                trigger_brush_sound(engine_view);
+                            }
+                        }
+
+                        let seed = engine_view.config.new_style_seed();
+                        engine_view
+                            .config
+                            .pens_config
+                            .brush_config
+                            .new_style_seeds(seed);
+
+                        // The whole stroke until it is finished or cancelled is recorded as a single history entry
+                        engine_view.store.begin_history_group();
 
-                    let preview_style = Self::get_preview_style(&engine_view.as_im());
-                    let brushstroke =
-                        Stroke::BrushStroke(BrushStroke::new(element, preview_style.clone()));
+                        let mut element = element;
+                        if let Some(endpoint) = endpoint_snap(element.pos, None, engine_view) {
+                            element.pos = endpoint;
+                        }
 
-                    let current_stroke_key = engine_view.store.insert_stroke(
-                        brushstroke,
-                        Some(
+                        let preview_style = Self::get_preview_style(&engine_view.as_im());
+                        let brushstroke =
+                            Stroke::BrushStroke(BrushStroke::new(element, preview_style.clone()));
+                        let layer = engine_view.store.in_active_layer(
                             engine_view
                                 .config
                                 .pens_config
                                 .brush_config
                                 .layer_for_current_options(),
-                        ),
-                    );
+                        );
 
-                    engine_view.store.regenerate_rendering_for_stroke(
-                        current_stroke_key,
-                        engine_view.camera.viewport(),
-                        engine_view.camera.image_scale(),
-                    );
+                        let current_stroke_key =
+                            engine_view.store.insert_stroke(brushstroke, Some(layer));
 
-                    self.state = BrushState::Drawing {
-                        path_builder: new_builder(
-                            engine_view.config.pens_config.brush_config.builder_type,
-                            element,
-                            now,
-                        ),
-                        current_stroke_key,
-                        preview_style,
-                    };
+                        engine_view.store.regenerate_rendering_for_stroke(
+                            current_stroke_key,
+                            engine_view.camera.viewport(),
+                            engine_view.camera.image_scale(),
+                        );
 
-                    EventResult {
-                        handled: true,
-                        propagate: EventPropagation::Stop,
-                        progress: PenProgress::InProgress,
-                    }
-                } else {
-                    EventResult {
-                        handled: false,
-                        propagate: EventPropagation::Proceed,
-                        progress: PenProgress::Idle,
+                        let symmetry_config = engine_view.config.pens_config.brush_config.symmetry;
+                        let symmetry = symmetry_config.map(|config| {
+                            start_symmetry_copies(
+                                config,
+                                element,
+                                &preview_style,
+                                layer,
+                                engine_view,
+                            )
+                        });
+
+                        self.state = BrushState::Drawing {
+                            path_builder: new_builder(
+                                &engine_view.config.pens_config.brush_config,
+                                element,
+                                now,
+                            ),
+                            current_stroke_key,
+                            preview_style,
+                            stabilizer: engine_view
+                                .config
+                                .pens_config
+                                .brush_config
+                                .stabilizer
+                                .map(|mode| Stabilizer::new(mode, element)),
+                            line_constraint: None,
+                            snap_indicator: None,
+                            symmetry,
+                            hold: None,
+                            pen_tip: engine_view
+                                .config
+                                .pens_config
+                                .brush_config
+                                .show_pressure_indicator
+                                .then_some(element),
+                            source: element.source,
+                        };
+
+                        EventResult {
+                            handled: true,
+                            propagate: EventPropagation::Stop,
+                            progress: PenProgress::InProgress,
+                        }
+                    } else {
+                        EventResult {
+                            handled: false,
+                            propagate: EventPropagation::Proceed,
+                            progress: PenProgress::Idle,
+                        }
                     }
                 }
             }
@@ -142,38 +355,165 @@ impl PenBehaviour for Brush {
             },
             (
                 BrushState::Drawing {
-                    current_stroke_key, ..
+                    current_stroke_key,
+                    line_constraint,
+                    symmetry,
+                    ..
                 },
-                PenEvent::Cancel,
-            ) => {
-                if let Some(Stroke::BrushStroke(brushstroke)) =
-                    engine_view.store.get_stroke_mut(*current_stroke_key)
-                {
-                    brushstroke.style = engine_view
-                        .config
-                        .pens_config
-                        .brush_config
-                        .style_for_current_options();
+                PenEvent::Down {
+                    element,
+                    modifier_keys,
+                },
+            ) if modifier_keys.contains(&Self::LINE_CONSTRAINT_MODIFIER_KEY) => {
+                let hatching_direction = if line_constraint.is_none() {
+                    hatching_direction(self.last_stroke_key, &modifier_keys, engine_view)
+                } else {
+                    None
+                };
+                widget_flags |= update_line_constraint(
+                    line_constraint,
+                    *current_stroke_key,
+                    element,
+                    hatching_direction,
+                    engine_view,
+                );
+                if let Some(symmetry) = symmetry {
+                    widget_flags |=
+                        sync_symmetry_copies(symmetry, *current_stroke_key, engine_view);
                 }
 
-                // Finish up the last stroke
-                engine_view
+                EventResult {
+                    handled: true,
+                    propagate: EventPropagation::Stop,
+                    progress: PenProgress::InProgress,
+                }
+            }
+            (
+                BrushState::Drawing {
+                    path_builder,
+                    current_stroke_key,
+                    stabilizer,
+                    line_constraint,
+                    ..
+                },
+                PenEvent::Down { element, .. },
+            ) if line_constraint.is_some() => {
+                // The line constraint was released, resume drawing freehand from the end of the line
+                *line_constraint = None;
+                let line_end = engine_view
                     .store
-                    .update_geometry_for_stroke(*current_stroke_key);
-                engine_view.store.regenerate_rendering_for_stroke_threaded(
-                    engine_view.tasks_tx.clone(),
+                    .get_stroke_ref(*current_stroke_key)
+                    .and_then(|stroke| match stroke {
+                        Stroke::BrushStroke(brushstroke) => {
+                            brushstroke.path().segments.last().map(|seg| seg.end())
+                        }
+                        _ => None,
+                    })
+                    .unwrap_or(element);
+                *path_builder =
+                    new_builder(&engine_view.config.pens_config.brush_config, line_end, now);
+                *stabilizer = engine_view
+                    .config
+                    .pens_config
+                    .brush_config
+                    .stabilizer
+                    .map(|mode| Stabilizer::new(mode, line_end));
+                widget_flags.redraw = true;
+
+                EventResult {
+                    handled: true,
+                    propagate: EventPropagation::Stop,
+                    progress: PenProgress::InProgress,
+                }
+            }
+            (
+                BrushState::Drawing {
+                    current_stroke_key,
+                    line_constraint,
+                    symmetry,
+                    ..
+                },
+                PenEvent::Up { element, .. },
+            ) if line_constraint.is_some() => {
+                widget_flags |= update_line_constraint(
+                    line_constraint,
                     *current_stroke_key,
-                    engine_view.camera.viewport(),
-                    engine_view.camera.image_scale(),
+                    element,
+                    None,
+                    engine_view,
                 );
-                widget_flags |= engine_view
-                    .document
-                    .resize_autoexpand(engine_view.store, engine_view.camera);
-
+                widget_flags |= finish_stroke(*current_stroke_key, symmetry.as_ref(), engine_view);
+                self.last_stroke_key = Some(*current_stroke_key);
                 self.state = BrushState::Idle;
 
-                widget_flags |= engine_view.store.record(Instant::now());
-                widget_flags.store_modified = true;
+                EventResult {
+                    handled: true,
+                    propagate: EventPropagation::Stop,
+                    progress: PenProgress::Finished,
+                }
+            }
+            (
+                BrushState::Drawing {
+                    path_builder,
+                    current_stroke_key,
+                    stabilizer,
+                    symmetry,
+                    hold: Some(hold),
+                    ..
+                },
+                PenEvent::Down { element, .. },
+            ) if engine_view
+                .config
+                .pens_config
+                .brush_config
+                .hold_to_straighten
+                .is_some_and(|timeout| {
+                    hold.reached(
+                        element,
+                        now,
+                        timeout,
+                        Self::hold_to_straighten_radius(engine_view),
+                    )
+                }) =>
+            {
+                widget_flags |= straighten_to_held_pos(
+                    path_builder,
+                    stabilizer,
+                    hold,
+                    *current_stroke_key,
+                    symmetry.as_ref(),
+                    now,
+                    engine_view,
+                );
+
+                EventResult {
+                    handled: true,
+                    propagate: EventPropagation::Stop,
+                    progress: PenProgress::InProgress,
+                }
+            }
+            (
+                BrushState::Drawing {
+                    current_stroke_key,
+                    symmetry,
+                    ..
+                },
+                PenEvent::Cancel,
+            ) => {
+                if engine_view
+                    .config
+                    .pens_config
+                    .brush_config
+                    .discard_on_cancel
+                {
+                    widget_flags |=
+                        discard_stroke(*current_stroke_key, symmetry.as_ref(), engine_view);
+                } else {
+                    widget_flags |=
+                        finish_stroke(*current_stroke_key, symmetry.as_ref(), engine_view);
+                    self.last_stroke_key = Some(*current_stroke_key);
+                }
+                self.state = BrushState::Idle;
 
                 EventResult {
                     handled: true,
@@ -185,12 +525,77 @@ impl PenBehaviour for Brush {
                 BrushState::Drawing {
                     path_builder,
                     current_stroke_key,
+                    preview_style,
+                    stabilizer,
+                    snap_indicator,
+                    symmetry,
+                    pen_tip,
+                    hold,
                     ..
                 },
                 pen_event,
             ) => {
-                let builder_result =
-                    path_builder.handle_event(pen_event, now, Constraints::default());
+                let zoom = engine_view.camera.zoom();
+                let prev_preview_bounds = Self::preview_bounds(
+                    path_builder.as_ref(),
+                    preview_style,
+                    stabilizer.as_ref(),
+                    *snap_indicator,
+                    symmetry.as_ref(),
+                    *pen_tip,
+                    zoom,
+                );
+                match pen_event {
+                    PenEvent::Down { element, .. }
+                        if engine_view
+                            .config
+                            .pens_config
+                            .brush_config
+                            .show_pressure_indicator =>
+                    {
+                        *pen_tip = Some(element);
+                    }
+                    PenEvent::Up { .. } | PenEvent::Cancel => *pen_tip = None,
+                    _ => {}
+                }
+                let pen_event = match pen_event {
+                    PenEvent::Down { element, .. } => {
+                        *snap_indicator =
+                            endpoint_snap(element.pos, Some(*current_stroke_key), engine_view);
+                        update_hold(hold, element, now, engine_view);
+                        pen_event
+                    }
+                    PenEvent::Up {
+                        mut element,
+                        modifier_keys,
+                    } => {
+                        if let Some(endpoint) =
+                            endpoint_snap(element.pos, Some(*current_stroke_key), engine_view)
+                        {
+                            element.pos = endpoint;
+                        }
+                        PenEvent::Up {
+                            element,
+                            modifier_keys,
+                        }
+                    }
+                    pen_event => pen_event,
+                };
+                let builder_result = match stabilizer {
+                    Some(stabilizer) => {
+                        handle_stabilized_event(path_builder.as_mut(), stabilizer, pen_event, now)
+                    }
+                    None => path_builder.handle_event(pen_event, now, Constraints::default()),
+                };
+                let preview_bounds = Self::preview_bounds(
+                    path_builder.as_ref(),
+                    preview_style,
+                    stabilizer.as_ref(),
+                    *snap_indicator,
+                    symmetry.as_ref(),
+                    *pen_tip,
+                    zoom,
+                );
                 let handled = builder_result.handled;
                 let propagate = builder_result.propagate;
 
@@ -198,33 +603,56 @@ impl PenBehaviour for Brush {
                     BuilderProgress::InProgress => {
                         #[cfg(feature = "ui")]
                         {
-                            if engine_view.config.pens_config.brush_config.style
-                                != BrushStyle::Marker
-                            {
+                            if !matches!(
+                                engine_view.config.pens_config.brush_config.style,
+                                BrushStyle::Marker | BrushStyle::Highlighter
+                            ) {
                                 trigger_brush_sound(engine_view);
                             }
                         }
 
+                        // Only the area of the preview needs to be redrawn
+                        widget_flags.redraw_region = prev_preview_bounds
+                            .into_iter()
+                            .chain(preview_bounds)
+                            .reduce(|acc, bounds| acc.merged(&bounds));
+
                         PenProgress::InProgress
                     }
                     BuilderProgress::EmitContinue(segments) => {
                         #[cfg(feature = "ui")]
                         {
-                            if engine_view.config.pens_config.brush_config.style
-                                != BrushStyle::Marker
-                            {
+                            if !matches!(
+                                engine_view.config.pens_config.brush_config.style,
+                                BrushStyle::Marker | BrushStyle::Highlighter
+                            ) {
                                 trigger_brush_sound(engine_view);
                             }
                         }
 
                         let n_segments = segments.len();
+                        let mut segments_bounds = None;
 
                         if n_segments != 0 {
+                            if let Some(symmetry) = symmetry {
+                                segments_bounds =
+                                    extend_symmetry_copies(symmetry, &segments, engine_view);
+                            }
                             if let Some(Stroke::BrushStroke(brushstroke)) =
                                 engine_view.store.get_stroke_mut(*current_stroke_key)
                             {
                                 brushstroke.extend_w_segments(segments);
                                 widget_flags.store_modified = true;
+                                // Strokes with color gradients are entirely rerendered
+                                let stroke_bounds = if brushstroke.has_color_gradient() {
+                                    brushstroke.bounds()
+                                } else {
+                                    brushstroke.last_segments_bounds(n_segments)
+                                };
+                                segments_bounds =
+                                    Some(segments_bounds.map_or(stroke_bounds, |bounds| {
+                                        bounds.merged(&stroke_bounds)
+                                    }));
                             }
 
                             engine_view.store.append_rendering_last_segments(
@@ -236,12 +664,22 @@ impl PenBehaviour for Brush {
                             );
                         }
 
+                        // Only the area of the preview and the appended segments needs to be redrawn
+                        widget_flags.redraw_region =
+                            [prev_preview_bounds, preview_bounds, segments_bounds]
+                                .into_iter()
+                                .flatten()
+                                .reduce(|acc, bounds| acc.merged(&bounds));
+
                         PenProgress::InProgress
                     }
                     BuilderProgress::Finished(segments) => {
                         let n_segments = segments.len();
 
                         if n_segments != 0 {
+                            if let Some(symmetry) = symmetry {
+                                extend_symmetry_copies(symmetry, &segments, engine_view);
+                            }
                             if let Some(Stroke::BrushStroke(brushstroke)) =
                                 engine_view.store.get_stroke_mut(*current_stroke_key)
                             {
@@ -258,35 +696,11 @@ impl PenBehaviour for Brush {
                             );
                         }
 
-                        if let Some(Stroke::BrushStroke(brushstroke)) =
-                            engine_view.store.get_stroke_mut(*current_stroke_key)
-                        {
-                            brushstroke.style = engine_view
-                                .config
-                                .pens_config
-                                .brush_config
-                                .style_for_current_options();
-                        }
-
-                        // Finish up the last stroke
-                        engine_view
-                            .store
-                            .update_geometry_for_stroke(*current_stroke_key);
-                        engine_view.store.regenerate_rendering_for_stroke_threaded(
-                            engine_view.tasks_tx.clone(),
-                            *current_stroke_key,
-                            engine_view.camera.viewport(),
-                            engine_view.camera.image_scale(),
-                        );
-                        widget_flags |= engine_view
-                            .document
-                            .resize_autoexpand(engine_view.store, engine_view.camera);
-
+                        widget_flags |=
+                            finish_stroke(*current_stroke_key, symmetry.as_ref(), engine_view);
+                        self.last_stroke_key = Some(*current_stroke_key);
                         self.state = BrushState::Idle;
 
-                        widget_flags |= engine_view.store.record(Instant::now());
-                        widget_flags.store_modified = true;
-
                         PenProgress::Finished
                     }
                 };
@@ -313,8 +727,47 @@ impl DrawableOnDoc for Brush {
 
         match &self.state {
             BrushState::Idle => None,
-            BrushState::Drawing { path_builder, .. } => {
-                path_builder.bounds(&style, engine_view.camera.zoom())
+            BrushState::Drawing {
+                path_builder,
+                stabilizer,
+                line_constraint,
+                snap_indicator,
+                symmetry,
+                pen_tip,
+                ..
+            } => {
+                let zoom = engine_view.camera.zoom();
+                [
+                    Self::preview_bounds(
+                        path_builder.as_ref(),
+                        &style,
+                        stabilizer.as_ref(),
+                        *snap_indicator,
+                        symmetry.as_ref(),
+                        *pen_tip,
+                        zoom,
+                    ),
+                    line_constraint
+                        .filter(|_| {
+                            engine_view
+                                .config
+                                .pens_config
+                                .brush_config
+                                .snap_angle
+                                .is_some()
+                        })
+                        .map(|line_constraint| {
+                            Self::trailing_line_bounds(line_constraint.guide_line(), zoom)
+                        }),
+                    line_constraint
+                        .and_then(|line_constraint| {
+                            line_constraint.hatching_guide(Self::HATCHING_GUIDE_EXTENSION / zoom)
+                        })
+                        .map(|guide| Self::trailing_line_bounds(guide, zoom)),
+                ]
+                .into_iter()
+                .flatten()
+                .reduce(|acc, bounds| acc.merged(&bounds))
             }
         }
     }
@@ -331,20 +784,107 @@ impl DrawableOnDoc for Brush {
             BrushState::Drawing {
                 path_builder,
                 preview_style,
+                stabilizer,
+                line_constraint,
+                snap_indicator,
+                symmetry,
+                pen_tip,
                 ..
             } => {
-                match engine_view.config.pens_config.brush_config.style {
-                    BrushStyle::Marker => {
-                        // Don't draw the marker, as the pen would render on top of other strokes, while the stroke itself would render underneath them.
-                    }
-                    BrushStyle::Solid | BrushStyle::Textured => {
-                        path_builder.draw_styled(
-                            cx,
-                            preview_style,
-                            engine_view.camera.total_zoom(),
-                        );
+                if engine_view
+                    .config
+                    .pens_config
+                    .brush_config
+                    .draws_below_strokes()
+                {
+                    // Don't draw the marker, as the pen would render on top of other strokes, while the stroke itself would render underneath them.
+                } else if line_constraint.is_some() {
+                    // The builder is paused while the line constraint is active, the line is part of the stroke
+                } else {
+                    path_builder.draw_styled(cx, preview_style, engine_view.camera.total_zoom());
+                    if let Some(symmetry) = symmetry {
+                        for transform in symmetry.transforms() {
+                            cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+                            cx.transform(kurbo::Affine::from(transform));
+                            path_builder.draw_styled(
+                                cx,
+                                preview_style,
+                                engine_view.camera.total_zoom(),
+                            );
+                            cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+                        }
                     }
                 }
+
+                // The guide to the pen position shows how far the line end is snapped away from it
+                if let Some(line_constraint) = line_constraint
+                    && engine_view
+                        .config
+                        .pens_config
+                        .brush_config
+                        .snap_angle
+                        .is_some()
+                {
+                    const SNAP_GUIDE_COLOR: piet::Color = color::GNOME_BLUES[3].with_a8(120);
+
+                    cx.stroke(
+                        line_constraint.guide_line().outline_path(),
+                        &SNAP_GUIDE_COLOR,
+                        Self::TRAILING_LINE_WIDTH / engine_view.camera.total_zoom(),
+                    );
+                }
+
+                // The guide along the direction of the previous stroke shows the angle the line is kept at
+                if let Some(guide) = line_constraint.and_then(|line_constraint| {
+                    line_constraint.hatching_guide(
+                        Self::HATCHING_GUIDE_EXTENSION / engine_view.camera.total_zoom(),
+                    )
+                }) {
+                    const HATCHING_GUIDE_COLOR: piet::Color = color::GNOME_GREENS[3].with_a8(160);
+
+                    cx.stroke(
+                        guide.outline_path(),
+                        &HATCHING_GUIDE_COLOR,
+                        Self::TRAILING_LINE_WIDTH / engine_view.camera.total_zoom(),
+                    );
+                }
+
+                if let Some(snap_indicator) = snap_indicator {
+                    indicators::draw_pos_indicator(
+                        cx,
+                        PenState::Down,
+                        *snap_indicator,
+                        engine_view.camera.total_zoom(),
+                    );
+                }
+
+                if let Some(pen_tip) = pen_tip {
+                    const PRESSURE_INDICATOR_FILL_COLOR: piet::Color =
+                        color::GNOME_DARKS[3].with_a8(30);
+                    const PRESSURE_INDICATOR_OUTLINE_COLOR: piet::Color =
+                        color::GNOME_DARKS[3].with_a8(120);
+                    let circle = kurbo::Circle::new(
+                        kurbo::Point::new(pen_tip.pos[0], pen_tip.pos[1]),
+                        Self::pressure_indicator_radius(*pen_tip, preview_style),
+                    );
+
+                    cx.fill(circle, &PRESSURE_INDICATOR_FILL_COLOR);
+                    cx.stroke(
+                        circle,
+                        &PRESSURE_INDICATOR_OUTLINE_COLOR,
+                        Self::PRESSURE_INDICATOR_OUTLINE_WIDTH / engine_view.camera.total_zoom(),
+                    );
+                }
+
+                if let Some(stabilizer) = stabilizer {
+                    const TRAILING_LINE_COLOR: piet::Color = color::GNOME_BLUES[3].with_a8(200);
+
+                    cx.stroke(
+                        stabilizer.trailing_line().outline_path(),
+                        &TRAILING_LINE_COLOR,
+                        Self::TRAILING_LINE_WIDTH / engine_view.camera.total_zoom(),
+                    );
+                }
             }
         }
 
@@ -354,7 +894,145 @@ impl DrawableOnDoc for Brush {
 }
 
 impl Brush {
-    const INPUT_OVERSHOOT: f64 = 30.0;
+    /// While held, the brush draws a straight line from the point where it was pressed.
+    const LINE_CONSTRAINT_MODIFIER_KEY: ModifierKey = ModifierKey::KeyboardShift;
+    /// Toggles keeping the straight line parallel to the previous stroke, when held while starting the line.
+    const HATCHING_TOGGLE_MODIFIER_KEY: ModifierKey = ModifierKey::KeyboardCtrl;
+    /// How far the hatching guide extends beyond the line, in surface coordinates.
+    const HATCHING_GUIDE_EXTENSION: f64 = 48.0;
+    /// The width of the stabilizer trailing line, in surface coordinates.
+    const TRAILING_LINE_WIDTH: f64 = 1.5;
+    /// The outline width of the pressure indicator, in surface coordinates.
+    const PRESSURE_INDICATOR_OUTLINE_WIDTH: f64 = 1.0;
+    /// How long touch input is still rejected after the last pen input, since the palm usually rests a bit longer.
+    const TOUCH_REJECTION_TIMEOUT: Duration = Duration::from_millis(500);
+    /// The radius the pen may move within while it is held still to straighten the stroke, in surface coordinates.
+    const HOLD_TO_STRAIGHTEN_RADIUS: f64 = 4.0;
+
+    /// Straighten the stroke in progress into a line from its start to the position the pen is held at,
+    /// when the hold timeout is reached without the pen having moved on.
+    pub(crate) fn straighten_held_stroke(
+        &mut self,
+        now: Instant,
+        engine_view: &mut EngineViewMut,
+    ) -> WidgetFlags {
+        let BrushState::Drawing {
+            path_builder,
+            current_stroke_key,
+            stabilizer,
+            line_constraint: None,
+            symmetry,
+            hold: Some(hold),
+            ..
+        } = &mut self.state
+        else {
+            return WidgetFlags::default();
+        };
+        if hold.straightened {
+            return WidgetFlags::default();
+        }
+        straighten_to_held_pos(
+            path_builder,
+            stabilizer,
+            hold,
+            *current_stroke_key,
+            symmetry.as_ref(),
+            now,
+            engine_view,
+        )
+    }
+
+    /// The radius the pen may move within while it is held still, in document coordinates.
+    fn hold_to_straighten_radius(engine_view: &EngineViewMut) -> f64 {
+        Self::HOLD_TO_STRAIGHTEN_RADIUS / engine_view.camera.total_zoom()
+    }
+
+    /// The key of the last finished stroke when stamp mode is enabled and it is still a brush stroke in the document.
+    fn stamp_key(&self, engine_view: &EngineViewMut) -> Option<StrokeKey> {
+        if !engine_view.config.pens_config.brush_config.stamp_mode {
+            return None;
+        }
+        let key = self.last_stroke_key?;
+        (!engine_view.store.trashed(key)?
+            && matches!(
+                engine_view.store.get_stroke_ref(key),
+                Some(Stroke::BrushStroke(_))
+            ))
+        .then_some(key)
+    }
+
+    /// Whether touch input should currently be rejected, because a pen stroke is in progress or recently ended.
+    fn reject_touch(&self, now: Instant, brush_config: &BrushConfig) -> bool {
+        if !brush_config.ignore_touch_while_pen_active {
+            return false;
+        }
+        matches!(
+            self.state,
+            BrushState::Drawing {
+                source: InputSource::Pen,
+                ..
+            }
+        ) || self.last_pen_input.is_some_and(|last_pen_input| {
+            now.saturating_duration_since(last_pen_input) < Self::TOUCH_REJECTION_TIMEOUT
+        })
+    }
+
+    fn trailing_line_bounds(line: Line, zoom: f64) -> Aabb {
+        line.bounds().loosened(Self::TRAILING_LINE_WIDTH / zoom)
+    }
+
+    /// The radius of the pressure indicator, matching the width a stroke has with the pressure.
+    fn pressure_indicator_radius(element: Element, style: &Style) -> f64 {
+        let width = match style {
+            Style::Smooth(options) => options.pressure_width(element.pressure),
+            Style::Textured(options) => options
+                .pressure_curve
+                .apply(options.stroke_width, element.pressure),
+            Style::Rough(options) => options.stroke_width,
+        };
+        width * 0.5
+    }
+
+    fn pressure_indicator_bounds(element: Element, style: &Style, zoom: f64) -> Aabb {
+        Aabb::from_half_extents(
+            element.pos.into(),
+            na::Vector2::repeat(
+                Self::pressure_indicator_radius(element, style)
+                    + Self::PRESSURE_INDICATOR_OUTLINE_WIDTH / zoom,
+            ),
+        )
+    }
+
+    fn snap_indicator_bounds(pos: na::Vector2<f64>, zoom: f64) -> Aabb {
+        Aabb::from_half_extents(
+            pos.into(),
+            na::Vector2::repeat(
+                (indicators::POS_INDICATOR_RADIUS + indicators::POS_INDICATOR_OUTLINE_WIDTH) / zoom,
+            ),
+        )
+    }
+
+    /// The bounds of the stroke preview and the indicators that are drawn along with it.
+    fn preview_bounds(
+        path_builder: &dyn Buildable<Emit = Segment>,
+        style: &Style,
+        stabilizer: Option<&Stabilizer>,
+        snap_indicator: Option<na::Vector2<f64>>,
+        symmetry: Option<&SymmetryCopies>,
+        pen_tip: Option<Element>,
+        zoom: f64,
+    ) -> Option<Aabb> {
+        [
+            symmetry_bounds(path_builder.bounds(style, zoom), symmetry),
+            stabilizer
+                .map(|stabilizer| Self::trailing_line_bounds(stabilizer.trailing_line(), zoom)),
+            snap_indicator.map(|pos| Self::snap_indicator_bounds(pos, zoom)),
+            pen_tip.map(|element| Self::pressure_indicator_bounds(element, style, zoom)),
+        ]
+        .into_iter()
+        .flatten()
+        .reduce(|acc, bounds| acc.merged(&bounds))
+    }
 
     fn get_preview_style(engine_view: &EngineView) -> Style {
         let mut style = engine_view
@@ -374,26 +1052,584 @@ impl Brush {
 
 #[cfg(feature = "ui")]
 fn play_marker_sound(engine_view: &mut EngineViewMut) {
-    if let Some(audioplayer) = engine_view.audioplayer {
+    if let Some(audioplayer) = engine_view.audioplayer
+        && audioplayer.enabled()
+    {
         audioplayer.play_random_marker_sound();
     }
 }
 
 #[cfg(feature = "ui")]
 fn trigger_brush_sound(engine_view: &mut EngineViewMut) {
-    if let Some(audioplayer) = engine_view.audioplayer.as_mut() {
+    if let Some(audioplayer) = engine_view.audioplayer.as_mut()
+        && audioplayer.enabled()
+    {
         audioplayer.trigger_random_brush_sound();
     }
 }
 
+/// The bounds merged with the bounds of the symmetric copies, if symmetry drawing is active.
+fn symmetry_bounds(bounds: Option<Aabb>, symmetry: Option<&SymmetryCopies>) -> Option<Aabb> {
+    match symmetry {
+        Some(symmetry) => bounds.map(|bounds| symmetry.merged_bounds(bounds)),
+        None => bounds,
+    }
+}
+
+/// Inserts the symmetric copies of a new stroke starting at the element.
+fn start_symmetry_copies(
+    config: SymmetryConfig,
+    element: Element,
+    style: &Style,
+    layer: StrokeLayer,
+    engine_view: &mut EngineViewMut,
+) -> SymmetryCopies {
+    let keys = (0..config.n_copies())
+        .map(|index| {
+            let mut element = element;
+            config.transform_to_copy(&mut element, index);
+            let key = engine_view.store.insert_stroke(
+                Stroke::BrushStroke(BrushStroke::new(element, style.clone())),
+                Some(layer),
+            );
+            engine_view.store.regenerate_rendering_for_stroke(
+                key,
+                engine_view.camera.viewport(),
+                engine_view.camera.image_scale(),
+            );
+            key
+        })
+        .collect();
+
+    SymmetryCopies { config, keys }
+}
+
+/// Appends the segments transformed onto the symmetric copies. Returns the bounds that need to be redrawn.
+fn extend_symmetry_copies(
+    symmetry: &SymmetryCopies,
+    segments: &[Segment],
+    engine_view: &mut EngineViewMut,
+) -> Option<Aabb> {
+    let mut bounds: Option<Aabb> = None;
+
+    for (index, &key) in symmetry.keys.iter().enumerate() {
+        let copy_segments = segments.iter().map(|segment| {
+            let mut segment = *segment;
+            symmetry.config.transform_to_copy(&mut segment, index);
+            segment
+        });
+        if let Some(Stroke::BrushStroke(brushstroke)) = engine_view.store.get_stroke_mut(key) {
+            brushstroke.extend_w_segments(copy_segments);
+            let stroke_bounds = if brushstroke.has_color_gradient() {
+                brushstroke.bounds()
+            } else {
+                brushstroke.last_segments_bounds(segments.len())
+            };
+            bounds = Some(bounds.map_or(stroke_bounds, |acc| acc.merged(&stroke_bounds)));
+        }
+
+        engine_view.store.append_rendering_last_segments(
+            engine_view.tasks_tx.clone(),
+            key,
+            segments.len(),
+            engine_view.camera.viewport(),
+            engine_view.camera.image_scale(),
+        );
+    }
+
+    bounds
+}
+
+/// Replaces the paths and styles of the symmetric copies with the transformed path and the style of the drawn
+/// stroke.
+fn sync_symmetry_copies(
+    symmetry: &SymmetryCopies,
+    current_stroke_key: StrokeKey,
+    engine_view: &mut EngineViewMut,
+) -> WidgetFlags {
+    let mut widget_flags = WidgetFlags::default();
+
+    let Some(stroke) = engine_view
+        .store
+        .get_stroke_ref(current_stroke_key)
+        .cloned()
+    else {
+        return widget_flags;
+    };
+
+    for (index, &key) in symmetry.keys.iter().enumerate() {
+        let Some(copy) = engine_view.store.get_stroke_mut(key) else {
+            continue;
+        };
+        match (&stroke, copy) {
+            (Stroke::BrushStroke(brushstroke), Stroke::BrushStroke(copy)) => {
+                let mut copy_path = brushstroke.path().clone();
+                symmetry.config.transform_to_copy(&mut copy_path, index);
+                *copy.style_mut() = brushstroke.style().clone();
+                copy.replace_path(copy_path);
+            }
+            // The drawn stroke was recognized as shape
+            (Stroke::ShapeStroke(shapestroke), copy) => {
+                let mut copy_shape = shapestroke.shape.clone();
+                symmetry.config.transform_to_copy(&mut copy_shape, index);
+                *copy =
+                    Stroke::ShapeStroke(ShapeStroke::new(copy_shape, shapestroke.style.clone()));
+            }
+            _ => {}
+        }
+
+        engine_view.store.update_bounds_for_stroke(key);
+        engine_view.store.regenerate_rendering_for_stroke_threaded(
+            engine_view.tasks_tx.clone(),
+            key,
+            engine_view.camera.viewport(),
+            engine_view.camera.image_scale(),
+        );
+    }
+    widget_flags.redraw = true;
+    widget_flags.store_modified = true;
+
+    widget_flags
+}
+
+/// The endpoint of existing brush strokes the position snaps to, when endpoint snapping is enabled.
+fn endpoint_snap(
+    pos: na::Vector2<f64>,
+    current_stroke_key: Option<StrokeKey>,
+    engine_view: &EngineViewMut,
+) -> Option<na::Vector2<f64>> {
+    let radius = engine_view
+        .config
+        .endpoint_snap_radius
+        .filter(|radius| *radius > 0.0)?;
+
+    engine_view
+        .store
+        .nearest_brushstroke_endpoint(pos, radius, current_stroke_key)
+}
+
+/// The dominant direction of the previous stroke that straight lines are kept parallel to, when hatching is enabled.
+///
+/// The hatching modifier key toggles the setting.
+fn hatching_direction(
+    last_stroke_key: Option<StrokeKey>,
+    modifier_keys: &HashSet<ModifierKey>,
+    engine_view: &EngineViewMut,
+) -> Option<na::Vector2<f64>> {
+    let enabled = engine_view
+        .config
+        .pens_config
+        .brush_config
+        .snap_to_previous_angle
+        != modifier_keys.contains(&Brush::HATCHING_TOGGLE_MODIFIER_KEY);
+    if !enabled {
+        return None;
+    }
+    match engine_view.store.get_stroke_ref(last_stroke_key?)? {
+        Stroke::BrushStroke(brushstroke) => brushstroke.path().dominant_direction(),
+        Stroke::ShapeStroke(shapestroke) => match &shapestroke.shape {
+            Shape::Line(line) => (line.end - line.start).try_normalize(f64::EPSILON),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Replaces the segments after the line start with a single straight line to the element.
+///
+/// Activates the line constraint at the current end of the stroke if it is not already active.
+/// The line end is kept parallel to the previous stroke when hatching, or else when an angle increment for snapping
+/// is configured, it is projected onto the nearest snapped angle.
+fn update_line_constraint(
+    line_constraint: &mut Option<LineConstraint>,
+    current_stroke_key: StrokeKey,
+    element: Element,
+    hatching_direction: Option<na::Vector2<f64>>,
+    engine_view: &mut EngineViewMut,
+) -> WidgetFlags {
+    let mut widget_flags = WidgetFlags::default();
+    let snap_angle = engine_view.config.pens_config.brush_config.snap_angle;
+
+    let Some(Stroke::BrushStroke(brushstroke)) =
+        engine_view.store.get_stroke_mut(current_stroke_key)
+    else {
+        return widget_flags;
+    };
+    let constraint = line_constraint.get_or_insert_with(|| LineConstraint {
+        start_index: brushstroke.path().segments.len(),
+        start: brushstroke
+            .path()
+            .segments
+            .last()
+            .map(|seg| seg.end())
+            .unwrap_or(brushstroke.path().start)
+            .pos,
+        pen_pos: element.pos,
+        hatching_direction,
+    });
+    constraint.pen_pos = element.pos;
+
+    let mut end = element;
+    if let Some(direction) = constraint.hatching_direction {
+        end.pos = constraint.start + direction * (element.pos - constraint.start).dot(&direction);
+    } else if let Some(snap_angle) = snap_angle.filter(|snap_angle| *snap_angle > 0.0) {
+        let increment = snap_angle.to_radians();
+        let offset = element.pos - constraint.start;
+        let angle = (offset[1].atan2(offset[0]) / increment).round() * increment;
+        let direction = na::vector![angle.cos(), angle.sin()];
+        end.pos = constraint.start + direction * offset.dot(&direction);
+    }
+
+    let mut path = brushstroke.path().clone();
+    path.segments.truncate(constraint.start_index);
+    path.segments.push(Segment::LineTo { end });
+    brushstroke.replace_path(path);
+
+    engine_view
+        .store
+        .update_bounds_for_stroke(current_stroke_key);
+    engine_view.store.regenerate_rendering_for_stroke(
+        current_stroke_key,
+        engine_view.camera.viewport(),
+        engine_view.camera.image_scale(),
+    );
+    widget_flags.redraw = true;
+    widget_flags.store_modified = true;
+
+    widget_flags
+}
+
+/// Restarts the dwell when the pen moved on beyond the hold radius, or stops tracking it when hold to straighten
+/// is disabled.
+fn update_hold(
+    hold: &mut Option<HoldToStraighten>,
+    element: Element,
+    now: Instant,
+    engine_view: &EngineViewMut,
+) {
+    let Some(timeout) = engine_view
+        .config
+        .pens_config
+        .brush_config
+        .hold_to_straighten
+    else {
+        *hold = None;
+        return;
+    };
+    let radius = Brush::hold_to_straighten_radius(engine_view);
+
+    match hold {
+        Some(hold) if (element.pos - hold.anchor.pos).magnitude() <= radius => {}
+        Some(hold) => {
+            if hold.timeout_task.reset_timeout().is_ok() {
+                hold.anchor = element;
+                hold.since = now;
+                hold.straightened = false;
+            } else {
+                // The timeout task already fired, so a new one needs to be started
+                *hold = HoldToStraighten::new(element, now, timeout, engine_view.tasks_tx.clone());
+            }
+        }
+        None => {
+            *hold = Some(HoldToStraighten::new(
+                element,
+                now,
+                timeout,
+                engine_view.tasks_tx.clone(),
+            ));
+        }
+    }
+}
+
+/// Replaces the stroke with a straight line from its start to the position the pen is held at.
+///
+/// Drawing then resumes freehand from the end of the line.
+fn straighten_to_held_pos(
+    path_builder: &mut Box<dyn Buildable<Emit = Segment>>,
+    stabilizer: &mut Option<Stabilizer>,
+    hold: &mut HoldToStraighten,
+    current_stroke_key: StrokeKey,
+    symmetry: Option<&SymmetryCopies>,
+    now: Instant,
+    engine_view: &mut EngineViewMut,
+) -> WidgetFlags {
+    let mut widget_flags = WidgetFlags::default();
+    hold.straightened = true;
+
+    let Some(Stroke::BrushStroke(brushstroke)) =
+        engine_view.store.get_stroke_mut(current_stroke_key)
+    else {
+        return widget_flags;
+    };
+    let mut path = brushstroke.path().clone();
+    path.segments = vec![Segment::LineTo { end: hold.anchor }];
+    brushstroke.replace_path(path);
+
+    engine_view
+        .store
+        .update_bounds_for_stroke(current_stroke_key);
+    engine_view.store.regenerate_rendering_for_stroke(
+        current_stroke_key,
+        engine_view.camera.viewport(),
+        engine_view.camera.image_scale(),
+    );
+    if let Some(symmetry) = symmetry {
+        widget_flags |= sync_symmetry_copies(symmetry, current_stroke_key, engine_view);
+    }
+
+    *path_builder = new_builder(
+        &engine_view.config.pens_config.brush_config,
+        hold.anchor,
+        now,
+    );
+    *stabilizer = engine_view
+        .config
+        .pens_config
+        .brush_config
+        .stabilizer
+        .map(|mode| Stabilizer::new(mode, hold.anchor));
+    widget_flags.redraw = true;
+    widget_flags.store_modified = true;
+
+    widget_flags
+}
+
+/// Finishes the stroke with the current brush style, updating its geometry and rendering and recording it
+/// in the history.
+///
+/// The symmetric copies are finished together with the stroke.
+fn finish_stroke(
+    current_stroke_key: StrokeKey,
+    symmetry: Option<&SymmetryCopies>,
+    engine_view: &mut EngineViewMut,
+) -> WidgetFlags {
+    let mut widget_flags = WidgetFlags::default();
+
+    let brush_config = &engine_view.config.pens_config.brush_config;
+    let mut simplified = false;
+    let mut recognized = false;
+    let mut stroke_color = None;
+    if let Some(stroke) = engine_view.store.get_stroke_mut(current_stroke_key)
+        && let Stroke::BrushStroke(brushstroke) = stroke
+    {
+        *brushstroke.style_mut() = brush_config.style_for_current_options();
+        stroke_color = brushstroke.style().stroke_color();
+        if let Style::Smooth(options) = brushstroke.style()
+            && options.pressure_smoothing_window > 1
+        {
+            let window = options.pressure_smoothing_window;
+            brushstroke.path_mut().smooth_pressures(window);
+            simplified = true;
+        }
+        if let Some(tolerance) = engine_view.document.config.simplification_tolerance {
+            brushstroke.path_mut().simplify(tolerance);
+            simplified = true;
+        }
+        // Replaced within the history group of the drawn stroke, so undoing removes the shape in one step
+        if brush_config.shape_recognition
+            && let Some(shape) = recognize_shape(brushstroke.path())
+        {
+            *stroke = Stroke::ShapeStroke(ShapeStroke::new(
+                shape,
+                brush_config.shape_style_for_current_options(),
+            ));
+            recognized = true;
+        }
+    }
+
+    if let Some(stroke_color) = stroke_color {
+        engine_view
+            .config
+            .pens_config
+            .brush_config
+            .recent_colors
+            .push(stroke_color);
+    }
+
+    // The geometry was grown while appending segments, so only a simplified or replaced stroke needs a full update.
+    if simplified || recognized {
+        engine_view
+            .store
+            .update_geometry_for_stroke(current_stroke_key);
+    } else {
+        engine_view
+            .store
+            .update_bounds_for_stroke(current_stroke_key);
+    }
+    engine_view.store.regenerate_rendering_for_stroke_threaded(
+        engine_view.tasks_tx.clone(),
+        current_stroke_key,
+        engine_view.camera.viewport(),
+        engine_view.camera.image_scale(),
+    );
+    if let Some(symmetry) = symmetry {
+        widget_flags |= sync_symmetry_copies(symmetry, current_stroke_key, engine_view);
+    }
+    widget_flags |= engine_view
+        .document
+        .resize_autoexpand(engine_view.store, engine_view.camera);
+
+    widget_flags |= engine_view.store.end_history_group(Instant::now());
+    widget_flags.store_modified = true;
+    widget_flags.stroke_committed = true;
+
+    widget_flags
+}
+
+/// Stamps a copy of the stroke for the given key at the position of the element, as a single history entry.
+fn stamp_stroke(key: StrokeKey, element: Element, engine_view: &mut EngineViewMut) -> WidgetFlags {
+    let mut widget_flags = WidgetFlags::default();
+
+    let seed = engine_view
+        .config
+        .pens_config
+        .brush_config
+        .stamp_new_seed
+        .then(|| engine_view.config.new_style_seed());
+    let Some(stamp_key) = engine_view.store.stamp_stroke(key, element.pos, seed) else {
+        return widget_flags;
+    };
+    engine_view.store.regenerate_rendering_for_stroke_threaded(
+        engine_view.tasks_tx.clone(),
+        stamp_key,
+        engine_view.camera.viewport(),
+        engine_view.camera.image_scale(),
+    );
+    widget_flags |= engine_view
+        .document
+        .resize_autoexpand(engine_view.store, engine_view.camera);
+
+    widget_flags |= engine_view.store.record(Instant::now());
+    widget_flags.store_modified = true;
+    widget_flags.stroke_committed = true;
+
+    widget_flags
+}
+
+/// Removes the partially drawn stroke and its symmetric copies from the store, instead of finishing it.
+fn discard_stroke(
+    current_stroke_key: StrokeKey,
+    symmetry: Option<&SymmetryCopies>,
+    engine_view: &mut EngineViewMut,
+) -> WidgetFlags {
+    let mut widget_flags = WidgetFlags::default();
+
+    #[cfg(feature = "ui")]
+    if let Some(audioplayer) = engine_view.audioplayer.as_mut() {
+        audioplayer.stop_brush_sound();
+    }
+
+    engine_view.store.remove_stroke(current_stroke_key);
+    for &key in symmetry.iter().flat_map(|symmetry| symmetry.keys.iter()) {
+        engine_view.store.remove_stroke(key);
+    }
+    // The state is then equal to the state before the stroke was started, so no history entry is recorded
+    widget_flags |= engine_view.store.end_history_group(Instant::now());
+    widget_flags.redraw = true;
+    widget_flags.store_modified = true;
+
+    widget_flags
+}
+
+/// Feeds the event stabilized into the builder.
+///
+/// On up events the remaining lag of the stabilizer is flushed, so that the stroke ends at the lifted pen.
+fn handle_stabilized_event(
+    path_builder: &mut dyn Buildable<Emit = Segment>,
+    stabilizer: &mut Stabilizer,
+    event: PenEvent,
+    now: Instant,
+) -> EventResult<BuilderProgress<Segment>> {
+    match event {
+        PenEvent::Down {
+            element,
+            modifier_keys,
+        } => match stabilizer.update(element) {
+            Some(element) => path_builder.handle_event(
+                PenEvent::Down {
+                    element,
+                    modifier_keys,
+                },
+                now,
+                Constraints::default(),
+            ),
+            None => EventResult {
+                handled: true,
+                propagate: EventPropagation::Stop,
+                progress: BuilderProgress::InProgress,
+            },
+        },
+        PenEvent::Up {
+            element,
+            modifier_keys,
+        } => {
+            let mut elements = stabilizer.flush(element);
+            let end = elements.pop().unwrap_or(element);
+            let mut segments = vec![];
+
+            for element in elements {
+                if let BuilderProgress::EmitContinue(emitted) = path_builder
+                    .handle_event(
+                        PenEvent::Down {
+                            element,
+                            modifier_keys: modifier_keys.clone(),
+                        },
+                        now,
+                        Constraints::default(),
+                    )
+                    .progress
+                {
+                    segments.extend(emitted);
+                }
+            }
+
+            let mut result = path_builder.handle_event(
+                PenEvent::Up {
+                    element: end,
+                    modifier_keys,
+                },
+                now,
+                Constraints::default(),
+            );
+            if let BuilderProgress::Finished(emitted) = result.progress {
+                segments.extend(emitted);
+                result.progress = BuilderProgress::Finished(segments);
+            } else if !segments.is_empty() {
+                result.progress = BuilderProgress::EmitContinue(segments);
+            }
+            result
+        }
+        event => path_builder.handle_event(event, now, Constraints::default()),
+    }
+}
+
 fn new_builder(
-    builder_type: PenPathBuilderType,
+    brush_config: &BrushConfig,
     element: Element,
     now: Instant,
 ) -> Box<dyn Buildable<Emit = Segment>> {
-    match builder_type {
-        PenPathBuilderType::Simple => Box::new(PenPathSimpleBuilder::start(element, now)),
-        PenPathBuilderType::Curved => Box::new(PenPathCurvedBuilder::start(element, now)),
-        PenPathBuilderType::Modeled => Box::new(PenPathModeledBuilder::start(element, now)),
+    let simulate_pressure = brush_config.simulate_pressure_from_speed;
+    let min_segment_distance = brush_config.min_segment_distance.clamp(
+        BrushConfig::MIN_SEGMENT_DISTANCE_MIN,
+        BrushConfig::MIN_SEGMENT_DISTANCE_MAX,
+    );
+
+    match brush_config.builder_type {
+        PenPathBuilderType::Simple => Box::new(
+            PenPathSimpleBuilder::start(element, now)
+                .with_live_smoothing(brush_config.live_smoothing)
+                .with_simulated_pressure(simulate_pressure)
+                .with_min_segment_distance(min_segment_distance),
+        ),
+        PenPathBuilderType::Curved => Box::new(
+            PenPathCurvedBuilder::start(element, now)
+                .with_simulated_pressure(simulate_pressure)
+                .with_min_segment_distance(min_segment_distance),
+        ),
+        PenPathBuilderType::Modeled => Box::new(
+            PenPathModeledBuilder::start(element, now)
+                .with_simulated_pressure(simulate_pressure)
+                .with_min_segment_distance(min_segment_distance),
+        ),
     }
 }
@@ -4,11 +4,10 @@ use super::PenStyle;
 use super::pensconfig::eraserconfig::EraserStyle;
 use crate::engine::{EngineView, EngineViewMut};
 use crate::{DrawableOnDoc, WidgetFlags};
-use p2d::bounding_volume::{Aabb, BoundingVolume};
+use p2d::bounding_volume::Aabb;
 use piet::RenderContext;
 use rnote_compose::color;
 use rnote_compose::eventresult::{EventPropagation, EventResult};
-use rnote_compose::ext::AabbExt;
 use rnote_compose::penevent::{PenEvent, PenProgress};
 use rnote_compose::penpath::Element;
 use std::time::Instant;
@@ -46,6 +45,10 @@ impl PenBehaviour for Eraser {
         PenStyle::Eraser
     }
 
+    fn is_active(&self) -> bool {
+        matches!(self.state, EraserState::Down(_))
+    }
+
     fn update_state(&mut self, _engine_view: &mut EngineViewMut) -> WidgetFlags {
         WidgetFlags::default()
     }
@@ -159,19 +162,31 @@ impl PenBehaviour for Eraser {
     }
 }
 
-impl DrawableOnDoc for Eraser {
-    fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
+impl Eraser {
+    /// The element the eraser preview is drawn for.
+    fn preview_element(&self) -> Option<Element> {
         match &self.state {
             EraserState::Up => None,
-            EraserState::Proximity(current_element) | EraserState::Down(current_element) => Some(
-                engine_view
-                    .config
-                    .pens_config
-                    .eraser_config
-                    .eraser_bounds(*current_element),
-            ),
+            // Proximity events carry no pressure, so the full width is previewed
+            EraserState::Proximity(current_element) => Some(Element {
+                pressure: 1.0,
+                ..*current_element
+            }),
+            EraserState::Down(current_element) => Some(*current_element),
         }
     }
+}
+
+impl DrawableOnDoc for Eraser {
+    fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
+        self.preview_element().map(|element| {
+            engine_view
+                .config
+                .pens_config
+                .eraser_config
+                .eraser_bounds(element)
+        })
+    }
 
     fn draw_on_doc(
         &self,
@@ -185,35 +200,30 @@ impl DrawableOnDoc for Eraser {
         const PROXIMITY_FILL_COLOR: piet::Color = color::GNOME_REDS[0].with_a8(51);
         let outline_width = 2.0 / engine_view.camera.total_zoom();
 
-        match &self.state {
-            EraserState::Up => {}
-            EraserState::Proximity(current_element) => {
-                let bounds = engine_view
-                    .config
-                    .pens_config
-                    .eraser_config
-                    .eraser_bounds(*current_element);
-
-                let fill_rect = bounds.to_kurbo_rect();
-                let outline_rect = bounds.tightened(outline_width * 0.5).to_kurbo_rect();
-
-                cx.fill(fill_rect, &PROXIMITY_FILL_COLOR);
-                cx.stroke(outline_rect, &OUTLINE_COLOR, outline_width);
-            }
-            EraserState::Down(current_element) => {
-                let bounds = engine_view
-                    .config
-                    .pens_config
-                    .eraser_config
-                    .eraser_bounds(*current_element);
-
-                let fill_rect = bounds.to_kurbo_rect();
-                let outline_rect = bounds.tightened(outline_width * 0.5).to_kurbo_rect();
+        let (Some(current_element), fill_color) = (
+            self.preview_element(),
+            match &self.state {
+                EraserState::Down(_) => FILL_COLOR,
+                _ => PROXIMITY_FILL_COLOR,
+            },
+        ) else {
+            cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+            return Ok(());
+        };
+        // The preview follows the current erase radius, which might be sized by the pressure
+        let radius = engine_view
+            .config
+            .pens_config
+            .eraser_config
+            .eraser_radius(current_element);
+        let center = kurbo::Point::new(current_element.pos[0], current_element.pos[1]);
 
-                cx.fill(fill_rect, &FILL_COLOR);
-                cx.stroke(outline_rect, &OUTLINE_COLOR, outline_width);
-            }
-        }
+        cx.fill(kurbo::Circle::new(center, radius), &fill_color);
+        cx.stroke(
+            kurbo::Circle::new(center, (radius - outline_width * 0.5).max(0.0)),
+            &OUTLINE_COLOR,
+            outline_width,
+        );
 
         cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
         Ok(())
@@ -233,6 +243,7 @@ fn erase(element: Element, engine_view: &mut EngineViewMut) -> WidgetFlags {
                     .eraser_config
                     .eraser_bounds(element),
                 engine_view.camera.viewport(),
+                engine_view.config.pens_config.eraser_config.erase_filter,
             );
         }
         EraserStyle::SplitCollidingStrokes => {
@@ -243,6 +254,7 @@ fn erase(element: Element, engine_view: &mut EngineViewMut) -> WidgetFlags {
                     .eraser_config
                     .eraser_bounds(element),
                 engine_view.camera.viewport(),
+                engine_view.config.pens_config.eraser_config.erase_filter,
             );
             widget_flags |= wf;
 
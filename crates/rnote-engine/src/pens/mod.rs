@@ -36,6 +36,7 @@ use rnote_compose::penevent::PenProgress;
 use serde::{Deserialize, Serialize};
 use std::time::Instant;
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 pub enum Pen {
     Brush(Brush),
@@ -86,6 +87,17 @@ impl PenBehaviour for Pen {
         }
     }
 
+    fn is_active(&self) -> bool {
+        match self {
+            Pen::Brush(brush) => brush.is_active(),
+            Pen::Shaper(shaper) => shaper.is_active(),
+            Pen::Typewriter(typewriter) => typewriter.is_active(),
+            Pen::Eraser(eraser) => eraser.is_active(),
+            Pen::Selector(selector) => selector.is_active(),
+            Pen::Tools(tools) => tools.is_active(),
+        }
+    }
+
     fn update_state(&mut self, engine_view: &mut EngineViewMut) -> WidgetFlags {
         match self {
             Pen::Brush(brush) => brush.update_state(engine_view),
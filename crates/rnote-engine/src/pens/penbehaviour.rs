@@ -21,6 +21,11 @@ pub trait PenBehaviour: DrawableOnDoc {
     // The pen style.
     fn style(&self) -> PenStyle;
 
+    /// Whether the pen is in the middle of an action, e.g. while a stroke is drawn.
+    ///
+    /// Is false in idle states.
+    fn is_active(&self) -> bool;
+
     /// Update the pen and pen config state with the state from the engine.
     fn update_state(&mut self, engine_view: &mut EngineViewMut) -> WidgetFlags;
 
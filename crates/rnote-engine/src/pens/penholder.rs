@@ -14,6 +14,7 @@ use p2d::bounding_volume::Aabb;
 use piet::RenderContext;
 use rnote_compose::eventresult::EventPropagation;
 use rnote_compose::penevent::{KeyboardKey, ModifierKey, PenEvent, PenProgress, ShortcutKey};
+use rnote_compose::penpath::InputSource;
 use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant};
 
@@ -45,6 +46,32 @@ pub struct PenHolder {
     toggle_pen_style: Option<PenStyle>,
     #[serde(skip)]
     prev_shortcut_key: Option<ShortcutKey>,
+    #[serde(skip)]
+    double_tap: DoubleTapTracker,
+}
+
+/// Tracks the taps of the stylus for recognizing the double tap gesture.
+#[derive(Debug, Clone, Copy, Default)]
+struct DoubleTapTracker {
+    /// The time and position the stylus was pressed down at, while it is pressed.
+    press: Option<(Instant, na::Vector2<f64>)>,
+    /// Whether the stylus was moved too far while it is pressed, so that the press is not a tap.
+    press_moved: bool,
+    /// Whether the current press is the second tap of a recognized gesture and is withheld from the pen.
+    press_consumed: bool,
+    /// The time and position of the last short tap.
+    last_tap: Option<(Instant, na::Vector2<f64>)>,
+    /// The time of the last press of the primary barrel button.
+    last_button_press: Option<Instant>,
+}
+
+impl DoubleTapTracker {
+    /// The maximum duration of a tap, longer presses are regular input.
+    const TAP_MAX_DURATION: Duration = Duration::from_millis(150);
+    /// The maximum distance in surface coordinates between the positions of both taps and while the stylus is pressed.
+    ///
+    /// Rapid dots are usually drawn at different positions, so they are not recognized as gesture.
+    const TAP_MAX_DISTANCE: f64 = 8.0;
 }
 
 impl Default for PenHolder {
@@ -57,6 +84,7 @@ impl Default for PenHolder {
             progress: PenProgress::Idle,
             toggle_pen_style: None,
             prev_shortcut_key: None,
+            double_tap: DoubleTapTracker::default(),
         }
     }
 }
@@ -193,6 +221,17 @@ impl PenHolder {
             widget_flags |= self.change_pen_mode(pen_mode, engine_view);
         }
 
+        if self.handle_double_tap(&event, now, engine_view) {
+            widget_flags |= self.toggle_double_tap_style(engine_view);
+        }
+        if self.double_tap.press_consumed {
+            if matches!(event, PenEvent::Up { .. } | PenEvent::Cancel) {
+                self.double_tap.press_consumed = false;
+            }
+            widget_flags.redraw = true;
+            return (EventPropagation::Stop, widget_flags);
+        }
+
         // Handle the event with the current pen
         let (mut event_result, wf) = self
             .current_pen
@@ -205,10 +244,12 @@ impl PenHolder {
             widget_flags |= wf;
         }
 
-        // Always redraw after handling a pen event.
+        // Always redraw after handling a pen event, except when the pen only requested to redraw a region.
         //
         // This is also needed because pens might have claimed/requested an animation frame.
-        widget_flags.redraw = true;
+        if widget_flags.redraw_region.is_none() {
+            widget_flags.redraw = true;
+        }
 
         (event_result.propagate, widget_flags)
     }
@@ -224,12 +265,32 @@ impl PenHolder {
     pub fn handle_pressed_shortcut_key(
         &mut self,
         shortcut_key: ShortcutKey,
-        _now: Instant,
+        now: Instant,
         engine_view: &mut EngineViewMut,
     ) -> (EventPropagation, WidgetFlags) {
         let mut widget_flags = WidgetFlags::default();
         let mut propagate = EventPropagation::Proceed;
 
+        let double_tap_config = &engine_view.config.pens_config.double_tap_config;
+        if double_tap_config.enabled
+            && double_tap_config.barrel_button
+            && shortcut_key == ShortcutKey::StylusPrimaryButton
+        {
+            let window = double_tap_config.window();
+            if self
+                .double_tap
+                .last_button_press
+                .is_some_and(|last_press| now.duration_since(last_press) <= window)
+            {
+                // The second press toggles instead of triggering the shortcut action again
+                self.double_tap.last_button_press = None;
+                widget_flags |= self.toggle_double_tap_style(engine_view);
+                widget_flags.redraw = true;
+                return (EventPropagation::Stop, widget_flags);
+            }
+            self.double_tap.last_button_press = Some(now);
+        }
+
         if let Some(action) = engine_view
             .config
             .pens_config
@@ -278,6 +339,78 @@ impl PenHolder {
         (propagate, widget_flags)
     }
 
+    /// Track the stylus taps of the event and return whether it is the second tap of a double tap gesture.
+    ///
+    /// Only taps of the tip are tracked, the barrel button is handled with the pressed shortcut keys.
+    /// The first tap is handled by the current pen as usual while the second one is withheld from it.
+    fn handle_double_tap(
+        &mut self,
+        event: &PenEvent,
+        now: Instant,
+        engine_view: &EngineViewMut,
+    ) -> bool {
+        let double_tap_config = &engine_view.config.pens_config.double_tap_config;
+        if !double_tap_config.enabled || double_tap_config.barrel_button {
+            self.double_tap = DoubleTapTracker::default();
+            return false;
+        }
+        let max_distance = DoubleTapTracker::TAP_MAX_DISTANCE / engine_view.camera.total_zoom();
+
+        match event {
+            PenEvent::Down { element, .. } if element.source == InputSource::Pen => {
+                let Some((_, press_pos)) = self.double_tap.press else {
+                    let recognized = self.double_tap.last_tap.is_some_and(|(tap_time, tap_pos)| {
+                        now.duration_since(tap_time) <= double_tap_config.window()
+                            && (element.pos - tap_pos).magnitude() <= max_distance
+                    });
+                    self.double_tap.press = Some((now, element.pos));
+                    self.double_tap.press_moved = false;
+                    self.double_tap.last_tap = None;
+                    self.double_tap.press_consumed = recognized;
+                    return recognized;
+                };
+                if (element.pos - press_pos).magnitude() > max_distance {
+                    self.double_tap.press_moved = true;
+                }
+                false
+            }
+            PenEvent::Up { element, .. } => {
+                if let Some((press_time, press_pos)) = self.double_tap.press.take() {
+                    let is_tap = !self.double_tap.press_moved
+                        && now.duration_since(press_time) <= DoubleTapTracker::TAP_MAX_DURATION
+                        && (element.pos - press_pos).magnitude() <= max_distance;
+                    self.double_tap.last_tap =
+                        (is_tap && !self.double_tap.press_consumed).then_some((now, press_pos));
+                }
+                false
+            }
+            PenEvent::Cancel => {
+                self.double_tap.press = None;
+                self.double_tap.press_moved = false;
+                self.double_tap.last_tap = None;
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Toggle between the current style and the style of the double tap gesture.
+    fn toggle_double_tap_style(&mut self, engine_view: &mut EngineViewMut) -> WidgetFlags {
+        self.prev_shortcut_key = None;
+        match self.toggle_pen_style.take() {
+            Some(toggle_pen_style) => self.change_style_int(toggle_pen_style, engine_view),
+            None => {
+                self.toggle_pen_style = Some(self.current_pen_style(&engine_view.as_im()));
+                let style = engine_view
+                    .config
+                    .pens_config
+                    .double_tap_config
+                    .toggle_style;
+                self.change_style_int(style, engine_view)
+            }
+        }
+    }
+
     /// Fetch clipboard content from the current pen.
     #[allow(clippy::type_complexity)]
     pub fn fetch_clipboard_content(
@@ -1,12 +1,14 @@
 // Imports
 use crate::store::chrono_comp::StrokeLayer;
-use rand::{RngExt, SeedableRng};
-use rnote_compose::Style;
-use rnote_compose::builders::PenPathBuilderType;
-use rnote_compose::style::PressureCurve;
+use rnote_compose::builders::{PenPathBuilderType, StabilizerMode};
 use rnote_compose::style::smooth::SmoothOptions;
-use rnote_compose::style::textured::TexturedOptions;
+use rnote_compose::style::textured::{TexturedOptions, TipTexture};
+use rnote_compose::style::{BlendMode, PressureCurve};
+use rnote_compose::transform::Transformable;
+use rnote_compose::{Color, Style};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
 
 #[derive(
     Debug,
@@ -27,6 +29,8 @@ pub enum BrushStyle {
     Solid,
     #[serde(rename = "textured")]
     Textured,
+    #[serde(rename = "highlighter")]
+    Highlighter,
 }
 
 impl Default for BrushStyle {
@@ -45,6 +49,209 @@ impl TryFrom<u32> for BrushStyle {
     }
 }
 
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    num_derive::FromPrimitive,
+    num_derive::ToPrimitive,
+)]
+#[serde(rename = "symmetry_mode")]
+pub enum SymmetryMode {
+    /// Mirrored across the vertical axis through the origin.
+    #[serde(rename = "vertical")]
+    Vertical = 0,
+    /// Mirrored across the horizontal axis through the origin.
+    #[serde(rename = "horizontal")]
+    Horizontal,
+    /// Rotated around the origin in equal angles.
+    #[serde(rename = "radial")]
+    Radial,
+}
+
+impl Default for SymmetryMode {
+    fn default() -> Self {
+        Self::Vertical
+    }
+}
+
+impl TryFrom<u32> for SymmetryMode {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u32(value).ok_or_else(|| {
+            anyhow::anyhow!("SymmetryMode try_from::<u32>() for value {} failed", value)
+        })
+    }
+}
+
+/// Symmetry drawing, inserting symmetric copies of the drawn stroke.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename = "symmetry_config")]
+pub struct SymmetryConfig {
+    #[serde(rename = "mode")]
+    pub mode: SymmetryMode,
+    /// The origin of the symmetry axes, in document coordinates.
+    #[serde(
+        rename = "origin",
+        with = "rnote_compose::serialize::na_vector2_f64_dp3"
+    )]
+    pub origin: na::Vector2<f64>,
+    /// The number of symmetric strokes including the drawn one. Only used by the radial mode.
+    #[serde(rename = "count")]
+    pub count: u32,
+}
+
+impl Default for SymmetryConfig {
+    fn default() -> Self {
+        Self {
+            mode: SymmetryMode::default(),
+            origin: na::Vector2::zeros(),
+            count: 6,
+        }
+    }
+}
+
+impl SymmetryConfig {
+    pub const COUNT_MIN: u32 = 2;
+    pub const COUNT_MAX: u32 = 32;
+
+    /// The number of copies of the drawn stroke.
+    pub fn n_copies(&self) -> usize {
+        match self.mode {
+            SymmetryMode::Vertical | SymmetryMode::Horizontal => 1,
+            SymmetryMode::Radial => self.count.clamp(Self::COUNT_MIN, Self::COUNT_MAX) as usize - 1,
+        }
+    }
+
+    /// Transforms the given drawn geometry onto the copy with the given index.
+    pub fn transform_to_copy(&self, transformable: &mut impl Transformable, index: usize) {
+        match self.mode {
+            SymmetryMode::Vertical => {
+                transformable.translate(-self.origin);
+                transformable.scale(na::vector![-1.0, 1.0]);
+                transformable.translate(self.origin);
+            }
+            SymmetryMode::Horizontal => {
+                transformable.translate(-self.origin);
+                transformable.scale(na::vector![1.0, -1.0]);
+                transformable.translate(self.origin);
+            }
+            SymmetryMode::Radial => {
+                let count = self.count.clamp(Self::COUNT_MIN, Self::COUNT_MAX);
+                let angle = std::f64::consts::TAU * (index + 1) as f64 / f64::from(count);
+                transformable.rotate(angle, self.origin.into());
+            }
+        }
+    }
+}
+
+/// The distinct stroke colors of the most recently finished brush strokes, the most recent first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename = "recent_colors")]
+pub struct RecentColors {
+    /// The maximum number of kept colors.
+    #[serde(rename = "capacity")]
+    pub capacity: usize,
+    #[serde(rename = "colors")]
+    colors: VecDeque<Color>,
+}
+
+impl Default for RecentColors {
+    fn default() -> Self {
+        Self {
+            capacity: Self::CAPACITY_DEFAULT,
+            colors: VecDeque::new(),
+        }
+    }
+}
+
+impl RecentColors {
+    pub const CAPACITY_MAX: usize = 32;
+    pub const CAPACITY_DEFAULT: usize = 8;
+
+    /// Move the color to the front, dropping the least recent colors that exceed the capacity.
+    pub fn push(&mut self, color: Color) {
+        self.colors.retain(|&recent| recent != color);
+        self.colors.push_front(color);
+        self.colors.truncate(self.capacity.min(Self::CAPACITY_MAX));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Color> {
+        self.colors.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.colors.clear();
+    }
+}
+
+/// A named bundle of the brush style and its options, that can be saved to a file and shared.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "brush_preset")]
+pub struct BrushPreset {
+    /// The version of the preset format. Options added later are filled with their defaults when missing.
+    #[serde(rename = "version")]
+    pub version: u32,
+    #[serde(rename = "name")]
+    pub name: String,
+    #[serde(rename = "style")]
+    pub style: BrushStyle,
+    #[serde(rename = "marker_options")]
+    pub marker_options: MarkerOptions,
+    #[serde(rename = "solid_options")]
+    pub solid_options: SolidOptions,
+    #[serde(rename = "textured_options")]
+    pub textured_options: TexturedOptions,
+    #[serde(rename = "highlighter_options")]
+    pub highlighter_options: HighlighterOptions,
+}
+
+impl Default for BrushPreset {
+    fn default() -> Self {
+        Self {
+            version: Self::FORMAT_VERSION,
+            name: String::default(),
+            style: BrushStyle::default(),
+            marker_options: MarkerOptions::default(),
+            solid_options: SolidOptions::default(),
+            textured_options: TexturedOptions::default(),
+            highlighter_options: HighlighterOptions::default(),
+        }
+    }
+}
+
+impl BrushPreset {
+    /// The current version of the preset format.
+    pub const FORMAT_VERSION: u32 = 1;
+
+    /// Load a preset from its Json representation.
+    ///
+    /// Fails for presets of a newer format version, which might contain options that can't be represented.
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        let preset = serde_json::from_str::<Self>(json)?;
+        if preset.version > Self::FORMAT_VERSION {
+            return Err(anyhow::anyhow!(
+                "Brush preset has unsupported format version {}, the latest supported version is {}.",
+                preset.version,
+                Self::FORMAT_VERSION
+            ));
+        }
+        Ok(preset)
+    }
+
+    /// The Json representation of the preset, in the current format version.
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        let mut preset = self.clone();
+        preset.version = Self::FORMAT_VERSION;
+        Ok(serde_json::to_string_pretty(&preset)?)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename = "marker_options")]
 pub struct MarkerOptions(SmoothOptions);
@@ -54,6 +261,7 @@ impl Default for MarkerOptions {
         let mut options = SmoothOptions::default();
         options.pressure_curve = PressureCurve::Const;
         options.stroke_width = 12.0;
+        options.blend_mode = BlendMode::Multiply;
 
         Self(options)
     }
@@ -73,6 +281,51 @@ impl std::ops::DerefMut for MarkerOptions {
     }
 }
 
+/// The options of the highlighter, marking text without obscuring it.
+///
+/// Draws with a constant width and darken blending: overlapping passes never get darker than the
+/// highlighter color itself, while dark text underneath keeps its color.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "highlighter_options")]
+pub struct HighlighterOptions(SmoothOptions);
+
+impl Default for HighlighterOptions {
+    fn default() -> Self {
+        let mut options = SmoothOptions::default();
+        options.pressure_curve = PressureCurve::Const;
+        options.stroke_width = 18.0;
+        options.stroke_color = Some(Self::STROKE_COLOR_DEFAULT);
+        options.blend_mode = BlendMode::Darken;
+        options.opacity = Self::OPACITY_DEFAULT;
+
+        Self(options)
+    }
+}
+
+impl HighlighterOptions {
+    pub const STROKE_COLOR_DEFAULT: Color = Color {
+        r: 1.0,
+        g: 0.92,
+        b: 0.23,
+        a: 1.0,
+    };
+    pub const OPACITY_DEFAULT: f64 = 0.6;
+}
+
+impl std::ops::Deref for HighlighterOptions {
+    type Target = SmoothOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for HighlighterOptions {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename = "solid_options")]
 pub struct SolidOptions(SmoothOptions);
@@ -97,11 +350,66 @@ impl std::ops::DerefMut for SolidOptions {
     }
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default, rename = "brush_config")]
 pub struct BrushConfig {
     #[serde(rename = "builder_type")]
     pub builder_type: PenPathBuilderType,
+    /// Interpolate the input while drawing. Only has an effect with the simple builder type,
+    /// the other builder types are already smoothing their input.
+    #[serde(rename = "live_smoothing")]
+    pub live_smoothing: bool,
+    /// Derive a synthetic pressure from the pen velocity for input devices without pressure support.
+    #[serde(rename = "simulate_pressure_from_speed")]
+    pub simulate_pressure_from_speed: bool,
+    /// Stabilize the input by letting the stroke trail behind the pen. Disabled when None.
+    #[serde(rename = "stabilizer")]
+    pub stabilizer: Option<StabilizerMode>,
+    /// The angle increment in degrees the straight line is snapped to. Free angles when None.
+    #[serde(rename = "snap_angle")]
+    pub snap_angle: Option<f64>,
+    /// Input elements closer than this distance to the last accepted element are dropped, reducing input noise.
+    #[serde(
+        rename = "min_segment_distance",
+        with = "rnote_compose::serialize::f64_dp3"
+    )]
+    pub min_segment_distance: f64,
+    /// How far beyond the document bounds pen input still starts a stroke.
+    #[serde(rename = "input_overshoot", with = "rnote_compose::serialize::f64_dp3")]
+    pub input_overshoot: f64,
+    /// Remove the stroke when drawing it is cancelled, e.g. by palm rejection, instead of keeping it.
+    #[serde(rename = "discard_on_cancel")]
+    pub discard_on_cancel: bool,
+    /// Draw symmetric copies of the stroke. Disabled when None.
+    #[serde(rename = "symmetry")]
+    pub symmetry: Option<SymmetryConfig>,
+    /// Drop touch input while a pen stroke is in progress or has recently ended, rejecting the palm resting on the screen.
+    ///
+    /// Touch drawing can be disabled entirely with the touch drawing setting of the ui, which keeps touch for panning.
+    #[serde(rename = "ignore_touch_while_pen_active")]
+    pub ignore_touch_while_pen_active: bool,
+    /// Show a circle at the pen tip while drawing, sized by the current pressure.
+    #[serde(rename = "show_pressure_indicator")]
+    pub show_pressure_indicator: bool,
+    /// Keep straight lines parallel to the previously drawn stroke, for even hatching.
+    ///
+    /// Holding the hatching modifier key while starting the straight line toggles it for the line.
+    #[serde(rename = "snap_to_previous_angle")]
+    pub snap_to_previous_angle: bool,
+    /// Stamp copies of the last finished stroke at the pen position instead of drawing,
+    /// once a stroke was drawn.
+    #[serde(rename = "stamp_mode")]
+    pub stamp_mode: bool,
+    /// Give stamped textured strokes a new seed, instead of keeping the one of the original stroke.
+    #[serde(rename = "stamp_new_seed")]
+    pub stamp_new_seed: bool,
+    /// Replace roughly drawn lines, rectangles, triangles and ellipses with clean shapes when the stroke is finished.
+    #[serde(rename = "shape_recognition")]
+    pub shape_recognition: bool,
+    /// Straighten the stroke into a line from its start when the pen is held still for this duration while drawing.
+    /// Moving on afterwards resumes drawing freehand. Disabled when None.
+    #[serde(rename = "hold_to_straighten")]
+    pub hold_to_straighten: Option<Duration>,
     #[serde(rename = "style")]
     pub style: BrushStyle,
     #[serde(rename = "marker_options")]
@@ -110,23 +418,131 @@ pub struct BrushConfig {
     pub solid_options: SolidOptions,
     #[serde(rename = "textured_options")]
     pub textured_options: TexturedOptions,
+    #[serde(rename = "highlighter_options")]
+    pub highlighter_options: HighlighterOptions,
+    /// Draw highlighter strokes underneath text and other strokes, like the marker, instead of on top of them.
+    #[serde(rename = "highlighter_below_strokes")]
+    pub highlighter_below_strokes: bool,
+    /// Maintained when brush strokes are finished.
+    #[serde(rename = "recent_colors")]
+    pub recent_colors: RecentColors,
+    /// The saved presets, with unique names.
+    #[serde(rename = "presets")]
+    pub presets: Vec<BrushPreset>,
+}
+
+impl Default for BrushConfig {
+    fn default() -> Self {
+        Self {
+            builder_type: PenPathBuilderType::default(),
+            live_smoothing: false,
+            simulate_pressure_from_speed: false,
+            stabilizer: None,
+            snap_angle: None,
+            min_segment_distance: Self::MIN_SEGMENT_DISTANCE_DEFAULT,
+            input_overshoot: Self::INPUT_OVERSHOOT_DEFAULT,
+            discard_on_cancel: false,
+            symmetry: None,
+            ignore_touch_while_pen_active: false,
+            show_pressure_indicator: false,
+            snap_to_previous_angle: false,
+            stamp_mode: false,
+            stamp_new_seed: true,
+            shape_recognition: false,
+            hold_to_straighten: None,
+            style: BrushStyle::default(),
+            marker_options: MarkerOptions::default(),
+            solid_options: SolidOptions::default(),
+            textured_options: TexturedOptions::default(),
+            highlighter_options: HighlighterOptions::default(),
+            highlighter_below_strokes: true,
+            recent_colors: RecentColors::default(),
+            presets: Vec::default(),
+        }
+    }
 }
 
 impl BrushConfig {
     pub const STROKE_WIDTH_MIN: f64 = 0.1;
     pub const STROKE_WIDTH_MAX: f64 = 500.0;
+    pub const MIN_SEGMENT_DISTANCE_MIN: f64 = 0.0;
+    pub const MIN_SEGMENT_DISTANCE_MAX: f64 = 20.0;
+    /// Small enough to not visibly change strokes.
+    pub const MIN_SEGMENT_DISTANCE_DEFAULT: f64 = 0.5;
+    pub const INPUT_OVERSHOOT_MIN: f64 = 0.0;
+    pub const INPUT_OVERSHOOT_MAX: f64 = 1000.0;
+    pub const INPUT_OVERSHOOT_DEFAULT: f64 = 30.0;
 
     pub(crate) fn layer_for_current_options(&self) -> StrokeLayer {
         match &self.style {
             BrushStyle::Marker => StrokeLayer::Highlighter,
-            BrushStyle::Solid | BrushStyle::Textured => StrokeLayer::UserLayer(0),
+            BrushStyle::Highlighter if self.highlighter_below_strokes => StrokeLayer::Highlighter,
+            BrushStyle::Solid | BrushStyle::Textured | BrushStyle::Highlighter => {
+                StrokeLayer::UserLayer(0)
+            }
+        }
+    }
+
+    /// Loads the tip texture of the textured style from encoded image bytes, e.g. a Png.
+    pub fn load_textured_tip_texture(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
+        let image = image::ImageReader::new(std::io::Cursor::new(bytes))
+            .with_guessed_format()?
+            .decode()?
+            .into_rgba8();
+        let (pixel_width, pixel_height) = image.dimensions();
+
+        self.textured_options.tip_texture = Some(TipTexture::try_from_rgba8(
+            image.into_raw(),
+            pixel_width,
+            pixel_height,
+        )?);
+        Ok(())
+    }
+
+    /// Removes the tip texture of the textured style, falling back to the procedural dots.
+    pub fn clear_textured_tip_texture(&mut self) {
+        self.textured_options.tip_texture = None;
+    }
+
+    /// Whether the strokes of the current style are drawn underneath other strokes.
+    pub(crate) fn draws_below_strokes(&self) -> bool {
+        self.layer_for_current_options() == StrokeLayer::Highlighter
+    }
+
+    /// Load the style and its options from the preset.
+    pub fn load_preset(&mut self, preset: BrushPreset) {
+        self.style = preset.style;
+        self.marker_options = preset.marker_options;
+        self.solid_options = preset.solid_options;
+        self.textured_options = preset.textured_options;
+        self.highlighter_options = preset.highlighter_options;
+    }
+
+    /// A preset with the given name from the current style and its options.
+    pub fn to_preset(&self, name: String) -> BrushPreset {
+        BrushPreset {
+            version: BrushPreset::FORMAT_VERSION,
+            name,
+            style: self.style,
+            marker_options: self.marker_options.clone(),
+            solid_options: self.solid_options.clone(),
+            textured_options: self.textured_options.clone(),
+            highlighter_options: self.highlighter_options.clone(),
         }
     }
 
-    /// A new seed for new shapes
-    pub(crate) fn new_style_seeds(&mut self) {
-        let seed = Some(rand_pcg::Pcg64::from_rng(&mut rand::rng()).random());
-        self.textured_options.seed = seed;
+    /// Add the preset, replacing a saved preset with the same name.
+    pub fn save_preset(&mut self, preset: BrushPreset) {
+        if let Some(existing) = self.presets.iter_mut().find(|p| p.name == preset.name) {
+            *existing = preset;
+        } else {
+            self.presets.push(preset);
+        }
+    }
+
+    /// Sets the seed for new shapes
+    pub(crate) fn new_style_seeds(&mut self, seed: u64) {
+        self.textured_options.seed = Some(seed);
     }
 
     pub(crate) fn style_for_current_options(&self) -> Style {
@@ -134,18 +550,101 @@ impl BrushConfig {
             BrushStyle::Marker => {
                 let MarkerOptions(options) = self.marker_options.clone();
 
-                Style::Smooth(options)
+                Style::Smooth(Box::new(options))
             }
             BrushStyle::Solid => {
                 let SolidOptions(options) = self.solid_options.clone();
 
-                Style::Smooth(options)
+                Style::Smooth(Box::new(options))
             }
             BrushStyle::Textured => {
                 let options = self.textured_options.clone();
 
                 Style::Textured(options)
             }
+            BrushStyle::Highlighter => {
+                let HighlighterOptions(options) = self.highlighter_options.clone();
+
+                Style::Smooth(Box::new(options))
+            }
         }
     }
+
+    /// The style for shapes recognized from brush strokes.
+    ///
+    /// Shapes can't be drawn textured, so the textured style falls back to the solid style with its width and color.
+    pub(crate) fn shape_style_for_current_options(&self) -> Style {
+        match self.style_for_current_options() {
+            Style::Textured(textured_options) => {
+                let SolidOptions(mut options) = self.solid_options.clone();
+                options.stroke_width = textured_options.stroke_width;
+                options.stroke_color = textured_options.stroke_color;
+
+                Style::Smooth(Box::new(options))
+            }
+            style => style,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brush_preset_round_trip() {
+        let mut brush_config = BrushConfig {
+            style: BrushStyle::Textured,
+            ..Default::default()
+        };
+        brush_config.solid_options.pressure_curve =
+            PressureCurve::Custom(vec![(0.0, 0.25), (0.5, 0.75), (1.0, 1.0)]);
+        brush_config.solid_options.stroke_width = 4.5;
+        brush_config.textured_options.rotation_jitter = 0.25;
+
+        let json = brush_config
+            .to_preset("Sketch".to_string())
+            .to_json()
+            .unwrap();
+        let mut loaded = BrushConfig::default();
+        loaded.load_preset(BrushPreset::from_json(&json).unwrap());
+
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&brush_config).unwrap()
+        );
+    }
+
+    #[test]
+    fn textured_options_legacy_density() {
+        // Configs, presets and clipboard content from before the density per length only have the area density
+        let options = serde_json::from_value::<TexturedOptions>(serde_json::json!({
+            "stroke_width": 10.0,
+            "density": 5.0
+        }))
+        .unwrap();
+        assert_eq!(options.density, 5.0);
+
+        let options = serde_json::from_value::<TexturedOptions>(serde_json::json!({
+            "stroke_width": 10.0,
+            "density": 5.0,
+            "density_per_length": 2.0
+        }))
+        .unwrap();
+        assert_eq!(options.density, 2.0);
+
+        let options = serde_json::from_value::<TexturedOptions>(serde_json::json!({})).unwrap();
+        assert_eq!(options.density, TexturedOptions::default().density);
+    }
+
+    #[test]
+    fn brush_preset_newer_version_rejected() {
+        let preset = BrushPreset {
+            version: BrushPreset::FORMAT_VERSION + 1,
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&preset).unwrap();
+
+        assert!(BrushPreset::from_json(&json).is_err());
+    }
 }
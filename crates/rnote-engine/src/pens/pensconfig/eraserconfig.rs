@@ -1,4 +1,5 @@
 // Imports
+use crate::strokes::Stroke;
 use p2d::bounding_volume::Aabb;
 use rnote_compose::penpath::Element;
 use serde::{Deserialize, Serialize};
@@ -30,6 +31,60 @@ impl TryFrom<u32> for EraserStyle {
     }
 }
 
+/// Filters the strokes that are erased by their type.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    num_derive::FromPrimitive,
+    num_derive::ToPrimitive,
+)]
+#[serde(rename = "erase_filter")]
+pub enum EraseFilter {
+    /// Brush strokes and shapes.
+    #[default]
+    #[serde(rename = "brush_and_shapes")]
+    BrushAndShapes,
+    /// Only brush strokes.
+    #[serde(rename = "brush_only")]
+    BrushOnly,
+    /// Only shapes.
+    #[serde(rename = "shapes_only")]
+    ShapesOnly,
+    /// All strokes, including text and images.
+    #[serde(rename = "all")]
+    All,
+}
+
+impl TryFrom<u32> for EraseFilter {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u32(value).ok_or_else(|| {
+            anyhow::anyhow!("EraseFilter try_from::<u32>() for value {} failed", value)
+        })
+    }
+}
+
+impl EraseFilter {
+    /// Whether the stroke passes the filter and can be erased.
+    pub(crate) fn matches(&self, stroke: &Stroke) -> bool {
+        match self {
+            Self::BrushAndShapes => {
+                matches!(stroke, Stroke::BrushStroke(_) | Stroke::ShapeStroke(_))
+            }
+            Self::BrushOnly => matches!(stroke, Stroke::BrushStroke(_)),
+            Self::ShapesOnly => matches!(stroke, Stroke::ShapeStroke(_)),
+            Self::All => true,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default, rename = "eraser_config")]
 pub struct EraserConfig {
@@ -37,6 +92,11 @@ pub struct EraserConfig {
     pub width: f64,
     #[serde(rename = "style")]
     pub style: EraserStyle,
+    #[serde(rename = "erase_filter")]
+    pub erase_filter: EraseFilter,
+    /// Scale the width with the pressure of the pen, like pressing a physical eraser harder.
+    #[serde(rename = "pressure_sized")]
+    pub pressure_sized: bool,
 }
 
 impl Default for EraserConfig {
@@ -44,6 +104,8 @@ impl Default for EraserConfig {
         Self {
             width: Self::WIDTH_DEFAULT,
             style: EraserStyle::default(),
+            erase_filter: EraseFilter::default(),
+            pressure_sized: false,
         }
     }
 }
@@ -53,7 +115,20 @@ impl EraserConfig {
     pub const WIDTH_MAX: f64 = 500.0;
     pub const WIDTH_DEFAULT: f64 = 12.0;
 
+    /// The radius the eraser currently erases with, scaled by the pressure of the element when pressure sized.
+    pub(crate) fn eraser_radius(&self, element: Element) -> f64 {
+        let width = if self.pressure_sized {
+            (self.width * element.pressure).max(Self::WIDTH_MIN)
+        } else {
+            self.width
+        };
+        width * 0.5
+    }
+
     pub(crate) fn eraser_bounds(&self, element: Element) -> Aabb {
-        Aabb::from_half_extents(element.pos.into(), na::Vector2::repeat(self.width * 0.5))
+        Aabb::from_half_extents(
+            element.pos.into(),
+            na::Vector2::repeat(self.eraser_radius(element)),
+        )
     }
 }
@@ -20,6 +20,7 @@ use super::{PenStyle, Shortcuts};
 use rnote_compose::Color;
 use rnote_compose::penevent::ShortcutKey;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default, rename = "pens_config")]
@@ -43,6 +44,8 @@ pub struct PensConfig {
     pub pen_mode_pen_style: PenStyle,
     #[serde(rename = "pen_mode_eraser_style")]
     pub pen_mode_eraser_style: PenStyle,
+    #[serde(rename = "double_tap_config")]
+    pub double_tap_config: DoubleTapConfig,
 }
 
 impl Default for PensConfig {
@@ -57,13 +60,56 @@ impl Default for PensConfig {
             shortcuts: Shortcuts::default(),
             pen_mode_pen_style: PenStyle::default(),
             pen_mode_eraser_style: PenStyle::Eraser,
+            double_tap_config: DoubleTapConfig::default(),
         }
     }
 }
 
+/// Toggling between the current pen style and another style with a quick double tap of the stylus.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "double_tap_config")]
+pub struct DoubleTapConfig {
+    #[serde(rename = "enabled")]
+    pub enabled: bool,
+    /// Recognize double presses of the primary stylus barrel button instead of double taps with the tip.
+    #[serde(rename = "barrel_button")]
+    pub barrel_button: bool,
+    /// The maximum time between the two taps in milliseconds.
+    #[serde(rename = "window_ms")]
+    pub window_ms: u32,
+    /// The style that is toggled to. Toggling again returns to the previous style.
+    #[serde(rename = "toggle_style")]
+    pub toggle_style: PenStyle,
+}
+
+impl Default for DoubleTapConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            barrel_button: false,
+            window_ms: 300,
+            toggle_style: PenStyle::Eraser,
+        }
+    }
+}
+
+impl DoubleTapConfig {
+    pub const WINDOW_MS_MIN: u32 = 100;
+    pub const WINDOW_MS_MAX: u32 = 1000;
+
+    /// The maximum time between the two taps.
+    pub fn window(&self) -> Duration {
+        Duration::from_millis(u64::from(
+            self.window_ms
+                .clamp(Self::WINDOW_MS_MIN, Self::WINDOW_MS_MAX),
+        ))
+    }
+}
+
 impl PensConfig {
     pub fn set_all_stroke_colors(&mut self, stroke_color: Color) {
         self.brush_config.marker_options.stroke_color = Some(stroke_color);
+        self.brush_config.highlighter_options.stroke_color = Some(stroke_color);
         self.brush_config.solid_options.stroke_color = Some(stroke_color);
         self.brush_config.textured_options.stroke_color = Some(stroke_color);
         self.shaper_config.smooth_options.stroke_color = Some(stroke_color);
@@ -73,9 +119,11 @@ impl PensConfig {
 
     pub fn set_all_fill_colors(&mut self, fill_color: Color) {
         self.brush_config.marker_options.fill_color = Some(fill_color);
+        self.brush_config.highlighter_options.fill_color = Some(fill_color);
         self.brush_config.solid_options.fill_color = Some(fill_color);
         self.shaper_config.smooth_options.fill_color = Some(fill_color);
         self.shaper_config.rough_options.fill_color = Some(fill_color);
+        self.tools_config.fill_tool_config.color = fill_color;
     }
 
     /// Get the current registered shortcuts.
@@ -24,6 +24,9 @@ pub enum SelectorStyle {
     Single,
     #[serde(rename = "intersectingpath")]
     IntersectingPath,
+    /// Select all strokes sharing the stroke color of the clicked stroke.
+    #[serde(rename = "color")]
+    Color,
 }
 
 impl Default for SelectorStyle {
@@ -1,5 +1,4 @@
 // Imports
-use rand::{RngExt, SeedableRng};
 use rnote_compose::Constraints;
 use rnote_compose::Style;
 use rnote_compose::builders::ShapeBuilderType;
@@ -55,6 +54,10 @@ pub struct ShaperConfig {
     pub highlight_opacity: f64,
     #[serde(rename = "constraints")]
     pub constraints: Constraints,
+    /// Snap the shape points to the intersections of the background pattern grid,
+    /// instead of the snapping configured by `snap_positions`.
+    #[serde(rename = "snap_to_grid")]
+    pub snap_to_grid: bool,
 }
 
 impl Default for ShaperConfig {
@@ -72,6 +75,7 @@ impl Default for ShaperConfig {
             highlight_mode: false,
             highlight_opacity: 0.45,
             constraints,
+            snap_to_grid: false,
         }
     }
 }
@@ -80,10 +84,9 @@ impl ShaperConfig {
     pub const STROKE_WIDTH_MIN: f64 = 0.1;
     pub const STROKE_WIDTH_MAX: f64 = 500.0;
 
-    /// A new seed for new shapes
-    pub(crate) fn new_style_seeds(&mut self) {
-        let seed = Some(rand_pcg::Pcg64::from_rng(&mut rand::rng()).random());
-        self.rough_options.seed = seed;
+    /// Sets the seed for new shapes
+    pub(crate) fn new_style_seeds(&mut self, seed: u64) {
+        self.rough_options.seed = Some(seed);
     }
 
     pub(crate) fn gen_style_for_current_options(&self) -> Style {
@@ -100,7 +103,7 @@ impl ShaperConfig {
                         color.a = self.highlight_opacity;
                     }
                 }
-                Style::Smooth(options)
+                Style::Smooth(Box::new(options))
             }
             ShaperStyle::Rough => {
                 let mut options = self.rough_options.clone();
@@ -1,4 +1,5 @@
 // Imports
+use rnote_compose::Color;
 use serde::{Deserialize, Serialize};
 
 #[derive(
@@ -24,6 +25,14 @@ pub enum ToolStyle {
     Zoom,
     #[serde(rename = "laser")]
     Laser,
+    #[serde(rename = "fill")]
+    Fill,
+    #[serde(rename = "colorpicker")]
+    ColorPicker,
+    #[serde(rename = "smudge")]
+    Smudge,
+    #[serde(rename = "nodeedit")]
+    NodeEdit,
 }
 
 impl Default for ToolStyle {
@@ -51,10 +60,78 @@ pub struct VerticalSpaceToolConfig {
     pub limit_movement_vertical_borders: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "fill_tool_config")]
+pub struct FillToolConfig {
+    /// The color the enclosed region is filled with.
+    #[serde(rename = "color")]
+    pub color: Color,
+}
+
+impl Default for FillToolConfig {
+    fn default() -> Self {
+        Self {
+            color: Color::BLACK,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "colorpicker_tool_config")]
+pub struct ColorPickerToolConfig {
+    /// The radius in surface pixels around the position that is averaged. A radius of 1.0 picks a single pixel.
+    #[serde(rename = "radius", with = "rnote_compose::serialize::f64_dp3")]
+    pub radius: f64,
+}
+
+impl Default for ColorPickerToolConfig {
+    fn default() -> Self {
+        Self { radius: 1.0 }
+    }
+}
+
+impl ColorPickerToolConfig {
+    pub const RADIUS_MIN: f64 = 1.0;
+    pub const RADIUS_MAX: f64 = 50.0;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "smudge_tool_config")]
+pub struct SmudgeToolConfig {
+    /// The radius in surface pixels around the position that is smudged.
+    #[serde(rename = "radius", with = "rnote_compose::serialize::f64_dp3")]
+    pub radius: f64,
+    /// How strongly the colors are pushed along the drag direction, between 0.0 and 1.0.
+    #[serde(rename = "strength", with = "rnote_compose::serialize::f64_dp3")]
+    pub strength: f64,
+}
+
+impl Default for SmudgeToolConfig {
+    fn default() -> Self {
+        Self {
+            radius: 12.0,
+            strength: 0.5,
+        }
+    }
+}
+
+impl SmudgeToolConfig {
+    pub const RADIUS_MIN: f64 = 1.0;
+    pub const RADIUS_MAX: f64 = 100.0;
+    pub const STRENGTH_MIN: f64 = 0.0;
+    pub const STRENGTH_MAX: f64 = 1.0;
+}
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 #[serde(default, rename = "tools_config")]
 pub struct ToolsConfig {
     #[serde(rename = "style")]
     pub style: ToolStyle,
     pub verticalspace_tool_config: VerticalSpaceToolConfig,
+    #[serde(rename = "fill_tool_config")]
+    pub fill_tool_config: FillToolConfig,
+    #[serde(rename = "colorpicker_tool_config")]
+    pub colorpicker_tool_config: ColorPickerToolConfig,
+    #[serde(rename = "smudge_tool_config")]
+    pub smudge_tool_config: SmudgeToolConfig,
 }
@@ -18,7 +18,7 @@ use p2d::query::PointQuery;
 use piet::RenderContext;
 use rnote_compose::EventResult;
 use rnote_compose::ext::{AabbExt, Vector2Ext};
-use rnote_compose::penevent::{PenEvent, PenProgress, PenState};
+use rnote_compose::penevent::{ModifierKey, PenEvent, PenProgress, PenState};
 use rnote_compose::penpath::Element;
 use rnote_compose::style::indicators;
 use rnote_compose::{Color, color};
@@ -107,6 +107,16 @@ impl PenBehaviour for Selector {
         PenStyle::Selector
     }
 
+    fn is_active(&self) -> bool {
+        match &self.state {
+            SelectorState::Idle => false,
+            SelectorState::Selecting { .. } => true,
+            SelectorState::ModifySelection { modify_state, .. } => {
+                !matches!(modify_state, ModifyState::Idle)
+            }
+        }
+    }
+
     fn update_state(&mut self, engine_view: &mut EngineViewMut) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
 
@@ -374,7 +384,7 @@ impl DrawableOnDoc for Selector {
                             );
                         }
                     }
-                    SelectorStyle::Single => {
+                    SelectorStyle::Single | SelectorStyle::Color => {
                         if let Some(last) = path.last() {
                             cx.stroke(
                                 kurbo::Circle::new(
@@ -465,12 +475,20 @@ impl Selector {
     const TRANSLATE_OFFSET_THRESHOLD: f64 = 1.414;
     /// The threshold angle (in radians) where above it the rotation is applied.
     const ROTATE_ANGLE_THRESHOLD: f64 = ((2.0 * std::f64::consts::PI) / 360.0) * 0.2;
+    /// The distance the selection is moved by a keyboard nudge.
+    const NUDGE_DISTANCE: f64 = 1.0;
+    /// The distance the selection is moved by a keyboard nudge while shift is held.
+    const NUDGE_DISTANCE_LARGE: f64 = 10.0;
     /// The outline stroke width when drawing a selection.
     const OUTLINE_STROKE_WIDTH: f64 = 2.0;
     /// The dash pattern while selecting.
     const SELECTING_DASH_PATTERN: [f64; 2] = [12.0, 6.0];
     /// The radius of the circle when selecting in single mode.
     const SELECTING_SINGLE_CIRCLE_RADIUS: f64 = 4.0;
+    /// The maximum difference of the color components for strokes to be selected in color mode.
+    const SELECT_COLOR_TOLERANCE: f64 = 0.01;
+    /// When held, clicking a grouped stroke selects only the stroke instead of its entire group.
+    const SELECT_WITHIN_GROUP_MODIFIER_KEY: ModifierKey = ModifierKey::KeyboardAlt;
     /// Resize node size, in surface coordinates.
     const RESIZE_NODE_SIZE: na::Vector2<f64> = na::vector![18.0, 18.0];
     /// Rotate node diameter, in surface coordinates.
@@ -482,7 +500,10 @@ impl Selector {
 
     fn add_to_select_path(style: SelectorStyle, path: &mut Vec<Element>, element: Element) {
         match style {
-            SelectorStyle::Polygon | SelectorStyle::Single | SelectorStyle::IntersectingPath => {
+            SelectorStyle::Polygon
+            | SelectorStyle::Single
+            | SelectorStyle::IntersectingPath
+            | SelectorStyle::Color => {
                 path.push(element);
             }
             SelectorStyle::Rectangle => {
@@ -792,8 +813,13 @@ impl Selector {
     }
 
     fn select_all(&mut self, engine_view: &mut EngineViewMut, widget_flags: &mut WidgetFlags) {
-        // Select all keys
-        let all_strokes = engine_view.store.stroke_keys_as_rendered();
+        // Select all keys, except the locked ones
+        let all_strokes = engine_view
+            .store
+            .stroke_keys_as_rendered()
+            .into_iter()
+            .filter(|&key| !engine_view.store.locked(key).unwrap_or(false))
+            .collect::<Vec<StrokeKey>>();
 
         if !all_strokes.is_empty() {
             engine_view.store.set_selected_keys(&all_strokes, true);
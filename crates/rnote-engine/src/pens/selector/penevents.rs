@@ -5,7 +5,7 @@ use crate::engine::EngineViewMut;
 use crate::pens::PenBehaviour;
 use crate::pens::pensconfig::selectorconfig::SelectorStyle;
 use crate::snap::SnapCorner;
-use crate::store::StrokeKey;
+use crate::store::{CoalesceKind, StrokeKey};
 use p2d::bounding_volume::Aabb;
 use p2d::query::PointQuery;
 use rnote_compose::eventresult::{EventPropagation, EventResult};
@@ -96,8 +96,15 @@ impl Selector {
                                 .unwrap_or(false)
                         {
                             let key_to_add = key_to_add.unwrap();
-                            engine_view.store.set_selected(key_to_add, true);
-                            selection.push(key_to_add);
+                            let keys_to_add = if modifier_keys
+                                .contains(&Self::SELECT_WITHIN_GROUP_MODIFIER_KEY)
+                            {
+                                vec![key_to_add]
+                            } else {
+                                engine_view.store.keys_grouped_with(key_to_add)
+                            };
+                            engine_view.store.set_selected_keys(&keys_to_add, true);
+                            selection.extend(keys_to_add);
                             if let Some(new_bounds) =
                                 engine_view.store.bounds_for_strokes(selection)
                             {
@@ -106,14 +113,18 @@ impl Selector {
                         } else if Self::rotate_node_sphere(*selection_bounds, engine_view.camera)
                             .contains_local_point(&element.pos.into())
                         {
-                            // clicking on the rotate node
+                            // clicking on the rotate node, rotating around the selection centroid
+                            let rotation_center = engine_view
+                                .store
+                                .centroid_for_strokes(selection)
+                                .unwrap_or_else(|| selection_bounds.center());
                             let rotation_angle = {
-                                let vec = element.pos - selection_bounds.center().coords;
+                                let vec = element.pos - rotation_center.coords;
                                 na::Vector2::x().angle_ahead(&vec)
                             };
 
                             *modify_state = ModifyState::Rotate {
-                                rotation_center: selection_bounds.center(),
+                                rotation_center,
                                 start_rotation_angle: rotation_angle,
                                 current_rotation_angle: rotation_angle,
                             };
@@ -179,8 +190,15 @@ impl Selector {
                             // defer the [SelectorStyle::Single] after checking whether we are
                             // clicking on a selection node
                             let key_to_add = key_to_add.unwrap();
-                            engine_view.store.set_selected(key_to_add, true);
-                            selection.push(key_to_add);
+                            let keys_to_add = if modifier_keys
+                                .contains(&Self::SELECT_WITHIN_GROUP_MODIFIER_KEY)
+                            {
+                                vec![key_to_add]
+                            } else {
+                                engine_view.store.keys_grouped_with(key_to_add)
+                            };
+                            engine_view.store.set_selected_keys(&keys_to_add, true);
+                            selection.extend(keys_to_add);
                             if let Some(new_bounds) =
                                 engine_view.store.bounds_for_strokes(selection)
                             {
@@ -412,7 +430,7 @@ impl Selector {
     pub(super) fn handle_pen_event_up(
         &mut self,
         element: Element,
-        _modifier_keys: HashSet<ModifierKey>,
+        modifier_keys: HashSet<ModifierKey>,
         _now: Instant,
         engine_view: &mut EngineViewMut,
     ) -> (EventResult<PenProgress>, WidgetFlags) {
@@ -464,7 +482,11 @@ impl Selector {
                                 )
                                 .pop()
                         }) {
-                            vec![key]
+                            if modifier_keys.contains(&Self::SELECT_WITHIN_GROUP_MODIFIER_KEY) {
+                                vec![key]
+                            } else {
+                                engine_view.store.keys_grouped_with(key)
+                            }
                         } else {
                             vec![]
                         }
@@ -479,6 +501,30 @@ impl Selector {
                             vec![]
                         }
                     }
+                    SelectorStyle::Color => {
+                        if let Some(color) = path
+                            .last()
+                            .and_then(|last| {
+                                engine_view
+                                    .store
+                                    .stroke_hitboxes_contain_coord(
+                                        engine_view.camera.viewport(),
+                                        last.pos,
+                                    )
+                                    .pop()
+                            })
+                            .and_then(|key| engine_view.store.get_stroke_ref(key))
+                            .and_then(|stroke| stroke.stroke_color())
+                        {
+                            let keys = engine_view
+                                .store
+                                .select_strokes_by_color(color, Self::SELECT_COLOR_TOLERANCE);
+                            widget_flags.selection_changed = true;
+                            keys
+                        } else {
+                            vec![]
+                        }
+                    }
                 };
 
                 if !new_selection.is_empty() {
@@ -582,7 +628,7 @@ impl Selector {
         &mut self,
         keyboard_key: KeyboardKey,
         modifier_keys: HashSet<ModifierKey>,
-        _now: Instant,
+        now: Instant,
         engine_view: &mut EngineViewMut,
     ) -> (EventResult<PenProgress>, WidgetFlags) {
         let mut widget_flags = WidgetFlags::default();
@@ -637,7 +683,11 @@ impl Selector {
                     progress: PenProgress::InProgress,
                 },
             },
-            SelectorState::ModifySelection { selection, .. } => {
+            SelectorState::ModifySelection {
+                selection,
+                selection_bounds,
+                ..
+            } => {
                 match keyboard_key {
                     KeyboardKey::Unicode('a') => {
                         if modifier_keys.contains(&ModifierKey::KeyboardCtrl) {
@@ -687,6 +737,46 @@ impl Selector {
                             }
                         }
                     }
+                    KeyboardKey::NavUp
+                    | KeyboardKey::NavDown
+                    | KeyboardKey::NavLeft
+                    | KeyboardKey::NavRight => {
+                        let distance = if modifier_keys.contains(&ModifierKey::KeyboardShift) {
+                            Self::NUDGE_DISTANCE_LARGE
+                        } else {
+                            Self::NUDGE_DISTANCE
+                        };
+                        let direction = match keyboard_key {
+                            KeyboardKey::NavUp => na::vector![0.0, -1.0],
+                            KeyboardKey::NavDown => na::vector![0.0, 1.0],
+                            KeyboardKey::NavLeft => na::vector![-1.0, 0.0],
+                            _ => na::vector![1.0, 0.0],
+                        };
+                        let offset = direction * distance;
+
+                        engine_view.store.translate_strokes(selection, offset);
+                        engine_view
+                            .store
+                            .translate_strokes_images(selection, offset);
+                        *selection_bounds = selection_bounds.translate(offset);
+
+                        widget_flags |= engine_view
+                            .document
+                            .resize_autoexpand(engine_view.store, engine_view.camera);
+                        // Repeated nudges end up in a single history entry
+                        widget_flags |=
+                            engine_view.store.record_coalesced(now, CoalesceKind::Nudge);
+
+                        widget_flags.redraw = true;
+                        widget_flags.store_modified = true;
+                        widget_flags.selection_changed = true;
+
+                        EventResult {
+                            handled: true,
+                            propagate: EventPropagation::Stop,
+                            progress: PenProgress::InProgress,
+                        }
+                    }
                     KeyboardKey::Delete | KeyboardKey::BackSpace => {
                         engine_view.store.set_trashed_keys(selection, true);
                         widget_flags |= super::cancel_selection(selection, engine_view);
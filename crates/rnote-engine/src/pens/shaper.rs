@@ -53,6 +53,10 @@ impl PenBehaviour for Shaper {
         PenStyle::Shaper
     }
 
+    fn is_active(&self) -> bool {
+        matches!(self.state, ShaperState::BuildShape { .. })
+    }
+
     fn update_state(&mut self, _engine_view: &mut EngineViewMut) -> WidgetFlags {
         WidgetFlags::default()
     }
@@ -67,14 +71,13 @@ impl PenBehaviour for Shaper {
 
         let event_result = match (&mut self.state, event) {
             (ShaperState::Idle, PenEvent::Down { mut element, .. }) => {
+                let seed = engine_view.config.new_style_seed();
                 engine_view
                     .config
                     .pens_config
                     .shaper_config
-                    .new_style_seeds();
-                element.pos = engine_view
-                    .document
-                    .snap_position(element.pos, engine_view.config);
+                    .new_style_seeds(seed);
+                element.pos = snap_shape_position(element.pos, engine_view);
 
                 self.state = ShaperState::BuildShape {
                     builder: new_builder(
@@ -129,9 +132,7 @@ impl PenBehaviour for Shaper {
                 };
                 match &mut event {
                     PenEvent::Down { element, .. } | PenEvent::Up { element, .. } => {
-                        element.pos = engine_view
-                            .document
-                            .snap_position(element.pos, engine_view.config);
+                        element.pos = snap_shape_position(element.pos, engine_view);
                     }
                     _ => {}
                 }
@@ -290,3 +291,13 @@ fn new_builder(
         ShapeBuilderType::Polygon => Box::new(PolygonBuilder::start(element, now)),
     }
 }
+
+/// Snaps the position of shape points, either to the background pattern grid when enabled for the shaper,
+/// or as configured by `snap_positions`.
+fn snap_shape_position(pos: na::Vector2<f64>, engine_view: &EngineViewMut) -> na::Vector2<f64> {
+    if engine_view.config.pens_config.shaper_config.snap_to_grid {
+        engine_view.document.snap_position_to_pattern_grid(pos)
+    } else {
+        engine_view.document.snap_position(pos, engine_view.config)
+    }
+}
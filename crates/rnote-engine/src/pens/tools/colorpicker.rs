@@ -0,0 +1,152 @@
+// Imports
+use super::ToolsState;
+use crate::engine::{EngineView, EngineViewMut, StrokeContent};
+use crate::pens::pensconfig::toolsconfig::ColorPickerToolConfig;
+use crate::{DrawableOnDoc, Image, WidgetFlags};
+use p2d::bounding_volume::Aabb;
+use rnote_compose::Color;
+use rnote_compose::eventresult::{EventPropagation, EventResult};
+use rnote_compose::penevent::{PenEvent, PenProgress};
+use rnote_compose::shapes::Shapeable;
+use std::time::Instant;
+use tracing::error;
+
+/// Picks the color of the rendered document at the pressed position.
+///
+/// The background and all strokes around the position are composited, so the picked color is what is visible
+/// instead of the color of the topmost stroke.
+#[derive(Debug, Default)]
+pub struct ColorPickerTool {
+    state: ToolsState,
+}
+
+impl ColorPickerTool {
+    pub(super) fn is_active(&self) -> bool {
+        matches!(self.state, ToolsState::Active)
+    }
+
+    pub(super) fn handle_event(
+        &mut self,
+        event: PenEvent,
+        _now: Instant,
+        engine_view: &mut EngineViewMut,
+    ) -> (EventResult<PenProgress>, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
+
+        let event_result = match (&mut self.state, event) {
+            (ToolsState::Idle, PenEvent::Down { element, .. }) => {
+                match Self::pick_color(element.pos, &engine_view.as_im()) {
+                    Ok(color) => widget_flags.picked_color = Some(color),
+                    Err(e) => error!("Picking color failed, Err: {e:?}"),
+                }
+                self.state = ToolsState::Active;
+
+                EventResult {
+                    handled: true,
+                    propagate: EventPropagation::Stop,
+                    progress: PenProgress::InProgress,
+                }
+            }
+            (ToolsState::Idle, _) => EventResult {
+                handled: false,
+                propagate: EventPropagation::Proceed,
+                progress: PenProgress::Idle,
+            },
+            (ToolsState::Active, PenEvent::Down { .. }) => EventResult {
+                handled: true,
+                propagate: EventPropagation::Stop,
+                progress: PenProgress::InProgress,
+            },
+            (ToolsState::Active, PenEvent::Up { .. } | PenEvent::Cancel) => {
+                self.state = ToolsState::Idle;
+
+                EventResult {
+                    handled: true,
+                    propagate: EventPropagation::Stop,
+                    progress: PenProgress::Finished,
+                }
+            }
+            (
+                ToolsState::Active,
+                PenEvent::Proximity { .. } | PenEvent::KeyPressed { .. } | PenEvent::Text { .. },
+            ) => EventResult {
+                handled: false,
+                propagate: EventPropagation::Proceed,
+                progress: PenProgress::InProgress,
+            },
+        };
+
+        (event_result, widget_flags)
+    }
+
+    /// Renders the document around the position and averages the pixels within the configured radius.
+    fn pick_color(pos: na::Vector2<f64>, engine_view: &EngineView) -> anyhow::Result<Color> {
+        let total_zoom = engine_view.camera.total_zoom();
+        let radius_px = engine_view
+            .config
+            .pens_config
+            .tools_config
+            .colorpicker_tool_config
+            .radius
+            .clamp(
+                ColorPickerToolConfig::RADIUS_MIN,
+                ColorPickerToolConfig::RADIUS_MAX,
+            );
+        // Rendered with the zoom, so that the pixels match what is visible on the canvas
+        let bounds =
+            Aabb::from_half_extents(pos.into(), na::Vector2::repeat(radius_px / total_zoom));
+        let keys = engine_view
+            .store
+            .stroke_keys_as_rendered_intersecting_bounds(bounds);
+        let content = StrokeContent::default()
+            .with_strokes_from_store(engine_view.store, &keys)
+            .with_bounds(bounds)
+            .with_background(engine_view.document.config.background);
+        let image = Image::gen_with_cairo(
+            |cx| content.draw_to_cairo(cx, true, true, false, 0.0, total_zoom),
+            bounds,
+            total_zoom,
+        )?;
+
+        let center = (pos - image.rect.bounds().mins.coords) * total_zoom;
+        let mut sum = [0.0; 4];
+        let mut n_pixels = 0;
+        for (i, px) in image.data.chunks_exact(4).enumerate() {
+            let x = (i % image.pixel_width as usize) as f64 + 0.5;
+            let y = (i / image.pixel_width as usize) as f64 + 0.5;
+            // The single pixel under the position is always picked
+            if (na::vector![x, y] - center).magnitude() > radius_px.max(0.5) {
+                continue;
+            }
+            for (channel_sum, channel) in sum.iter_mut().zip(px) {
+                *channel_sum += f64::from(*channel) / 255.0;
+            }
+            n_pixels += 1;
+        }
+        if n_pixels == 0 {
+            return Err(anyhow::anyhow!(
+                "Picking color failed, no pixels within the radius."
+            ));
+        }
+        let [r, g, b, a] = sum.map(|channel_sum| channel_sum / f64::from(n_pixels));
+        // The pixels are premultiplied with their alpha
+        if a <= 0.0 {
+            return Ok(Color::TRANSPARENT);
+        }
+        Ok(Color::new(r / a, g / a, b / a, a))
+    }
+}
+
+impl DrawableOnDoc for ColorPickerTool {
+    fn bounds_on_doc(&self, _engine_view: &EngineView) -> Option<Aabb> {
+        None
+    }
+
+    fn draw_on_doc(
+        &self,
+        _cx: &mut piet_cairo::CairoRenderContext,
+        _engine_view: &EngineView,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
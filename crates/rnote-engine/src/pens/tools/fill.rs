@@ -0,0 +1,398 @@
+// Imports
+use super::ToolsState;
+use crate::engine::{EngineView, EngineViewMut};
+use crate::store::chrono_comp::StrokeLayer;
+use crate::strokes::{BrushStroke, Stroke};
+use crate::{Drawable, DrawableOnDoc, Image, UserWarning, WidgetFlags};
+use p2d::bounding_volume::Aabb;
+use rnote_compose::eventresult::{EventPropagation, EventResult};
+use rnote_compose::penevent::{PenEvent, PenProgress};
+use rnote_compose::penpath::Element;
+use rnote_compose::shapes::Shapeable;
+use rnote_compose::style::PressureCurve;
+use rnote_compose::style::smooth::SmoothOptions;
+use rnote_compose::{PenPath, Style};
+use std::time::Instant;
+use tracing::error;
+
+/// Fills the region enclosed by the strokes around the pressed position.
+///
+/// The strokes in the viewport are rasterized, the region is flood filled and its outer boundary is traced back
+/// into a filled brush stroke. The fill is inserted into the active layer below the strokes it overlaps,
+/// so that the strokes enclosing the region and the islands inside it stay visible.
+#[derive(Debug, Default)]
+pub struct FillTool {
+    state: ToolsState,
+}
+
+impl FillTool {
+    /// Pixels with an alpha value equal or above this threshold are part of the region boundary.
+    const BOUNDARY_ALPHA_THRESHOLD: u8 = 64;
+    /// The stroke width of the fill outline in pixels, covering the anti-aliased edges toward the boundary.
+    const OUTLINE_WIDTH_PX: f64 = 2.0;
+
+    pub(super) fn is_active(&self) -> bool {
+        matches!(self.state, ToolsState::Active)
+    }
+
+    pub(super) fn handle_event(
+        &mut self,
+        event: PenEvent,
+        _now: Instant,
+        engine_view: &mut EngineViewMut,
+    ) -> (EventResult<PenProgress>, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
+
+        let event_result = match (&mut self.state, event) {
+            (ToolsState::Idle, PenEvent::Down { element, .. }) => {
+                match Self::fill_region(element.pos, engine_view) {
+                    Ok(Some(fill_widget_flags)) => widget_flags |= fill_widget_flags,
+                    Ok(None) => widget_flags.warning = Some(UserWarning::FillRegionNotEnclosed),
+                    Err(e) => error!("Filling region failed, Err: {e:?}"),
+                }
+                self.state = ToolsState::Active;
+
+                EventResult {
+                    handled: true,
+                    propagate: EventPropagation::Stop,
+                    progress: PenProgress::InProgress,
+                }
+            }
+            (ToolsState::Idle, _) => EventResult {
+                handled: false,
+                propagate: EventPropagation::Proceed,
+                progress: PenProgress::Idle,
+            },
+            (ToolsState::Active, PenEvent::Down { .. }) => EventResult {
+                handled: true,
+                propagate: EventPropagation::Stop,
+                progress: PenProgress::InProgress,
+            },
+            (ToolsState::Active, PenEvent::Up { .. } | PenEvent::Cancel) => {
+                self.state = ToolsState::Idle;
+
+                EventResult {
+                    handled: true,
+                    propagate: EventPropagation::Stop,
+                    progress: PenProgress::Finished,
+                }
+            }
+            (
+                ToolsState::Active,
+                PenEvent::Proximity { .. } | PenEvent::KeyPressed { .. } | PenEvent::Text { .. },
+            ) => EventResult {
+                handled: false,
+                propagate: EventPropagation::Proceed,
+                progress: PenProgress::InProgress,
+            },
+        };
+
+        (event_result, widget_flags)
+    }
+
+    /// Fills the region around the position and inserts it as new stroke.
+    ///
+    /// Returns None when the region is not enclosed within the viewport.
+    fn fill_region(
+        pos: na::Vector2<f64>,
+        engine_view: &mut EngineViewMut,
+    ) -> anyhow::Result<Option<WidgetFlags>> {
+        let mut widget_flags = WidgetFlags::default();
+        let viewport = engine_view.camera.viewport();
+        let image_scale = engine_view.camera.image_scale();
+
+        let keys = engine_view
+            .store
+            .stroke_keys_as_rendered_intersecting_bounds(viewport);
+        let store = &*engine_view.store;
+        let image = Image::gen_with_cairo(
+            |cx| {
+                for key in keys {
+                    if let Some(stroke) = store.get_stroke_ref(key) {
+                        stroke.draw_to_cairo(cx, image_scale)?;
+                    }
+                }
+                Ok(())
+            },
+            viewport,
+            image_scale,
+        )?;
+
+        let image_mins = image.rect.bounds().mins.coords;
+        let width = image.pixel_width as usize;
+        let height = image.pixel_height as usize;
+        let start = (pos - image_mins) * image_scale;
+        if start[0] < 0.0 || start[1] < 0.0 {
+            return Ok(None);
+        }
+        let start = (start[0] as usize, start[1] as usize);
+        if start.0 >= width || start.1 >= height {
+            return Ok(None);
+        }
+        let boundary = image
+            .data
+            .chunks_exact(4)
+            .map(|px| px[3] >= Self::BOUNDARY_ALPHA_THRESHOLD)
+            .collect::<Vec<bool>>();
+
+        let Some(filled) = flood_fill(&boundary, width, height, start) else {
+            return Ok(None);
+        };
+        let Some(corners) = trace_outer_boundary(&filled, width, height) else {
+            return Ok(None);
+        };
+
+        let elements = corners
+            .into_iter()
+            .map(|(x, y)| {
+                Element::new(
+                    image_mins + na::vector![x as f64, y as f64] / image_scale,
+                    1.0,
+                )
+            })
+            .collect::<Vec<Element>>();
+        let Some(mut path) =
+            PenPath::try_from_elements(elements.iter().copied().chain(elements.first().copied()))
+        else {
+            return Ok(None);
+        };
+        path.simplify(1.0 / image_scale);
+
+        let color = engine_view
+            .config
+            .pens_config
+            .tools_config
+            .fill_tool_config
+            .color;
+        let mut options = SmoothOptions::default();
+        options.stroke_width = Self::OUTLINE_WIDTH_PX / image_scale;
+        options.stroke_color = Some(color);
+        options.pressure_curve = PressureCurve::Const;
+        options.update_piet_stroke_style();
+        let mut brushstroke = BrushStroke::from_penpath(path, Style::Smooth(Box::new(options)));
+        brushstroke.fill_color = Some(color);
+        let stroke = Stroke::BrushStroke(brushstroke);
+
+        let overlapped_keys = engine_view
+            .store
+            .stroke_keys_as_rendered_intersecting_bounds(stroke.bounds());
+        let key = engine_view.store.insert_stroke(
+            stroke,
+            Some(StrokeLayer::UserLayer(engine_view.store.active_layer())),
+        );
+        widget_flags |= engine_view.store.move_stroke_below(key, &overlapped_keys);
+        engine_view
+            .store
+            .regenerate_rendering_for_stroke(key, viewport, image_scale);
+
+        widget_flags |= engine_view.store.record(Instant::now());
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+        widget_flags.stroke_committed = true;
+
+        Ok(Some(widget_flags))
+    }
+}
+
+impl DrawableOnDoc for FillTool {
+    fn bounds_on_doc(&self, _engine_view: &EngineView) -> Option<Aabb> {
+        None
+    }
+
+    fn draw_on_doc(
+        &self,
+        _cx: &mut piet_cairo::CairoRenderContext,
+        _engine_view: &EngineView,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Scanline flood fill of the 4-connected region around the start pixel that does not contain boundary pixels.
+///
+/// Returns None if the region touches the image border, meaning it is not enclosed.
+fn flood_fill(
+    boundary: &[bool],
+    width: usize,
+    height: usize,
+    start: (usize, usize),
+) -> Option<Vec<bool>> {
+    let mut filled = vec![false; width * height];
+    let fillable =
+        |filled: &[bool], x: usize, y: usize| !boundary[y * width + x] && !filled[y * width + x];
+    if !fillable(&filled, start.0, start.1) {
+        return None;
+    }
+
+    let mut stack = vec![start];
+    while let Some((x, y)) = stack.pop() {
+        if !fillable(&filled, x, y) {
+            continue;
+        }
+        let mut x_start = x;
+        while x_start > 0 && fillable(&filled, x_start - 1, y) {
+            x_start -= 1;
+        }
+        let mut x_end = x;
+        while x_end + 1 < width && fillable(&filled, x_end + 1, y) {
+            x_end += 1;
+        }
+        if x_start == 0 || x_end == width - 1 || y == 0 || y == height - 1 {
+            return None;
+        }
+        filled[y * width + x_start..=y * width + x_end].fill(true);
+
+        for y_next in [y - 1, y + 1] {
+            let mut in_span = false;
+            for x_next in x_start..=x_end {
+                if fillable(&filled, x_next, y_next) {
+                    if !in_span {
+                        stack.push((x_next, y_next));
+                        in_span = true;
+                    }
+                } else {
+                    in_span = false;
+                }
+            }
+        }
+    }
+
+    Some(filled)
+}
+
+/// Traces the outer boundary of the filled region along the pixel corners, keeping the region on the right side.
+///
+/// Returns the corners where the boundary changes its direction.
+fn trace_outer_boundary(filled: &[bool], width: usize, height: usize) -> Option<Vec<(i64, i64)>> {
+    let is_filled = |x: i64, y: i64| {
+        x >= 0
+            && y >= 0
+            && (x as usize) < width
+            && (y as usize) < height
+            && filled[y as usize * width + x as usize]
+    };
+    let first = filled.iter().position(|&f| f)?;
+    // The top left corner of the first filled pixel is always on the outer boundary
+    let start = ((first % width) as i64, (first / width) as i64);
+
+    let mut corners = vec![start];
+    let mut corner = start;
+    let mut dir = (1_i64, 0_i64);
+    for _ in 0..=4 * width * height {
+        let (dx, dy) = dir;
+        let (rx, ry) = (-dy, dx);
+        let ahead_right = is_filled(corner.0 + (dx + rx - 1) / 2, corner.1 + (dy + ry - 1) / 2);
+        let ahead_left = is_filled(corner.0 + (dx - rx - 1) / 2, corner.1 + (dy - ry - 1) / 2);
+        let new_dir = if !ahead_right {
+            (rx, ry)
+        } else if ahead_left {
+            (-rx, -ry)
+        } else {
+            dir
+        };
+        if new_dir != dir {
+            corners.push(corner);
+            dir = new_dir;
+        }
+        corner = (corner.0 + dir.0, corner.1 + dir.1);
+        if corner == start {
+            return Some(corners);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The boundary from rows of `#` for boundary pixels and `.` for empty pixels.
+    fn boundary_from_rows(rows: &[&str]) -> (Vec<bool>, usize, usize) {
+        let boundary = rows
+            .iter()
+            .flat_map(|row| row.chars().map(|c| c == '#'))
+            .collect();
+        (boundary, rows[0].len(), rows.len())
+    }
+
+    const CLOSED_SQUARE: [&str; 7] = [
+        ".......", //
+        ".#####.", //
+        ".#...#.", //
+        ".#...#.", //
+        ".#...#.", //
+        ".#####.", //
+        ".......", //
+    ];
+
+    #[test]
+    fn closed_shape_is_filled() {
+        let (boundary, width, height) = boundary_from_rows(&CLOSED_SQUARE);
+        let filled = flood_fill(&boundary, width, height, (3, 3)).unwrap();
+
+        let filled_pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| filled[y * width + x])
+            .collect::<Vec<(usize, usize)>>();
+        assert_eq!(filled_pixels.len(), 9);
+        assert!(
+            filled_pixels
+                .iter()
+                .all(|&(x, y)| (2..=4).contains(&x) && (2..=4).contains(&y))
+        );
+        assert_eq!(
+            trace_outer_boundary(&filled, width, height).unwrap(),
+            vec![(2, 2), (5, 2), (5, 5), (2, 5)]
+        );
+    }
+
+    #[test]
+    fn concave_region_boundary() {
+        let (boundary, width, height) = boundary_from_rows(&[
+            ".......", //
+            ".#####.", //
+            ".#..##.", //
+            ".#..##.", //
+            ".#...#.", //
+            ".#####.", //
+            ".......", //
+        ]);
+        let filled = flood_fill(&boundary, width, height, (2, 2)).unwrap();
+
+        assert_eq!(
+            trace_outer_boundary(&filled, width, height).unwrap(),
+            vec![(2, 2), (4, 2), (4, 4), (5, 4), (5, 5), (2, 5)]
+        );
+    }
+
+    #[test]
+    fn shape_with_gap_is_not_filled() {
+        let (boundary, width, height) = boundary_from_rows(&[
+            ".......", //
+            ".#####.", //
+            ".#...#.", //
+            ".#.....", //
+            ".#...#.", //
+            ".#####.", //
+            ".......", //
+        ]);
+        assert!(flood_fill(&boundary, width, height, (3, 3)).is_none());
+    }
+
+    #[test]
+    fn region_reaching_the_raster_border_is_not_filled() {
+        // The fill is limited to the rasterized viewport, regions that extend beyond it are not enclosed
+        let (boundary, width, height) = boundary_from_rows(&[
+            ".....#.", //
+            ".....#.", //
+            ".....#.", //
+            "######.", //
+            ".......", //
+        ]);
+        assert!(flood_fill(&boundary, width, height, (2, 1)).is_none());
+        // Starting on the boundary itself
+        let (boundary, width, height) = boundary_from_rows(&CLOSED_SQUARE);
+        assert!(flood_fill(&boundary, width, height, (1, 1)).is_none());
+    }
+}
@@ -55,6 +55,10 @@ impl LaserTool {
         .line_join(piet::LineJoin::Round)
         .line_cap(piet::LineCap::Round);
 
+    pub(super) fn is_active(&self) -> bool {
+        matches!(self.state, ToolsState::Active)
+    }
+
     pub fn add_new_stroke(&mut self, element: Element) {
         self.pen_paths.push(PenPath::new(element));
         self.stop_fade();
@@ -1,12 +1,20 @@
 // Modules
+mod colorpicker;
+mod fill;
 mod laser;
+mod nodeedit;
 mod offsetcamera;
+mod smudge;
 mod verticalspace;
 mod zoom;
 
 // Re-Exports
+use colorpicker::ColorPickerTool;
+use fill::FillTool;
 use laser::LaserTool;
+use nodeedit::NodeEditTool;
 use offsetcamera::OffsetCameraTool;
+use smudge::SmudgeTool;
 use verticalspace::VerticalSpaceTool;
 use zoom::ZoomTool;
 
@@ -40,6 +48,10 @@ pub struct Tools {
     offsetcamera_tool: OffsetCameraTool,
     zoom_tool: ZoomTool,
     laser_tool: LaserTool,
+    fill_tool: FillTool,
+    colorpicker_tool: ColorPickerTool,
+    smudge_tool: SmudgeTool,
+    nodeedit_tool: NodeEditTool,
 }
 
 impl PenBehaviour for Tools {
@@ -55,6 +67,17 @@ impl PenBehaviour for Tools {
         PenStyle::Tools
     }
 
+    fn is_active(&self) -> bool {
+        self.verticalspace_tool.is_active()
+            || self.offsetcamera_tool.is_active()
+            || self.zoom_tool.is_active()
+            || self.laser_tool.is_active()
+            || self.fill_tool.is_active()
+            || self.colorpicker_tool.is_active()
+            || self.smudge_tool.is_active()
+            || self.nodeedit_tool.is_active()
+    }
+
     fn update_state(&mut self, _engine_view: &mut EngineViewMut) -> WidgetFlags {
         WidgetFlags::default()
     }
@@ -73,6 +96,10 @@ impl PenBehaviour for Tools {
             ToolStyle::OffsetCamera => self.offsetcamera_tool.handle_event(event, now, engine_view),
             ToolStyle::Zoom => self.zoom_tool.handle_event(event, now, engine_view),
             ToolStyle::Laser => self.laser_tool.handle_event(event, now, engine_view),
+            ToolStyle::Fill => self.fill_tool.handle_event(event, now, engine_view),
+            ToolStyle::ColorPicker => self.colorpicker_tool.handle_event(event, now, engine_view),
+            ToolStyle::Smudge => self.smudge_tool.handle_event(event, now, engine_view),
+            ToolStyle::NodeEdit => self.nodeedit_tool.handle_event(event, now, engine_view),
         }
     }
 
@@ -91,6 +118,10 @@ impl DrawableOnDoc for Tools {
             ToolStyle::OffsetCamera => self.offsetcamera_tool.bounds_on_doc(engine_view),
             ToolStyle::Zoom => self.zoom_tool.bounds_on_doc(engine_view),
             ToolStyle::Laser => self.laser_tool.bounds_on_doc(engine_view),
+            ToolStyle::Fill => self.fill_tool.bounds_on_doc(engine_view),
+            ToolStyle::ColorPicker => self.colorpicker_tool.bounds_on_doc(engine_view),
+            ToolStyle::Smudge => self.smudge_tool.bounds_on_doc(engine_view),
+            ToolStyle::NodeEdit => self.nodeedit_tool.bounds_on_doc(engine_view),
         }
     }
 
@@ -114,6 +145,18 @@ impl DrawableOnDoc for Tools {
             ToolStyle::Laser => {
                 self.laser_tool.draw_on_doc(cx, engine_view)?;
             }
+            ToolStyle::Fill => {
+                self.fill_tool.draw_on_doc(cx, engine_view)?;
+            }
+            ToolStyle::ColorPicker => {
+                self.colorpicker_tool.draw_on_doc(cx, engine_view)?;
+            }
+            ToolStyle::Smudge => {
+                self.smudge_tool.draw_on_doc(cx, engine_view)?;
+            }
+            ToolStyle::NodeEdit => {
+                self.nodeedit_tool.draw_on_doc(cx, engine_view)?;
+            }
         }
 
         cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
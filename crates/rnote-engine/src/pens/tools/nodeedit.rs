@@ -0,0 +1,341 @@
+// Imports
+use crate::engine::{EngineView, EngineViewMut};
+use crate::store::StrokeKey;
+use crate::strokes::Stroke;
+use crate::{DrawableOnDoc, WidgetFlags};
+use p2d::bounding_volume::{Aabb, BoundingSphere, BoundingVolume};
+use rnote_compose::eventresult::{EventPropagation, EventResult};
+use rnote_compose::penevent::{KeyboardKey, PenEvent, PenProgress, PenState};
+use rnote_compose::shapes::Shapeable;
+use rnote_compose::style::indicators;
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, Default)]
+enum NodeEditState {
+    #[default]
+    Idle,
+    Editing {
+        /// The brush stroke whose path elements are edited.
+        key: StrokeKey,
+        /// The index of the node that was pressed last, which the pressure is adjusted for.
+        active_node: Option<usize>,
+        /// The last pen position while the pen is pressed.
+        pressed_pos: Option<na::Vector2<f64>>,
+        /// Whether the path was edited since entering the mode.
+        modified: bool,
+    },
+}
+
+/// Edits the path elements of a brush stroke as nodes.
+///
+/// Pressing a brush stroke enters the mode and shows its elements as nodes. Nodes can be dragged to move them and
+/// the pressure of the last pressed node is adjusted with the arrow up and down keys.
+/// Pressing outside of the stroke, escape or enter exits the mode and records all edits as a single history entry.
+#[derive(Debug, Default)]
+pub struct NodeEditTool {
+    state: NodeEditState,
+}
+
+impl NodeEditTool {
+    /// The radius of the nodes in surface coordinates, for a pressure of 1.0.
+    const NODE_RADIUS: f64 = 6.0;
+    /// The pressure change for a single arrow key press.
+    const PRESSURE_STEP: f64 = 0.05;
+
+    pub(super) fn is_active(&self) -> bool {
+        matches!(self.state, NodeEditState::Editing { .. })
+    }
+
+    pub(super) fn handle_event(
+        &mut self,
+        event: PenEvent,
+        now: Instant,
+        engine_view: &mut EngineViewMut,
+    ) -> (EventResult<PenProgress>, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
+
+        let event_result = match (&mut self.state, event) {
+            (NodeEditState::Idle, PenEvent::Down { element, .. }) => {
+                let Some(key) = Self::brushstroke_at(element.pos, &engine_view.as_im()) else {
+                    return (
+                        EventResult {
+                            handled: false,
+                            propagate: EventPropagation::Proceed,
+                            progress: PenProgress::Idle,
+                        },
+                        widget_flags,
+                    );
+                };
+                self.state = NodeEditState::Editing {
+                    key,
+                    active_node: None,
+                    pressed_pos: Some(element.pos),
+                    modified: false,
+                };
+                widget_flags.redraw = true;
+
+                EventResult {
+                    handled: true,
+                    propagate: EventPropagation::Stop,
+                    progress: PenProgress::InProgress,
+                }
+            }
+            (NodeEditState::Idle, _) => EventResult {
+                handled: false,
+                propagate: EventPropagation::Proceed,
+                progress: PenProgress::Idle,
+            },
+            (
+                NodeEditState::Editing {
+                    key,
+                    active_node,
+                    pressed_pos,
+                    modified,
+                },
+                PenEvent::Down { element, .. },
+            ) => {
+                match pressed_pos {
+                    Some(pressed_pos) => {
+                        // Dragging the pressed node
+                        if let Some(index) = *active_node {
+                            let offset = element.pos - *pressed_pos;
+                            if let Some(Stroke::BrushStroke(brushstroke)) =
+                                engine_view.store.get_stroke_mut(*key)
+                            {
+                                brushstroke.path_mut().translate_element(index, offset);
+                                widget_flags |= Self::update_edited_stroke(*key, engine_view);
+                                *modified = true;
+                            }
+                        }
+                        *pressed_pos = element.pos;
+                    }
+                    None => {
+                        if let Some(index) = Self::node_at(*key, element.pos, &engine_view.as_im())
+                        {
+                            *active_node = Some(index);
+                        } else if let Some(other_key) =
+                            Self::brushstroke_at(element.pos, &engine_view.as_im())
+                        {
+                            if *modified {
+                                widget_flags |= engine_view.store.record(now);
+                            }
+                            *key = other_key;
+                            *active_node = None;
+                            *modified = false;
+                        } else {
+                            widget_flags |= self.exit(now, engine_view);
+                            return (
+                                EventResult {
+                                    handled: true,
+                                    propagate: EventPropagation::Stop,
+                                    progress: PenProgress::Finished,
+                                },
+                                widget_flags,
+                            );
+                        }
+                        *pressed_pos = Some(element.pos);
+                    }
+                }
+                widget_flags.redraw = true;
+
+                EventResult {
+                    handled: true,
+                    propagate: EventPropagation::Stop,
+                    progress: PenProgress::InProgress,
+                }
+            }
+            (NodeEditState::Editing { pressed_pos, .. }, PenEvent::Up { .. }) => {
+                *pressed_pos = None;
+                widget_flags.redraw = true;
+
+                EventResult {
+                    handled: true,
+                    propagate: EventPropagation::Stop,
+                    progress: PenProgress::InProgress,
+                }
+            }
+            (
+                NodeEditState::Editing {
+                    key,
+                    active_node,
+                    modified,
+                    ..
+                },
+                PenEvent::KeyPressed { keyboard_key, .. },
+            ) => match keyboard_key {
+                KeyboardKey::NavUp | KeyboardKey::NavDown => {
+                    let step = if keyboard_key == KeyboardKey::NavUp {
+                        Self::PRESSURE_STEP
+                    } else {
+                        -Self::PRESSURE_STEP
+                    };
+                    if let Some(index) = *active_node
+                        && let Some(Stroke::BrushStroke(brushstroke)) =
+                            engine_view.store.get_stroke_mut(*key)
+                    {
+                        let pressure = brushstroke
+                            .path()
+                            .elements()
+                            .nth(index)
+                            .map(|el| el.pressure);
+                        if let Some(pressure) = pressure {
+                            brushstroke
+                                .path_mut()
+                                .set_element_pressure(index, pressure + step);
+                            widget_flags |= Self::update_edited_stroke(*key, engine_view);
+                            *modified = true;
+                        }
+                    }
+
+                    EventResult {
+                        handled: true,
+                        propagate: EventPropagation::Stop,
+                        progress: PenProgress::InProgress,
+                    }
+                }
+                KeyboardKey::Escape | KeyboardKey::CarriageReturn => {
+                    widget_flags |= self.exit(now, engine_view);
+
+                    EventResult {
+                        handled: true,
+                        propagate: EventPropagation::Stop,
+                        progress: PenProgress::Finished,
+                    }
+                }
+                _ => EventResult {
+                    handled: false,
+                    propagate: EventPropagation::Proceed,
+                    progress: PenProgress::InProgress,
+                },
+            },
+            (NodeEditState::Editing { .. }, PenEvent::Cancel) => {
+                widget_flags |= self.exit(now, engine_view);
+
+                EventResult {
+                    handled: true,
+                    propagate: EventPropagation::Stop,
+                    progress: PenProgress::Finished,
+                }
+            }
+            (NodeEditState::Editing { .. }, PenEvent::Proximity { .. } | PenEvent::Text { .. }) => {
+                EventResult {
+                    handled: false,
+                    propagate: EventPropagation::Proceed,
+                    progress: PenProgress::InProgress,
+                }
+            }
+        };
+
+        (event_result, widget_flags)
+    }
+
+    /// Exits the mode, recording the edits as a single history entry.
+    fn exit(&mut self, now: Instant, engine_view: &mut EngineViewMut) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if let NodeEditState::Editing { modified: true, .. } = self.state {
+            widget_flags |= engine_view.store.record(now);
+        }
+        self.state = NodeEditState::Idle;
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
+    /// A brush stroke at the position.
+    fn brushstroke_at(pos: na::Vector2<f64>, engine_view: &EngineView) -> Option<StrokeKey> {
+        engine_view
+            .store
+            .stroke_hitboxes_contain_coord(engine_view.camera.viewport(), pos)
+            .into_iter()
+            .find(|&key| {
+                matches!(
+                    engine_view.store.get_stroke_ref(key),
+                    Some(Stroke::BrushStroke(_))
+                )
+            })
+    }
+
+    /// The index of the node of the brush stroke nearest to the position, if it is within the node radius.
+    fn node_at(key: StrokeKey, pos: na::Vector2<f64>, engine_view: &EngineView) -> Option<usize> {
+        let Some(Stroke::BrushStroke(brushstroke)) = engine_view.store.get_stroke_ref(key) else {
+            return None;
+        };
+        let radius = Self::NODE_RADIUS / engine_view.camera.total_zoom();
+        brushstroke
+            .path()
+            .elements()
+            .enumerate()
+            .map(|(index, element)| (index, (element.pos - pos).magnitude()))
+            .filter(|(_, dist)| *dist <= radius)
+            .min_by(|(_, first), (_, second)| first.total_cmp(second))
+            .map(|(index, _)| index)
+    }
+
+    /// Updates the geometry and the rendering of the edited stroke.
+    fn update_edited_stroke(key: StrokeKey, engine_view: &mut EngineViewMut) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        engine_view.store.update_geometry_for_stroke(key);
+        engine_view.store.regenerate_rendering_for_stroke_threaded(
+            engine_view.tasks_tx.clone(),
+            key,
+            engine_view.camera.viewport(),
+            engine_view.camera.image_scale(),
+        );
+        widget_flags |= engine_view
+            .document
+            .resize_autoexpand(engine_view.store, engine_view.camera);
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+}
+
+impl DrawableOnDoc for NodeEditTool {
+    fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
+        let NodeEditState::Editing { key, .. } = self.state else {
+            return None;
+        };
+        let stroke = engine_view.store.get_stroke_ref(key)?;
+        Some(
+            stroke
+                .bounds()
+                .loosened(Self::NODE_RADIUS * 2.0 / engine_view.camera.total_zoom()),
+        )
+    }
+
+    fn draw_on_doc(
+        &self,
+        cx: &mut piet_cairo::CairoRenderContext,
+        engine_view: &EngineView,
+    ) -> anyhow::Result<()> {
+        let NodeEditState::Editing {
+            key,
+            active_node,
+            pressed_pos,
+            ..
+        } = self.state
+        else {
+            return Ok(());
+        };
+        let Some(Stroke::BrushStroke(brushstroke)) = engine_view.store.get_stroke_ref(key) else {
+            return Ok(());
+        };
+        let total_zoom = engine_view.camera.total_zoom();
+
+        for (index, element) in brushstroke.path().elements().enumerate() {
+            let node_state = match active_node {
+                Some(active) if active == index && pressed_pos.is_some() => PenState::Down,
+                Some(active) if active == index => PenState::Proximity,
+                _ => PenState::Up,
+            };
+            // The node size shows the pressure of the element
+            let radius = Self::NODE_RADIUS * (0.5 + element.pressure * 0.5) / total_zoom;
+            indicators::draw_circular_node(
+                cx,
+                node_state,
+                BoundingSphere::new(element.pos.into(), radius),
+                total_zoom,
+            );
+        }
+
+        Ok(())
+    }
+}
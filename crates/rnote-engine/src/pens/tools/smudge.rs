@@ -0,0 +1,418 @@
+// Imports
+use crate::engine::{EngineView, EngineViewMut};
+use crate::pens::pensconfig::toolsconfig::SmudgeToolConfig;
+use crate::store::chrono_comp::StrokeLayer;
+use crate::strokes::{BitmapImage, Stroke};
+use crate::{Drawable, DrawableOnDoc, Image, WidgetFlags};
+use p2d::bounding_volume::Aabb;
+use piet::RenderContext;
+use rnote_compose::eventresult::{EventPropagation, EventResult};
+use rnote_compose::ext::AabbExt;
+use rnote_compose::penevent::{PenEvent, PenProgress};
+use rnote_compose::shapes::{Rectangle, Shapeable};
+use std::time::Instant;
+use tracing::error;
+
+/// The strokes in the viewport rasterized when the drag started, which are smudged while dragging.
+#[derive(Debug, Clone)]
+struct SmudgeRaster {
+    /// The pixels in rgba8-premultiplied format.
+    data: Vec<u8>,
+    width: usize,
+    height: usize,
+    /// The bounds of the raster on the document.
+    bounds: Aabb,
+    image_scale: f64,
+    /// The pixel range `(x_min, y_min, x_max, y_max)` that was changed, exclusive maxima.
+    modified: Option<(usize, usize, usize, usize)>,
+}
+
+impl SmudgeRaster {
+    /// The raster position of the position on the document.
+    fn pixel_pos(&self, pos: na::Vector2<f64>) -> na::Vector2<f64> {
+        (pos - self.bounds.mins.coords) * self.image_scale
+    }
+
+    /// Pushes the pixels within the radius around the position along the offset from the previous position.
+    ///
+    /// Each pixel is blended with the pixel the offset behind it, weighted by the strength and a linear falloff
+    /// toward the radius.
+    fn smudge(
+        &mut self,
+        prev_pos: na::Vector2<f64>,
+        pos: na::Vector2<f64>,
+        radius_px: f64,
+        strength: f64,
+    ) {
+        let center = self.pixel_pos(pos);
+        let offset = center - self.pixel_pos(prev_pos);
+        let x_min = (center[0] - radius_px).floor().max(0.0) as usize;
+        let y_min = (center[1] - radius_px).floor().max(0.0) as usize;
+        let x_max = ((center[0] + radius_px).ceil().max(0.0) as usize).min(self.width);
+        let y_max = ((center[1] + radius_px).ceil().max(0.0) as usize).min(self.height);
+        if x_min >= x_max || y_min >= y_max {
+            return;
+        }
+
+        // Sampled from the unmodified pixels and written afterwards, so the result does not depend on the iteration order
+        let mut smudged_pixels = Vec::new();
+        for y in y_min..y_max {
+            for x in x_min..x_max {
+                let px = na::vector![x as f64 + 0.5, y as f64 + 0.5];
+                let dist = (px - center).magnitude();
+                if dist > radius_px {
+                    continue;
+                }
+                let src = px - offset;
+                if src[0] < 0.0 || src[1] < 0.0 {
+                    continue;
+                }
+                let (src_x, src_y) = (src[0] as usize, src[1] as usize);
+                if src_x >= self.width || src_y >= self.height {
+                    continue;
+                }
+                let weight = strength * (1.0 - dist / radius_px);
+                let i = (y * self.width + x) * 4;
+                let src_i = (src_y * self.width + src_x) * 4;
+                let current = &self.data[i..i + 4];
+                let smudged: [u8; 4] = std::array::from_fn(|c| {
+                    let current = f64::from(current[c]);
+                    (current + (f64::from(self.data[src_i + c]) - current) * weight)
+                        .round()
+                        .clamp(0.0, 255.0) as u8
+                });
+                if smudged != current {
+                    smudged_pixels.push((i, smudged));
+                }
+            }
+        }
+
+        if !smudged_pixels.is_empty() {
+            for (i, smudged) in smudged_pixels {
+                self.data[i..i + 4].copy_from_slice(&smudged);
+            }
+            self.modified = Some(match self.modified {
+                Some((x0, y0, x1, y1)) => {
+                    (x0.min(x_min), y0.min(y_min), x1.max(x_max), y1.max(y_max))
+                }
+                None => (x_min, y_min, x_max, y_max),
+            });
+        }
+    }
+
+    /// The bounds of the modified pixels on the document.
+    fn modified_bounds(&self) -> Option<Aabb> {
+        let (x0, y0, x1, y1) = self.modified?;
+        Some(Aabb::new(
+            (self.bounds.mins.coords + na::vector![x0 as f64, y0 as f64] / self.image_scale).into(),
+            (self.bounds.mins.coords + na::vector![x1 as f64, y1 as f64] / self.image_scale).into(),
+        ))
+    }
+
+    /// The modified pixels as image, or None if nothing was modified.
+    fn modified_image(&self) -> Option<Image> {
+        let (x0, y0, x1, y1) = self.modified?;
+        let data = (y0..y1)
+            .flat_map(|y| &self.data[(y * self.width + x0) * 4..(y * self.width + x1) * 4])
+            .copied()
+            .collect::<Vec<u8>>();
+        Some(Image {
+            data: glib::Bytes::from_owned(data),
+            rect: Rectangle::from_p2d_aabb(self.modified_bounds()?),
+            pixel_width: (x1 - x0) as u32,
+            pixel_height: (y1 - y0) as u32,
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+enum SmudgeState {
+    #[default]
+    Idle,
+    Smudging {
+        raster: SmudgeRaster,
+        prev_pos: na::Vector2<f64>,
+    },
+}
+
+/// Smudges the rendered strokes, pushing their colors along the drag direction.
+///
+/// The strokes in the viewport are rasterized when the drag starts. The smudged pixels are inserted as bitmap image
+/// above the strokes when the drag ends, so each drag is a single undo step and the strokes themselves are kept.
+#[derive(Debug, Default)]
+pub struct SmudgeTool {
+    state: SmudgeState,
+}
+
+impl SmudgeTool {
+    pub(super) fn is_active(&self) -> bool {
+        matches!(self.state, SmudgeState::Smudging { .. })
+    }
+
+    pub(super) fn handle_event(
+        &mut self,
+        event: PenEvent,
+        _now: Instant,
+        engine_view: &mut EngineViewMut,
+    ) -> (EventResult<PenProgress>, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
+
+        let event_result = match (&mut self.state, event) {
+            (SmudgeState::Idle, PenEvent::Down { element, .. }) => {
+                match Self::rasterize_viewport(&engine_view.as_im()) {
+                    Ok(raster) => {
+                        self.state = SmudgeState::Smudging {
+                            raster,
+                            prev_pos: element.pos,
+                        };
+                    }
+                    Err(e) => error!("Rasterizing strokes for smudging failed, Err: {e:?}"),
+                }
+
+                EventResult {
+                    handled: true,
+                    propagate: EventPropagation::Stop,
+                    progress: PenProgress::InProgress,
+                }
+            }
+            (SmudgeState::Idle, _) => EventResult {
+                handled: false,
+                propagate: EventPropagation::Proceed,
+                progress: PenProgress::Idle,
+            },
+            (SmudgeState::Smudging { raster, prev_pos }, PenEvent::Down { element, .. }) => {
+                let config = &engine_view
+                    .config
+                    .pens_config
+                    .tools_config
+                    .smudge_tool_config;
+                let radius_px = config
+                    .radius
+                    .clamp(SmudgeToolConfig::RADIUS_MIN, SmudgeToolConfig::RADIUS_MAX)
+                    / engine_view.camera.total_zoom()
+                    * raster.image_scale;
+                let strength = config.strength.clamp(
+                    SmudgeToolConfig::STRENGTH_MIN,
+                    SmudgeToolConfig::STRENGTH_MAX,
+                );
+
+                // Smudged in steps, so that fast drags push the colors continuously
+                let step_len = (radius_px * 0.25).max(1.0) / raster.image_scale;
+                let start = *prev_pos;
+                let n_steps = ((element.pos - start).magnitude() / step_len).ceil() as usize;
+                for i in 1..=n_steps {
+                    let pos = start.lerp(&element.pos, i as f64 / n_steps as f64);
+                    raster.smudge(*prev_pos, pos, radius_px, strength);
+                    *prev_pos = pos;
+                }
+                widget_flags.redraw = true;
+
+                EventResult {
+                    handled: true,
+                    propagate: EventPropagation::Stop,
+                    progress: PenProgress::InProgress,
+                }
+            }
+            (SmudgeState::Smudging { raster, .. }, PenEvent::Up { .. }) => {
+                // Nothing changes when there are no strokes beneath the drag
+                if let Some(image) = raster.modified_image() {
+                    let rectangle = image.rect;
+                    // Inserted into the active layer above the smudged strokes, not below them in the image layer
+                    let layer = StrokeLayer::UserLayer(engine_view.store.active_layer());
+                    let key = engine_view.store.insert_stroke(
+                        Stroke::BitmapImage(BitmapImage { image, rectangle }),
+                        Some(layer),
+                    );
+                    engine_view.store.regenerate_rendering_for_stroke(
+                        key,
+                        engine_view.camera.viewport(),
+                        engine_view.camera.image_scale(),
+                    );
+
+                    widget_flags |= engine_view.store.record(Instant::now());
+                    widget_flags.store_modified = true;
+                    widget_flags.stroke_committed = true;
+                }
+                widget_flags.redraw = true;
+                self.state = SmudgeState::Idle;
+
+                EventResult {
+                    handled: true,
+                    propagate: EventPropagation::Stop,
+                    progress: PenProgress::Finished,
+                }
+            }
+            (SmudgeState::Smudging { .. }, PenEvent::Cancel) => {
+                widget_flags.redraw = true;
+                self.state = SmudgeState::Idle;
+
+                EventResult {
+                    handled: true,
+                    propagate: EventPropagation::Stop,
+                    progress: PenProgress::Finished,
+                }
+            }
+            (
+                SmudgeState::Smudging { .. },
+                PenEvent::Proximity { .. } | PenEvent::KeyPressed { .. } | PenEvent::Text { .. },
+            ) => EventResult {
+                handled: false,
+                propagate: EventPropagation::Proceed,
+                progress: PenProgress::InProgress,
+            },
+        };
+
+        (event_result, widget_flags)
+    }
+
+    /// Composites the strokes in the viewport without the background, with the image scale of the camera.
+    fn rasterize_viewport(engine_view: &EngineView) -> anyhow::Result<SmudgeRaster> {
+        let viewport = engine_view.camera.viewport();
+        let image_scale = engine_view.camera.image_scale();
+        let keys = engine_view
+            .store
+            .stroke_keys_as_rendered_intersecting_bounds(viewport);
+        let store = engine_view.store;
+        let image = Image::gen_with_cairo(
+            |cx| {
+                for key in keys {
+                    if let Some(stroke) = store.get_stroke_ref(key) {
+                        stroke.draw_to_cairo(cx, image_scale)?;
+                    }
+                }
+                Ok(())
+            },
+            viewport,
+            image_scale,
+        )?;
+
+        Ok(SmudgeRaster {
+            width: image.pixel_width as usize,
+            height: image.pixel_height as usize,
+            bounds: image.rect.bounds(),
+            data: image.data.to_vec(),
+            image_scale,
+            modified: None,
+        })
+    }
+}
+
+impl DrawableOnDoc for SmudgeTool {
+    fn bounds_on_doc(&self, _engine_view: &EngineView) -> Option<Aabb> {
+        match &self.state {
+            SmudgeState::Idle => None,
+            SmudgeState::Smudging { raster, .. } => raster.modified_bounds(),
+        }
+    }
+
+    fn draw_on_doc(
+        &self,
+        cx: &mut piet_cairo::CairoRenderContext,
+        _engine_view: &EngineView,
+    ) -> anyhow::Result<()> {
+        let SmudgeState::Smudging { raster, .. } = &self.state else {
+            return Ok(());
+        };
+        let Some(image) = raster.modified_image() else {
+            return Ok(());
+        };
+        let piet_image = cx
+            .make_image(
+                image.pixel_width as usize,
+                image.pixel_height as usize,
+                &image.data,
+                piet::ImageFormat::from(image.memory_format),
+            )
+            .map_err(|e| {
+                anyhow::anyhow!("Make piet image for smudge preview failed, Err: {e:?}")
+            })?;
+        cx.draw_image(
+            &piet_image,
+            image.rect.bounds().to_kurbo_rect(),
+            piet::InterpolationMode::Bilinear,
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+
+    /// A raster with a scale of 1.0 at the origin, the left half red and the right half transparent.
+    fn half_red_raster(width: usize, height: usize) -> SmudgeRaster {
+        let data = (0..height)
+            .flat_map(|_| (0..width).flat_map(move |x| if x < width / 2 { RED } else { [0; 4] }))
+            .collect();
+        SmudgeRaster {
+            data,
+            width,
+            height,
+            bounds: Aabb::new(
+                na::point![0.0, 0.0],
+                na::point![width as f64, height as f64],
+            ),
+            image_scale: 1.0,
+            modified: None,
+        }
+    }
+
+    fn pixel(raster: &SmudgeRaster, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * raster.width + x) * 4;
+        raster.data[i..i + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn smudge_pushes_colors_along_drag() {
+        let mut raster = half_red_raster(8, 8);
+        raster.smudge(na::vector![3.0, 4.0], na::vector![5.0, 4.0], 2.0, 1.0);
+
+        let [r, g, b, a] = pixel(&raster, 4, 4);
+        assert!(r > 0 && a > 0);
+        assert_eq!((r, g, b), (a, 0, 0));
+        // Outside of the radius
+        assert_eq!(pixel(&raster, 7, 4), [0; 4]);
+        assert_eq!(pixel(&raster, 5, 0), [0; 4]);
+
+        let (x0, y0, x1, y1) = raster.modified.unwrap();
+        assert!(x0 <= 4 && y0 <= 4 && x1 > 4 && y1 > 4);
+        assert!(x1 <= 8 && y1 <= 8);
+        let image = raster.modified_image().unwrap();
+        assert_eq!(image.pixel_width as usize, x1 - x0);
+        assert_eq!(image.pixel_height as usize, y1 - y0);
+        assert_eq!(image.data.len(), (x1 - x0) * (y1 - y0) * 4);
+    }
+
+    #[test]
+    fn smudge_without_change_is_not_modified() {
+        let mut raster = half_red_raster(8, 8);
+        // Within the uniformly colored halves
+        raster.smudge(na::vector![0.0, 4.0], na::vector![1.0, 4.0], 1.0, 1.0);
+        raster.smudge(na::vector![6.0, 4.0], na::vector![7.0, 4.0], 1.0, 1.0);
+        // Without strength
+        raster.smudge(na::vector![3.0, 4.0], na::vector![5.0, 4.0], 2.0, 0.0);
+        // Outside of the raster
+        raster.smudge(na::vector![-20.0, 4.0], na::vector![-10.0, 4.0], 2.0, 1.0);
+        raster.smudge(na::vector![20.0, 4.0], na::vector![30.0, 4.0], 2.0, 1.0);
+
+        assert!(raster.modified.is_none());
+        assert!(raster.modified_image().is_none());
+        assert_eq!(raster.data, half_red_raster(8, 8).data);
+    }
+
+    #[test]
+    fn modified_bounds_on_doc() {
+        let mut raster = half_red_raster(8, 8);
+        raster.bounds = Aabb::new(na::point![10.0, 20.0], na::point![14.0, 24.0]);
+        raster.image_scale = 2.0;
+        raster.modified = Some((2, 4, 6, 8));
+
+        let bounds = raster.modified_bounds().unwrap();
+        assert_eq!(bounds.mins, na::point![11.0, 22.0]);
+        assert_eq!(bounds.maxs, na::point![13.0, 24.0]);
+    }
+}
@@ -42,6 +42,10 @@ impl VerticalSpaceTool {
     const FILL_COLOR: piet::Color = color::GNOME_BRIGHTS[2].with_a8(23);
     const THRESHOLD_LINE_COLOR: piet::Color = color::GNOME_GREENS[4].with_a8(240);
 
+    pub(super) fn is_active(&self) -> bool {
+        matches!(self.state, ToolsState::Active)
+    }
+
     pub(super) fn handle_event(
         &mut self,
         event: PenEvent,
@@ -33,6 +33,10 @@ impl ZoomTool {
     const DARK_COLOR: piet::Color = color::GNOME_DARKS[3].with_a8(240);
     const LIGHT_COLOR: piet::Color = color::GNOME_BRIGHTS[1].with_a8(240);
 
+    pub(super) fn is_active(&self) -> bool {
+        matches!(self.state, ToolsState::Active)
+    }
+
     pub(super) fn handle_event(
         &mut self,
         event: PenEvent,
@@ -317,6 +317,13 @@ impl PenBehaviour for Typewriter {
         PenStyle::Typewriter
     }
 
+    fn is_active(&self) -> bool {
+        matches!(
+            self.state,
+            TypewriterState::Modifying { pen_down: true, .. }
+        )
+    }
+
     fn update_state(&mut self, engine_view: &mut EngineViewMut) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
 
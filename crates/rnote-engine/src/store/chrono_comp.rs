@@ -1,5 +1,6 @@
 // Imports
 use super::{StrokeKey, StrokeStore};
+use crate::WidgetFlags;
 use p2d::bounding_volume::Aabb;
 use rayon::slice::ParallelSliceMut;
 use serde::{Deserialize, Serialize};
@@ -94,6 +95,96 @@ impl StrokeStore {
         }
     }
 
+    /// Move the stroke to the front of its layer, so that it gets drawn above all other strokes of the layer.
+    pub fn move_stroke_to_front(&mut self, key: StrokeKey) -> WidgetFlags {
+        self.move_stroke_in_layer(key, |_, len| len - 1)
+    }
+
+    /// Move the stroke to the back of its layer, so that it gets drawn below all other strokes of the layer.
+    pub fn move_stroke_to_back(&mut self, key: StrokeKey) -> WidgetFlags {
+        self.move_stroke_in_layer(key, |_, _| 0)
+    }
+
+    /// Move the stroke one step forward in its layer, above the next stroke.
+    pub fn move_stroke_forward(&mut self, key: StrokeKey) -> WidgetFlags {
+        self.move_stroke_in_layer(key, |index, len| (index + 1).min(len - 1))
+    }
+
+    /// Move the stroke one step backward in its layer, below the previous stroke.
+    pub fn move_stroke_backward(&mut self, key: StrokeKey) -> WidgetFlags {
+        self.move_stroke_in_layer(key, |index, _| index.saturating_sub(1))
+    }
+
+    /// Move the stroke in its layer directly below the lowest of the given strokes that are on the same layer,
+    /// so that it gets drawn below all of them. Stays in place when it is already below them.
+    pub(crate) fn move_stroke_below(&mut self, key: StrokeKey, keys: &[StrokeKey]) -> WidgetFlags {
+        let Some(layer) = self.chrono_components.get(key).map(|c| c.layer) else {
+            return WidgetFlags::default();
+        };
+        let Some(lowest_index) = self
+            .layer_keys_sorted_chrono(layer)
+            .iter()
+            .position(|k| *k != key && keys.contains(k))
+        else {
+            return WidgetFlags::default();
+        };
+        self.move_stroke_in_layer(key, |index, _| index.min(lowest_index))
+    }
+
+    /// The keys of the not trashed strokes in the layer, in chronological order.
+    fn layer_keys_sorted_chrono(&self, layer: StrokeLayer) -> Vec<StrokeKey> {
+        self.keys_sorted_chrono()
+            .into_iter()
+            .filter(|&k| {
+                !(self.trashed(k).unwrap_or(false))
+                    && self
+                        .chrono_components
+                        .get(k)
+                        .is_some_and(|c| c.layer == layer)
+            })
+            .collect()
+    }
+
+    /// Move the stroke to the new index in the draw order of the not trashed strokes in its layer.
+    ///
+    /// The strokes of the layer get their existing chrono times reassigned in the new order,
+    /// so the order relative to the other strokes and the chrono counter stay untouched.
+    fn move_stroke_in_layer(
+        &mut self,
+        key: StrokeKey,
+        new_index: impl FnOnce(usize, usize) -> usize,
+    ) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let Some(layer) = self.chrono_components.get(key).map(|c| c.layer) else {
+            return widget_flags;
+        };
+        let mut layer_keys = self.layer_keys_sorted_chrono(layer);
+        let Some(index) = layer_keys.iter().position(|&k| k == key) else {
+            return widget_flags;
+        };
+        let new_index = new_index(index, layer_keys.len());
+        if new_index == index {
+            return widget_flags;
+        }
+        let times = layer_keys
+            .iter()
+            .filter_map(|&k| self.chrono_components.get(k).map(|c| c.t))
+            .collect::<Vec<u32>>();
+        let moved = layer_keys.remove(index);
+        layer_keys.insert(new_index, moved);
+
+        let chrono_components = Arc::make_mut(&mut self.chrono_components);
+        for (k, t) in layer_keys.into_iter().zip(times) {
+            if let Some(chrono_comp) = chrono_components.get_mut(k) {
+                Arc::make_mut(chrono_comp).t = t;
+            }
+        }
+
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+
     /// Returns the keys in chronological order, as in first: gets drawn first, last: gets drawn last.
     pub(crate) fn keys_sorted_chrono(&self) -> Vec<StrokeKey> {
         let mut keys = self.stroke_components.keys().collect::<Vec<StrokeKey>>();
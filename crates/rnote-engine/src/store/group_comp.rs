@@ -0,0 +1,413 @@
+// Imports
+use super::{StrokeKey, StrokeStore};
+use crate::WidgetFlags;
+use serde::{Deserialize, Serialize};
+use slotmap::SlotMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+slotmap::new_key_type! {
+    pub struct GroupId;
+}
+
+/// A named group of strokes, which are selected, moved, transformed, hidden and locked together.
+///
+/// The group a stroke belongs to is stored in its [MetadataComponent](super::MetadataComponent).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "stroke_group")]
+pub struct StrokeGroup {
+    #[serde(rename = "name")]
+    pub name: String,
+    /// The group this group is nested in.
+    #[serde(rename = "parent")]
+    pub parent: Option<GroupId>,
+    /// The strokes of hidden groups are not rendered and can't be selected.
+    #[serde(rename = "hidden")]
+    pub hidden: bool,
+}
+
+/// Systems that are related to groups.
+impl StrokeStore {
+    /// Rebuild the groups from the given map, removing the parents and stroke memberships that don't exist in it.
+    ///
+    /// Parents that would nest a group in itself are removed as well.
+    pub(crate) fn rebuild_groups(&mut self, groups: &SlotMap<GroupId, Arc<StrokeGroup>>) {
+        self.groups = Arc::new(groups.clone());
+
+        let dangling_parents = self
+            .groups
+            .iter()
+            .filter_map(|(group, g)| {
+                g.parent
+                    .filter(|parent| {
+                        !self.groups.contains_key(*parent)
+                            || self.group_ancestors(group).skip(1).any(|a| a == group)
+                    })
+                    .map(|_| group)
+            })
+            .collect::<Vec<GroupId>>();
+        for group in dangling_parents {
+            if let Some(g) = Arc::make_mut(&mut self.groups).get_mut(group) {
+                Arc::make_mut(g).parent = None;
+            }
+        }
+
+        let dangling_members = self
+            .metadata_components
+            .iter()
+            .filter_map(|(key, metadata_comp)| {
+                metadata_comp
+                    .group
+                    .filter(|group| !self.groups.contains_key(*group))
+                    .map(|_| key)
+            })
+            .collect::<Vec<StrokeKey>>();
+        for key in dangling_members {
+            self.set_stroke_group(key, None);
+        }
+    }
+
+    /// The group the stroke is directly a member of.
+    pub fn stroke_group(&self, key: StrokeKey) -> Option<GroupId> {
+        self.metadata_components.get(key).and_then(|m| m.group)
+    }
+
+    pub fn get_group(&self, group: GroupId) -> Option<&StrokeGroup> {
+        self.groups.get(group).map(Arc::as_ref)
+    }
+
+    /// All groups, unordered.
+    pub fn groups_unordered(&self) -> impl Iterator<Item = (GroupId, &StrokeGroup)> {
+        self.groups.iter().map(|(group, g)| (group, g.as_ref()))
+    }
+
+    /// The group itself and the groups it is nested in, from the innermost to the outermost.
+    fn group_ancestors(&self, group: GroupId) -> impl Iterator<Item = GroupId> + '_ {
+        std::iter::successors(Some(group), |&group| {
+            self.groups.get(group).and_then(|g| g.parent)
+        })
+        .take(self.groups.len())
+    }
+
+    /// The outermost group that the stroke is a member of, directly or through nested groups.
+    pub fn stroke_top_level_group(&self, key: StrokeKey) -> Option<GroupId> {
+        self.stroke_group(key)
+            .and_then(|group| self.group_ancestors(group).last())
+    }
+
+    /// Whether one of the groups the stroke is nested in is hidden.
+    pub(crate) fn group_hidden(&self, key: StrokeKey) -> bool {
+        self.stroke_group(key).is_some_and(|group| {
+            self.group_ancestors(group)
+                .any(|group| self.groups.get(group).is_some_and(|g| g.hidden))
+        })
+    }
+
+    /// The keys of the strokes that are not trashed and are members of the group, directly or through nested groups,
+    /// including the locked and hidden ones.
+    fn group_member_keys_unordered(&self, group: GroupId) -> Vec<StrokeKey> {
+        self.stroke_components
+            .keys()
+            .filter(|&key| {
+                !(self.trashed(key).unwrap_or(false))
+                    && self
+                        .stroke_group(key)
+                        .is_some_and(|g| self.group_ancestors(g).any(|g| g == group))
+            })
+            .collect()
+    }
+
+    /// The keys of the strokes that are not trashed, locked or hidden
+    /// and are members of the group, directly or through nested groups.
+    pub fn group_keys_unordered(&self, group: GroupId) -> Vec<StrokeKey> {
+        self.group_member_keys_unordered(group)
+            .into_iter()
+            .filter(|&key| !self.locked(key).unwrap_or(false) && !self.hidden(key))
+            .collect()
+    }
+
+    /// The keys of the strokes that are selected together with the given stroke,
+    /// which are the members of its top level group or only the stroke itself when it is not grouped.
+    ///
+    /// Locked and hidden strokes are excluded.
+    pub(crate) fn keys_grouped_with(&self, key: StrokeKey) -> Vec<StrokeKey> {
+        match self.stroke_top_level_group(key) {
+            Some(group) => self.group_keys_unordered(group),
+            None if !self.locked(key).unwrap_or(false) && !self.hidden(key) => vec![key],
+            None => vec![],
+        }
+    }
+
+    fn set_stroke_group(&mut self, key: StrokeKey, group: Option<GroupId>) {
+        if let Some(metadata_comp) = Arc::make_mut(&mut self.metadata_components)
+            .get_mut(key)
+            .map(Arc::make_mut)
+        {
+            metadata_comp.group = group;
+        }
+    }
+
+    /// Group the strokes into a new group with the given name.
+    ///
+    /// Strokes that are already grouped keep their group, which instead gets nested in the new group.
+    pub fn group_strokes(&mut self, keys: &[StrokeKey], name: String) -> GroupId {
+        let new_group = Arc::make_mut(&mut self.groups).insert(Arc::new(StrokeGroup {
+            name,
+            ..Default::default()
+        }));
+
+        for &key in keys {
+            match self.stroke_top_level_group(key) {
+                Some(group) if group != new_group => {
+                    if let Some(g) = Arc::make_mut(&mut self.groups).get_mut(group) {
+                        Arc::make_mut(g).parent = Some(new_group);
+                    }
+                }
+                Some(_) => {}
+                None => self.set_stroke_group(key, Some(new_group)),
+            }
+        }
+
+        new_group
+    }
+
+    /// Move the given strokes into copies of the groups they are members of, including the groups they are nested in.
+    ///
+    /// Used for duplicated strokes, so that they are grouped the same way as the originals without joining their groups.
+    pub(crate) fn regroup_into_group_copies(&mut self, keys: &[StrokeKey]) {
+        let mut copies = HashMap::<GroupId, GroupId>::new();
+        for &key in keys {
+            let Some(group) = self.stroke_group(key) else {
+                continue;
+            };
+            let copy = self.group_copy(group, &mut copies);
+            self.set_stroke_group(key, Some(copy));
+        }
+    }
+
+    fn group_copy(&mut self, group: GroupId, copies: &mut HashMap<GroupId, GroupId>) -> GroupId {
+        if let Some(&copy) = copies.get(&group) {
+            return copy;
+        }
+        let mut stroke_group = self
+            .groups
+            .get(group)
+            .map(|g| (**g).clone())
+            .unwrap_or_default();
+        if let Some(parent) = stroke_group.parent {
+            stroke_group.parent = Some(self.group_copy(parent, copies));
+        }
+        let copy = Arc::make_mut(&mut self.groups).insert(Arc::new(stroke_group));
+        copies.insert(group, copy);
+        copy
+    }
+
+    /// Remove the group. Its members and nested groups are moved into the group it was nested in.
+    pub fn ungroup(&mut self, group: GroupId) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let Some(removed) = Arc::make_mut(&mut self.groups).remove(group) else {
+            return widget_flags;
+        };
+
+        let member_keys = self
+            .metadata_components
+            .iter()
+            .filter_map(|(key, m)| (m.group == Some(group)).then_some(key))
+            .collect::<Vec<StrokeKey>>();
+        for key in member_keys {
+            self.set_stroke_group(key, removed.parent);
+        }
+        for g in Arc::make_mut(&mut self.groups).values_mut() {
+            if g.parent == Some(group) {
+                Arc::make_mut(g).parent = removed.parent;
+            }
+        }
+
+        if removed.hidden {
+            widget_flags.redraw = true;
+        }
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+
+    pub fn rename_group(&mut self, group: GroupId, name: String) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if let Some(g) = Arc::make_mut(&mut self.groups).get_mut(group) {
+            Arc::make_mut(g).name = name;
+            widget_flags.store_modified = true;
+        }
+        widget_flags
+    }
+
+    /// Set if the group is hidden. Hiding deselects the members of the group.
+    pub fn set_group_hidden(&mut self, group: GroupId, hidden: bool) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let Some(g) = Arc::make_mut(&mut self.groups).get_mut(group) else {
+            return widget_flags;
+        };
+        if g.hidden == hidden {
+            return widget_flags;
+        }
+        Arc::make_mut(g).hidden = hidden;
+
+        if hidden {
+            let keys = self.group_member_keys_unordered(group);
+            self.set_selected_keys(&keys, false);
+            widget_flags.selection_changed = true;
+        }
+        widget_flags.store_modified = true;
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
+    /// Move the members of the group to the front of their layers, keeping their order among each other.
+    pub fn move_group_to_front(&mut self, group: GroupId) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        for key in self.group_keys_sorted_chrono(group) {
+            widget_flags |= self.move_stroke_to_front(key);
+        }
+        widget_flags
+    }
+
+    /// Move the members of the group to the back of their layers, keeping their order among each other.
+    pub fn move_group_to_back(&mut self, group: GroupId) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        for key in self.group_keys_sorted_chrono(group).into_iter().rev() {
+            widget_flags |= self.move_stroke_to_back(key);
+        }
+        widget_flags
+    }
+
+    fn group_keys_sorted_chrono(&self, group: GroupId) -> Vec<StrokeKey> {
+        let member_keys = self
+            .group_member_keys_unordered(group)
+            .into_iter()
+            .collect::<HashSet<StrokeKey>>();
+        self.keys_sorted_chrono()
+            .into_iter()
+            .filter(|key| member_keys.contains(key))
+            .collect()
+    }
+
+    /// Lock or unlock all members of the group.
+    pub fn set_group_locked(&mut self, group: GroupId, locked: bool) -> WidgetFlags {
+        let keys = self.group_member_keys_unordered(group);
+        self.set_locked_keys(&keys, locked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::brushstroke_at_origin;
+
+    fn sorted(mut keys: Vec<StrokeKey>) -> Vec<StrokeKey> {
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn nested_group_members() {
+        let mut store = StrokeStore::default();
+        let (a, b, c) = (
+            store.insert_stroke(brushstroke_at_origin(), None),
+            store.insert_stroke(brushstroke_at_origin(), None),
+            store.insert_stroke(brushstroke_at_origin(), None),
+        );
+        let inner = store.group_strokes(&[a, b], String::from("Inner"));
+        let outer = store.group_strokes(&[b, c], String::from("Outer"));
+
+        assert_eq!(store.get_group(inner).unwrap().parent, Some(outer));
+        assert_eq!(store.stroke_group(a), Some(inner));
+        assert_eq!(store.stroke_group(c), Some(outer));
+        assert_eq!(store.stroke_top_level_group(a), Some(outer));
+        assert_eq!(
+            sorted(store.group_keys_unordered(inner)),
+            sorted(vec![a, b])
+        );
+        assert_eq!(sorted(store.keys_grouped_with(a)), sorted(vec![a, b, c]));
+    }
+
+    #[test]
+    fn locked_and_hidden_members_are_excluded() {
+        let mut store = StrokeStore::default();
+        let (a, b, c) = (
+            store.insert_stroke(brushstroke_at_origin(), None),
+            store.insert_stroke(brushstroke_at_origin(), None),
+            store.insert_stroke(brushstroke_at_origin(), None),
+        );
+        let group = store.group_strokes(&[a, b, c], String::from("Group"));
+        let _ = store.set_locked(b, true);
+        let _ = store.set_stroke_visible(c, false);
+        assert_eq!(store.group_keys_unordered(group), vec![a]);
+        assert_eq!(store.keys_grouped_with(a), vec![a]);
+
+        // Unlocking the group reaches the locked member
+        let _ = store.set_group_locked(group, false);
+        assert_eq!(sorted(store.keys_grouped_with(a)), sorted(vec![a, b]));
+
+        let ungrouped = store.insert_stroke(brushstroke_at_origin(), None);
+        let _ = store.set_locked(ungrouped, true);
+        assert!(store.keys_grouped_with(ungrouped).is_empty());
+    }
+
+    #[test]
+    fn ungroup_moves_members_into_parent() {
+        let mut store = StrokeStore::default();
+        let (a, b, c) = (
+            store.insert_stroke(brushstroke_at_origin(), None),
+            store.insert_stroke(brushstroke_at_origin(), None),
+            store.insert_stroke(brushstroke_at_origin(), None),
+        );
+        let innermost = store.group_strokes(&[a], String::from("Innermost"));
+        let inner = store.group_strokes(&[a, b], String::from("Inner"));
+        let outer = store.group_strokes(&[b, c], String::from("Outer"));
+
+        let _ = store.ungroup(inner);
+        assert!(store.get_group(inner).is_none());
+        assert_eq!(store.get_group(innermost).unwrap().parent, Some(outer));
+        assert_eq!(store.stroke_group(b), Some(outer));
+        assert_eq!(sorted(store.keys_grouped_with(a)), sorted(vec![a, b, c]));
+
+        let _ = store.ungroup(outer);
+        assert_eq!(store.get_group(innermost).unwrap().parent, None);
+        assert_eq!(store.stroke_group(b), None);
+        assert_eq!(store.keys_grouped_with(a), vec![a]);
+        assert_eq!(store.keys_grouped_with(c), vec![c]);
+    }
+
+    #[test]
+    fn regroup_into_group_copies_keeps_nesting() {
+        let mut store = StrokeStore::default();
+        let (a, b) = (
+            store.insert_stroke(brushstroke_at_origin(), None),
+            store.insert_stroke(brushstroke_at_origin(), None),
+        );
+        let inner = store.group_strokes(&[a], String::from("Inner"));
+        let outer = store.group_strokes(&[a, b], String::from("Outer"));
+        let (a_copy, b_copy) = (
+            store.insert_stroke(brushstroke_at_origin(), None),
+            store.insert_stroke(brushstroke_at_origin(), None),
+        );
+        // Duplicated strokes copy the metadata of their originals
+        store.set_stroke_group(a_copy, Some(inner));
+        store.set_stroke_group(b_copy, Some(outer));
+
+        store.regroup_into_group_copies(&[a_copy, b_copy]);
+        let (inner_copy, outer_copy) = (
+            store.stroke_group(a_copy).unwrap(),
+            store.stroke_group(b_copy).unwrap(),
+        );
+        assert!(inner_copy != inner && outer_copy != outer);
+        assert_eq!(store.get_group(inner_copy).unwrap().name, "Inner");
+        assert_eq!(
+            store.get_group(inner_copy).unwrap().parent,
+            Some(outer_copy)
+        );
+        assert_eq!(sorted(store.keys_grouped_with(a)), sorted(vec![a, b]));
+        assert_eq!(
+            sorted(store.keys_grouped_with(a_copy)),
+            sorted(vec![a_copy, b_copy])
+        );
+    }
+}
@@ -0,0 +1,457 @@
+// Imports
+use super::chrono_comp::StrokeLayer;
+use super::{StrokeKey, StrokeStore};
+use crate::WidgetFlags;
+use crate::strokes::Stroke;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+/// A layer that strokes are drawn on.
+///
+/// The layers are stacked in the order of their indices, which are referenced by [StrokeLayer::UserLayer].
+/// The highlighter, image and document layers are always drawn below them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename = "layer")]
+pub struct Layer {
+    #[serde(rename = "name")]
+    pub name: String,
+    /// The opacity the strokes of the layer are composited with, between 0.0 and 1.0.
+    #[serde(rename = "opacity")]
+    pub opacity: f64,
+    /// The strokes of hidden layers are not rendered or exported and can't be selected.
+    #[serde(rename = "visible")]
+    pub visible: bool,
+    /// The strokes of locked layers are still rendered, but can't be selected or erased.
+    #[serde(rename = "locked")]
+    pub locked: bool,
+}
+
+impl Default for Layer {
+    fn default() -> Self {
+        Self {
+            name: String::from("Layer"),
+            opacity: 1.0,
+            visible: true,
+            locked: false,
+        }
+    }
+}
+
+impl Layer {
+    pub const OPACITY_MIN: f64 = 0.0;
+    pub const OPACITY_MAX: f64 = 1.0;
+
+    /// A layer with the default name for the given index.
+    pub(crate) fn new_default_for(index: usize) -> Self {
+        Self {
+            name: format!("Layer {}", index + 1),
+            ..Default::default()
+        }
+    }
+}
+
+/// The layers of an empty document.
+pub(crate) fn default_layers() -> Vec<Layer> {
+    vec![Layer::new_default_for(0)]
+}
+
+/// Draw the strokes on the cairo context with the given draw function, in order.
+///
+/// Expects the strokes together with the layer they are on and its opacity.
+/// Consecutive strokes on a translucent layer are drawn to a group that is composited with the layer opacity,
+/// the same way as on the canvas.
+pub(crate) fn draw_with_layer_opacity_to_cairo<'a>(
+    cairo_cx: &cairo::Context,
+    strokes: impl IntoIterator<Item = (&'a Stroke, Option<(StrokeLayer, f64)>)>,
+    mut draw_stroke: impl FnMut(&Stroke) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    // The translucent layer and its opacity, while its strokes are drawn to a group.
+    let mut translucent_layer: Option<(StrokeLayer, f64)> = None;
+
+    for (stroke, layer) in strokes {
+        let layer = layer.filter(|(_, layer_opacity)| *layer_opacity < 1.0);
+        if let Some((translucent, layer_opacity)) = translucent_layer
+            && layer.is_none_or(|(layer, _)| layer != translucent)
+        {
+            cairo_cx.pop_group_to_source()?;
+            cairo_cx.paint_with_alpha(layer_opacity)?;
+            translucent_layer = None;
+        }
+        if translucent_layer.is_none()
+            && let Some(layer) = layer
+        {
+            cairo_cx.push_group();
+            translucent_layer = Some(layer);
+        }
+
+        draw_stroke(stroke)?;
+    }
+
+    if let Some((_, layer_opacity)) = translucent_layer {
+        cairo_cx.pop_group_to_source()?;
+        cairo_cx.paint_with_alpha(layer_opacity)?;
+    }
+    Ok(())
+}
+
+/// Systems that are related to layers.
+///
+/// The layer of a stroke is stored in its [ChronoComponent](super::ChronoComponent).
+impl StrokeStore {
+    /// Rebuild the layers from the given ones.
+    ///
+    /// Layers are added for strokes that reference a layer that doesn't exist,
+    /// which loads documents from before layers were introduced into a default layer.
+    pub(crate) fn rebuild_layers(&mut self, layers: &[Layer]) {
+        let needed_len = self
+            .chrono_components
+            .values()
+            .filter_map(|chrono_comp| match chrono_comp.layer {
+                StrokeLayer::UserLayer(index) => Some(index as usize + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        let mut layers = layers.to_vec();
+        while layers.len() < needed_len {
+            layers.push(Layer::new_default_for(layers.len()));
+        }
+        self.layers = Arc::new(layers);
+        self.clamp_active_layer();
+    }
+
+    pub(super) fn clamp_active_layer(&mut self) {
+        self.active_layer = self
+            .active_layer
+            .min(self.layers.len().saturating_sub(1) as u32);
+    }
+
+    /// The layers, from the bottom to the top.
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    /// The index of the layer that new strokes are inserted into.
+    pub fn active_layer(&self) -> u32 {
+        self.active_layer
+    }
+
+    /// Set the layer that new strokes are inserted into.
+    pub fn set_active_layer(&mut self, index: u32) {
+        self.active_layer = index;
+        self.clamp_active_layer();
+    }
+
+    /// Map strokes in a user layer into the active layer. The other layers are kept.
+    pub(crate) fn in_active_layer(&self, layer: StrokeLayer) -> StrokeLayer {
+        match layer {
+            StrokeLayer::UserLayer(_) => StrokeLayer::UserLayer(self.active_layer),
+            layer => layer,
+        }
+    }
+
+    /// The layer the stroke is on, if it is on a user layer.
+    fn stroke_user_layer(&self, key: StrokeKey) -> Option<&Layer> {
+        match self.chrono_components.get(key)?.layer {
+            StrokeLayer::UserLayer(index) => self.layers.get(index as usize),
+            _ => None,
+        }
+    }
+
+    /// Whether the stroke is on a hidden layer.
+    pub(crate) fn layer_hidden(&self, key: StrokeKey) -> bool {
+        self.stroke_user_layer(key)
+            .is_some_and(|layer| !layer.visible)
+    }
+
+    /// Whether the stroke is on a locked layer.
+    pub(crate) fn layer_locked(&self, key: StrokeKey) -> bool {
+        self.stroke_user_layer(key)
+            .is_some_and(|layer| layer.locked)
+    }
+
+    /// The opacity the strokes of the layer are composited with. Is 1.0 for all layers that are not user layers.
+    pub(crate) fn layer_opacity(&self, layer: StrokeLayer) -> f64 {
+        match layer {
+            StrokeLayer::UserLayer(index) => self
+                .layers
+                .get(index as usize)
+                .map(|layer| layer.opacity)
+                .unwrap_or(1.0),
+            _ => 1.0,
+        }
+    }
+
+    /// The layers of the strokes for the keys together with the layer opacity, for all keys that have a stroke.
+    pub(crate) fn get_stroke_layers_with_opacity(
+        &self,
+        keys: &[StrokeKey],
+    ) -> Vec<(StrokeLayer, f64)> {
+        keys.iter()
+            .filter(|&&key| self.stroke_components.contains_key(key))
+            .map(|&key| {
+                let layer = self
+                    .chrono_components
+                    .get(key)
+                    .map(|c| c.layer)
+                    .unwrap_or_default();
+                (layer, self.layer_opacity(layer))
+            })
+            .collect()
+    }
+
+    /// The keys of the strokes that are not trashed and are on the layer, unordered.
+    pub fn layer_keys_unordered(&self, index: u32) -> Vec<StrokeKey> {
+        self.stroke_components
+            .keys()
+            .filter(|&key| {
+                !(self.trashed(key).unwrap_or(false))
+                    && self
+                        .chrono_components
+                        .get(key)
+                        .is_some_and(|c| c.layer == StrokeLayer::UserLayer(index))
+            })
+            .collect()
+    }
+
+    /// Create a new layer above all other layers and make it the active layer.
+    ///
+    /// Returns the index of the new layer.
+    pub fn create_layer(&mut self, name: String) -> (u32, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
+        let layers = Arc::make_mut(&mut self.layers);
+        layers.push(Layer {
+            name,
+            ..Default::default()
+        });
+        self.active_layer = (layers.len() - 1) as u32;
+
+        widget_flags.store_modified = true;
+        (self.active_layer, widget_flags)
+    }
+
+    /// Delete the layer and trash its strokes. The last remaining layer can't be deleted.
+    ///
+    /// The trashed strokes are moved onto the layer below, or the layer above when it was the bottom layer,
+    /// so that they never reference a layer that doesn't exist.
+    pub fn delete_layer(&mut self, index: u32) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if self.layers.len() <= 1 || index as usize >= self.layers.len() {
+            return widget_flags;
+        }
+
+        let keys = self.layer_keys_unordered(index);
+        self.set_selected_keys(&keys, false);
+        self.set_trashed_keys(&keys, true);
+        Arc::make_mut(&mut self.layers).remove(index as usize);
+        self.remap_user_layers(|i| match i.cmp(&index) {
+            Ordering::Less => i,
+            Ordering::Equal => index.saturating_sub(1),
+            Ordering::Greater => i - 1,
+        });
+        if self.active_layer > index {
+            self.active_layer -= 1;
+        }
+        self.clamp_active_layer();
+
+        widget_flags.store_modified = true;
+        widget_flags.selection_changed = true;
+        widget_flags.resize = true;
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
+    /// Move the layer to the new index in the layer stack. The active layer stays the same.
+    pub fn move_layer(&mut self, from: u32, to: u32) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let len = self.layers.len() as u32;
+        if from >= len || to >= len || from == to {
+            return widget_flags;
+        }
+
+        let layers = Arc::make_mut(&mut self.layers);
+        let layer = layers.remove(from as usize);
+        layers.insert(to as usize, layer);
+        let new_index = |i: u32| {
+            if i == from {
+                to
+            } else if from < to && i > from && i <= to {
+                i - 1
+            } else if to < from && i >= to && i < from {
+                i + 1
+            } else {
+                i
+            }
+        };
+        self.remap_user_layers(new_index);
+        self.active_layer = new_index(self.active_layer);
+
+        widget_flags.store_modified = true;
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
+    /// Reassign the user layers of all strokes, including trashed ones.
+    fn remap_user_layers(&mut self, new_index: impl Fn(u32) -> u32) {
+        for chrono_comp in Arc::make_mut(&mut self.chrono_components).values_mut() {
+            if let StrokeLayer::UserLayer(index) = chrono_comp.layer {
+                let new = new_index(index);
+                if new != index {
+                    Arc::make_mut(chrono_comp).layer = StrokeLayer::UserLayer(new);
+                }
+            }
+        }
+    }
+
+    /// Move the strokes onto the layer, above its other strokes.
+    pub fn move_strokes_to_layer(&mut self, keys: &[StrokeKey], index: u32) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if index as usize >= self.layers.len() {
+            return widget_flags;
+        }
+
+        let mut keys = keys.to_vec();
+        self.sort_keys_chrono(&mut keys);
+        for key in keys {
+            if let Some(chrono_comp) = Arc::make_mut(&mut self.chrono_components).get_mut(key) {
+                Arc::make_mut(chrono_comp).layer = StrokeLayer::UserLayer(index);
+                self.update_chrono_to_last(key);
+                widget_flags.store_modified = true;
+                widget_flags.redraw = true;
+            }
+        }
+
+        if self.layers[index as usize].locked || !self.layers[index as usize].visible {
+            let keys = self.layer_keys_unordered(index);
+            self.set_selected_keys(&keys, false);
+            widget_flags.selection_changed = true;
+        }
+        widget_flags
+    }
+
+    pub fn set_layer_name(&mut self, index: u32, name: String) -> WidgetFlags {
+        self.modify_layer(index, |layer| layer.name = name)
+    }
+
+    /// Set the opacity the strokes of the layer are composited with.
+    pub fn set_layer_opacity(&mut self, index: u32, opacity: f64) -> WidgetFlags {
+        let opacity = opacity.clamp(Layer::OPACITY_MIN, Layer::OPACITY_MAX);
+        self.modify_layer(index, |layer| layer.opacity = opacity)
+    }
+
+    /// Set if the layer is visible. Hiding deselects the strokes of the layer.
+    pub fn set_layer_visible(&mut self, index: u32, visible: bool) -> WidgetFlags {
+        let mut widget_flags = self.modify_layer(index, |layer| layer.visible = visible);
+        if !visible {
+            self.set_selected_keys(&self.layer_keys_unordered(index), false);
+            widget_flags.selection_changed = true;
+        }
+        widget_flags
+    }
+
+    /// Set if the layer is locked. Locking deselects the strokes of the layer.
+    pub fn set_layer_locked(&mut self, index: u32, locked: bool) -> WidgetFlags {
+        let mut widget_flags = self.modify_layer(index, |layer| layer.locked = locked);
+        if locked {
+            self.set_selected_keys(&self.layer_keys_unordered(index), false);
+            widget_flags.selection_changed = true;
+        }
+        if widget_flags.store_modified {
+            widget_flags.lock_state_changed = true;
+        }
+        widget_flags
+    }
+
+    fn modify_layer(&mut self, index: u32, modify: impl FnOnce(&mut Layer)) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let Some(layer) = Arc::make_mut(&mut self.layers).get_mut(index as usize) else {
+            return widget_flags;
+        };
+        let prev = layer.clone();
+        modify(layer);
+        if *layer != prev {
+            widget_flags.store_modified = true;
+            widget_flags.redraw = true;
+        }
+        widget_flags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::brushstroke_at_origin;
+
+    #[test]
+    fn strokes_without_layers_load_into_default_layers() {
+        let mut store = StrokeStore::default();
+        store.insert_stroke(brushstroke_at_origin(), Some(StrokeLayer::UserLayer(0)));
+        store.rebuild_layers(&[]);
+        assert_eq!(store.layers(), default_layers().as_slice());
+
+        store.insert_stroke(brushstroke_at_origin(), Some(StrokeLayer::UserLayer(2)));
+        store.rebuild_layers(&default_layers());
+        assert_eq!(store.layers().len(), 3);
+        assert_eq!(store.layers()[2].name, "Layer 3");
+    }
+
+    #[test]
+    fn move_layer_keeps_strokes_on_their_layer() {
+        let mut store = StrokeStore::default();
+        let bottom_key = store.insert_stroke(brushstroke_at_origin(), None);
+        let _ = store.create_layer(String::from("Top"));
+        let top_key = store.insert_stroke(brushstroke_at_origin(), None);
+
+        let _ = store.move_layer(1, 0);
+        assert_eq!(store.layers()[0].name, "Top");
+        assert_eq!(store.active_layer(), 0);
+        assert_eq!(store.layer_keys_unordered(0), vec![top_key]);
+        assert_eq!(store.layer_keys_unordered(1), vec![bottom_key]);
+    }
+
+    #[test]
+    fn delete_layer_moves_trashed_strokes_onto_existing_layer() {
+        let mut store = StrokeStore::default();
+        store.insert_stroke(brushstroke_at_origin(), None);
+        let _ = store.create_layer(String::from("Middle"));
+        let middle_key = store.insert_stroke(brushstroke_at_origin(), None);
+        let _ = store.create_layer(String::from("Top"));
+        let top_key = store.insert_stroke(brushstroke_at_origin(), None);
+
+        let _ = store.delete_layer(1);
+        assert_eq!(store.layers().len(), 2);
+        assert_eq!(store.trashed(middle_key), Some(true));
+        assert_eq!(
+            store.chrono_components.get(middle_key).map(|c| c.layer),
+            Some(StrokeLayer::UserLayer(0))
+        );
+        assert_eq!(store.layer_keys_unordered(1), vec![top_key]);
+
+        let _ = store.delete_layer(0);
+        assert_eq!(
+            store.chrono_components.get(middle_key).map(|c| c.layer),
+            Some(StrokeLayer::UserLayer(0))
+        );
+        assert_eq!(store.layer_keys_unordered(0), vec![top_key]);
+    }
+
+    #[test]
+    fn stroke_layers_have_the_layer_opacity() {
+        let mut store = StrokeStore::default();
+        let bottom_key = store.insert_stroke(brushstroke_at_origin(), None);
+        let _ = store.create_layer(String::from("Top"));
+        let _ = store.set_layer_opacity(1, 0.5);
+        let top_key = store.insert_stroke(brushstroke_at_origin(), None);
+
+        assert_eq!(
+            store.get_stroke_layers_with_opacity(&[bottom_key, top_key]),
+            vec![
+                (StrokeLayer::UserLayer(0), 1.0),
+                (StrokeLayer::UserLayer(1), 0.5)
+            ]
+        );
+    }
+}
@@ -0,0 +1,83 @@
+// Imports
+use super::{StrokeKey, StrokeStore};
+use crate::WidgetFlags;
+use serde::{Deserialize, Serialize};
+use slotmap::SecondaryMap;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "lock_component")]
+pub struct LockComponent {
+    #[serde(rename = "locked")]
+    pub locked: bool,
+}
+
+impl Default for LockComponent {
+    fn default() -> Self {
+        Self { locked: false }
+    }
+}
+
+/// Systems that are related to locking.
+///
+/// Locked strokes are still rendered, but can't be selected or erased.
+impl StrokeStore {
+    /// Rebuild the slotmap with the keys returned from the stroke components,
+    /// taking over the lock components of the given map and inserting empty ones for the remaining keys.
+    pub(crate) fn rebuild_lock_components_slotmap(
+        &mut self,
+        lock_components: &SecondaryMap<StrokeKey, Arc<LockComponent>>,
+    ) {
+        self.lock_components = Arc::new(slotmap::SecondaryMap::new());
+        self.stroke_components.keys().for_each(|key| {
+            let lock_comp = lock_components
+                .get(key)
+                .cloned()
+                .unwrap_or_else(|| Arc::new(LockComponent::default()));
+            Arc::make_mut(&mut self.lock_components).insert(key, lock_comp);
+        });
+    }
+
+    /// Whether the stroke is locked by itself or by the layer it is on.
+    pub(crate) fn locked(&self, key: StrokeKey) -> Option<bool> {
+        self.lock_components
+            .get(key)
+            .map(|l| l.locked || self.layer_locked(key))
+    }
+
+    /// Set if the stroke is locked. Locking deselects the stroke.
+    pub(crate) fn set_locked(&mut self, key: StrokeKey, locked: bool) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        if let Some(lock_comp) = Arc::make_mut(&mut self.lock_components)
+            .get_mut(key)
+            .map(Arc::make_mut)
+        {
+            if lock_comp.locked != locked {
+                lock_comp.locked = locked;
+                widget_flags.lock_state_changed = true;
+                widget_flags.store_modified = true;
+            }
+            if locked {
+                self.set_selected(key, false);
+            }
+        }
+
+        widget_flags
+    }
+
+    pub(crate) fn set_locked_keys(&mut self, keys: &[StrokeKey], locked: bool) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        keys.iter().for_each(|&key| {
+            widget_flags |= self.set_locked(key, locked);
+        });
+        widget_flags
+    }
+
+    pub(crate) fn locked_keys_unordered(&self) -> Vec<StrokeKey> {
+        self.stroke_components
+            .keys()
+            .filter(|&key| self.locked(key).unwrap_or(false))
+            .collect()
+    }
+}
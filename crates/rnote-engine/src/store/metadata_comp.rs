@@ -0,0 +1,92 @@
+// Imports
+use super::{GroupId, StrokeKey, StrokeStore};
+use crate::WidgetFlags;
+use serde::{Deserialize, Serialize};
+use slotmap::SecondaryMap;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+/// The tag of the invisible text strokes imported from the text of Pdf pages.
+pub const PDF_TEXT_LAYER_TAG: &str = "pdf-text-layer";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "metadata_component")]
+pub struct MetadataComponent {
+    /// Arbitrary tags the user attached to the stroke for organizing, e.g. "draft" or "annotation".
+    #[serde(rename = "tags")]
+    pub tags: BTreeSet<String>,
+    /// The group the stroke is a member of, see [StrokeStore::group_strokes()].
+    #[serde(rename = "group")]
+    pub group: Option<GroupId>,
+}
+
+/// Systems that are related to the stroke metadata.
+impl StrokeStore {
+    /// Rebuild the slotmap with the keys returned from the stroke components,
+    /// taking over the metadata components of the given map and inserting empty ones for the remaining keys.
+    pub(crate) fn rebuild_metadata_components_slotmap(
+        &mut self,
+        metadata_components: &SecondaryMap<StrokeKey, Arc<MetadataComponent>>,
+    ) {
+        self.metadata_components = Arc::new(slotmap::SecondaryMap::new());
+        self.stroke_components.keys().for_each(|key| {
+            let metadata_comp = metadata_components
+                .get(key)
+                .cloned()
+                .unwrap_or_else(|| Arc::new(MetadataComponent::default()));
+            Arc::make_mut(&mut self.metadata_components).insert(key, metadata_comp);
+        });
+    }
+
+    /// The tags of the stroke.
+    pub fn get_stroke_tags(&self, key: StrokeKey) -> Option<&BTreeSet<String>> {
+        self.metadata_components.get(key).map(|m| &m.tags)
+    }
+
+    /// Attach the tag to the stroke when `tagged` is true, else remove it.
+    pub fn set_stroke_tag(&mut self, key: StrokeKey, tag: &str, tagged: bool) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        if let Some(metadata_comp) = Arc::make_mut(&mut self.metadata_components)
+            .get_mut(key)
+            .map(Arc::make_mut)
+        {
+            let changed = if tagged {
+                metadata_comp.tags.insert(tag.to_string())
+            } else {
+                metadata_comp.tags.remove(tag)
+            };
+            if changed {
+                widget_flags.store_modified = true;
+            }
+        }
+
+        widget_flags
+    }
+
+    pub(crate) fn set_stroke_tag_keys(
+        &mut self,
+        keys: &[StrokeKey],
+        tag: &str,
+        tagged: bool,
+    ) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        keys.iter().for_each(|&key| {
+            widget_flags |= self.set_stroke_tag(key, tag, tagged);
+        });
+        widget_flags
+    }
+
+    /// The keys of all strokes that are not trashed and have the given tag.
+    pub fn stroke_keys_with_tag_unordered(&self, tag: &str) -> Vec<StrokeKey> {
+        self.stroke_components
+            .keys()
+            .filter(|&key| {
+                !(self.trashed(key).unwrap_or(false))
+                    && self
+                        .get_stroke_tags(key)
+                        .is_some_and(|tags| tags.contains(tag))
+            })
+            .collect()
+    }
+}
@@ -1,20 +1,34 @@
 // Modules
 pub mod chrono_comp;
+pub mod group_comp;
 pub mod keytree;
+pub mod layer_comp;
+pub mod lock_comp;
+pub mod metadata_comp;
+pub mod render_cache;
 pub mod render_comp;
 pub mod selection_comp;
 pub mod stroke_comp;
 pub mod trash_comp;
+pub mod visibility_comp;
 
 // Re-exports
 pub use chrono_comp::ChronoComponent;
+pub use group_comp::{GroupId, StrokeGroup};
 use keytree::KeyTree;
-pub use render_comp::RenderComponent;
+pub use layer_comp::Layer;
+pub use lock_comp::LockComponent;
+pub use metadata_comp::MetadataComponent;
+pub use render_cache::RenderCacheLimits;
+pub use render_comp::{LodConfig, RenderBatchHandle, RenderComponent, RenderProgressCallback};
 pub use selection_comp::SelectionComponent;
+pub use stroke_comp::{AlignMode, Axis};
 pub use trash_comp::TrashComponent;
+pub use visibility_comp::VisibilityComponent;
 
 // Imports
 use self::chrono_comp::StrokeLayer;
+use self::render_cache::RenderCache;
 use crate::WidgetFlags;
 use crate::engine::EngineSnapshot;
 use crate::strokes::Stroke;
@@ -23,13 +37,22 @@ use serde::{Deserialize, Serialize};
 use slotmap::{SecondaryMap, SlotMap};
 use std::collections::VecDeque;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tracing::debug;
 
 slotmap::new_key_type! {
     pub struct StrokeKey;
 }
 
+/// The kinds of edits that are merged into a single history entry when recorded in quick succession.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoalesceKind {
+    /// Moving the selection with the keyboard.
+    Nudge,
+    /// Changing the opacity of the selection, e.g. while dragging a slider.
+    Opacity,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "history_entry")]
 pub struct HistoryEntry {
@@ -37,10 +60,20 @@ pub struct HistoryEntry {
     pub stroke_components: Arc<SlotMap<StrokeKey, Arc<Stroke>>>,
     #[serde(rename = "trash_components")]
     pub trash_components: Arc<SecondaryMap<StrokeKey, Arc<TrashComponent>>>,
+    #[serde(rename = "lock_components")]
+    pub lock_components: Arc<SecondaryMap<StrokeKey, Arc<LockComponent>>>,
+    #[serde(rename = "visibility_components")]
+    pub visibility_components: Arc<SecondaryMap<StrokeKey, Arc<VisibilityComponent>>>,
+    #[serde(rename = "metadata_components")]
+    pub metadata_components: Arc<SecondaryMap<StrokeKey, Arc<MetadataComponent>>>,
+    #[serde(rename = "groups")]
+    pub groups: Arc<SlotMap<GroupId, Arc<StrokeGroup>>>,
     #[serde(rename = "chrono_components")]
     pub chrono_components: Arc<SecondaryMap<StrokeKey, Arc<ChronoComponent>>>,
     #[serde(rename = "chrono_counter")]
     pub chrono_counter: u32,
+    #[serde(rename = "layers")]
+    pub layers: Arc<Vec<Layer>>,
 }
 
 impl Default for HistoryEntry {
@@ -48,9 +81,49 @@ impl Default for HistoryEntry {
         Self {
             stroke_components: Arc::new(SlotMap::with_key()),
             trash_components: Arc::new(SecondaryMap::new()),
+            lock_components: Arc::new(SecondaryMap::new()),
+            visibility_components: Arc::new(SecondaryMap::new()),
+            metadata_components: Arc::new(SecondaryMap::new()),
+            groups: Arc::new(SlotMap::with_key()),
             chrono_components: Arc::new(SecondaryMap::new()),
 
             chrono_counter: 0,
+            layers: Arc::new(layer_comp::default_layers()),
+        }
+    }
+}
+
+/// A change of the store content, reported to the listeners registered with [StrokeStore::on_change()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreChange {
+    /// The stroke was inserted, or restored from the trash.
+    Inserted(StrokeKey),
+    /// The stroke was removed, or moved to the trash.
+    Removed(StrokeKey),
+    /// The stroke geometry or style was modified.
+    ///
+    /// Not emitted for every segment while a stroke is drawn, but when the stroke is finished.
+    Modified(StrokeKey),
+    /// The entire content was replaced, e.g. by undo, redo or loading a document.
+    Reset,
+}
+
+/// The listeners for store changes.
+#[derive(Default)]
+pub(crate) struct StoreChangeListeners(Vec<Box<dyn FnMut(StoreChange)>>);
+
+impl std::fmt::Debug for StoreChangeListeners {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("StoreChangeListeners")
+            .field(&self.0.len())
+            .finish()
+    }
+}
+
+impl StoreChangeListeners {
+    pub(crate) fn emit(&mut self, change: StoreChange) {
+        for listener in self.0.iter_mut() {
+            listener(change);
         }
     }
 }
@@ -60,8 +133,11 @@ impl Default for HistoryEntry {
 ///     * 'stroke_components': Holds state about geometric properties. These components are special in the way that they are the primary map.
 ///         A new stroke must have this component. (another name for them could be 'geometric_components')
 ///     * 'trash_components': Holds state whether the strokes are trashed
+///     * 'lock_components': Holds state whether the strokes are locked against selecting and erasing
+///     * 'visibility_components': Holds state whether the strokes are hidden from rendering and exporting
+///     * 'metadata_components': Holds user defined metadata like tags and the group of the stroke
 ///     * 'selection_components': Holds state whether the strokes are selected
-///     * 'chrono_components': Holds state about the chronological ordering
+///     * 'chrono_components': Holds state about the chronological ordering and the layer of the strokes
 ///     * 'render_components': Holds state about the rendering.
 ///
 /// The systems are implemented as methods on StrokesStore, loosely categorized to the different components (but often modify others as well).
@@ -75,6 +151,15 @@ pub struct StrokeStore {
     stroke_components: Arc<SlotMap<StrokeKey, Arc<Stroke>>>,
     #[serde(rename = "trash_components")]
     trash_components: Arc<SecondaryMap<StrokeKey, Arc<TrashComponent>>>,
+    #[serde(rename = "lock_components")]
+    lock_components: Arc<SecondaryMap<StrokeKey, Arc<LockComponent>>>,
+    #[serde(rename = "visibility_components")]
+    visibility_components: Arc<SecondaryMap<StrokeKey, Arc<VisibilityComponent>>>,
+    #[serde(rename = "metadata_components")]
+    metadata_components: Arc<SecondaryMap<StrokeKey, Arc<MetadataComponent>>>,
+    /// The stroke groups, referenced by the metadata components.
+    #[serde(rename = "groups")]
+    groups: Arc<SlotMap<GroupId, Arc<StrokeGroup>>>,
     #[serde(rename = "selection_components")]
     selection_components: Arc<SecondaryMap<StrokeKey, Arc<SelectionComponent>>>,
     #[serde(rename = "chrono_components")]
@@ -84,6 +169,12 @@ pub struct StrokeStore {
     /// Value must be kept equal to the [ChronoComponent] of the newest inserted or modified stroke.
     #[serde(rename = "chrono_counter")]
     chrono_counter: u32,
+    /// The user layers, from the bottom to the top, referenced by the chrono components.
+    #[serde(rename = "layers")]
+    layers: Arc<Vec<Layer>>,
+    /// The index of the layer that new strokes are inserted into.
+    #[serde(skip)]
+    active_layer: u32,
     #[serde(skip)]
     render_components: SecondaryMap<StrokeKey, RenderComponent>,
     #[serde(skip)]
@@ -91,11 +182,31 @@ pub struct StrokeStore {
     /// The index of the current live document in the history stack.
     #[serde(skip)]
     live_index: usize,
+    /// The nesting depth of open history groups. While greater than zero, recording is deferred.
+    #[serde(skip)]
+    history_group_depth: usize,
+    /// The maximum number of entries in the history, bounding its memory usage.
+    #[serde(skip)]
+    history_max_len: usize,
+    /// The kind and time of the latest coalescing record, used to merge following records of the same kind.
+    #[serde(skip)]
+    last_coalesced_record: Option<(CoalesceKind, Instant)>,
+    /// Whether thin horizontal and vertical lines are snapped to the pixel grid when rendering.
+    #[serde(skip)]
+    pixel_snap_thin_lines: bool,
+    /// The level of detail strokes are rendered with when zoomed out.
+    #[serde(skip)]
+    lod_config: LodConfig,
+    /// Caches the rendering of unchanged strokes, so that it doesn't need to be regenerated when zooming or panning.
+    #[serde(skip)]
+    render_cache: RenderCache,
     /// An rtree backed by the slotmap store, for faster spatial queries.
     ///
     /// Needs to be updated with `update_with_key()` when strokes changed their geometry or position!
     #[serde(skip)]
     key_tree: KeyTree,
+    #[serde(skip)]
+    change_listeners: StoreChangeListeners,
 }
 
 impl Default for StrokeStore {
@@ -103,6 +214,10 @@ impl Default for StrokeStore {
         Self {
             stroke_components: Arc::new(SlotMap::with_key()),
             trash_components: Arc::new(SecondaryMap::new()),
+            lock_components: Arc::new(SecondaryMap::new()),
+            visibility_components: Arc::new(SecondaryMap::new()),
+            metadata_components: Arc::new(SecondaryMap::new()),
+            groups: Arc::new(SlotMap::with_key()),
             selection_components: Arc::new(SecondaryMap::new()),
             chrono_components: Arc::new(SecondaryMap::new()),
             render_components: SecondaryMap::new(),
@@ -110,17 +225,37 @@ impl Default for StrokeStore {
             // Start off with state in the history
             history: VecDeque::from(vec![HistoryEntry::default()]),
             live_index: 0,
+            history_group_depth: 0,
+            history_max_len: Self::HISTORY_MAX_LEN,
+            last_coalesced_record: None,
+            pixel_snap_thin_lines: false,
+            lod_config: LodConfig::default(),
+            render_cache: RenderCache::default(),
 
             key_tree: KeyTree::default(),
+            change_listeners: StoreChangeListeners::default(),
 
             chrono_counter: 0,
+            layers: Arc::new(layer_comp::default_layers()),
+            active_layer: 0,
         }
     }
 }
 
 impl StrokeStore {
-    /// Max length of the history.
+    /// Default max length of the history.
     pub(crate) const HISTORY_MAX_LEN: usize = 100;
+    /// The minimum configurable max length of the history. The live state always needs an entry.
+    pub(crate) const HISTORY_MAX_LEN_MIN: usize = 2;
+    /// Records of the same kind following the previous one within this duration are coalesced.
+    pub(crate) const HISTORY_COALESCE_TIMEOUT: Duration = Duration::from_millis(1000);
+
+    /// Register a listener that is called on every change of the store content.
+    ///
+    /// Listeners are not stored in snapshots and stay registered when the content is replaced.
+    pub fn on_change(&mut self, listener: Box<dyn FnMut(StoreChange)>) {
+        self.change_listeners.0.push(listener);
+    }
 
     /// Import from a engine snapshot. A loaded strokes store should always be imported with this method.
     ///
@@ -136,9 +271,15 @@ impl StrokeStore {
         self.update_geometry_for_strokes(&self.keys_unordered());
         self.rebuild_selection_components_slotmap();
         self.rebuild_trash_components_slotmap();
+        self.rebuild_lock_components_slotmap(&snapshot.lock_components);
+        self.rebuild_visibility_components_slotmap(&snapshot.visibility_components);
+        self.rebuild_metadata_components_slotmap(&snapshot.metadata_components);
+        self.rebuild_groups(&snapshot.groups);
+        self.rebuild_layers(&snapshot.layers);
         self.rebuild_render_components_slotmap();
         self.rebuild_rtree();
         widget_flags |= self.clear_history(self.create_history_entry());
+        self.change_listeners.emit(StoreChange::Reset);
         widget_flags
     }
 
@@ -157,8 +298,19 @@ impl StrokeStore {
     fn eq_w_history_entry(&self, history_entry: &HistoryEntry) -> bool {
         Arc::ptr_eq(&self.stroke_components, &history_entry.stroke_components)
             && Arc::ptr_eq(&self.trash_components, &history_entry.trash_components)
+            && Arc::ptr_eq(&self.lock_components, &history_entry.lock_components)
+            && Arc::ptr_eq(
+                &self.visibility_components,
+                &history_entry.visibility_components,
+            )
+            && Arc::ptr_eq(
+                &self.metadata_components,
+                &history_entry.metadata_components,
+            )
+            && Arc::ptr_eq(&self.groups, &history_entry.groups)
             && Arc::ptr_eq(&self.chrono_components, &history_entry.chrono_components)
             && self.chrono_counter == history_entry.chrono_counter
+            && Arc::ptr_eq(&self.layers, &history_entry.layers)
     }
 
     /// Create a history entry from the current state.
@@ -166,8 +318,13 @@ impl StrokeStore {
         HistoryEntry {
             stroke_components: Arc::clone(&self.stroke_components),
             trash_components: Arc::clone(&self.trash_components),
+            lock_components: Arc::clone(&self.lock_components),
+            visibility_components: Arc::clone(&self.visibility_components),
+            metadata_components: Arc::clone(&self.metadata_components),
+            groups: Arc::clone(&self.groups),
             chrono_components: Arc::clone(&self.chrono_components),
             chrono_counter: self.chrono_counter,
+            layers: Arc::clone(&self.layers),
         }
     }
 
@@ -175,8 +332,14 @@ impl StrokeStore {
     fn import_history_entry(&mut self, history_entry: HistoryEntry) {
         self.stroke_components = Arc::clone(&history_entry.stroke_components);
         self.trash_components = Arc::clone(&history_entry.trash_components);
+        self.lock_components = Arc::clone(&history_entry.lock_components);
+        self.visibility_components = Arc::clone(&history_entry.visibility_components);
+        self.metadata_components = Arc::clone(&history_entry.metadata_components);
+        self.groups = Arc::clone(&history_entry.groups);
         self.chrono_components = Arc::clone(&history_entry.chrono_components);
         self.chrono_counter = history_entry.chrono_counter;
+        self.layers = Arc::clone(&history_entry.layers);
+        self.clamp_active_layer();
 
         // Since we don't store the rtree in the history, we need to rebuild it.
         self.rebuild_rtree();
@@ -187,11 +350,20 @@ impl StrokeStore {
 
         let all_strokes = self.stroke_keys_unordered();
         self.set_rendering_dirty_for_strokes(&all_strokes);
+        self.change_listeners.emit(StoreChange::Reset);
     }
 
     /// Record the current state and save it in the history.
+    ///
+    /// Deferred while a history group is open, the state is then recorded when the group is ended.
     pub(crate) fn record(&mut self, _now: Instant) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
+        self.last_coalesced_record = None;
+
+        if self.history_group_depth > 0 {
+            debug!("History group is open, deferring record.");
+            return widget_flags;
+        }
 
         if self
             .history
@@ -207,10 +379,7 @@ impl StrokeStore {
             self.live_index += 1;
 
             // truncate history if necessary
-            while self.history.len() > Self::HISTORY_MAX_LEN {
-                self.history.pop_front();
-                self.live_index -= 1;
-            }
+            self.truncate_history();
         } else {
             debug!("State has not changed, no need to record.");
         }
@@ -221,6 +390,71 @@ impl StrokeStore {
         widget_flags
     }
 
+    /// Record the current state, merging it into the latest history entry when the previous record was of the same
+    /// kind and happened less than [Self::HISTORY_COALESCE_TIMEOUT] ago.
+    ///
+    /// Any plain [Self::record()] in between ends the coalescing.
+    pub(crate) fn record_coalesced(&mut self, now: Instant, kind: CoalesceKind) -> WidgetFlags {
+        let coalesce = self.history_group_depth == 0
+            && self.can_undo()
+            && self.last_coalesced_record.is_some_and(|(last_kind, last)| {
+                last_kind == kind && now.duration_since(last) < Self::HISTORY_COALESCE_TIMEOUT
+            });
+
+        let widget_flags = if coalesce {
+            self.update_latest_history_entry(now)
+        } else {
+            self.record(now)
+        };
+        self.last_coalesced_record = Some((kind, now));
+
+        widget_flags
+    }
+
+    /// The maximum number of entries in the history.
+    pub(crate) fn history_max_len(&self) -> usize {
+        self.history_max_len
+    }
+
+    /// Set the maximum number of entries in the history, dropping the oldest entries that exceed it.
+    pub(crate) fn set_history_max_len(&mut self, max_len: usize) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        self.history_max_len = max_len.max(Self::HISTORY_MAX_LEN_MIN);
+        self.truncate_history();
+
+        widget_flags.hide_undo = Some(!self.can_undo());
+        widget_flags.hide_redo = Some(!self.can_redo());
+
+        widget_flags
+    }
+
+    /// Drop the oldest history entries exceeding the max length, and the newest ones when the live state is reached.
+    fn truncate_history(&mut self) {
+        while self.history.len() > self.history_max_len {
+            if self.live_index > 0 {
+                self.history.pop_front();
+                self.live_index -= 1;
+            } else {
+                self.history.pop_back();
+            }
+        }
+    }
+
+    /// Begin a history group. Records are deferred until the matching [Self::end_history_group()],
+    /// so that all changes in between end up in a single history entry.
+    ///
+    /// Groups can be nested, only ending the outermost group records the state.
+    pub(crate) fn begin_history_group(&mut self) {
+        self.history_group_depth += 1;
+    }
+
+    /// End a history group and record the current state when it was the outermost group.
+    pub(crate) fn end_history_group(&mut self, now: Instant) -> WidgetFlags {
+        self.history_group_depth = self.history_group_depth.saturating_sub(1);
+        self.record(now)
+    }
+
     /// Update the state of the latest history entry with the current document state.
     pub(crate) fn update_latest_history_entry(&mut self, _now: Instant) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
@@ -251,6 +485,7 @@ impl StrokeStore {
     /// Should only be called from inside the engine undo wrapper function.
     pub(crate) fn undo(&mut self, _now: Instant) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
+        self.last_coalesced_record = None;
 
         if !self.can_undo() {
             return widget_flags;
@@ -272,6 +507,7 @@ impl StrokeStore {
     /// Should only be called from inside the engine redo wrapper function.
     pub(crate) fn redo(&mut self, _now: Instant) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
+        self.last_coalesced_record = None;
 
         if !self.can_redo() {
             return widget_flags;
@@ -302,6 +538,8 @@ impl StrokeStore {
 
         self.history = VecDeque::from(vec![initial_state]);
         self.live_index = 0;
+        self.history_group_depth = 0;
+        self.last_coalesced_record = None;
 
         widget_flags.hide_undo = Some(true);
         widget_flags.hide_redo = Some(true);
@@ -312,6 +550,7 @@ impl StrokeStore {
     /// Insert a new stroke into the store.
     ///
     /// Optionally a desired layer can be specified, or the default stroke layer is used.
+    /// Strokes that default to a user layer are inserted into the active layer.
     ///
     /// The stroke then needs to update its rendering.
     pub(crate) fn insert_stroke(
@@ -320,13 +559,18 @@ impl StrokeStore {
         layer: Option<StrokeLayer>,
     ) -> StrokeKey {
         let bounds = stroke.bounds();
-        let layer = layer.unwrap_or_else(|| stroke.extract_default_layer());
+        let layer = layer.unwrap_or_else(|| self.in_active_layer(stroke.extract_default_layer()));
 
         let key = Arc::make_mut(&mut self.stroke_components).insert(Arc::new(stroke));
         self.key_tree.insert_with_key(key, bounds);
         self.chrono_counter += 1;
 
         Arc::make_mut(&mut self.trash_components).insert(key, Arc::new(TrashComponent::default()));
+        Arc::make_mut(&mut self.lock_components).insert(key, Arc::new(LockComponent::default()));
+        Arc::make_mut(&mut self.visibility_components)
+            .insert(key, Arc::new(VisibilityComponent::default()));
+        Arc::make_mut(&mut self.metadata_components)
+            .insert(key, Arc::new(MetadataComponent::default()));
         Arc::make_mut(&mut self.selection_components)
             .insert(key, Arc::new(SelectionComponent::default()));
         Arc::make_mut(&mut self.chrono_components).insert(
@@ -335,37 +579,62 @@ impl StrokeStore {
         );
         self.render_components
             .insert(key, RenderComponent::default());
+        self.change_listeners.emit(StoreChange::Inserted(key));
 
         key
     }
 
     /// Permanently remove a stroke with the given key from the store.
-    #[allow(unused)]
     pub(crate) fn remove_stroke(&mut self, key: StrokeKey) -> Option<Stroke> {
         Arc::make_mut(&mut self.trash_components).remove(key);
+        Arc::make_mut(&mut self.lock_components).remove(key);
+        Arc::make_mut(&mut self.visibility_components).remove(key);
+        Arc::make_mut(&mut self.metadata_components).remove(key);
         Arc::make_mut(&mut self.selection_components).remove(key);
         Arc::make_mut(&mut self.chrono_components).remove(key);
         self.render_components.remove(key);
+        self.render_cache.remove(key);
 
         self.key_tree.remove_with_key(key);
-        Arc::make_mut(&mut self.stroke_components)
+        let removed = Arc::make_mut(&mut self.stroke_components)
             .remove(key)
-            .map(|stroke| (*stroke).clone())
+            .map(|stroke| (*stroke).clone());
+        if removed.is_some() {
+            self.change_listeners.emit(StoreChange::Removed(key));
+        }
+        removed
     }
 
     /// Clears the entire store.
     pub(super) fn clear(&mut self) -> WidgetFlags {
         Arc::make_mut(&mut self.stroke_components).clear();
         Arc::make_mut(&mut self.trash_components).clear();
+        Arc::make_mut(&mut self.lock_components).clear();
+        Arc::make_mut(&mut self.visibility_components).clear();
+        Arc::make_mut(&mut self.metadata_components).clear();
+        Arc::make_mut(&mut self.groups).clear();
         Arc::make_mut(&mut self.selection_components).clear();
         Arc::make_mut(&mut self.chrono_components).clear();
 
         self.chrono_counter = 0;
+        self.layers = Arc::new(layer_comp::default_layers());
+        self.active_layer = 0;
         let widget_flags = self.clear_history(HistoryEntry::default());
 
         self.render_components.clear();
+        self.render_cache.clear();
         self.key_tree.clear();
+        self.change_listeners.emit(StoreChange::Reset);
 
         widget_flags
     }
 }
+
+/// A brush stroke with a single element at the origin, for the tests of the store components.
+#[cfg(test)]
+pub(crate) fn brushstroke_at_origin() -> Stroke {
+    Stroke::BrushStroke(crate::strokes::BrushStroke::new(
+        rnote_compose::penpath::Element::new(na::vector![0.0, 0.0], 0.5),
+        rnote_compose::Style::default(),
+    ))
+}
@@ -0,0 +1,401 @@
+// Imports
+use super::StrokeKey;
+use super::render_comp::{LodConfig, RENDER_IMAGE_SCALE_TOLERANCE};
+use crate::Image;
+use crate::strokes::Stroke;
+use rnote_compose::PenPath;
+use rnote_compose::penpath::{Element, Segment};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use tracing::error;
+
+/// The limits of the rendering cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename = "render_cache_limits")]
+pub struct RenderCacheLimits {
+    /// The maximum number of cached renderings. Zero disables the cache.
+    #[serde(rename = "max_entries")]
+    pub max_entries: usize,
+    /// The maximum size of the image data of all cached renderings in bytes.
+    #[serde(rename = "max_size_bytes")]
+    pub max_size_bytes: usize,
+}
+
+impl Default for RenderCacheLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: 4096,
+            max_size_bytes: 256 * 1024 * 1024,
+        }
+    }
+}
+
+/// Identifies the stroke content and the options a rendering was generated from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderedContent {
+    /// The hash of the geometry and style of the stroke and the options that change its rendering.
+    ///
+    /// None when the stroke could not be hashed, its rendering then can't be cached.
+    content_hash: Option<u64>,
+}
+
+impl RenderedContent {
+    pub(crate) fn new(stroke: &Stroke, pixel_snap_thin_lines: bool, lod_config: LodConfig) -> Self {
+        let mut hasher = DefaultHasher::new();
+        if let Err(e) = hash_stroke_content(stroke, &mut hasher) {
+            error!("Hashing stroke content for the rendering cache failed, Err: {e:?}");
+            return Self { content_hash: None };
+        }
+        pixel_snap_thin_lines.hash(&mut hasher);
+        lod_config.enabled.hash(&mut hasher);
+        lod_config.image_scale_threshold.to_bits().hash(&mut hasher);
+        lod_config.simplify_tolerance.to_bits().hash(&mut hasher);
+        lod_config.textured_as_solid.hash(&mut hasher);
+        Self {
+            content_hash: Some(hasher.finish()),
+        }
+    }
+
+    /// Whether the content is the same as the current one, rendered with the same options.
+    pub(crate) fn is_current(&self, current: &RenderedContent) -> bool {
+        self.content_hash.is_some() && self.content_hash == current.content_hash
+    }
+}
+
+/// A cached rendering of a stroke.
+#[derive(Debug, Clone)]
+pub(crate) struct CachedRendering {
+    pub(crate) images: Vec<Image>,
+    #[cfg(feature = "ui")]
+    pub(crate) rendernodes: Vec<gtk4::gsk::RenderNode>,
+}
+
+impl CachedRendering {
+    fn size_bytes(&self) -> usize {
+        self.images.iter().map(|image| image.data.len()).sum()
+    }
+}
+
+/// The cached renderings of a single stroke, for different image scales.
+#[derive(Debug)]
+struct StrokeRenderings {
+    /// The content the renderings were generated from.
+    content: RenderedContent,
+    /// The renderings and their last usage tick, keyed by the quantized image scale.
+    renderings: HashMap<i64, (CachedRendering, u64)>,
+}
+
+/// Caches full stroke renderings keyed by the content hash of the stroke and the image scale.
+///
+/// Strokes that are unchanged while zooming or panning can then be displayed again
+/// without regenerating their images. Only renderings of entire strokes are cached,
+/// renderings for a viewport would be outdated as soon as the viewport moves.
+///
+/// When a stroke is edited its content hash changes, and its outdated renderings are dropped once the new one is cached.
+/// The least recently used renderings are evicted when exceeding the limits.
+#[derive(Debug, Default)]
+pub(crate) struct RenderCache {
+    limits: RenderCacheLimits,
+    strokes: HashMap<StrokeKey, StrokeRenderings>,
+    /// The cached renderings by their last usage tick, least recently used first.
+    lru: BTreeMap<u64, (StrokeKey, i64)>,
+    tick: u64,
+    size_bytes: usize,
+}
+
+impl RenderCache {
+    pub(crate) fn limits(&self) -> RenderCacheLimits {
+        self.limits
+    }
+
+    /// Set the limits, evicting renderings exceeding them.
+    pub(crate) fn set_limits(&mut self, limits: RenderCacheLimits) {
+        self.limits = limits;
+        self.evict_exceeding();
+    }
+
+    /// The number of cached renderings.
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.lru.len()
+    }
+
+    /// The size of the image data of all cached renderings in bytes.
+    #[cfg(test)]
+    pub(crate) fn size_bytes(&self) -> usize {
+        self.size_bytes
+    }
+
+    /// Get the cached rendering of the stroke with the content for the image scale.
+    pub(crate) fn get(
+        &mut self,
+        key: StrokeKey,
+        content: &RenderedContent,
+        image_scale: f64,
+    ) -> Option<CachedRendering> {
+        let scale = quantized_image_scale(image_scale);
+        let stroke_renderings = self.strokes.get_mut(&key)?;
+        if !stroke_renderings.content.is_current(content) {
+            return None;
+        }
+        let (rendering, last_used) = stroke_renderings.renderings.get_mut(&scale)?;
+
+        self.lru.remove(last_used);
+        self.tick += 1;
+        *last_used = self.tick;
+        self.lru.insert(self.tick, (key, scale));
+        Some(rendering.clone())
+    }
+
+    /// Cache the rendering of the stroke with the content for the image scale.
+    ///
+    /// Drops the cached renderings of the stroke that were generated from a different content.
+    pub(crate) fn insert(
+        &mut self,
+        key: StrokeKey,
+        content: RenderedContent,
+        image_scale: f64,
+        rendering: CachedRendering,
+    ) {
+        let size_bytes = rendering.size_bytes();
+        if self.limits.max_entries == 0
+            || size_bytes > self.limits.max_size_bytes
+            || content.content_hash.is_none()
+        {
+            return;
+        }
+        if self
+            .strokes
+            .get(&key)
+            .is_some_and(|stroke_renderings| !stroke_renderings.content.is_current(&content))
+        {
+            self.remove(key);
+        }
+        let scale = quantized_image_scale(image_scale);
+        let stroke_renderings = self.strokes.entry(key).or_insert_with(|| StrokeRenderings {
+            content,
+            renderings: HashMap::new(),
+        });
+
+        self.tick += 1;
+        if let Some((old, last_used)) = stroke_renderings
+            .renderings
+            .insert(scale, (rendering, self.tick))
+        {
+            self.lru.remove(&last_used);
+            self.size_bytes -= old.size_bytes();
+        }
+        self.lru.insert(self.tick, (key, scale));
+        self.size_bytes += size_bytes;
+        self.evict_exceeding();
+    }
+
+    /// Remove all cached renderings of the stroke.
+    pub(crate) fn remove(&mut self, key: StrokeKey) {
+        let Some(stroke_renderings) = self.strokes.remove(&key) else {
+            return;
+        };
+        for (rendering, last_used) in stroke_renderings.renderings.into_values() {
+            self.lru.remove(&last_used);
+            self.size_bytes -= rendering.size_bytes();
+        }
+    }
+
+    /// Remove all cached renderings.
+    pub(crate) fn clear(&mut self) {
+        self.strokes.clear();
+        self.lru.clear();
+        self.size_bytes = 0;
+    }
+
+    /// Evict the least recently used renderings until the cache is within its limits.
+    fn evict_exceeding(&mut self) {
+        while self.lru.len() > self.limits.max_entries
+            || self.size_bytes > self.limits.max_size_bytes
+        {
+            let Some((_, (key, scale))) = self.lru.pop_first() else {
+                break;
+            };
+            if let Some(stroke_renderings) = self.strokes.get_mut(&key) {
+                if let Some((rendering, _)) = stroke_renderings.renderings.remove(&scale) {
+                    self.size_bytes -= rendering.size_bytes();
+                }
+                if stroke_renderings.renderings.is_empty() {
+                    self.strokes.remove(&key);
+                }
+            }
+        }
+    }
+}
+
+/// Image scales within the tolerance are considered equal and share cached renderings.
+fn quantized_image_scale(image_scale: f64) -> i64 {
+    (image_scale / RENDER_IMAGE_SCALE_TOLERANCE).round() as i64
+}
+
+/// Feeds the written bytes into the hasher, to hash serialized data without allocating.
+struct HashWriter<'a, H: Hasher>(&'a mut H);
+
+impl<H: Hasher> std::io::Write for HashWriter<'_, H> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.write(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Hash the geometry and the style of the stroke.
+///
+/// Paths, texts and image data are hashed directly, only the comparatively small styles,
+/// shapes and transforms are hashed in their serialized form.
+fn hash_stroke_content<H: Hasher>(stroke: &Stroke, state: &mut H) -> anyhow::Result<()> {
+    std::mem::discriminant(stroke).hash(state);
+    match stroke {
+        Stroke::BrushStroke(brushstroke) => {
+            hash_pen_path(brushstroke.path(), state);
+            hash_serialized(brushstroke.style(), state)?;
+            hash_serialized(&brushstroke.fill_color, state)?;
+        }
+        Stroke::ShapeStroke(shapestroke) => {
+            hash_serialized(&shapestroke.shape, state)?;
+            hash_serialized(&shapestroke.style, state)?;
+        }
+        Stroke::TextStroke(textstroke) => {
+            textstroke.text.hash(state);
+            hash_serialized(&textstroke.transform, state)?;
+            hash_serialized(&textstroke.text_style, state)?;
+        }
+        Stroke::VectorImage(vectorimage) => {
+            vectorimage.svg_data.hash(state);
+            hash_serialized(&vectorimage.rectangle, state)?;
+        }
+        Stroke::BitmapImage(bitmapimage) => {
+            let image = &bitmapimage.image;
+            state.write(&image.data);
+            image.pixel_width.hash(state);
+            image.pixel_height.hash(state);
+            hash_serialized(&image.memory_format, state)?;
+            hash_serialized(&bitmapimage.rectangle, state)?;
+        }
+    }
+    Ok(())
+}
+
+/// Hash the positions and pressures of the elements and the control points of the path.
+fn hash_pen_path<H: Hasher>(path: &PenPath, state: &mut H) {
+    let hash_element = |element: &Element, state: &mut H| {
+        element.pos.x.to_bits().hash(state);
+        element.pos.y.to_bits().hash(state);
+        element.pressure.to_bits().hash(state);
+    };
+    let hash_point = |point: &na::Vector2<f64>, state: &mut H| {
+        point.x.to_bits().hash(state);
+        point.y.to_bits().hash(state);
+    };
+
+    hash_element(&path.start, state);
+    for seg in path.segments.iter() {
+        std::mem::discriminant(seg).hash(state);
+        match seg {
+            Segment::LineTo { end } | Segment::MoveTo { end } => hash_element(end, state),
+            Segment::QuadBezTo { cp, end } => {
+                hash_point(cp, state);
+                hash_element(end, state);
+            }
+            Segment::CubBezTo { cp1, cp2, end } => {
+                hash_point(cp1, state);
+                hash_point(cp2, state);
+                hash_element(end, state);
+            }
+        }
+    }
+}
+
+fn hash_serialized<H: Hasher>(value: &impl Serialize, state: &mut H) -> anyhow::Result<()> {
+    serde_json::to_writer(HashWriter(state), value)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::brushstroke_at_origin;
+    use rnote_compose::transform::Transformable;
+    use slotmap::SlotMap;
+
+    fn content(stroke: &Stroke) -> RenderedContent {
+        RenderedContent::new(stroke, false, LodConfig::default())
+    }
+
+    fn rendering(size_bytes: usize) -> CachedRendering {
+        CachedRendering {
+            images: vec![Image {
+                data: glib::Bytes::from_owned(vec![0_u8; size_bytes]),
+                ..Default::default()
+            }],
+            #[cfg(feature = "ui")]
+            rendernodes: vec![],
+        }
+    }
+
+    #[test]
+    fn cache_hit_and_edit_invalidation() {
+        let mut keys = SlotMap::<StrokeKey, ()>::with_key();
+        let key = keys.insert(());
+        let mut cache = RenderCache::default();
+        let mut stroke = brushstroke_at_origin();
+        let unedited = content(&stroke);
+
+        cache.insert(key, unedited, 1.0, rendering(16));
+        cache.insert(key, unedited, 2.0, rendering(16));
+        assert!(cache.get(key, &unedited, 1.0).is_some());
+        assert!(cache.get(key, &unedited, 1.001).is_some());
+        assert!(cache.get(key, &unedited, 1.5).is_none());
+        // An equal stroke has the same content
+        assert!(
+            cache
+                .get(key, &content(&brushstroke_at_origin()), 1.0)
+                .is_some()
+        );
+        let other_options = RenderedContent::new(&stroke, true, LodConfig::default());
+        assert!(cache.get(key, &other_options, 1.0).is_none());
+
+        // The stroke was edited, the renderings of its previous content are dropped
+        stroke.translate(na::vector![1.0, 0.0]);
+        let edited = content(&stroke);
+        assert!(cache.get(key, &edited, 1.0).is_none());
+        cache.insert(key, edited, 1.0, rendering(16));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.size_bytes(), 16);
+        assert!(cache.get(key, &unedited, 2.0).is_none());
+    }
+
+    #[test]
+    fn cache_evicts_least_recently_used() {
+        let mut keys = SlotMap::<StrokeKey, ()>::with_key();
+        let (first, second, third) = (keys.insert(()), keys.insert(()), keys.insert(()));
+        let mut cache = RenderCache::default();
+        cache.set_limits(RenderCacheLimits {
+            max_entries: 2,
+            max_size_bytes: 40,
+        });
+        let content = content(&brushstroke_at_origin());
+
+        cache.insert(first, content, 1.0, rendering(16));
+        cache.insert(second, content, 1.0, rendering(16));
+        assert!(cache.get(first, &content, 1.0).is_some());
+        cache.insert(third, content, 1.0, rendering(16));
+        assert!(cache.get(second, &content, 1.0).is_none());
+        assert!(cache.get(first, &content, 1.0).is_some());
+        assert!(cache.get(third, &content, 1.0).is_some());
+
+        // Exceeds the size limit by itself
+        cache.insert(second, content, 1.0, rendering(64));
+        assert!(cache.get(second, &content, 1.0).is_none());
+        assert_eq!(cache.size_bytes(), 32);
+    }
+}
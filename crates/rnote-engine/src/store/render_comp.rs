@@ -1,17 +1,25 @@
 // Imports
-use super::{Stroke, StrokeKey, StrokeStore};
+use super::render_cache::{CachedRendering, RenderCacheLimits, RenderedContent};
+use super::{Stroke, StrokeKey, StrokeStore, layer_comp};
 use crate::Image;
 use crate::engine::{EngineTask, EngineTaskSender};
 use crate::strokes::Content;
 use crate::strokes::content::GeneratedContentImages;
 use crate::{Drawable, image};
 use p2d::bounding_volume::{Aabb, BoundingVolume};
+use rayon::prelude::*;
 use rnote_compose::ext::AabbExt;
 use rnote_compose::shapes::Shapeable;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use tracing::error;
 
 /// The tolerance where check between scale-factors are considered "equal".
 pub(crate) const RENDER_IMAGE_SCALE_TOLERANCE: f64 = 0.01;
+/// The maximum number of pixels of the images of a single stroke when rendering all strokes in a batch.
+/// Larger strokes are left to the viewport rendering.
+pub(crate) const RENDER_BATCH_STROKE_PIXELS_MAX: f64 = 4096.0 * 4096.0;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum RenderCompState {
@@ -35,6 +43,68 @@ pub struct RenderComponent {
     pub(super) rendernodes: Vec<gtk4::gsk::RenderNode>,
 }
 
+/// The level of detail strokes are rendered with when zoomed out.
+///
+/// Below the image scale threshold textured strokes are rendered as solid lines and dense paths are simplified,
+/// which is barely visible at these scales but makes rendering large documents a lot faster.
+/// Exports are always rendered in full detail.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename = "lod_config")]
+pub struct LodConfig {
+    #[serde(rename = "enabled")]
+    pub enabled: bool,
+    /// The image scale below which strokes are rendered with reduced detail.
+    #[serde(rename = "image_scale_threshold")]
+    pub image_scale_threshold: f64,
+    /// The tolerance in surface pixels paths are simplified with.
+    #[serde(rename = "simplify_tolerance")]
+    pub simplify_tolerance: f64,
+    /// Render textured strokes as solid lines.
+    #[serde(rename = "textured_as_solid")]
+    pub textured_as_solid: bool,
+}
+
+impl Default for LodConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            image_scale_threshold: 0.3,
+            simplify_tolerance: 0.5,
+            textured_as_solid: true,
+        }
+    }
+}
+
+impl LodConfig {
+    /// Whether strokes are rendered with reduced detail at the image scale.
+    pub fn applies(&self, image_scale: f64) -> bool {
+        self.enabled && image_scale < self.image_scale_threshold
+    }
+}
+
+/// Reports the progress of a batch rendering with the number of rendered strokes and the total number of strokes.
+///
+/// Called from the rendering threads.
+pub type RenderProgressCallback = Box<dyn Fn(usize, usize) + Send + Sync>;
+
+/// A handle to a batch rendering running in the background, for cancelling it.
+#[derive(Debug, Clone, Default)]
+pub struct RenderBatchHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl RenderBatchHandle {
+    /// Cancel the batch rendering. The strokes that are not rendered yet are skipped.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the batch rendering was cancelled. It might still be finishing the strokes that are currently rendered.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
 impl Default for RenderComponent {
     fn default() -> Self {
         Self {
@@ -47,6 +117,55 @@ impl Default for RenderComponent {
 }
 
 impl StrokeStore {
+    /// Whether thin horizontal and vertical lines are snapped to the pixel grid when rendering.
+    pub(crate) fn pixel_snap_thin_lines(&self) -> bool {
+        self.pixel_snap_thin_lines
+    }
+
+    /// Set whether thin horizontal and vertical lines are snapped to the pixel grid when rendering.
+    ///
+    /// The rendering of the strokes then needs to be regenerated.
+    pub(crate) fn set_pixel_snap_thin_lines(&mut self, pixel_snap_thin_lines: bool) {
+        self.pixel_snap_thin_lines = pixel_snap_thin_lines;
+    }
+
+    /// The level of detail strokes are rendered with when zoomed out.
+    pub(crate) fn lod_config(&self) -> LodConfig {
+        self.lod_config
+    }
+
+    /// Set the level of detail strokes are rendered with when zoomed out.
+    ///
+    /// The rendering of the strokes then needs to be regenerated.
+    pub(crate) fn set_lod_config(&mut self, lod_config: LodConfig) {
+        self.lod_config = lod_config;
+    }
+
+    /// The limits of the cache for the rendering of unchanged strokes.
+    pub(crate) fn render_cache_limits(&self) -> RenderCacheLimits {
+        self.render_cache.limits()
+    }
+
+    /// Set the limits of the cache for the rendering of unchanged strokes, evicting cached renderings exceeding them.
+    pub(crate) fn set_render_cache_limits(&mut self, limits: RenderCacheLimits) {
+        self.render_cache.set_limits(limits);
+    }
+
+    /// Cache the current rendering of the stroke, when it was fully generated from the content.
+    pub(crate) fn cache_rendering(
+        &mut self,
+        key: StrokeKey,
+        content: RenderedContent,
+        image_scale: f64,
+    ) {
+        if let Some(render_comp) = self.render_components.get(key)
+            && render_comp.state == RenderCompState::Complete
+        {
+            self.render_cache
+                .insert(key, content, image_scale, cached_rendering(render_comp));
+        }
+    }
+
     /// Rebuild the slotmap with empty render components with the keys returned from the stroke components.
     pub(crate) fn rebuild_render_components_slotmap(&mut self) {
         self.render_components = slotmap::SecondaryMap::new();
@@ -110,10 +229,22 @@ impl StrokeStore {
                 return;
             }
 
+            let content = RenderedContent::new(stroke, self.pixel_snap_thin_lines, self.lod_config);
+            if let Some(cached) = self.render_cache.get(key, &content, image_scale) {
+                apply_cached_rendering(render_comp, cached);
+                return;
+            }
+
             let viewport_extended =
                 viewport.extend_by(viewport.extents() * image::VIEWPORT_EXTENTS_MARGIN_FACTOR);
 
-            match stroke.gen_images(viewport_extended, image_scale) {
+            match gen_stroke_images(
+                stroke,
+                viewport_extended,
+                image_scale,
+                self.pixel_snap_thin_lines,
+                self.lod_config,
+            ) {
                 Ok(GeneratedContentImages::Partial { images, viewport }) => {
                     #[cfg(feature = "ui")]
                     match Image::images_to_rendernodes(&images) {
@@ -161,10 +292,17 @@ impl StrokeStore {
                     error!("Generating images for stroke with key {key:?} failed, Err: {e:?}");
                 }
             }
+            if render_comp.state == RenderCompState::Complete {
+                self.render_cache
+                    .insert(key, content, image_scale, cached_rendering(render_comp));
+            }
         }
     }
 
-    pub(crate) fn regenerate_rendering_for_strokes(
+    /// Regenerate the rendering of the strokes for the viewport and image scale, blocking until it is finished.
+    ///
+    /// Unchanged strokes that were already rendered at the image scale are taken from the rendering cache.
+    pub fn regenerate_rendering_for_strokes(
         &mut self,
         keys: &[StrokeKey],
         viewport: Aabb,
@@ -189,7 +327,15 @@ impl StrokeStore {
                 return;
             }
 
+            let content = RenderedContent::new(stroke, self.pixel_snap_thin_lines, self.lod_config);
+            if let Some(cached) = self.render_cache.get(key, &content, image_scale) {
+                apply_cached_rendering(render_comp, cached);
+                return;
+            }
+
             let stroke = stroke.clone();
+            let pixel_snap_thin_lines = self.pixel_snap_thin_lines;
+            let lod_config = self.lod_config;
             let viewport_extended =
                 viewport.extend_by(viewport.extents() * image::VIEWPORT_EXTENTS_MARGIN_FACTOR);
 
@@ -197,13 +343,20 @@ impl StrokeStore {
             render_comp.state = RenderCompState::BusyRenderingInTask;
 
             // Spawn a new thread for image rendering
-            rayon::spawn(
-                move || match stroke.gen_images(viewport_extended, image_scale) {
+            rayon::spawn(move || {
+                match gen_stroke_images(
+                    &stroke,
+                    viewport_extended,
+                    image_scale,
+                    pixel_snap_thin_lines,
+                    lod_config,
+                ) {
                     Ok(images) => {
                         tasks_tx.send(EngineTask::UpdateStrokeWithImages {
                             key,
                             images,
                             image_scale,
+                            content,
                         });
                     }
                     Err(e) => {
@@ -211,8 +364,8 @@ impl StrokeStore {
                             "Generating images of stroke failed while regenerating stroke rendering, stroke key {key:?} , Err: {e:?}"
                         );
                     }
-                },
-            );
+                }
+            });
         }
     }
 
@@ -242,6 +395,8 @@ impl StrokeStore {
         image_scale: f64,
     ) {
         let keys = self.render_components.keys().collect::<Vec<StrokeKey>>();
+        let pixel_snap_thin_lines = self.pixel_snap_thin_lines;
+        let lod_config = self.lod_config;
 
         for key in keys {
             if let Some(stroke) = self.stroke_components.get(key)
@@ -289,18 +444,31 @@ impl StrokeStore {
                     }
                 }
 
+                let content = RenderedContent::new(stroke, pixel_snap_thin_lines, lod_config);
+                if let Some(cached) = self.render_cache.get(key, &content, image_scale) {
+                    apply_cached_rendering(render_comp, cached);
+                    continue;
+                }
+
                 // indicates that a task has now started to render the stroke
                 render_comp.state = RenderCompState::BusyRenderingInTask;
                 let stroke = stroke.clone();
 
                 // Spawn a new thread for image rendering
-                rayon::spawn(
-                    move || match stroke.gen_images(viewport_extended, image_scale) {
+                rayon::spawn(move || {
+                    match gen_stroke_images(
+                        &stroke,
+                        viewport_extended,
+                        image_scale,
+                        pixel_snap_thin_lines,
+                        lod_config,
+                    ) {
                         Ok(images) => {
                             tasks_tx.send(EngineTask::UpdateStrokeWithImages {
                                 key,
                                 images,
                                 image_scale,
+                                content,
                             });
                         }
                         Err(e) => {
@@ -308,14 +476,108 @@ impl StrokeStore {
                                 "Generating stroke images failed stroke while regenerating rendering in viewport `{viewport:?}`, stroke key: {key:?}, Err: {e:?}"
                             );
                         }
-                    },
-                );
+                    }
+                });
+            }
+        }
+    }
+
+    /// Regenerate the entire rendering of all strokes for the image scale in the background.
+    ///
+    /// Instead of spawning a task for every stroke, the strokes are rendered in a single batch with the parallelism
+    /// bounded by the thread pool. The rendered images are sent to the engine as tasks, and when the batch is finished
+    /// the strokes that were not rendered because of cancellation or errors are flagged dirty again.
+    ///
+    /// The strokes are rendered clipped to the document bounds. Strokes which images would exceed
+    /// [RENDER_BATCH_STROKE_PIXELS_MAX] are skipped and left to the viewport rendering.
+    pub(crate) fn regenerate_all_rendering(
+        &mut self,
+        tasks_tx: EngineTaskSender,
+        doc_bounds: Aabb,
+        image_scale: f64,
+        progress_cb: RenderProgressCallback,
+    ) -> RenderBatchHandle {
+        let handle = RenderBatchHandle::default();
+        let pixel_snap_thin_lines = self.pixel_snap_thin_lines;
+        let lod_config = self.lod_config;
+        let mut strokes = Vec::new();
+
+        for key in self.stroke_keys_as_rendered() {
+            if let Some(stroke) = self.stroke_components.get(key)
+                && let Some(render_comp) = self.render_components.get_mut(key)
+            {
+                if render_comp.state == RenderCompState::BusyRenderingInTask {
+                    continue;
+                }
+                let content = RenderedContent::new(stroke, pixel_snap_thin_lines, lod_config);
+                if let Some(cached) = self.render_cache.get(key, &content, image_scale) {
+                    apply_cached_rendering(render_comp, cached);
+                    continue;
+                }
+                render_comp.state = RenderCompState::BusyRenderingInTask;
+                strokes.push((key, Arc::clone(stroke), content));
             }
         }
+
+        let cancelled = Arc::clone(&handle.cancelled);
+        rayon::spawn(move || {
+            let total = strokes.len();
+            let rendered = AtomicUsize::new(0);
+
+            let unrendered_keys = strokes
+                .par_iter()
+                .filter_map(|(key, stroke, content)| {
+                    if cancelled.load(Ordering::Relaxed) {
+                        return Some(*key);
+                    }
+                    // The entire stroke is rendered up to the document bounds, so that the images are not limited to a viewport
+                    let viewport = doc_bounds.loosened(1.0);
+                    let Some(render_bounds) = stroke.bounds().intersection(&viewport) else {
+                        progress_cb(rendered.fetch_add(1, Ordering::Relaxed) + 1, total);
+                        return Some(*key);
+                    };
+                    let render_extents = render_bounds.extents() * image_scale;
+                    if render_extents[0] * render_extents[1] > RENDER_BATCH_STROKE_PIXELS_MAX {
+                        progress_cb(rendered.fetch_add(1, Ordering::Relaxed) + 1, total);
+                        return Some(*key);
+                    }
+                    let unrendered = match gen_stroke_images(
+                        stroke,
+                        viewport,
+                        image_scale,
+                        pixel_snap_thin_lines,
+                        lod_config,
+                    ) {
+                        Ok(images) => {
+                            tasks_tx.send(EngineTask::UpdateStrokeWithImages {
+                                key: *key,
+                                images,
+                                image_scale,
+                                content: *content,
+                            });
+                            None
+                        }
+                        Err(e) => {
+                            error!(
+                                "Generating stroke images failed while regenerating all rendering, stroke key: {key:?}, Err: {e:?}"
+                            );
+                            Some(*key)
+                        }
+                    };
+                    progress_cb(rendered.fetch_add(1, Ordering::Relaxed) + 1, total);
+                    unrendered
+                })
+                .collect::<Vec<StrokeKey>>();
+
+            tasks_tx.send(EngineTask::RenderBatchFinished { unrendered_keys });
+        });
+
+        handle
     }
 
-    /// Clear all rendering for all strokes.
+    /// Clear all rendering for all strokes, including the cached rendering.
     pub(crate) fn clear_rendering(&mut self) {
+        self.render_cache.clear();
         for (_key, render_comp) in self.render_components.iter_mut() {
             #[cfg(feature = "ui")]
             {
@@ -341,7 +603,7 @@ impl StrokeStore {
             && let Some(render_comp) = self.render_components.get_mut(key)
         {
             match stroke.as_ref() {
-                Stroke::BrushStroke(brushstroke) => {
+                Stroke::BrushStroke(brushstroke) if !brushstroke.has_color_gradient() => {
                     match brushstroke.gen_image_for_last_segments(n_last_segments, image_scale) {
                         Ok(Some(image)) => {
                             #[cfg(feature = "ui")]
@@ -373,7 +635,8 @@ impl StrokeStore {
                     }
                 }
                 // regenerate everything for strokes that don't support generating svgs for the last added elements
-                Stroke::ShapeStroke(_)
+                Stroke::BrushStroke(_)
+                | Stroke::ShapeStroke(_)
                 | Stroke::TextStroke(_)
                 | Stroke::VectorImage(_)
                 | Stroke::BitmapImage(_) => {
@@ -482,23 +745,81 @@ impl StrokeStore {
     }
 
     /// Draw all strokes on the gtk snapshot.
+    ///
+    /// When tracing, image strokes are drawn with the given trace opacity.
     #[cfg(feature = "ui")]
     pub(crate) fn draw_strokes_to_gtk_snapshot(
         &self,
         snapshot: &gtk4::Snapshot,
         doc_bounds: Aabb,
         viewport: Aabb,
+        trace_opacity: Option<f64>,
     ) {
+        use super::chrono_comp::StrokeLayer;
         use crate::ext::{GdkRGBAExt, GrapheneRectExt};
-        use gtk4::{gdk, graphene, prelude::*};
+        use gtk4::{gdk, graphene, gsk, prelude::*};
         use rnote_compose::color;
+        use rnote_compose::style::BlendMode;
 
         snapshot.push_clip(&graphene::Rect::from_p2d_aabb(doc_bounds));
 
+        // Strokes with a non-normal blend mode need the content drawn before them as the bottom of the blend,
+        // so the strokes are drawn to a separate snapshot. Consecutive strokes with the same blend mode are drawn
+        // to a snapshot of their own and blended in one run, holding the blend mode and the content below.
+        // This keeps the depth of the nested blend nodes bounded by the number of runs instead of the blended strokes.
+        let mut strokes_snapshot = gtk4::Snapshot::new();
+        let mut blend_run: Option<(gsk::BlendMode, Option<gsk::RenderNode>)> = None;
+        let mut prev_layer: Option<StrokeLayer> = None;
+        // Layers with an opacity below one are drawn to their own snapshot and composited with their opacity
+        // once all their strokes are drawn. Holds the layer, its opacity and the snapshot of the layers below it.
+        let mut translucent_layer: Option<(StrokeLayer, f64, gtk4::Snapshot)> = None;
+
         for key in self.stroke_keys_as_rendered_intersecting_bounds(viewport) {
             if let Some(stroke) = self.stroke_components.get(key)
                 && let Some(render_comp) = self.render_components.get(key)
             {
+                let layer = self
+                    .chrono_components
+                    .get(key)
+                    .map(|c| c.layer)
+                    .unwrap_or_default();
+                let gsk_blend_mode = match stroke.blend_mode() {
+                    _ if render_comp.rendernodes.is_empty() => None,
+                    BlendMode::Normal => None,
+                    BlendMode::Multiply => Some(gsk::BlendMode::Multiply),
+                    BlendMode::Screen => Some(gsk::BlendMode::Screen),
+                    BlendMode::Overlay => Some(gsk::BlendMode::Overlay),
+                    BlendMode::Darken => Some(gsk::BlendMode::Darken),
+                };
+                // Blend runs don't extend over layers, so that they are finished before a translucent layer
+                let layer_changed = prev_layer.replace(layer).is_some_and(|prev| prev != layer);
+                if (layer_changed
+                    || blend_run
+                        .as_ref()
+                        .is_some_and(|(run_blend_mode, _)| Some(*run_blend_mode) != gsk_blend_mode))
+                    && let Some((run_blend_mode, below)) = blend_run.take()
+                {
+                    composite_blend_run(&mut strokes_snapshot, run_blend_mode, below);
+                }
+                if translucent_layer
+                    .as_ref()
+                    .is_some_and(|(translucent, ..)| *translucent != layer)
+                    && let Some((_, layer_opacity, below)) = translucent_layer.take()
+                {
+                    composite_translucent_layer(&mut strokes_snapshot, layer_opacity, below);
+                }
+                let layer_opacity = self.layer_opacity(layer);
+                if translucent_layer.is_none() && layer_opacity < 1.0 {
+                    let below = std::mem::replace(&mut strokes_snapshot, gtk4::Snapshot::new());
+                    translucent_layer = Some((layer, layer_opacity, below));
+                }
+                if blend_run.is_none()
+                    && let Some(gsk_blend_mode) = gsk_blend_mode
+                {
+                    let below = std::mem::replace(&mut strokes_snapshot, gtk4::Snapshot::new());
+                    blend_run = Some((gsk_blend_mode, below.to_node()));
+                }
+
                 // if the stroke currently does not have a rendering and is will create one,
                 // draw a placeholder filled rect
                 if render_comp.rendernodes.is_empty()
@@ -507,38 +828,68 @@ impl StrokeStore {
                         RenderCompState::Dirty | RenderCompState::BusyRenderingInTask
                     )
                 {
-                    snapshot.append_color(
+                    strokes_snapshot.append_color(
                         &gdk::RGBA::from_piet_color(color::GNOME_BRIGHTS[1].with_alpha(0.13)),
                         &graphene::Rect::from_p2d_aabb(stroke.bounds()),
                     );
                 }
 
-                for rendernode in render_comp.rendernodes.iter() {
-                    snapshot.append_node(rendernode);
-                }
+                let opacity = match (trace_opacity, stroke.as_ref()) {
+                    (Some(trace_opacity), Stroke::VectorImage(_) | Stroke::BitmapImage(_)) => {
+                        trace_opacity
+                    }
+                    _ => stroke.opacity(),
+                };
+                append_stroke_rendernodes(&strokes_snapshot, opacity, render_comp);
             }
         }
 
+        if let Some((run_blend_mode, below)) = blend_run.take() {
+            composite_blend_run(&mut strokes_snapshot, run_blend_mode, below);
+        }
+        if let Some((_, layer_opacity, below)) = translucent_layer.take() {
+            composite_translucent_layer(&mut strokes_snapshot, layer_opacity, below);
+        }
+        if let Some(strokes_node) = strokes_snapshot.to_node() {
+            snapshot.append_node(strokes_node);
+        }
+
         snapshot.pop();
     }
 
-    /// Draw the strokes for the given keys on the [piet::RenderContext].
+    /// Render the strokes for the given keys to an image, cropped to their bounds with a transparent background.
     ///
-    /// This always draws all strokes for the given keys, even trashed ones.
-    #[allow(unused)]
-    pub(crate) fn draw_keys_immediate(
+    /// The image scale is relative to the document, so it is the target dpi divided by the document dpi.
+    /// The strokes are drawn in the order they are rendered on the canvas.
+    pub fn render_selection_to_image(
         &self,
         keys: &[StrokeKey],
-        piet_cx: &mut impl piet::RenderContext,
         image_scale: f64,
-    ) -> anyhow::Result<()> {
-        for &key in keys {
-            if let Some(stroke) = self.stroke_components.get(key) {
-                stroke.draw(piet_cx, image_scale)?;
-            }
-        }
+    ) -> anyhow::Result<::image::RgbaImage> {
+        let mut keys = keys.to_vec();
+        self.sort_keys_chrono(&mut keys);
+        let bounds = self
+            .bounds_for_strokes(&keys)
+            .ok_or_else(|| anyhow::anyhow!("Rendering selection to image failed, no bounds."))?;
 
-        Ok(())
+        let strokes = self.get_strokes_arc(&keys);
+        let stroke_layers = self.get_stroke_layers_with_opacity(&keys);
+
+        Image::gen_with_cairo(
+            |cairo_cx| {
+                layer_comp::draw_with_layer_opacity_to_cairo(
+                    cairo_cx,
+                    strokes
+                        .iter()
+                        .map(|stroke| stroke.as_ref())
+                        .zip(stroke_layers.iter().copied().map(Some)),
+                    |stroke| stroke.draw_to_cairo(cairo_cx, image_scale),
+                )
+            },
+            bounds,
+            image_scale,
+        )?
+        .into_imgbuf()
     }
 
     /// Draw all strokes intersecting the viewport on the [piet::RenderContext].
@@ -631,7 +982,7 @@ impl StrokeStore {
                 match stroke.as_ref() {
                     // Draw positions for brushstrokes
                     Stroke::BrushStroke(brushstroke) => {
-                        for element in brushstroke.path.clone().into_elements().iter() {
+                        for element in brushstroke.path().clone().into_elements().iter() {
                             visual_debug::draw_pos_to_gtk_snapshot(
                                 snapshot,
                                 element.pos,
@@ -655,3 +1006,103 @@ impl StrokeStore {
         Ok(())
     }
 }
+
+/// Generate the images of the stroke, with the level of detail for the image scale and thin horizontal and vertical
+/// lines snapped to the pixel grid when enabled.
+fn gen_stroke_images(
+    stroke: &Stroke,
+    viewport: Aabb,
+    image_scale: f64,
+    pixel_snap_thin_lines: bool,
+    lod_config: LodConfig,
+) -> anyhow::Result<GeneratedContentImages> {
+    let lod_stroke = if lod_config.applies(image_scale) {
+        stroke.lod_simplified(image_scale, &lod_config)
+    } else {
+        None
+    };
+    let stroke = lod_stroke.as_ref().unwrap_or(stroke);
+    if pixel_snap_thin_lines && let Some(snapped) = stroke.pixel_snapped(image_scale) {
+        return snapped.gen_images(viewport, image_scale);
+    }
+    stroke.gen_images(viewport, image_scale)
+}
+
+/// The cacheable rendering of the render component.
+fn cached_rendering(render_comp: &RenderComponent) -> CachedRendering {
+    CachedRendering {
+        images: render_comp.images.clone(),
+        #[cfg(feature = "ui")]
+        rendernodes: render_comp.rendernodes.clone(),
+    }
+}
+
+/// Replace the rendering of the render component with the cached full rendering.
+fn apply_cached_rendering(render_comp: &mut RenderComponent, cached: CachedRendering) {
+    render_comp.images = cached.images;
+    #[cfg(feature = "ui")]
+    {
+        render_comp.rendernodes = cached.rendernodes;
+    }
+    render_comp.state = RenderCompState::Complete;
+}
+
+/// Composite the strokes drawn on the layer snapshot with the layer opacity onto the snapshot of the layers below,
+/// which then becomes the snapshot for the following strokes.
+#[cfg(feature = "ui")]
+fn composite_translucent_layer(
+    layer_snapshot: &mut gtk4::Snapshot,
+    layer_opacity: f64,
+    below: gtk4::Snapshot,
+) {
+    use gtk4::prelude::*;
+
+    let layer_node = std::mem::replace(layer_snapshot, below).to_node();
+    if let Some(layer_node) = layer_node {
+        layer_snapshot.push_opacity(layer_opacity);
+        layer_snapshot.append_node(layer_node);
+        layer_snapshot.pop();
+    }
+}
+
+/// Blend the run of strokes drawn on the snapshot with the blend mode onto the content below them,
+/// which then becomes the snapshot for the following strokes.
+#[cfg(feature = "ui")]
+fn composite_blend_run(
+    strokes_snapshot: &mut gtk4::Snapshot,
+    blend_mode: gtk4::gsk::BlendMode,
+    below: Option<gtk4::gsk::RenderNode>,
+) {
+    use gtk4::prelude::*;
+
+    let run_node = std::mem::replace(strokes_snapshot, gtk4::Snapshot::new()).to_node();
+    strokes_snapshot.push_blend(blend_mode);
+    if let Some(below) = below {
+        strokes_snapshot.append_node(below);
+    }
+    strokes_snapshot.pop();
+    if let Some(run_node) = run_node {
+        strokes_snapshot.append_node(run_node);
+    }
+    strokes_snapshot.pop();
+}
+
+/// Append the rendernodes of the stroke to the snapshot, composited with the opacity.
+#[cfg(feature = "ui")]
+fn append_stroke_rendernodes(
+    snapshot: &gtk4::Snapshot,
+    opacity: f64,
+    render_comp: &RenderComponent,
+) {
+    use gtk4::prelude::*;
+
+    if opacity < 1.0 {
+        snapshot.push_opacity(opacity);
+    }
+    for rendernode in render_comp.rendernodes.iter() {
+        snapshot.append_node(rendernode);
+    }
+    if opacity < 1.0 {
+        snapshot.pop();
+    }
+}
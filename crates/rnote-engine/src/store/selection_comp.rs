@@ -4,6 +4,7 @@ use super::{StrokeKey, StrokeStore};
 use crate::strokes::Stroke;
 use crate::strokes::content::GeneratedContentImages;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
+use rnote_compose::Color;
 use rnote_compose::shapes::Shapeable;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -85,6 +86,32 @@ impl StrokeStore {
             .collect::<Vec<StrokeKey>>()
     }
 
+    /// Select all strokes with a stroke color that differs no more than the tolerance in each component from the color.
+    ///
+    /// The selection of the other strokes is kept. Returns the newly selected keys in the order they are rendered.
+    pub(crate) fn select_strokes_by_color(
+        &mut self,
+        color: Color,
+        tolerance: f64,
+    ) -> Vec<StrokeKey> {
+        let keys = self
+            .stroke_keys_as_rendered()
+            .into_iter()
+            .filter(|&key| {
+                self.get_stroke_ref(key)
+                    .and_then(|stroke| stroke.stroke_color())
+                    .is_some_and(|stroke_color| {
+                        (stroke_color.r - color.r).abs() <= tolerance
+                            && (stroke_color.g - color.g).abs() <= tolerance
+                            && (stroke_color.b - color.b).abs() <= tolerance
+                            && (stroke_color.a - color.a).abs() <= tolerance
+                    })
+            })
+            .collect::<Vec<StrokeKey>>();
+        self.set_selected_keys(&keys, true);
+        keys
+    }
+
     /// Generate the bounds that include all selected strokes.
     ///
     /// None if no strokes are selected
@@ -103,9 +130,13 @@ impl StrokeStore {
         let new_selected = old_selected
             .iter()
             .filter_map(|&old_key| {
+                let layer = self.chrono_components.get(old_key).map(|c| c.layer);
                 let new_key =
-                    self.insert_stroke((**self.stroke_components.get(old_key)?).clone(), None);
+                    self.insert_stroke((**self.stroke_components.get(old_key)?).clone(), layer);
                 self.set_selected(new_key, true);
+                if let Some(metadata_comp) = self.metadata_components.get(old_key).cloned() {
+                    Arc::make_mut(&mut self.metadata_components).insert(new_key, metadata_comp);
+                }
 
                 // duplicate and insert the render images of the old stroke to avoid flickering
                 if let Some(render_comp) = self.render_components.get(old_key) {
@@ -125,6 +156,7 @@ impl StrokeStore {
                 Some(new_key)
             })
             .collect::<Vec<StrokeKey>>();
+        self.regroup_into_group_copies(&new_selected);
 
         // Offsetting the new selected stroke to make the duplication apparent
         self.translate_strokes(&new_selected, Stroke::IMPORT_OFFSET_DEFAULT);
@@ -144,7 +176,7 @@ impl StrokeStore {
         let keys: Vec<(StrokeKey, Aabb)> = self
             .key_tree
             .iter()
-            .filter(|(key, _)| !(self.trashed(*key).unwrap_or(false)))
+            .filter(|(key, _)| !(self.trashed(*key).unwrap_or(false)) && !self.hidden(*key))
             .collect();
         if keys.is_empty() {
             return (Vec::new(), None);
@@ -1,20 +1,55 @@
 // Imports
-use super::StrokeKey;
 use super::render_comp::RenderCompState;
+use super::{StoreChange, StrokeKey};
 use crate::engine::StrokeContent;
-use crate::strokes::{Content, Stroke};
+use crate::pens::pensconfig::BrushConfig;
+use crate::strokes::{BrushStroke, Content, Stroke};
 use crate::{StrokeStore, WidgetFlags};
 use geo::intersects::Intersects;
 use geo::prelude::Contains;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
-use rnote_compose::Color;
-use rnote_compose::penpath::Element;
+use rnote_compose::penpath::{Element, Segment};
 use rnote_compose::shapes::Shapeable;
 use rnote_compose::transform::Transformable;
+use rnote_compose::{Color, PenPath, Style};
+use slotmap::SecondaryMap;
 use std::sync::Arc;
 #[cfg(feature = "ui")]
 use tracing::error;
 
+/// The distance in surface coords a coord may have to the geometry of a stroke to still hit it.
+pub(crate) const HIT_TEST_TOLERANCE: f64 = 4.0;
+
+/// The edge or center of the bounds along which strokes are aligned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlignMode {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    /// Centered on the vertical center line.
+    HorizontalCenter,
+    /// Centered on the horizontal center line.
+    VerticalCenter,
+}
+
+/// The axis along which strokes are distributed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+impl Axis {
+    /// The index of the axis in vectors.
+    fn index(self) -> usize {
+        match self {
+            Axis::Horizontal => 0,
+            Axis::Vertical => 1,
+        }
+    }
+}
+
 /// Systems that are related to the stroke components.
 impl StrokeStore {
     /// Gets a immutable reference to a stroke.
@@ -54,6 +89,11 @@ impl StrokeStore {
         self.stroke_components.keys().collect()
     }
 
+    /// The number of strokes in the stroke components slotmap, including the trashed ones.
+    pub fn n_strokes(&self) -> usize {
+        self.stroke_components.len()
+    }
+
     #[allow(unused)]
     pub(crate) fn keys_unordered_intersecting_bounds(&self, bounds: Aabb) -> Vec<StrokeKey> {
         self.key_tree.keys_intersecting_bounds(bounds)
@@ -71,10 +111,120 @@ impl StrokeStore {
     pub(crate) fn stroke_keys_as_rendered(&self) -> Vec<StrokeKey> {
         self.keys_sorted_chrono()
             .into_iter()
-            .filter(|&key| !(self.trashed(key).unwrap_or(false)))
+            .filter(|&key| !(self.trashed(key).unwrap_or(false)) && !self.hidden(key))
             .collect::<Vec<StrokeKey>>()
     }
 
+    /// Iterate the strokes that are not trashed in the order that they are rendered, together with their bounds.
+    ///
+    /// The bounds are the cached bounds from the last geometry update of each stroke.
+    /// Is read-only and does not trigger any geometry update or rendering.
+    pub fn iter_strokes_ordered(&self) -> impl Iterator<Item = (StrokeKey, &Stroke, Aabb)> {
+        let cached_bounds = self
+            .key_tree
+            .iter()
+            .collect::<SecondaryMap<StrokeKey, Aabb>>();
+
+        self.stroke_keys_as_rendered()
+            .into_iter()
+            .filter_map(move |key| {
+                Some((
+                    key,
+                    self.stroke_components.get(key)?.as_ref(),
+                    *cached_bounds.get(key)?,
+                ))
+            })
+    }
+
+    /// The total length of the ink drawn by all strokes that are not trashed, in document coordinates.
+    ///
+    /// Sums the lengths that the strokes cached on their last geometry update, see [Stroke::path_length()].
+    pub fn total_path_length(&self) -> f64 {
+        self.stroke_components
+            .iter()
+            .filter(|&(key, _)| !(self.trashed(key).unwrap_or(false)))
+            .map(|(_, stroke)| stroke.path_length())
+            .sum()
+    }
+
+    /// Find pairs of brush strokes that duplicate each other, in the order that they are rendered.
+    ///
+    /// Both strokes need to have the same style and fill, and all path elements must match within the tolerance.
+    /// The seeds of the styles are ignored, since they only vary the generated texture.
+    /// The second key of each pair is the later duplicate.
+    pub fn find_duplicate_strokes(&self, tolerance: f64) -> Vec<(StrokeKey, StrokeKey)> {
+        let keys = self.stroke_keys_as_rendered();
+        let mut render_order = SecondaryMap::new();
+        for (i, &key) in keys.iter().enumerate() {
+            render_order.insert(key, i);
+        }
+        let style_value = |brushstroke: &BrushStroke| {
+            let mut style = brushstroke.style().clone();
+            match &mut style {
+                Style::Smooth(_) => {}
+                Style::Rough(options) => options.seed = None,
+                Style::Textured(options) => options.seed = None,
+            }
+            serde_json::to_value((style, brushstroke.fill_color)).ok()
+        };
+
+        let mut duplicates = Vec::new();
+        for (i, &key) in keys.iter().enumerate() {
+            let Some(Stroke::BrushStroke(brushstroke)) = self.get_stroke_ref(key) else {
+                continue;
+            };
+            let bounds = brushstroke.bounds();
+            let mut candidates = self
+                .key_tree
+                .keys_intersecting_bounds(bounds.loosened(tolerance))
+                .into_iter()
+                .filter(|&other| render_order.get(other).is_some_and(|&j| j > i))
+                .collect::<Vec<StrokeKey>>();
+            candidates.sort_unstable_by_key(|&other| render_order[other]);
+            let mut style = None;
+
+            for other in candidates {
+                let Some(Stroke::BrushStroke(other_brushstroke)) = self.get_stroke_ref(other)
+                else {
+                    continue;
+                };
+                let other_bounds = other_brushstroke.bounds();
+                if (bounds.mins - other_bounds.mins).abs().max() > tolerance
+                    || (bounds.maxs - other_bounds.maxs).abs().max() > tolerance
+                    || brushstroke.path().segments.len() != other_brushstroke.path().segments.len()
+                    || !brushstroke
+                        .path()
+                        .elements()
+                        .zip(other_brushstroke.path().elements())
+                        .all(|(a, b)| (a.pos - b.pos).magnitude() <= tolerance)
+                {
+                    continue;
+                }
+                let style = style.get_or_insert_with(|| style_value(brushstroke));
+                if style.is_some() && *style == style_value(other_brushstroke) {
+                    duplicates.push((key, other));
+                }
+            }
+        }
+
+        duplicates
+    }
+
+    /// Trash the later stroke of all pairs of duplicate strokes found with [StrokeStore::find_duplicate_strokes].
+    ///
+    /// Returns the keys of the trashed strokes.
+    pub(crate) fn dedup_strokes(&mut self, tolerance: f64) -> Vec<StrokeKey> {
+        let mut keys = self
+            .find_duplicate_strokes(tolerance)
+            .into_iter()
+            .map(|(_, duplicate)| duplicate)
+            .collect::<Vec<StrokeKey>>();
+        keys.sort_unstable();
+        keys.dedup();
+        self.set_trashed_keys(&keys, true);
+        keys
+    }
+
     /// Stroke keys intersecting the given bounds, in the order that they should be rendered.
     pub(crate) fn stroke_keys_as_rendered_intersecting_bounds(
         &self,
@@ -82,15 +232,70 @@ impl StrokeStore {
     ) -> Vec<StrokeKey> {
         self.keys_sorted_chrono_intersecting_bounds(bounds)
             .into_iter()
-            .filter(|&key| !(self.trashed(key).unwrap_or(false)))
+            .filter(|&key| !(self.trashed(key).unwrap_or(false)) && !self.hidden(key))
             .collect::<Vec<StrokeKey>>()
     }
 
+    /// Keys of the strokes intersecting the given bounds, in the order that they should be rendered.
+    ///
+    /// The candidates are queried from the spatial index with the stroke bounds. When `exact` is true,
+    /// only strokes where at least one of their hitboxes intersects the bounds are returned.
+    /// Trashed and hidden strokes are excluded.
+    pub fn strokes_in_bounds(&self, bounds: Aabb, exact: bool) -> Vec<StrokeKey> {
+        self.stroke_keys_as_rendered_intersecting_bounds(bounds)
+            .into_iter()
+            .filter(|&key| {
+                !exact
+                    || self.stroke_components.get(key).is_some_and(|stroke| {
+                        stroke
+                            .hitboxes()
+                            .iter()
+                            .any(|hitbox| hitbox.intersects(&bounds))
+                    })
+            })
+            .collect()
+    }
+
+    /// The start or end of a brush stroke nearest to the position within the radius,
+    /// excluding the stroke of the given key.
+    pub(crate) fn nearest_brushstroke_endpoint(
+        &self,
+        pos: na::Vector2<f64>,
+        radius: f64,
+        exclude: Option<StrokeKey>,
+    ) -> Option<na::Vector2<f64>> {
+        let bounds = Aabb::from_half_extents(pos.into(), na::Vector2::repeat(radius));
+
+        self.stroke_keys_as_rendered_intersecting_bounds(bounds)
+            .into_iter()
+            .filter(|&key| Some(key) != exclude)
+            .filter_map(
+                |key| match self.stroke_components.get(key).map(Arc::as_ref) {
+                    Some(Stroke::BrushStroke(brushstroke)) => Some(brushstroke),
+                    _ => None,
+                },
+            )
+            .flat_map(|brushstroke| {
+                let start = brushstroke.path().start.pos;
+                let end = brushstroke
+                    .path()
+                    .segments
+                    .last()
+                    .map(|seg| seg.end().pos)
+                    .unwrap_or(start);
+                [start, end]
+            })
+            .map(|endpoint| (endpoint, (endpoint - pos).magnitude()))
+            .filter(|(_, dist)| *dist <= radius)
+            .min_by(|(_, first), (_, second)| first.total_cmp(second))
+            .map(|(endpoint, _)| endpoint)
+    }
+
     /// Stroke keys contained in the given bounds, in the order that they should be rendered.
     pub(crate) fn stroke_keys_as_rendered_in_bounds(&self, bounds: Aabb) -> Vec<StrokeKey> {
         self.keys_sorted_chrono_in_bounds(bounds)
             .into_iter()
-            .filter(|&key| !(self.trashed(key).unwrap_or(false)))
+            .filter(|&key| !(self.trashed(key).unwrap_or(false)) && !self.hidden(key))
             .collect::<Vec<StrokeKey>>()
     }
 
@@ -112,10 +317,28 @@ impl StrokeStore {
         {
             stroke.update_geometry();
             self.key_tree.update_with_key(key, stroke.bounds());
+            self.change_listeners.emit(StoreChange::Modified(key));
             self.set_rendering_dirty(key);
         }
     }
 
+    /// Updates the stroke bounds in the key tree, keeping the stroke geometry that was grown incrementally
+    /// while appending segments.
+    ///
+    /// Must not be used when segments were removed or moved. The stroke then needs to update its rendering.
+    pub(crate) fn update_bounds_for_stroke(&mut self, key: StrokeKey) {
+        let Some(bounds) = self
+            .stroke_components
+            .get(key)
+            .map(|stroke| stroke.bounds())
+        else {
+            return;
+        };
+        self.key_tree.update_with_key(key, bounds);
+        self.change_listeners.emit(StoreChange::Modified(key));
+        self.set_rendering_dirty(key);
+    }
+
     /// Updates the strokes geometries.
     ///
     /// The strokes then need to update their rendering.
@@ -172,6 +395,27 @@ impl StrokeStore {
         Some(bounds)
     }
 
+    /// The centroid of the strokes for the given keys, weighting the center of each stroke bounds by its area.
+    pub(crate) fn centroid_for_strokes(&self, keys: &[StrokeKey]) -> Option<na::Point2<f64>> {
+        let bounds = self.strokes_bounds(keys);
+        if bounds.is_empty() {
+            return None;
+        }
+        let (weighted_sum, total_weight) = bounds.iter().fold(
+            (na::Vector2::<f64>::zeros(), 0.0),
+            |(weighted_sum, total_weight), bounds| {
+                // Degenerate bounds (e.g. a straight horizontal line) still need some weight
+                let weight = bounds.volume().max(f64::EPSILON);
+                (
+                    weighted_sum + bounds.center().coords * weight,
+                    total_weight + weight,
+                )
+            },
+        );
+
+        Some((weighted_sum / total_weight).into())
+    }
+
     /// Collect all stroke bounds for the given keys.
     pub(crate) fn strokes_bounds(&self, keys: &[StrokeKey]) -> Vec<Aabb> {
         keys.iter()
@@ -202,11 +446,97 @@ impl StrokeStore {
                     // translate the stroke geometry
                     stroke.translate(offset);
                     self.key_tree.update_with_key(key, stroke.bounds());
+                    self.change_listeners.emit(StoreChange::Modified(key));
                 }
             }
         });
     }
 
+    /// Align the bounds of the strokes to the edge or center of their combined bounds.
+    ///
+    /// The strokes then need to update their rendering.
+    pub(crate) fn align_strokes(&mut self, keys: &[StrokeKey], mode: AlignMode) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if keys.len() < 2 {
+            return widget_flags;
+        }
+        let Some(all_bounds) = self.bounds_for_strokes(keys) else {
+            return widget_flags;
+        };
+
+        for &key in keys {
+            let Some(bounds) = self.get_stroke_ref(key).map(|stroke| stroke.bounds()) else {
+                continue;
+            };
+            let offset = match mode {
+                AlignMode::Left => na::vector![all_bounds.mins[0] - bounds.mins[0], 0.0],
+                AlignMode::Right => na::vector![all_bounds.maxs[0] - bounds.maxs[0], 0.0],
+                AlignMode::Top => na::vector![0.0, all_bounds.mins[1] - bounds.mins[1]],
+                AlignMode::Bottom => na::vector![0.0, all_bounds.maxs[1] - bounds.maxs[1]],
+                AlignMode::HorizontalCenter => {
+                    na::vector![all_bounds.center()[0] - bounds.center()[0], 0.0]
+                }
+                AlignMode::VerticalCenter => {
+                    na::vector![0.0, all_bounds.center()[1] - bounds.center()[1]]
+                }
+            };
+            if offset == na::Vector2::zeros() {
+                continue;
+            }
+            self.translate_strokes(&[key], offset);
+            self.translate_strokes_images(&[key], offset);
+            widget_flags.redraw = true;
+            widget_flags.store_modified = true;
+            widget_flags.selection_changed = true;
+        }
+
+        widget_flags
+    }
+
+    /// Distribute the strokes along the axis with equal spacing between their bounds.
+    ///
+    /// The outermost strokes keep their position. Needs at least three strokes.
+    /// The strokes then need to update their rendering.
+    pub(crate) fn distribute_strokes(&mut self, keys: &[StrokeKey], axis: Axis) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let i = axis.index();
+        let mut strokes_bounds = keys
+            .iter()
+            .filter_map(|&key| Some((key, self.get_stroke_ref(key)?.bounds())))
+            .collect::<Vec<(StrokeKey, Aabb)>>();
+        if strokes_bounds.len() < 3 {
+            return widget_flags;
+        }
+        strokes_bounds
+            .sort_by(|(_, first), (_, second)| first.center()[i].total_cmp(&second.center()[i]));
+        let (Some(first), Some(last)) = (strokes_bounds.first(), strokes_bounds.last()) else {
+            return widget_flags;
+        };
+        let total_extent = strokes_bounds
+            .iter()
+            .map(|(_, bounds)| bounds.extents()[i])
+            .sum::<f64>();
+        let spacing =
+            (last.1.maxs[i] - first.1.mins[i] - total_extent) / (strokes_bounds.len() - 1) as f64;
+
+        let mut pos = first.1.maxs[i] + spacing;
+        for &(key, bounds) in &strokes_bounds[1..strokes_bounds.len() - 1] {
+            let mut offset = na::Vector2::zeros();
+            offset[i] = pos - bounds.mins[i];
+            pos += bounds.extents()[i] + spacing;
+            if offset == na::Vector2::zeros() {
+                continue;
+            }
+            self.translate_strokes(&[key], offset);
+            self.translate_strokes_images(&[key], offset);
+            widget_flags.redraw = true;
+            widget_flags.store_modified = true;
+            widget_flags.selection_changed = true;
+        }
+
+        widget_flags
+    }
+
     /// Translate the stroke rendering images.
     ///
     /// The strokes then need to update their rendering.
@@ -252,9 +582,43 @@ impl StrokeStore {
                     // rotate the stroke geometry
                     stroke.rotate(angle, center);
                     self.key_tree.update_with_key(key, stroke.bounds());
+                    self.change_listeners.emit(StoreChange::Modified(key));
+                }
+            }
+        });
+    }
+
+    /// Apply the affine transformation to the strokes in a single pass, updating their geometry and bounds.
+    ///
+    /// Widths are scaled along for uniform scales. The strokes then need to update their rendering.
+    pub fn transform_strokes(
+        &mut self,
+        keys: &[StrokeKey],
+        transform: na::Affine2<f64>,
+    ) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        if keys.is_empty() {
+            return widget_flags;
+        }
+
+        let stroke_components = Arc::make_mut(&mut self.stroke_components);
+        keys.iter().for_each(|&key| {
+            if let Some(stroke) = stroke_components.get_mut(key).map(Arc::make_mut) {
+                stroke.apply_affine(&transform);
+                stroke.update_geometry();
+                self.key_tree.update_with_key(key, stroke.bounds());
+                self.change_listeners.emit(StoreChange::Modified(key));
+                if let Some(render_comp) = self.render_components.get_mut(key) {
+                    render_comp.state = RenderCompState::Dirty;
                 }
             }
         });
+
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+
+        widget_flags
     }
 
     /// Change the stroke and text color for the given keys.
@@ -275,16 +639,19 @@ impl StrokeStore {
                 {
                     match stroke {
                         Stroke::BrushStroke(brush_stroke) => {
-                            brush_stroke.style.set_stroke_color(color);
+                            brush_stroke.style_mut().set_stroke_color(color);
                             self.set_rendering_dirty(key);
+                            self.change_listeners.emit(StoreChange::Modified(key));
                         }
                         Stroke::ShapeStroke(shape_stroke) => {
                             shape_stroke.style.set_stroke_color(color);
                             self.set_rendering_dirty(key);
+                            self.change_listeners.emit(StoreChange::Modified(key));
                         }
                         Stroke::TextStroke(text_stroke) => {
                             text_stroke.text_style.color = color;
                             self.set_rendering_dirty(key);
+                            self.change_listeners.emit(StoreChange::Modified(key));
                         }
                         _ => {}
                     }
@@ -298,6 +665,76 @@ impl StrokeStore {
         widget_flags
     }
 
+    /// Recolor the strokes of the given keys, without otherwise altering them.
+    ///
+    /// Only the stroke color of the style is changed, so the seeds of textured styles are kept.
+    /// Strokes that don't carry a stroke color are skipped.
+    /// The strokes then need to update their rendering.
+    pub fn recolor_strokes(&mut self, keys: &[StrokeKey], color: Color) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let mut recolored = false;
+
+        for &key in keys {
+            let Some(stroke) = Arc::make_mut(&mut self.stroke_components)
+                .get_mut(key)
+                .map(Arc::make_mut)
+            else {
+                continue;
+            };
+            let style = match stroke {
+                Stroke::BrushStroke(brush_stroke) => brush_stroke.style_mut(),
+                Stroke::ShapeStroke(shape_stroke) => &mut shape_stroke.style,
+                Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => {
+                    continue;
+                }
+            };
+            if style.stroke_color().is_none() {
+                continue;
+            }
+            style.set_stroke_color(color);
+            self.set_rendering_dirty(key);
+            self.change_listeners.emit(StoreChange::Modified(key));
+            recolored = true;
+        }
+
+        if recolored {
+            widget_flags.redraw = true;
+            widget_flags.store_modified = true;
+            widget_flags.selection_changed = true;
+        }
+
+        widget_flags
+    }
+
+    /// Set the opacity the whole stroke is composited with, clamped to the range [0.0 - 1.0].
+    ///
+    /// Only the compositing is affected, so neither the geometry nor the rendering of the stroke need to be updated.
+    /// Strokes without a style are skipped.
+    pub fn set_stroke_opacity(&mut self, key: StrokeKey, opacity: f64) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        let Some(stroke) = Arc::make_mut(&mut self.stroke_components)
+            .get_mut(key)
+            .map(Arc::make_mut)
+        else {
+            return widget_flags;
+        };
+        let style = match stroke {
+            Stroke::BrushStroke(brush_stroke) => brush_stroke.style_mut(),
+            Stroke::ShapeStroke(shape_stroke) => &mut shape_stroke.style,
+            Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => {
+                return widget_flags;
+            }
+        };
+        style.set_opacity(opacity);
+        self.change_listeners.emit(StoreChange::Modified(key));
+
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+
+        widget_flags
+    }
+
     /// Invert the stroke, text and fill color of the given keys.
     ///
     /// Strokes then need to update their rendering.
@@ -317,6 +754,7 @@ impl StrokeStore {
 
                 if stroke_modified {
                     self.set_rendering_dirty(key);
+                    self.change_listeners.emit(StoreChange::Modified(key));
                 }
             }
         });
@@ -345,12 +783,17 @@ impl StrokeStore {
                 {
                     match stroke {
                         Stroke::BrushStroke(brush_stroke) => {
-                            brush_stroke.style.set_fill_color(color);
+                            brush_stroke.style_mut().set_fill_color(color);
+                            if brush_stroke.fill_color.is_some() {
+                                brush_stroke.fill_color = Some(color);
+                            }
                             self.set_rendering_dirty(key);
+                            self.change_listeners.emit(StoreChange::Modified(key));
                         }
                         Stroke::ShapeStroke(shape_stroke) => {
                             shape_stroke.style.set_fill_color(color);
                             self.set_rendering_dirty(key);
+                            self.change_listeners.emit(StoreChange::Modified(key));
                         }
                         _ => {}
                     }
@@ -364,6 +807,99 @@ impl StrokeStore {
         widget_flags
     }
 
+    /// Fill the area enclosed by the brush stroke with the given color, drawn behind the stroke outline.
+    ///
+    /// The fill is part of the stroke and follows changes of its geometry.
+    /// Errors if the stroke is not a brush stroke or its path is not closed.
+    pub fn fill_brushstroke(
+        &mut self,
+        key: StrokeKey,
+        fill_color: Color,
+    ) -> anyhow::Result<WidgetFlags> {
+        let mut widget_flags = WidgetFlags::default();
+
+        let Some(Stroke::BrushStroke(brushstroke)) = Arc::make_mut(&mut self.stroke_components)
+            .get_mut(key)
+            .map(Arc::make_mut)
+        else {
+            return Err(anyhow::anyhow!(
+                "Filling brushstroke failed, no brushstroke for key {key:?}"
+            ));
+        };
+        if !brushstroke.is_closed() {
+            return Err(anyhow::anyhow!(
+                "Filling brushstroke failed, the path is not closed"
+            ));
+        }
+        brushstroke.fill_color = Some(fill_color);
+        self.set_rendering_dirty(key);
+        self.change_listeners.emit(StoreChange::Modified(key));
+
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+
+        Ok(widget_flags)
+    }
+
+    /// Scale the overall width of the brush stroke by the given factor, keeping the relative widths along the path.
+    ///
+    /// The resulting width is clamped to the brush width range.
+    /// Errors if the stroke is not a brush stroke or the factor is not positive.
+    pub fn scale_stroke_width(
+        &mut self,
+        key: StrokeKey,
+        factor: f64,
+    ) -> anyhow::Result<WidgetFlags> {
+        let mut widget_flags = WidgetFlags::default();
+
+        if !factor.is_finite() || factor <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "Scaling stroke width failed, invalid factor {factor}"
+            ));
+        }
+        let Some(Stroke::BrushStroke(brushstroke)) = Arc::make_mut(&mut self.stroke_components)
+            .get_mut(key)
+            .map(Arc::make_mut)
+        else {
+            return Err(anyhow::anyhow!(
+                "Scaling stroke width failed, no brushstroke for key {key:?}"
+            ));
+        };
+        // The element widths are derived from the style width by the pressure, so they scale along
+        let width = (brushstroke.style().stroke_width() * factor)
+            .clamp(BrushConfig::STROKE_WIDTH_MIN, BrushConfig::STROKE_WIDTH_MAX);
+        brushstroke.style_mut().set_stroke_width(width);
+        self.update_geometry_for_stroke(key);
+
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+
+        Ok(widget_flags)
+    }
+
+    /// Straighten the brush stroke into a line from its first to its last element, keeping its pressure profile.
+    ///
+    /// Errors if the stroke is not a brush stroke.
+    pub fn straighten_stroke(&mut self, key: StrokeKey) -> anyhow::Result<WidgetFlags> {
+        let mut widget_flags = WidgetFlags::default();
+
+        let Some(Stroke::BrushStroke(brushstroke)) = Arc::make_mut(&mut self.stroke_components)
+            .get_mut(key)
+            .map(Arc::make_mut)
+        else {
+            return Err(anyhow::anyhow!(
+                "Straightening stroke failed, no brushstroke for key {key:?}"
+            ));
+        };
+        brushstroke.path_mut().straighten();
+        self.update_geometry_for_stroke(key);
+
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+
+        Ok(widget_flags)
+    }
+
     /// Rotate the stroke rendering images.
     ///
     /// The strokes then need to update their rendering.
@@ -407,6 +943,7 @@ impl StrokeStore {
                     // rotate the stroke geometry
                     stroke.scale(scale);
                     self.key_tree.update_with_key(key, stroke.bounds());
+                    self.change_listeners.emit(StoreChange::Modified(key));
                 }
             }
         });
@@ -488,11 +1025,11 @@ impl StrokeStore {
             geo::Polygon::new(selector_path_points.into(), vec![])
         };
 
-        self.keys_sorted_chrono_intersecting_bounds(bounds)
+        self.strokes_in_bounds(bounds, false)
             .into_iter()
             .filter_map(|key| {
-                // skip if stroke is trashed
-                if self.trashed(key)? {
+                // skip if stroke is locked
+                if self.locked(key).unwrap_or(false) {
                     return None;
                 }
 
@@ -543,11 +1080,11 @@ impl StrokeStore {
             geo::LineString::new(selector_path_points)
         };
 
-        self.keys_sorted_chrono_intersecting_bounds(bounds)
+        self.strokes_in_bounds(bounds, false)
             .into_iter()
             .filter_map(|key| {
-                // skip if stroke is trashed
-                if self.trashed(key)? {
+                // skip if stroke is locked
+                if self.locked(key).unwrap_or(false) {
                     return None;
                 }
 
@@ -576,11 +1113,11 @@ impl StrokeStore {
         aabb: Aabb,
         viewport: Aabb,
     ) -> Vec<StrokeKey> {
-        self.keys_sorted_chrono_intersecting_bounds(viewport.merged(&aabb))
+        self.strokes_in_bounds(viewport.merged(&aabb), false)
             .into_iter()
             .filter_map(|key| {
-                // skip if stroke is trashed
-                if self.trashed(key)? {
+                // skip if stroke is locked
+                if self.locked(key).unwrap_or(false) {
                     return None;
                 }
 
@@ -604,7 +1141,8 @@ impl StrokeStore {
             .collect()
     }
 
-    /// Return the keys for strokes where the given coord is inside at least one of their hitboxes.
+    /// Return the keys for strokes where the given coord is inside at least one of their hitboxes
+    /// and close to their actual geometry.
     pub(crate) fn stroke_hitboxes_contain_coord(
         &self,
         viewport: Aabb,
@@ -613,17 +1151,19 @@ impl StrokeStore {
         let mut bounds = viewport;
         bounds.take_point(coord.into());
 
-        self.stroke_keys_as_rendered_intersecting_bounds(bounds)
+        self.strokes_in_bounds(bounds, false)
             .into_iter()
             .filter(|&key| {
-                if let Some(stroke) = self.stroke_components.get(key) {
+                if self.locked(key).unwrap_or(false) {
+                    return false;
+                }
+                self.stroke_components.get(key).is_some_and(|stroke| {
                     stroke
                         .hitboxes()
                         .into_iter()
                         .any(|hitbox| hitbox.contains_local_point(&coord.into()))
-                } else {
-                    false
-                }
+                        && stroke.hit_test(&Element::new(coord, 1.0), HIT_TEST_TOLERANCE)
+                })
             })
             .collect()
     }
@@ -749,4 +1289,234 @@ impl StrokeStore {
             })
             .collect()
     }
+
+    /// Export the strokes for the given keys as a standalone Svg document, with a view box tightly around them.
+    ///
+    /// The strokes are drawn in the order they are rendered on the document.
+    /// The dots of textured brush strokes are exported as individual shapes, so that they stay editable.
+    pub fn export_selection_as_svg(&self, keys: &[StrokeKey]) -> anyhow::Result<String> {
+        let keys = self
+            .stroke_keys_as_rendered()
+            .into_iter()
+            .filter(|key| keys.contains(key))
+            .collect::<Vec<StrokeKey>>();
+        let Some(bounds) = self.bounds_for_strokes(&keys) else {
+            return Err(anyhow::anyhow!(
+                "Exporting selection as Svg failed, no strokes for the given keys"
+            ));
+        };
+
+        let mut svg_data = String::new();
+        for stroke in self.get_strokes_ref(&keys) {
+            let svg = match stroke {
+                Stroke::BrushStroke(brushstroke) => brushstroke.gen_svg_w_separate_dots()?,
+                stroke => stroke.gen_svg()?,
+            };
+            svg_data.push_str(svg.svg_data.as_str());
+            svg_data.push('\n');
+        }
+
+        Ok(rnote_compose::utils::add_xml_header(
+            rnote_compose::utils::wrap_svg_root(
+                svg_data.as_str(),
+                Some(bounds),
+                Some(bounds),
+                false,
+            )
+            .as_str(),
+        ))
+    }
+
+    /// Merge the brush strokes for the given keys into a single brush stroke.
+    ///
+    /// The paths are concatenated in the order of the keys, separated by gaps, and the style of the first stroke is kept.
+    /// The merged stroke replaces the original strokes, which are trashed, so that the merge is undone in a single step.
+    /// Returns None if fewer than two keys are given or if any of them is not a brush stroke.
+    ///
+    /// The merged stroke then needs to update its rendering.
+    pub fn merge_brushstrokes(&mut self, keys: &[StrokeKey]) -> Option<StrokeKey> {
+        if keys.len() < 2 {
+            return None;
+        }
+        let brushstrokes = keys
+            .iter()
+            .map(|&key| match self.stroke_components.get(key)?.as_ref() {
+                Stroke::BrushStroke(brushstroke) => Some(brushstroke),
+                _ => None,
+            })
+            .collect::<Option<Vec<&BrushStroke>>>()?;
+
+        let first = brushstrokes[0];
+        let mut path = first.path().clone();
+        for brushstroke in brushstrokes.iter().skip(1) {
+            path.segments.push(Segment::MoveTo {
+                end: brushstroke.path().start,
+            });
+            if brushstroke.path().segments.is_empty() {
+                // Keep single dots visible after the gap
+                path.segments.push(Segment::LineTo {
+                    end: brushstroke.path().start,
+                });
+            } else {
+                path.segments
+                    .extend(brushstroke.path().segments.iter().copied());
+            }
+        }
+        let merged = Stroke::BrushStroke(BrushStroke::from_penpath(path, first.style().clone()));
+        let layer = self
+            .chrono_components
+            .get(keys[0])
+            .map(|chrono_comp| chrono_comp.layer);
+        let selected = self.selected(keys[0]).unwrap_or(false);
+
+        self.set_trashed_keys(keys, true);
+        let merged_key = self.insert_stroke(merged, layer);
+        self.set_selected(merged_key, selected);
+
+        Some(merged_key)
+    }
+
+    /// Insert a new brush stroke parallel to the brush stroke for the key, offset from its centerline by the distance.
+    ///
+    /// Negative distances offset to the inside. The new stroke adopts the style and the layer of the brush stroke,
+    /// with the given color or the color of the brush stroke when None.
+    /// Returns None if the stroke is not a brush stroke.
+    ///
+    /// The new stroke then needs to update its rendering.
+    pub fn offset_stroke(
+        &mut self,
+        key: StrokeKey,
+        distance: f64,
+        color: Option<Color>,
+    ) -> Option<StrokeKey> {
+        let Stroke::BrushStroke(brushstroke) = self.stroke_components.get(key)?.as_ref() else {
+            return None;
+        };
+        let mut style = brushstroke.style().clone();
+        if let Some(color) = color {
+            style.set_stroke_color(color);
+        }
+        let offset = Stroke::BrushStroke(BrushStroke::from_penpath(
+            brushstroke.path().offset(distance),
+            style,
+        ));
+        let layer = self
+            .chrono_components
+            .get(key)
+            .map(|chrono_comp| chrono_comp.layer);
+
+        let offset_key = self.insert_stroke(offset, layer);
+        self.update_geometry_for_stroke(offset_key);
+
+        Some(offset_key)
+    }
+
+    /// Insert a copy of the brush stroke for the given key, translated so that its path starts at the given position.
+    ///
+    /// The copy is inserted into the layer of the original. Textured copies get the given seed, or keep the one
+    /// of the original when None.
+    /// Returns None if the key is not a brush stroke.
+    ///
+    /// The copy then needs to update its rendering.
+    pub fn stamp_stroke(
+        &mut self,
+        key: StrokeKey,
+        pos: na::Vector2<f64>,
+        seed: Option<u64>,
+    ) -> Option<StrokeKey> {
+        let Stroke::BrushStroke(brushstroke) = self.stroke_components.get(key)?.as_ref() else {
+            return None;
+        };
+        let mut stamp = brushstroke.clone();
+        stamp.translate(pos - stamp.path().start.pos);
+        if let Some(seed) = seed
+            && let Style::Textured(options) = stamp.style_mut()
+        {
+            options.seed = Some(seed);
+        }
+        let layer = self
+            .chrono_components
+            .get(key)
+            .map(|chrono_comp| chrono_comp.layer);
+
+        let stamp_key = self.insert_stroke(Stroke::BrushStroke(stamp), layer);
+        self.update_geometry_for_stroke(stamp_key);
+
+        Some(stamp_key)
+    }
+
+    /// Join the brush strokes for the given keys into a single continuous brush stroke.
+    ///
+    /// The strokes are connected at their closest pair of endpoints with a smooth bridging segment,
+    /// reversing their paths where needed. The style of the first stroke is kept.
+    /// The joined stroke replaces the original strokes, which are trashed, so that the join is undone in a single step.
+    /// Returns None if the keys are equal or if any of them is not a brush stroke.
+    ///
+    /// The joined stroke then needs to update its rendering.
+    pub fn join_strokes(&mut self, a: StrokeKey, b: StrokeKey) -> Option<StrokeKey> {
+        if a == b {
+            return None;
+        }
+        let (Stroke::BrushStroke(first), Stroke::BrushStroke(second)) = (
+            self.stroke_components.get(a)?.as_ref(),
+            self.stroke_components.get(b)?.as_ref(),
+        ) else {
+            return None;
+        };
+        let (first_path, second_path) = (first.path(), second.path());
+        let distance = |from: Element, to: Element| (to.pos - from.pos).magnitude();
+        // Always joined from the end of the first path to the start of the second one
+        let candidates = [
+            (false, false, distance(first_path.end(), second_path.start)),
+            (false, true, distance(first_path.end(), second_path.end())),
+            (true, false, distance(first_path.start, second_path.start)),
+            (true, true, distance(first_path.start, second_path.end())),
+        ];
+        let (reverse_first, reverse_second, _) = candidates
+            .into_iter()
+            .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b))?;
+
+        let mut path = if reverse_first {
+            first_path.reversed()
+        } else {
+            first_path.clone()
+        };
+        path.join_smooth(if reverse_second {
+            second_path.reversed()
+        } else {
+            second_path.clone()
+        });
+        let joined = Stroke::BrushStroke(BrushStroke::from_penpath(path, first.style().clone()));
+        let layer = self
+            .chrono_components
+            .get(a)
+            .map(|chrono_comp| chrono_comp.layer);
+        let selected = self.selected(a).unwrap_or(false) || self.selected(b).unwrap_or(false);
+
+        self.set_trashed_keys(&[a, b], true);
+        let joined_key = self.insert_stroke(joined, layer);
+        self.set_selected(joined_key, selected);
+
+        Some(joined_key)
+    }
+
+    /// Insert a complete brush stroke built from the given elements, the first element being the start.
+    ///
+    /// The geometry is updated and the rendering is regenerated. Returns None if no elements are given.
+    pub fn insert_brushstroke_from_points(
+        &mut self,
+        points: &[Element],
+        style: Style,
+        viewport: Aabb,
+        image_scale: f64,
+    ) -> Option<StrokeKey> {
+        let path = PenPath::try_from_elements(points.iter().copied())?;
+        let key = self.insert_stroke(
+            Stroke::BrushStroke(BrushStroke::from_penpath(path, style)),
+            None,
+        );
+        self.regenerate_rendering_for_stroke(key, viewport, image_scale);
+
+        Some(key)
+    }
 }
@@ -1,11 +1,13 @@
 // Imports
 use super::chrono_comp::StrokeLayer;
-use super::{StrokeKey, StrokeStore};
+use super::{StoreChange, StrokeKey, StrokeStore};
 use crate::WidgetFlags;
+use crate::pens::pensconfig::eraserconfig::EraseFilter;
 use crate::strokes::{BrushStroke, Stroke};
 use p2d::bounding_volume::{Aabb, BoundingVolume};
-use rnote_compose::PenPath;
+use rnote_compose::penpath::Element;
 use rnote_compose::shapes::Shapeable;
+use rnote_compose::{PenPath, Style};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -48,8 +50,16 @@ impl StrokeStore {
             .get_mut(key)
             .map(Arc::make_mut)
         {
+            let changed = trash_comp.trashed != trash;
             trash_comp.trashed = trash;
             self.update_chrono_to_last(key);
+            if changed {
+                self.change_listeners.emit(if trash {
+                    StoreChange::Removed(key)
+                } else {
+                    StoreChange::Inserted(key)
+                });
+            }
         }
     }
 
@@ -77,38 +87,32 @@ impl StrokeStore {
             .collect()
     }
 
-    /// Trash strokes that collide with the given bounds.
+    /// Trash strokes that collide with the given bounds and pass the erase filter.
     pub(crate) fn trash_colliding_strokes(
         &mut self,
         eraser_bounds: Aabb,
         viewport: Aabb,
+        erase_filter: EraseFilter,
     ) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
 
-        self.stroke_keys_as_rendered_intersecting_bounds(viewport)
+        if !eraser_bounds.intersects(&viewport) {
+            return widget_flags;
+        }
+
+        // The eraser is a circle inscribed into its bounds
+        let eraser_center = Element::new(eraser_bounds.center().coords, 1.0);
+        let eraser_radius = eraser_bounds.half_extents().min();
+        self.strokes_in_bounds(eraser_bounds, true)
             .into_iter()
             .for_each(|key| {
-                let mut trash_current_stroke = false;
-
-                if let Some(stroke) = self.stroke_components.get(key) {
-                    match stroke.as_ref() {
-                        Stroke::BrushStroke(_) | Stroke::ShapeStroke(_) => {
-                            // First check if eraser even intersects stroke bounds, avoiding unnecessary work
-                            if eraser_bounds.intersects(&stroke.bounds()) {
-                                for hitbox in stroke.hitboxes().into_iter() {
-                                    if eraser_bounds.intersects(&hitbox) {
-                                        trash_current_stroke = true;
-
-                                        break;
-                                    }
-                                }
-                            }
-                        }
-                        // Ignore other strokes when trashing with the Eraser
-                        Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => {
-                        }
-                    }
-                }
+                // Ignore locked strokes, strokes that don't pass the filter
+                // and strokes that are only inside the bounds but not touched by the eraser
+                let trash_current_stroke = !self.locked(key).unwrap_or(false)
+                    && self.stroke_components.get(key).is_some_and(|stroke| {
+                        erase_filter.matches(stroke)
+                            && stroke.hit_test(&eraser_center, eraser_radius)
+                    });
 
                 if trash_current_stroke {
                     self.set_trashed(key, true);
@@ -123,6 +127,8 @@ impl StrokeStore {
     /// Remove colliding stroke segments with the given bounds.
     /// The stroke is then split. Strokes that don't have segments are trashed completely.
     ///
+    /// Only strokes that pass the erase filter are split or trashed.
+    ///
     /// Returns the keys of all created or modified strokes.
     ///
     /// The returned strokes need to update their rendering.
@@ -130,20 +136,31 @@ impl StrokeStore {
         &mut self,
         eraser_bounds: Aabb,
         viewport: Aabb,
+        erase_filter: EraseFilter,
     ) -> (Vec<StrokeKey>, WidgetFlags) {
         let mut widget_flags = WidgetFlags::default();
         let mut modified_keys = vec![];
 
+        if !eraser_bounds.intersects(&viewport) {
+            return (modified_keys, widget_flags);
+        }
+
         let new_strokes = self
-            .stroke_keys_as_rendered_intersecting_bounds(viewport)
+            .strokes_in_bounds(eraser_bounds, false)
             .into_iter()
             .flat_map(|key| {
+                if self.locked(key).unwrap_or(false) {
+                    return vec![];
+                }
                 let Some(stroke) = Arc::make_mut(&mut self.stroke_components)
                     .get_mut(key)
                     .map(Arc::make_mut)
                 else {
                     return vec![];
                 };
+                if !erase_filter.matches(stroke) {
+                    return vec![];
+                }
 
                 let Some(chrono_comp) = self.chrono_components.get(key) else {
                     return vec![];
@@ -159,48 +176,55 @@ impl StrokeStore {
                             let mut split = Vec::new();
 
                             let mut hits = brushstroke
-                                .path
-                                .hittest(&eraser_bounds, brushstroke.style.stroke_width() * 0.5)
+                                .path()
+                                .hittest(&eraser_bounds, brushstroke.style().stroke_width() * 0.5)
                                 .into_iter();
 
                             if let Some(first_hit) = hits.next() {
                                 let mut prev = first_hit;
                                 for hit in hits {
-                                    let split_slice = &brushstroke.path.segments[prev..hit];
+                                    let split_slice = &brushstroke.path().segments[prev..hit];
 
                                     // skip splits that don't have at least two segments (one's end as path start, one additional)
                                     if split_slice.len() > 1 {
-                                        split.push(split_slice.to_vec());
+                                        split.push((prev, split_slice.to_vec()));
                                     }
 
                                     prev = hit;
                                 }
 
                                 // Catch the last
-                                let last_split = &brushstroke.path.segments[prev..];
+                                let last_split = &brushstroke.path().segments[prev..];
                                 if last_split.len() > 1 {
-                                    split.push(last_split.to_vec());
+                                    split.push((prev, last_split.to_vec()));
                                 }
 
-                                for next_split in split {
+                                for (split_index, next_split) in split {
                                     let mut next_split_iter = next_split.into_iter();
                                     let next_start = next_split_iter.next().unwrap().end();
 
+                                    let mut style = brushstroke.style().clone();
+                                    // The textured seed is advanced for every segment,
+                                    // so it needs to be advanced up to the first segment of the split to keep its appearance.
+                                    if let Style::Textured(options) = &mut style {
+                                        (0..=split_index).for_each(|_| options.advance_seed());
+                                    }
+
                                     new_strokes.push((
                                         Stroke::BrushStroke(BrushStroke::from_penpath(
                                             PenPath::new_w_segments(next_start, next_split_iter),
-                                            brushstroke.style.clone(),
+                                            style,
                                         )),
                                         chrono_comp.layer,
                                     ));
                                 }
 
-                                let first_split = &brushstroke.path.segments[..first_hit];
+                                let first_split = &brushstroke.path().segments[..first_hit];
                                 // Modify the original stroke at the end.
                                 // We keep the start, so we only need at least one segment
                                 if !first_split.is_empty() {
                                     brushstroke.replace_path(PenPath::new_w_segments(
-                                        brushstroke.path.start,
+                                        brushstroke.path().start,
                                         first_split.to_vec(),
                                     ));
                                 } else {
@@ -211,7 +235,11 @@ impl StrokeStore {
                             }
                         }
                     }
-                    Stroke::ShapeStroke(_) => {
+                    // Other strokes can't be split and are trashed completely
+                    Stroke::ShapeStroke(_)
+                    | Stroke::TextStroke(_)
+                    | Stroke::VectorImage(_)
+                    | Stroke::BitmapImage(_) => {
                         if eraser_bounds.intersects(&stroke_bounds) {
                             for hitbox_elem in stroke.hitboxes().iter() {
                                 if eraser_bounds.intersects(hitbox_elem) {
@@ -221,8 +249,6 @@ impl StrokeStore {
                             }
                         }
                     }
-                    // Ignore other strokes when trashing with the Eraser
-                    Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => {}
                 }
 
                 if trash_current_stroke {
@@ -0,0 +1,189 @@
+// Imports
+use super::{StrokeKey, StrokeStore};
+use crate::WidgetFlags;
+use serde::{Deserialize, Serialize};
+use slotmap::SecondaryMap;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "visibility_component")]
+pub struct VisibilityComponent {
+    #[serde(rename = "visible")]
+    pub visible: bool,
+}
+
+impl Default for VisibilityComponent {
+    fn default() -> Self {
+        Self { visible: true }
+    }
+}
+
+/// Systems that are related to the visibility.
+///
+/// Hidden strokes stay in the document, but are not rendered or exported and can't be selected or erased.
+impl StrokeStore {
+    /// Rebuild the slotmap with the keys returned from the stroke components,
+    /// taking over the visibility components of the given map and inserting visible ones for the remaining keys.
+    pub(crate) fn rebuild_visibility_components_slotmap(
+        &mut self,
+        visibility_components: &SecondaryMap<StrokeKey, Arc<VisibilityComponent>>,
+    ) {
+        self.visibility_components = Arc::new(slotmap::SecondaryMap::new());
+        self.stroke_components.keys().for_each(|key| {
+            let visibility_comp = visibility_components
+                .get(key)
+                .cloned()
+                .unwrap_or_else(|| Arc::new(VisibilityComponent::default()));
+            Arc::make_mut(&mut self.visibility_components).insert(key, visibility_comp);
+        });
+    }
+
+    pub fn stroke_visible(&self, key: StrokeKey) -> Option<bool> {
+        self.visibility_components.get(key).map(|v| v.visible)
+    }
+
+    /// Whether the stroke is hidden, either by itself, by one of the groups it is nested in or by its layer.
+    pub(crate) fn hidden(&self, key: StrokeKey) -> bool {
+        !self.stroke_visible(key).unwrap_or(true)
+            || self.group_hidden(key)
+            || self.layer_hidden(key)
+    }
+
+    /// Set if the stroke is visible. Hiding deselects the stroke.
+    pub fn set_stroke_visible(&mut self, key: StrokeKey, visible: bool) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        if let Some(visibility_comp) = Arc::make_mut(&mut self.visibility_components)
+            .get_mut(key)
+            .map(Arc::make_mut)
+        {
+            if visibility_comp.visible != visible {
+                visibility_comp.visible = visible;
+                widget_flags.store_modified = true;
+                widget_flags.redraw = true;
+            }
+            if !visible && self.selected(key).unwrap_or(false) {
+                self.set_selected(key, false);
+                widget_flags.selection_changed = true;
+            }
+        }
+
+        widget_flags
+    }
+
+    pub(crate) fn set_stroke_visible_keys(
+        &mut self,
+        keys: &[StrokeKey],
+        visible: bool,
+    ) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        keys.iter().for_each(|&key| {
+            widget_flags |= self.set_stroke_visible(key, visible);
+        });
+        widget_flags
+    }
+
+    /// The keys of all strokes that are not trashed and are hidden by themselves, unordered.
+    pub(crate) fn invisible_keys_unordered(&self) -> Vec<StrokeKey> {
+        self.stroke_components
+            .keys()
+            .filter(|&key| {
+                !(self.trashed(key).unwrap_or(false)) && !self.stroke_visible(key).unwrap_or(true)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Engine;
+    use crate::store::brushstroke_at_origin;
+    use p2d::bounding_volume::Aabb;
+
+    fn around_origin() -> Aabb {
+        Aabb::new(na::point![-10.0, -10.0], na::point![10.0, 10.0])
+    }
+
+    #[test]
+    fn toggle_stroke_visibility() {
+        let mut store = StrokeStore::default();
+        let key = store.insert_stroke(brushstroke_at_origin(), None);
+        store.set_selected(key, true);
+
+        let widget_flags = store.set_stroke_visible(key, false);
+        assert!(widget_flags.store_modified && widget_flags.selection_changed);
+        assert_eq!(store.stroke_visible(key), Some(false));
+        assert!(store.hidden(key));
+        assert_eq!(store.selected(key), Some(false));
+        assert_eq!(store.invisible_keys_unordered(), vec![key]);
+        // Unchanged
+        assert!(!store.set_stroke_visible(key, false).store_modified);
+
+        let _ = store.set_stroke_visible(key, true);
+        assert!(!store.hidden(key));
+        assert!(store.invisible_keys_unordered().is_empty());
+    }
+
+    #[test]
+    fn hidden_by_group_or_layer() {
+        let mut store = StrokeStore::default();
+        let (a, b) = (
+            store.insert_stroke(brushstroke_at_origin(), None),
+            store.insert_stroke(brushstroke_at_origin(), None),
+        );
+        let group = store.group_strokes(&[a, b], String::from("Group"));
+
+        let _ = store.set_group_hidden(group, true);
+        assert!(store.hidden(a) && store.hidden(b));
+        // Hidden by the group, but not by themselves
+        assert_eq!(store.stroke_visible(a), Some(true));
+        assert!(store.invisible_keys_unordered().is_empty());
+
+        let _ = store.set_stroke_visible(a, false);
+        let _ = store.set_group_hidden(group, false);
+        assert!(store.hidden(a));
+        assert!(!store.hidden(b));
+
+        let _ = store.set_stroke_visible(a, true);
+        let _ = store.set_layer_visible(0, false);
+        assert!(store.hidden(a) && store.hidden(b));
+        let _ = store.set_layer_visible(0, true);
+        assert!(!store.hidden(a) && !store.hidden(b));
+    }
+
+    #[test]
+    fn hidden_strokes_are_not_hit_or_rendered() {
+        let mut store = StrokeStore::default();
+        let (visible, hidden) = (
+            store.insert_stroke(brushstroke_at_origin(), None),
+            store.insert_stroke(brushstroke_at_origin(), None),
+        );
+        let _ = store.set_stroke_visible(hidden, false);
+
+        assert_eq!(
+            store.strokes_in_bounds(around_origin(), false),
+            vec![visible]
+        );
+        assert_eq!(store.stroke_keys_as_rendered(), vec![visible]);
+        assert_eq!(
+            store.stroke_keys_as_rendered_intersecting_bounds(around_origin()),
+            vec![visible]
+        );
+    }
+
+    #[test]
+    fn hidden_strokes_are_not_exported() {
+        let mut engine = Engine::default();
+        let visible = engine.store.insert_stroke(brushstroke_at_origin(), None);
+        let hidden = engine.store.insert_stroke(brushstroke_at_origin(), None);
+        let _ = engine.store.set_stroke_visible(hidden, false);
+
+        let content = engine.extract_document_content();
+        assert_eq!(content.strokes.len(), 1);
+        assert!(Arc::ptr_eq(
+            &content.strokes[0],
+            engine.store.stroke_components.get(visible).unwrap()
+        ));
+    }
+}
@@ -4,7 +4,7 @@ use super::{Content, Stroke};
 use crate::Drawable;
 use crate::Image;
 use crate::document::Format;
-use crate::engine::import::{PdfImportPageSpacing, PdfImportPrefs};
+use crate::engine::import::{PdfImportPageSpacing, PdfImportPrefs, PdfImportProgress};
 use anyhow::anyhow;
 use hayro::{hayro_interpret, hayro_syntax, vello_cpu};
 use kurbo::Shape;
@@ -134,6 +134,7 @@ impl BitmapImage {
         page_range: Option<Range<usize>>,
         format: &Format,
         password: Option<String>,
+        progress: impl Fn(PdfImportProgress),
     ) -> Result<Vec<Self>, anyhow::Error> {
         // TODO: how to avoid this allocation without lifetime issues?
         let data = Arc::new(to_be_read.to_vec());
@@ -147,6 +148,7 @@ impl BitmapImage {
         let interpreter_settings = hayro_interpret::InterpreterSettings::default();
         let pages = pdf.pages();
         let page_range = page_range.unwrap_or(0..pages.len());
+        let total = page_range.len();
         let page_width = if pdf_import_prefs.adjust_document {
             format.width()
         } else {
@@ -164,7 +166,8 @@ impl BitmapImage {
 
         // TODO: investigate if this can be parallelized with rayon's `par_iter()`
         let pngs = page_range
-            .map(|page_i| {
+            .enumerate()
+            .map(|(i, page_i)| {
                 let page = pages
                     .get(page_i)
                     .ok_or_else(|| anyhow::anyhow!("no page at index '{page_i}"))?;
@@ -201,6 +204,11 @@ impl BitmapImage {
                     };
                 }
 
+                progress(PdfImportProgress {
+                    imported: i + 1,
+                    total,
+                });
+
                 Ok((png_data, image_pos, image_size))
             })
             .collect::<anyhow::Result<Vec<(Vec<u8>, na::Vector2<f64>, na::Vector2<f64>)>>>()?;
@@ -3,27 +3,38 @@ use super::Content;
 use super::content::GeneratedContentImages;
 use crate::Drawable;
 use crate::Image;
+use crate::Svg;
 use crate::strokes::content;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rnote_compose::ext::AabbExt;
 use rnote_compose::penpath::{Element, Segment};
 use rnote_compose::shapes::Shapeable;
-use rnote_compose::style::Composer;
+use rnote_compose::style::{Composer, textured};
 use rnote_compose::transform::Transformable;
-use rnote_compose::{PenPath, Style};
+use rnote_compose::{Color, PenPath, Style};
 use serde::{Deserialize, Serialize};
 use tracing::error;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename = "brushstroke")]
 pub struct BrushStroke {
+    // the path and style are only accessible through methods, so that modifying them invalidates the cached geometry
     #[serde(rename = "path")]
-    pub path: PenPath,
+    path: PenPath,
     #[serde(default, rename = "style")]
-    pub style: Style,
+    style: Style,
+    /// The color the area enclosed by the closed path is filled with, drawn behind the stroke.
+    #[serde(default, rename = "fill_color")]
+    pub fill_color: Option<Color>,
     // since the path can have many hitboxes, we store them here and update them when the stroke geometry changes
     #[serde(skip)]
     hitboxes: Vec<Aabb>,
+    // the bounds are cached as well, grown when segments are appended and invalidated when the stroke is transformed
+    #[serde(skip)]
+    bounds: Option<Aabb>,
+    // the path length is cached the same way as the bounds
+    #[serde(skip)]
+    path_length: Option<f64>,
 }
 
 impl Content for BrushStroke {
@@ -58,14 +69,26 @@ impl Content for BrushStroke {
 
         let stroke_opacity_condition = self.style.stroke_color().is_some_and(|color| color.a < 1.0);
 
+        // The color of every segment depends on the length of the entire path
+        let color_gradient_condition = self.has_color_gradient();
+
+        // The fill spans the entire path
+        let fill_condition = self.fill_color.is_some();
+
         // if these conditions evaluate true the stroke is rendered as a single image
-        let images = if image_size_condition || stroke_width_condition || stroke_opacity_condition {
+        let images = if image_size_condition
+            || stroke_width_condition
+            || stroke_opacity_condition
+            || color_gradient_condition
+            || fill_condition
+        {
             // generate a single image when bounds are smaller than threshold
             match &self.style {
                 Style::Smooth(options) => {
                     let image = Image::gen_with_piet(
                         |piet_cx| {
-                            self.path.draw_composed(piet_cx, options);
+                            self.draw_fill(piet_cx);
+                            self.path.draw_composed(piet_cx, options.as_ref());
                             Ok(())
                         },
                         bounds,
@@ -87,6 +110,7 @@ impl Content for BrushStroke {
                 Style::Textured(options) => {
                     let image = Image::gen_with_piet(
                         |piet_cx| {
+                            self.draw_fill(piet_cx);
                             self.path.draw_composed(piet_cx, options);
                             Ok(())
                         },
@@ -110,13 +134,17 @@ impl Content for BrushStroke {
 
                     let mut prev = self.path.start;
                     for seg in self.path.segments.iter() {
+                        if let Segment::MoveTo { end } = seg {
+                            prev = *end;
+                            continue;
+                        }
                         let seg_path = PenPath::new_w_segments(prev, [*seg]);
                         let image = Image::gen_with_piet(
                             |piet_cx| {
-                                seg_path.draw_composed(piet_cx, options);
+                                seg_path.draw_composed(piet_cx, options.as_ref());
                                 Ok(())
                             },
-                            seg_path.composed_bounds(options),
+                            seg_path.composed_bounds(options.as_ref()),
                             image_scale,
                         );
                         match image {
@@ -141,6 +169,11 @@ impl Content for BrushStroke {
 
                     let mut prev = self.path.start;
                     for seg in self.path.segments.iter() {
+                        if let Segment::MoveTo { end } = seg {
+                            options.advance_seed();
+                            prev = *end;
+                            continue;
+                        }
                         let seg_path = PenPath::new_w_segments(prev, [*seg]);
                         let image = Image::gen_with_piet(
                             |piet_cx| {
@@ -202,6 +235,8 @@ impl Content for BrushStroke {
 
     fn update_geometry(&mut self) {
         self.hitboxes = self.gen_hitboxes_int();
+        self.bounds = Some(self.gen_bounds_int());
+        self.path_length = Some(self.path.length());
     }
 }
 
@@ -209,8 +244,9 @@ impl Drawable for BrushStroke {
     fn draw(&self, cx: &mut impl piet::RenderContext, _image_scale: f64) -> anyhow::Result<()> {
         cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
 
+        self.draw_fill(cx);
         match &self.style {
-            Style::Smooth(options) => self.path.draw_composed(cx, options),
+            Style::Smooth(options) => self.path.draw_composed(cx, options.as_ref()),
             Style::Rough(_) => {
                 // Rough style currently unsupported for pen paths
                 unimplemented!()
@@ -225,11 +261,7 @@ impl Drawable for BrushStroke {
 
 impl Shapeable for BrushStroke {
     fn bounds(&self) -> Aabb {
-        match &self.style {
-            Style::Smooth(options) => self.path.composed_bounds(options),
-            Style::Rough(_options) => unimplemented!(),
-            Style::Textured(options) => self.path.composed_bounds(options),
-        }
+        self.bounds.unwrap_or_else(|| self.gen_bounds_int())
     }
 
     fn hitboxes(&self) -> Vec<Aabb> {
@@ -244,20 +276,25 @@ impl Shapeable for BrushStroke {
 impl Transformable for BrushStroke {
     fn translate(&mut self, offset: na::Vector2<f64>) {
         self.path.translate(offset);
+        self.bounds = None;
     }
     fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
         self.path.rotate(angle, center);
+        self.bounds = None;
     }
     fn scale(&mut self, scale: na::Vector2<f64>) {
         self.path.scale(scale);
+        self.bounds = None;
+        self.path_length = None;
         // Using the geometric mean behaves the best when scaling non-uniformly.
-        let scale_scalar = (scale[0] * scale[1]).sqrt();
-        self.style
-            .set_stroke_width(self.style.stroke_width() * scale_scalar);
+        self.style.scale((scale[0] * scale[1]).sqrt());
     }
 }
 
 impl BrushStroke {
+    /// The minimum distance between the start and the end for the path to be considered closed.
+    pub const CLOSED_DISTANCE_MIN: f64 = 10.0;
+
     pub fn new(start: Element, style: Style) -> Self {
         let path = PenPath::new(start);
 
@@ -269,19 +306,75 @@ impl BrushStroke {
         let mut new_brushstroke = Self {
             path,
             style,
+            fill_color: None,
             hitboxes: vec![],
+            bounds: None,
+            path_length: None,
         };
         new_brushstroke.update_geometry();
 
         new_brushstroke
     }
 
+    pub fn path(&self) -> &PenPath {
+        &self.path
+    }
+
+    /// The path for modifying it, invalidating the cached bounds and path length.
+    ///
+    /// The hitboxes are only updated with [Content::update_geometry].
+    pub fn path_mut(&mut self) -> &mut PenPath {
+        self.bounds = None;
+        self.path_length = None;
+        &mut self.path
+    }
+
+    pub fn style(&self) -> &Style {
+        &self.style
+    }
+
+    /// The style for modifying it, invalidating the cached bounds.
+    ///
+    /// The hitboxes are only updated with [Content::update_geometry].
+    pub fn style_mut(&mut self) -> &mut Style {
+        self.bounds = None;
+        &mut self.style
+    }
+
     pub fn push_segment(&mut self, segment: Segment) {
-        self.path.segments.push(segment);
+        self.extend_w_segments([segment]);
     }
 
+    /// Append the segments, growing the cached geometry only by the appended segments.
     pub fn extend_w_segments(&mut self, segments: impl IntoIterator<Item = Segment>) {
+        let n_prev_segments = self.path.segments.len();
         self.path.extend(segments);
+        let n_segments = self.path.segments.len() - n_prev_segments;
+        if n_segments == 0 {
+            return;
+        }
+
+        let segments_bounds = self.last_segments_bounds(n_segments);
+        self.bounds = self.bounds.map(|bounds| bounds.merged(&segments_bounds));
+        let segments_length = self.last_segments_path(n_segments).length();
+        self.path_length = self.path_length.map(|length| length + segments_length);
+
+        let stroke_width = self.style.stroke_width();
+        let segments_hitboxes = self
+            .last_segments_path(n_segments)
+            .hitboxes()
+            .into_iter()
+            .map(|hb| hb.loosened(stroke_width * 0.5));
+        // A path without segments only has a hitbox for its start
+        if n_prev_segments == 0 {
+            self.hitboxes.clear();
+        }
+        self.hitboxes.extend(segments_hitboxes);
+    }
+
+    /// The arc length of the path in document coordinates.
+    pub fn path_length(&self) -> f64 {
+        self.path_length.unwrap_or_else(|| self.path.length())
     }
 
     /// Replace the current path with the given new one. the new path must not be empty.
@@ -290,6 +383,102 @@ impl BrushStroke {
         self.update_geometry();
     }
 
+    /// Whether the stroke is drawn with a color gradient along its path.
+    ///
+    /// The rendering then can't be generated only for the last segments.
+    pub fn has_color_gradient(&self) -> bool {
+        matches!(&self.style, Style::Smooth(options) if options.color_gradient.is_some())
+    }
+
+    /// Whether the path is roughly closed, having its end near its start. Paths with gaps are never closed.
+    pub fn is_closed(&self) -> bool {
+        let Some(end) = self.path.segments.last().map(|seg| seg.end()) else {
+            return false;
+        };
+
+        self.path.segments.len() >= 2
+            && !self
+                .path
+                .segments
+                .iter()
+                .any(|seg| matches!(seg, Segment::MoveTo { .. }))
+            && (end.pos - self.path.start.pos).magnitude()
+                <= (self.style.stroke_width() * 2.0).max(Self::CLOSED_DISTANCE_MIN)
+    }
+
+    /// Generate Svg with every dot of the textured style as an individual path element, so that the dots stay
+    /// editable in other applications.
+    ///
+    /// Other styles and textured styles with a tip texture are generated as usual.
+    pub fn gen_svg_w_separate_dots(&self) -> anyhow::Result<Svg> {
+        let Style::Textured(options) = &self.style else {
+            return self.gen_svg();
+        };
+        if options.tip_texture.is_some() {
+            return self.gen_svg();
+        }
+        let mut group = svg::node::element::Group::new();
+
+        if let Some(fill_color) = self.fill_color {
+            let mut fill_path = self.path.outline_path();
+            fill_path.close_path();
+            group = group.add(
+                svg::node::element::Path::new()
+                    .set("d", fill_path.to_svg())
+                    .set("fill", fill_color.to_css_color_attr())
+                    .set("fill-rule", "evenodd"),
+            );
+        }
+        if let Some(stroke_color) = options.stroke_color {
+            let fill = stroke_color.to_css_color_attr();
+            let mut dot = kurbo::BezPath::new();
+
+            for el in textured::compose_textured_pen_path(&self.path, options).iter() {
+                if matches!(el, kurbo::PathEl::MoveTo(_)) && !dot.elements().is_empty() {
+                    group = group.add(
+                        svg::node::element::Path::new()
+                            .set("d", std::mem::take(&mut dot).to_svg())
+                            .set("fill", fill.as_str()),
+                    );
+                }
+                dot.push(el);
+            }
+            if !dot.elements().is_empty() {
+                group = group.add(
+                    svg::node::element::Path::new()
+                        .set("d", dot.to_svg())
+                        .set("fill", fill.as_str()),
+                );
+            }
+        }
+
+        Ok(Svg {
+            svg_data: rnote_compose::utils::svg_node_to_string(&group)?,
+            bounds: self.bounds(),
+        })
+    }
+
+    /// Draws the fill of the enclosed area with the even-odd rule, so that self-intersecting paths are filled
+    /// alternately.
+    fn draw_fill(&self, cx: &mut impl piet::RenderContext) {
+        let Some(fill_color) = self.fill_color else {
+            return;
+        };
+        let mut fill_path = self.path.outline_path();
+        fill_path.close_path();
+
+        cx.fill_even_odd(fill_path, &Into::<piet::Color>::into(fill_color));
+    }
+
+    // internal method generating the current bounds.
+    fn gen_bounds_int(&self) -> Aabb {
+        match &self.style {
+            Style::Smooth(options) => self.path.composed_bounds(options.as_ref()),
+            Style::Rough(_options) => unimplemented!(),
+            Style::Textured(options) => self.path.composed_bounds(options),
+        }
+    }
+
     // internal method generating the current hitboxes.
     fn gen_hitboxes_int(&self) -> Vec<Aabb> {
         let stroke_width = self.style.stroke_width();
@@ -301,6 +490,36 @@ impl BrushStroke {
             .collect()
     }
 
+    /// The path of the last n segments, starting at the end of the segment before them.
+    fn last_segments_path(&self, n_last_segments: usize) -> PenPath {
+        let path_len = self.path.segments.len();
+
+        let start_el = self
+            .path
+            .segments
+            .get(path_len.saturating_sub(n_last_segments).saturating_sub(1))
+            .map(|s| s.end())
+            .unwrap_or(self.path.start);
+
+        PenPath::new_w_segments(
+            start_el,
+            self.path.segments[path_len.saturating_sub(n_last_segments)..]
+                .iter()
+                .copied(),
+        )
+    }
+
+    /// The bounds of the last n segments, including the style.
+    pub fn last_segments_bounds(&self, n_last_segments: usize) -> Aabb {
+        let range_path = self.last_segments_path(n_last_segments);
+
+        match &self.style {
+            Style::Smooth(options) => range_path.composed_bounds(options.as_ref()),
+            Style::Rough(_) => range_path.bounds(),
+            Style::Textured(options) => range_path.composed_bounds(options),
+        }
+    }
+
     pub fn gen_image_for_last_segments(
         &self,
         n_last_segments: usize,
@@ -308,28 +527,19 @@ impl BrushStroke {
     ) -> Result<Option<Image>, anyhow::Error> {
         let image = match &self.style {
             Style::Smooth(options) => {
-                let path_len = self.path.segments.len();
-
-                let start_el = self
-                    .path
-                    .segments
-                    .get(path_len.saturating_sub(n_last_segments).saturating_sub(1))
-                    .map(|s| s.end())
-                    .unwrap_or(self.path.start);
-
-                let range_path = PenPath::new_w_segments(
-                    start_el,
-                    self.path.segments[path_len.saturating_sub(n_last_segments)..]
-                        .iter()
-                        .copied(),
-                );
+                let mut options = options.clone();
+                // The tapering depends on the length of the entire path, so it is only applied
+                // when the rendering of the entire stroke is regenerated
+                options.taper_start = 0.0;
+                options.taper_end = 0.0;
+                let range_path = self.last_segments_path(n_last_segments);
 
                 let image = Image::gen_with_piet(
                     |piet_cx| {
-                        range_path.draw_composed(piet_cx, options);
+                        range_path.draw_composed(piet_cx, options.as_ref());
                         Ok(())
                     },
-                    range_path.composed_bounds(options),
+                    range_path.composed_bounds(options.as_ref()),
                     image_scale,
                 )?;
 
@@ -344,19 +554,7 @@ impl BrushStroke {
                     options.advance_seed();
                 });
 
-                let start_el = self
-                    .path
-                    .segments
-                    .get(path_len.saturating_sub(n_last_segments).saturating_sub(1))
-                    .map(|s| s.end())
-                    .unwrap_or(self.path.start);
-
-                let range_path = PenPath::new_w_segments(
-                    start_el,
-                    self.path.segments[path_len.saturating_sub(n_last_segments)..]
-                        .iter()
-                        .copied(),
-                );
+                let range_path = self.last_segments_path(n_last_segments);
 
                 let image = Image::gen_with_piet(
                     |piet_cx| {
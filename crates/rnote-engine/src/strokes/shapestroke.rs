@@ -20,6 +20,9 @@ pub struct ShapeStroke {
     #[serde(skip)]
     // since the shape can have many hitboxes, we store them and update them when the stroke geometry changes
     hitboxes: Vec<Aabb>,
+    // the perimeter is cached as well
+    #[serde(skip)]
+    perimeter: f64,
 }
 
 impl Content for ShapeStroke {
@@ -52,6 +55,8 @@ impl Content for ShapeStroke {
 
     fn update_geometry(&mut self) {
         self.hitboxes = self.gen_hitboxes_int();
+        self.perimeter =
+            kurbo::Shape::perimeter(&self.shape.outline_path(), Self::PERIMETER_ACCURACY);
     }
 }
 
@@ -69,7 +74,7 @@ impl Drawable for ShapeStroke {
 impl Shapeable for ShapeStroke {
     fn bounds(&self) -> Aabb {
         match &self.style {
-            Style::Smooth(options) => self.shape.composed_bounds(options),
+            Style::Smooth(options) => self.shape.composed_bounds(options.as_ref()),
             Style::Rough(options) => self.shape.composed_bounds(options),
             Style::Textured(_) => self.shape.bounds(),
         }
@@ -94,24 +99,31 @@ impl Transformable for ShapeStroke {
     fn scale(&mut self, scale: na::Vector2<f64>) {
         self.shape.scale(scale);
         // Using the geometric mean behaves the best when scaling non-uniformly.
-        let scale_scalar = (scale[0] * scale[1]).sqrt();
-        self.style
-            .set_stroke_width(self.style.stroke_width() * scale_scalar);
+        self.style.scale((scale[0] * scale[1]).sqrt());
     }
 }
 
 impl ShapeStroke {
+    /// The accuracy the perimeter is calculated with.
+    const PERIMETER_ACCURACY: f64 = 0.1;
+
     pub fn new(shape: Shape, style: Style) -> Self {
         let mut shapestroke = Self {
             shape,
             style,
             hitboxes: vec![],
+            perimeter: 0.0,
         };
         shapestroke.update_geometry();
 
         shapestroke
     }
 
+    /// The perimeter of the shape in document coordinates.
+    pub fn perimeter(&self) -> f64 {
+        self.perimeter
+    }
+
     fn gen_hitboxes_int(&self) -> Vec<Aabb> {
         let width = self.style.stroke_width();
 
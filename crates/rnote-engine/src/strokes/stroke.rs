@@ -9,13 +9,16 @@ use crate::Engine;
 use crate::Image;
 use crate::Svg;
 use crate::fileformats::xoppformat::{self, XoppColor};
+use crate::store::LodConfig;
 use crate::store::chrono_comp::StrokeLayer;
 use crate::strokes::textstroke::TextStyle;
 use crate::{Drawable, utils};
-use p2d::bounding_volume::Aabb;
-use rnote_compose::ext::AabbExt;
+use kurbo::Shape;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use rnote_compose::ext::{AabbExt, Vector2Ext};
 use rnote_compose::penpath::Element;
 use rnote_compose::shapes::{Rectangle, Shapeable};
+use rnote_compose::style::BlendMode;
 use rnote_compose::style::smooth::SmoothOptions;
 use rnote_compose::transform::Transform;
 use rnote_compose::transform::Transformable;
@@ -100,13 +103,39 @@ impl Drawable for Stroke {
     }
 
     fn draw_to_cairo(&self, cx: &cairo::Context, image_scale: f64) -> anyhow::Result<()> {
-        match self {
+        let set_blend_operator = |cx: &cairo::Context| match self.blend_mode() {
+            BlendMode::Normal => {}
+            BlendMode::Multiply => cx.set_operator(cairo::Operator::Multiply),
+            BlendMode::Screen => cx.set_operator(cairo::Operator::Screen),
+            BlendMode::Overlay => cx.set_operator(cairo::Operator::Overlay),
+            BlendMode::Darken => cx.set_operator(cairo::Operator::Darken),
+        };
+        let opacity = self.opacity();
+        // Translucent strokes are drawn to a group first, so that their overlapping parts don't accumulate the opacity
+        let grouped = opacity < 1.0;
+
+        cx.save()?;
+        if grouped {
+            cx.push_group();
+        } else {
+            set_blend_operator(cx);
+        }
+
+        let res = match self {
             Stroke::BrushStroke(brushstroke) => brushstroke.draw_to_cairo(cx, image_scale),
             Stroke::ShapeStroke(shapestroke) => shapestroke.draw_to_cairo(cx, image_scale),
             Stroke::TextStroke(textstroke) => textstroke.draw_to_cairo(cx, image_scale),
             Stroke::VectorImage(vectorimage) => vectorimage.draw_to_cairo(cx, image_scale),
             Stroke::BitmapImage(bitmapimage) => bitmapimage.draw_to_cairo(cx, image_scale),
+        };
+
+        if grouped {
+            cx.pop_group_to_source()?;
+            set_blend_operator(cx);
+            cx.paint_with_alpha(opacity)?;
         }
+        cx.restore()?;
+        res
     }
 }
 
@@ -207,6 +236,10 @@ impl Transformable for Stroke {
 impl Stroke {
     /// The default offset in surface coords when importing a stroke.
     pub const IMPORT_OFFSET_DEFAULT: na::Vector2<f64> = na::vector![32.0, 32.0];
+    /// The maximum stroke width in surface pixels up to which lines are snapped to the pixel grid.
+    pub const PIXEL_SNAP_MAX_WIDTH: f64 = 4.0;
+    /// The maximum deviation in surface pixels from a horizontal or vertical line for lines to be snapped.
+    pub const PIXEL_SNAP_TOLERANCE: f64 = 1.0;
 
     pub fn extract_default_layer(&self) -> StrokeLayer {
         match self {
@@ -217,24 +250,244 @@ impl Stroke {
         }
     }
 
+    /// The blend mode the stroke is composited with onto the content underneath.
+    pub fn blend_mode(&self) -> BlendMode {
+        match self {
+            Stroke::BrushStroke(brushstroke) => brushstroke.style().blend_mode(),
+            Stroke::ShapeStroke(shapestroke) => shapestroke.style.blend_mode(),
+            Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => {
+                BlendMode::Normal
+            }
+        }
+    }
+
+    /// The color the outline or the text of the stroke is drawn with. None for images and strokes without outline.
+    pub fn stroke_color(&self) -> Option<Color> {
+        match self {
+            Stroke::BrushStroke(brushstroke) => brushstroke.style().stroke_color(),
+            Stroke::ShapeStroke(shapestroke) => shapestroke.style.stroke_color(),
+            Stroke::TextStroke(textstroke) => Some(textstroke.text_style.color),
+            Stroke::VectorImage(_) | Stroke::BitmapImage(_) => None,
+        }
+    }
+
+    /// The opacity the whole stroke is composited with onto the content underneath.
+    pub fn opacity(&self) -> f64 {
+        match self {
+            Stroke::BrushStroke(brushstroke) => brushstroke.style().opacity(),
+            Stroke::ShapeStroke(shapestroke) => shapestroke.style.opacity(),
+            Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => 1.0,
+        }
+    }
+
+    /// A copy of the stroke snapped to the pixel grid at the image scale, for crisper rendering of thin lines.
+    ///
+    /// Only thin horizontal and vertical lines with the smooth style are snapped, being brush strokes, lines and
+    /// polylines. Returns None for all other strokes, which stay anti-aliased.
+    pub fn pixel_snapped(&self, image_scale: f64) -> Option<Self> {
+        let is_thin = |style: &Style| match style {
+            Style::Smooth(options) => {
+                options.stroke_width * image_scale <= Self::PIXEL_SNAP_MAX_WIDTH
+            }
+            Style::Rough(_) | Style::Textured(_) => false,
+        };
+        let tolerance = Self::PIXEL_SNAP_TOLERANCE / image_scale;
+
+        match self {
+            Stroke::BrushStroke(brushstroke) if is_thin(brushstroke.style()) => {
+                let mut snapped = brushstroke.clone();
+                if !snapped.path_mut().pixel_snap_axis_aligned(
+                    brushstroke.style().stroke_width(),
+                    image_scale,
+                    tolerance,
+                ) {
+                    return None;
+                }
+                snapped.update_geometry();
+                Some(Stroke::BrushStroke(snapped))
+            }
+            Stroke::ShapeStroke(shapestroke) if is_thin(&shapestroke.style) => {
+                let width = shapestroke.style.stroke_width();
+                let mut snapped = shapestroke.clone();
+                let points = match &mut snapped.shape {
+                    rnote_compose::shapes::Shape::Line(line) => {
+                        vec![&mut line.start, &mut line.end]
+                    }
+                    rnote_compose::shapes::Shape::Polyline(polyline) => {
+                        std::iter::once(&mut polyline.start)
+                            .chain(polyline.path.iter_mut())
+                            .collect()
+                    }
+                    _ => return None,
+                };
+                let (axis, coord) = rnote_compose::utils::pixel_snap_axis_aligned(
+                    points.iter().map(|point| **point),
+                    width,
+                    image_scale,
+                    tolerance,
+                )?;
+                for point in points {
+                    point[axis] = coord;
+                }
+                snapped.update_geometry();
+                Some(Stroke::ShapeStroke(snapped))
+            }
+            _ => None,
+        }
+    }
+
+    /// A copy of the stroke with reduced detail for rendering at the image scale, when zoomed out.
+    ///
+    /// Brush stroke paths are simplified and textured brush strokes are turned into solid lines, depending on
+    /// the config. Returns None for all other strokes, which are rendered in full detail.
+    pub fn lod_simplified(&self, image_scale: f64, lod_config: &LodConfig) -> Option<Self> {
+        let Stroke::BrushStroke(brushstroke) = self else {
+            return None;
+        };
+        let mut simplified = brushstroke.clone();
+        if lod_config.textured_as_solid
+            && let Style::Textured(options) = brushstroke.style()
+        {
+            let mut style = Style::Smooth(Box::new(SmoothOptions {
+                stroke_color: options.stroke_color,
+                pressure_curve: options.pressure_curve.clone(),
+                blend_mode: options.blend_mode,
+                opacity: options.opacity,
+                ..Default::default()
+            }));
+            style.set_stroke_width(options.stroke_width);
+            *simplified.style_mut() = style;
+        }
+        simplified
+            .path_mut()
+            .simplify(lod_config.simplify_tolerance / image_scale);
+        simplified.update_geometry();
+        Some(Stroke::BrushStroke(simplified))
+    }
+
+    /// The length of the drawn ink in document coordinates.
+    ///
+    /// The arc length of the path for brush strokes and the perimeter for shapes. Text and images have no length.
+    pub fn path_length(&self) -> f64 {
+        match self {
+            Stroke::BrushStroke(brushstroke) => brushstroke.path_length(),
+            Stroke::ShapeStroke(shapestroke) => shapestroke.perimeter(),
+            Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => 0.0,
+        }
+    }
+
+    /// Whether the stroke is hit by a circle with the given radius around the position of the element.
+    ///
+    /// Tests against the actual geometry instead of the hitboxes: brush strokes against their path widened by
+    /// the stroke width, shapes against their primitive, filled areas additionally against their inside.
+    /// Text and images are tested against their hitboxes.
+    pub fn hit_test(&self, point: &Element, radius: f64) -> bool {
+        let pos = point.pos.to_kurbo_point();
+        match self {
+            Stroke::BrushStroke(brushstroke) => {
+                if brushstroke.fill_color.is_some() {
+                    let mut fill_path = brushstroke.path().outline_path();
+                    fill_path.close_path();
+                    // Filled with the even-odd rule
+                    if fill_path.winding(pos) % 2 != 0 {
+                        return true;
+                    }
+                }
+                if brushstroke.path().segments.is_empty() {
+                    return (brushstroke.path().start.pos - point.pos).magnitude()
+                        <= radius + brushstroke.style().bounds_margin() * 0.5;
+                }
+                distance_to_path(&brushstroke.path().outline_path(), pos)
+                    <= radius + brushstroke.style().bounds_margin() * 0.5
+            }
+            Stroke::ShapeStroke(shapestroke) => {
+                let outline = shapestroke.shape.outline_path();
+                (shapestroke.style.fill_color().is_some() && outline.winding(pos) != 0)
+                    || distance_to_path(&outline, pos)
+                        <= radius + shapestroke.style.bounds_margin() * 0.5
+            }
+            Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => {
+                self.hitboxes().into_iter().any(|hitbox| {
+                    hitbox
+                        .loosened(radius)
+                        .contains_local_point(&point.pos.into())
+                })
+            }
+        }
+    }
+
+    /// The stroke geometry as SVG path data in document coordinates, without any styling.
+    ///
+    /// Brush strokes emit their pen path with quadratic segments elevated to cubic ones, single element brush strokes
+    /// a tiny circle. Shapes emit their primitives and text and images their outline.
+    pub fn to_svg_path_data(&self) -> String {
+        let bez_path = match self {
+            Stroke::BrushStroke(brushstroke) if brushstroke.path().segments.is_empty() => {
+                kurbo::Circle::new(
+                    brushstroke.path().start.pos.to_kurbo_point(),
+                    brushstroke.style().stroke_width() * 0.5,
+                )
+                .to_path(0.1)
+            }
+            Stroke::BrushStroke(brushstroke) => {
+                let mut bez_path = kurbo::BezPath::new();
+                // The current point and the start of the current subpath, which becomes the current point when closing
+                let mut prev = kurbo::Point::ZERO;
+                let mut subpath_start = kurbo::Point::ZERO;
+                for el in brushstroke.outline_path().elements() {
+                    match *el {
+                        kurbo::PathEl::MoveTo(start) => {
+                            bez_path.move_to(start);
+                            subpath_start = start;
+                            prev = start;
+                        }
+                        kurbo::PathEl::QuadTo(cp, end) => {
+                            let cubbez = kurbo::QuadBez::new(prev, cp, end).raise();
+                            bez_path.curve_to(cubbez.p1, cubbez.p2, cubbez.p3);
+                            prev = end;
+                        }
+                        kurbo::PathEl::ClosePath => {
+                            bez_path.close_path();
+                            prev = subpath_start;
+                        }
+                        el => {
+                            bez_path.push(el);
+                            if let Some(end) = el.end_point() {
+                                prev = end;
+                            }
+                        }
+                    }
+                }
+                bez_path
+            }
+            _ => self.outline_path(),
+        };
+
+        bez_path.to_svg()
+    }
+
     /// Invert the brightness of all colors of the stroke.
     ///
     /// Returns true if the stroke was modified and needs to update its rendering.
     pub fn set_to_inverted_brightness_color(&mut self) -> bool {
         match self {
             Stroke::BrushStroke(brush_stroke) => {
-                if let Some(color) = brush_stroke.style.stroke_color() {
+                if let Some(color) = brush_stroke.style().stroke_color() {
                     brush_stroke
-                        .style
+                        .style_mut()
                         .set_stroke_color(color.to_inverted_brightness_color());
                 }
 
-                if let Some(color) = brush_stroke.style.fill_color() {
+                if let Some(color) = brush_stroke.style().fill_color() {
                     brush_stroke
-                        .style
+                        .style_mut()
                         .set_fill_color(color.to_inverted_brightness_color());
                 }
 
+                brush_stroke.fill_color = brush_stroke
+                    .fill_color
+                    .map(|color| color.to_inverted_brightness_color());
+
                 true
             }
             Stroke::ShapeStroke(shape_stroke) => {
@@ -269,16 +522,22 @@ impl Stroke {
     pub fn set_to_darkest_color(&mut self) -> bool {
         match self {
             Stroke::BrushStroke(brush_stroke) => {
-                if let Some(color) = brush_stroke.style.stroke_color() {
+                if let Some(color) = brush_stroke.style().stroke_color() {
                     brush_stroke
-                        .style
+                        .style_mut()
                         .set_stroke_color(color.to_darkest_color());
                 }
 
-                if let Some(color) = brush_stroke.style.fill_color() {
-                    brush_stroke.style.set_fill_color(color.to_darkest_color());
+                if let Some(color) = brush_stroke.style().fill_color() {
+                    brush_stroke
+                        .style_mut()
+                        .set_fill_color(color.to_darkest_color());
                 }
 
+                brush_stroke.fill_color = brush_stroke
+                    .fill_color
+                    .map(|color| color.to_darkest_color());
+
                 true
             }
             Stroke::ShapeStroke(shape_stroke) => {
@@ -380,7 +639,8 @@ impl Stroke {
         )
         .ok_or_else(|| anyhow::anyhow!("Could not generate pen path from coordinates vector"))?;
 
-        let brushstroke = BrushStroke::from_penpath(penpath, Style::Smooth(smooth_options));
+        let brushstroke =
+            BrushStroke::from_penpath(penpath, Style::Smooth(Box::new(smooth_options)));
 
         Ok((Stroke::BrushStroke(brushstroke), layer))
     }
@@ -456,7 +716,7 @@ impl Stroke {
     pub fn into_xopp(self, current_dpi: f64) -> Option<xoppformat::XoppStrokeType> {
         match self {
             Stroke::BrushStroke(brushstroke) => {
-                let (stroke_width, color): (f64, XoppColor) = match &brushstroke.style {
+                let (stroke_width, color): (f64, XoppColor) = match brushstroke.style() {
                     // Return early if color is None
                     Style::Smooth(options) => (
                         options.stroke_width,
@@ -473,8 +733,8 @@ impl Stroke {
                 };
 
                 let tool = xoppformat::XoppTool::Pen;
-                let elements_vec = brushstroke.path.into_elements();
-                let stroke_style = &brushstroke.style;
+                let elements_vec = brushstroke.path().clone().into_elements();
+                let stroke_style = brushstroke.style();
                 let stroke_width =
                     utils::convert_value_dpi(stroke_width, current_dpi, xoppformat::XoppFile::DPI);
 
@@ -485,9 +745,9 @@ impl Stroke {
                 let mut pressures: Vec<f64> = elements_vec
                     .iter()
                     .map(|element| match &stroke_style {
-                        Style::Smooth(options) => {
-                            options.pressure_curve.apply(stroke_width, element.pressure)
-                        }
+                        Style::Smooth(options) => options
+                            .pressure_curve
+                            .apply(stroke_width, options.calibrated_pressure(element.pressure)),
                         Style::Rough(_) | Style::Textured(_) => stroke_width * element.pressure,
                     })
                     .collect();
@@ -714,3 +974,106 @@ impl Stroke {
         }
     }
 }
+
+/// The minimum distance of the point to the segments of the path.
+fn distance_to_path(path: &kurbo::BezPath, point: kurbo::Point) -> f64 {
+    use kurbo::ParamCurveNearest;
+
+    path.segments()
+        .map(|seg| seg.nearest(point, 0.01).distance_sq)
+        .fold(f64::INFINITY, f64::min)
+        .sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rnote_compose::penpath::Segment;
+
+    #[test]
+    fn hit_test_thin_diagonal_line() {
+        let mut style = Style::Smooth(Box::default());
+        style.set_stroke_width(2.0);
+        let path = PenPath::try_from_elements(
+            (0..=10).map(|i| Element::new(na::vector![i as f64 * 10.0, i as f64 * 10.0], 1.0)),
+        )
+        .unwrap();
+        let stroke = Stroke::BrushStroke(BrushStroke::from_penpath(path, style));
+
+        // Inside the bounds, but far away from the line
+        let corner = Element::new(na::vector![90.0, 10.0], 1.0);
+        assert!(stroke.bounds().contains_local_point(&corner.pos.into()));
+        assert!(!stroke.hit_test(&corner, 2.0));
+
+        assert!(stroke.hit_test(&Element::new(na::vector![50.5, 50.0], 1.0), 0.0));
+        assert!(stroke.hit_test(&Element::new(na::vector![54.0, 50.0], 1.0), 3.0));
+        assert!(!stroke.hit_test(&Element::new(na::vector![54.0, 50.0], 1.0), 0.0));
+    }
+
+    #[test]
+    fn svg_path_data_of_strokes() {
+        let parse = |stroke: &Stroke| kurbo::BezPath::from_svg(&stroke.to_svg_path_data()).unwrap();
+        let approx_eq = |a: kurbo::Point, b: kurbo::Point| (a - b).hypot() < 1e-3;
+
+        // The quadratic segment after the gap is elevated starting from the end of the move-to
+        let path = PenPath::new_w_segments(
+            Element::new(na::vector![0.0, 0.0], 1.0),
+            [
+                Segment::LineTo {
+                    end: Element::new(na::vector![10.0, 0.0], 1.0),
+                },
+                Segment::MoveTo {
+                    end: Element::new(na::vector![20.0, 0.0], 1.0),
+                },
+                Segment::QuadBezTo {
+                    cp: na::vector![25.0, 15.0],
+                    end: Element::new(na::vector![30.0, 0.0], 1.0),
+                },
+            ],
+        );
+        let brushstroke = Stroke::BrushStroke(BrushStroke::from_penpath(
+            path,
+            Style::Smooth(Box::default()),
+        ));
+        let data = brushstroke.to_svg_path_data();
+        assert!(!data.contains('Q'));
+        let elements = parse(&brushstroke).elements().to_vec();
+        assert_eq!(elements.len(), 4);
+        let kurbo::PathEl::MoveTo(gap_end) = elements[2] else {
+            panic!("expected a move-to after the line, got {:?}", elements[2]);
+        };
+        assert!(approx_eq(gap_end, kurbo::Point::new(20.0, 0.0)));
+        let kurbo::PathEl::CurveTo(cp1, cp2, end) = elements[3] else {
+            panic!("expected an elevated curve, got {:?}", elements[3]);
+        };
+        assert!(approx_eq(cp1, kurbo::Point::new(20.0 + 10.0 / 3.0, 10.0)));
+        assert!(approx_eq(cp2, kurbo::Point::new(30.0 - 10.0 / 3.0, 10.0)));
+        assert!(approx_eq(end, kurbo::Point::new(30.0, 0.0)));
+
+        // A single element is a dot with the stroke width as diameter
+        let mut style = Style::Smooth(Box::default());
+        style.set_stroke_width(4.0);
+        let dot = Stroke::BrushStroke(BrushStroke::new(
+            Element::new(na::vector![5.0, 5.0], 1.0),
+            style,
+        ));
+        let dot_bounds = parse(&dot).bounding_box();
+        assert!(approx_eq(dot_bounds.center(), kurbo::Point::new(5.0, 5.0)));
+        assert!((dot_bounds.width() - 4.0).abs() < 1e-3);
+
+        // Shapes emit their outline
+        let rectangle = Stroke::ShapeStroke(ShapeStroke::new(
+            rnote_compose::shapes::Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                na::point![0.0, 0.0],
+                na::point![10.0, 20.0],
+            ))),
+            Style::Smooth(Box::default()),
+        ));
+        let rectangle_bounds = parse(&rectangle).bounding_box();
+        assert!(approx_eq(rectangle_bounds.origin(), kurbo::Point::ZERO));
+        assert!(approx_eq(
+            rectangle_bounds.origin() + rectangle_bounds.size().to_vec2(),
+            kurbo::Point::new(10.0, 20.0)
+        ));
+    }
+}
@@ -1,6 +1,9 @@
 // Imports
-use super::Content;
-use crate::{Camera, Drawable};
+use super::{Content, Stroke};
+use crate::document::Format;
+use crate::engine::import::{PdfImportPageSpacing, PdfImportPrefs};
+use crate::{Camera, Drawable, Svg};
+use hayro::{hayro_interpret, hayro_syntax};
 use itertools::Itertools;
 use kurbo::Shape;
 use p2d::bounding_volume::Aabb;
@@ -11,6 +14,8 @@ use rnote_compose::transform::Transformable;
 use rnote_compose::{Color, Transform, color};
 use serde::{Deserialize, Serialize};
 use std::ops::Range;
+use std::sync::Arc;
+use svg::Node;
 use tracing::error;
 use unicode_segmentation::{GraphemeCursor, UnicodeSegmentation};
 
@@ -38,6 +43,16 @@ impl From<piet::FontStyle> for FontStyle {
     }
 }
 
+impl FontStyle {
+    /// The value of the Svg `font-style` attribute.
+    pub fn to_svg_attr(self) -> &'static str {
+        match self {
+            FontStyle::Regular => "normal",
+            FontStyle::Italic => "italic",
+        }
+    }
+}
+
 impl From<FontStyle> for piet::FontStyle {
     fn from(font_style: FontStyle) -> Self {
         match font_style {
@@ -518,6 +533,112 @@ impl Shapeable for TextStroke {
 }
 
 impl Content for TextStroke {
+    /// Generate a Svg `<text>` element, so that the text stays selectable and editable in other applications.
+    ///
+    /// Each laid out line is a `<tspan>`, which is split further where the ranged text attributes change.
+    fn gen_svg(&self) -> Result<Svg, anyhow::Error> {
+        use svg::node::element;
+
+        let text_layout = self
+            .text_style
+            .build_text_layout(&mut piet_cairo::CairoText::new(), self.text.clone())?;
+        let layout_width = self
+            .text_style
+            .max_width()
+            .unwrap_or(text_layout.size().width);
+        let (text_anchor, x) = match self.text_style.alignment {
+            TextAlignment::Start | TextAlignment::Fill => ("start", 0.0),
+            TextAlignment::Center => ("middle", layout_width * 0.5),
+            TextAlignment::End => ("end", layout_width),
+        };
+
+        let mut text_element = element::Text::new("")
+            .set("transform", self.transform.to_svg_transform_attr_str())
+            .set("xml:space", "preserve")
+            .set("text-anchor", text_anchor)
+            .set("font-family", self.text_style.font_family.as_str())
+            .set("font-size", self.text_style.font_size)
+            .set("font-weight", u32::from(self.text_style.font_weight))
+            .set("font-style", self.text_style.font_style.to_svg_attr())
+            .set("fill", self.text_style.color.to_css_color_attr());
+
+        for line in 0..text_layout.line_count() {
+            let Some(line_metric) = text_layout.line_metric(line) else {
+                continue;
+            };
+            let line_range = line_metric.start_offset
+                ..self.text[..line_metric.end_offset]
+                    .trim_end_matches(['\n', '\r'])
+                    .len()
+                    .max(line_metric.start_offset);
+            if line_range.is_empty() {
+                continue;
+            }
+
+            // The line is split at the boundaries of the ranged attributes
+            let boundaries = self
+                .text_style
+                .ranged_text_attributes
+                .iter()
+                .flat_map(|ranged_attr| [ranged_attr.range.start, ranged_attr.range.end])
+                .filter(|offset| line_range.contains(offset))
+                .chain([line_range.start, line_range.end])
+                .sorted_unstable()
+                .dedup()
+                .collect::<Vec<usize>>();
+
+            let mut line_element = element::TSpan::new("")
+                .set("x", x)
+                .set("y", line_metric.y_offset + line_metric.baseline);
+            for (&start, &end) in boundaries.iter().tuple_windows() {
+                let Some(piece) = self.text.get(start..end) else {
+                    continue;
+                };
+                let mut piece_element = element::TSpan::new(piece);
+                let mut text_decorations = vec![];
+                for ranged_attr in
+                    self.text_style
+                        .ranged_text_attributes
+                        .iter()
+                        .filter(|ranged_attr| {
+                            ranged_attr.range.start <= start && ranged_attr.range.end >= end
+                        })
+                {
+                    match &ranged_attr.attribute {
+                        TextAttribute::FontFamily(font_family) => {
+                            piece_element.assign("font-family", font_family.as_str());
+                        }
+                        TextAttribute::FontSize(font_size) => {
+                            piece_element.assign("font-size", *font_size);
+                        }
+                        TextAttribute::FontWeight(font_weight) => {
+                            piece_element.assign("font-weight", u32::from(*font_weight));
+                        }
+                        TextAttribute::TextColor(color) => {
+                            piece_element.assign("fill", color.to_css_color_attr());
+                        }
+                        TextAttribute::Style(font_style) => {
+                            piece_element.assign("font-style", font_style.to_svg_attr());
+                        }
+                        TextAttribute::Underline(true) => text_decorations.push("underline"),
+                        TextAttribute::Strikethrough(true) => text_decorations.push("line-through"),
+                        TextAttribute::Underline(false) | TextAttribute::Strikethrough(false) => {}
+                    }
+                }
+                if !text_decorations.is_empty() {
+                    piece_element.assign("text-decoration", text_decorations.join(" "));
+                }
+                line_element = line_element.add(piece_element);
+            }
+            text_element = text_element.add(line_element);
+        }
+
+        Ok(Svg {
+            svg_data: rnote_compose::utils::svg_node_to_string(&text_element)?,
+            bounds: self.bounds(),
+        })
+    }
+
     fn update_geometry(&mut self) {}
 }
 
@@ -547,6 +668,110 @@ impl TextStroke {
         }
     }
 
+    /// Extract the text of the Pdf pages into invisible text strokes positioned over the page images.
+    ///
+    /// The pages are laid out the same way as the page images from [`BitmapImage::from_pdf_bytes()`],
+    /// and the text strokes are stretched to the width of the text in the Pdf, so that they cover the rendered text.
+    ///
+    /// [`BitmapImage::from_pdf_bytes()`]: crate::strokes::BitmapImage::from_pdf_bytes
+    pub fn pdf_text_layer_from_pdf_bytes(
+        to_be_read: &[u8],
+        pdf_import_prefs: PdfImportPrefs,
+        insert_pos: na::Vector2<f64>,
+        page_range: Option<Range<usize>>,
+        format: &Format,
+        password: Option<String>,
+    ) -> anyhow::Result<Vec<Self>> {
+        let data = Arc::new(to_be_read.to_vec());
+        let pdf = if let Some(password) = password {
+            hayro_syntax::Pdf::new_with_password(data, &password)
+                .map_err(|err| anyhow::anyhow!("Creating Pdf instance failed, Err: {err:?}"))?
+        } else {
+            hayro_syntax::Pdf::new(data)
+                .map_err(|err| anyhow::anyhow!("Creating Pdf instance failed, Err: {err:?}"))?
+        };
+        let interpreter_settings = hayro_interpret::InterpreterSettings::default();
+        let pages = pdf.pages();
+        let page_range = page_range.unwrap_or(0..pages.len());
+        let page_width = if pdf_import_prefs.adjust_document {
+            format.width()
+        } else {
+            format.width() * (pdf_import_prefs.page_width_perc / 100.0)
+        };
+
+        // calculate the page zoom based on the width of the first page.
+        let page_zoom = if let Some(first_page) = pages.first() {
+            page_width / first_page.render_dimensions().0 as f64
+        } else {
+            return Ok(vec![]);
+        };
+        let x = insert_pos[0];
+        let mut y = insert_pos[1];
+        let mut piet_text = piet_cairo::CairoText::new();
+
+        let mut textstrokes = vec![];
+        for page_i in page_range {
+            let page = pages
+                .get(page_i)
+                .ok_or_else(|| anyhow::anyhow!("no page at index '{page_i}"))?;
+            let (intrinsic_width, intrinsic_height) = {
+                let dimensions = page.render_dimensions();
+                (dimensions.0 as f64, dimensions.1 as f64)
+            };
+            let page_pos = na::vector![x, y];
+
+            let mut device = PdfTextRunsDevice::default();
+            let mut context = hayro_interpret::Context::new(
+                page.initial_transform(true),
+                kurbo::Rect::new(0.0, 0.0, intrinsic_width, intrinsic_height),
+                page.xref(),
+                interpreter_settings.clone(),
+            );
+            hayro_interpret::interpret_page(page, &mut context, &mut device);
+
+            textstrokes.extend(device.runs.into_iter().filter_map(|run| {
+                let text = run.text.trim_end().to_string();
+                if text.is_empty() {
+                    return None;
+                }
+                let font_size = run.font_size * page_zoom;
+                let upper_left_pos = page_pos
+                    + (run.baseline_start - na::vector![0.0, run.font_size * PDF_TEXT_ASCENT])
+                        * page_zoom;
+                let text_style = TextStyle {
+                    font_size,
+                    color: Color::TRANSPARENT,
+                    ..Default::default()
+                };
+                let natural_width = text_style
+                    .untransformed_size(&mut piet_text, text.clone())?
+                    .x;
+                let mut textstroke = Self::new(text, upper_left_pos, text_style);
+                if natural_width > 0.0 {
+                    let stretch = ((run.width * page_zoom) / natural_width).clamp(0.2, 5.0);
+                    textstroke.translate(-upper_left_pos);
+                    textstroke.scale(na::vector![stretch, 1.0]);
+                    textstroke.translate(upper_left_pos);
+                }
+                Some(textstroke)
+            }));
+
+            let height = intrinsic_height * page_zoom;
+            if pdf_import_prefs.adjust_document {
+                y += height
+            } else {
+                y += match pdf_import_prefs.page_spacing {
+                    PdfImportPageSpacing::Continuous => {
+                        height + Stroke::IMPORT_OFFSET_DEFAULT[1] * 0.5
+                    }
+                    PdfImportPageSpacing::OnePerDocumentPage => format.height(),
+                };
+            }
+        }
+
+        Ok(textstrokes)
+    }
+
     pub fn get_text_slice_for_range(&self, range: Range<usize>) -> &str {
         &self.text[range]
     }
@@ -1057,3 +1282,103 @@ fn remove_intersecting_attrs_in_range(
         .filter(|attr| !attr.range.is_empty())
         .collect::<Vec<RangedTextAttribute>>()
 }
+
+/// The approximate ascent of Pdf text, relative to the font size.
+const PDF_TEXT_ASCENT: f64 = 0.8;
+
+/// A run of text on a single line of a Pdf page, in page coordinates.
+#[derive(Debug, Clone)]
+struct PdfTextRun {
+    text: String,
+    baseline_start: na::Vector2<f64>,
+    /// The x coordinate of the origin of the last glyph.
+    last_glyph_x: f64,
+    width: f64,
+    font_size: f64,
+}
+
+/// Collects the glyphs of an interpreted Pdf page into text runs, ignoring everything else that is drawn.
+#[derive(Debug, Default)]
+struct PdfTextRunsDevice {
+    runs: Vec<PdfTextRun>,
+}
+
+impl PdfTextRunsDevice {
+    /// The advance of a glyph without a known width, relative to the font size.
+    const GLYPH_ADVANCE_ESTIMATE: f64 = 0.5;
+
+    fn push_glyph(&mut self, c: char, origin: na::Vector2<f64>, font_size: f64) {
+        if let Some(run) = self.runs.last_mut() {
+            let dx = origin[0] - run.last_glyph_x;
+            let same_line = (origin[1] - run.baseline_start[1]).abs() < run.font_size * 0.3;
+            if same_line && dx > -run.font_size * 0.1 && dx < run.font_size * 3.0 {
+                // A larger gap than the advance of a glyph is a space that is not drawn
+                if dx > run.font_size * 0.8 && !run.text.ends_with(' ') {
+                    run.text.push(' ');
+                }
+                run.text.push(c);
+                run.last_glyph_x = origin[0];
+                run.width =
+                    origin[0] - run.baseline_start[0] + font_size * Self::GLYPH_ADVANCE_ESTIMATE;
+                return;
+            }
+        }
+        self.runs.push(PdfTextRun {
+            text: c.to_string(),
+            baseline_start: origin,
+            last_glyph_x: origin[0],
+            width: font_size * Self::GLYPH_ADVANCE_ESTIMATE,
+            font_size,
+        });
+    }
+}
+
+impl<'a> hayro_interpret::Device<'a> for PdfTextRunsDevice {
+    fn set_soft_mask(&mut self, _mask: Option<hayro_interpret::SoftMask<'a>>) {}
+
+    fn draw_path(
+        &mut self,
+        _path: &kurbo::BezPath,
+        _transform: kurbo::Affine,
+        _paint: &hayro_interpret::Paint<'a>,
+        _draw_mode: &hayro_interpret::PathDrawMode,
+    ) {
+    }
+
+    fn push_clip_path(&mut self, _clip_path: &hayro_interpret::ClipPath) {}
+
+    fn push_transparency_group(
+        &mut self,
+        _opacity: f32,
+        _mask: Option<hayro_interpret::SoftMask<'a>>,
+        _blend_mode: hayro_interpret::BlendMode,
+    ) {
+    }
+
+    fn draw_glyph(
+        &mut self,
+        glyph: &hayro_interpret::font::Glyph<'a>,
+        transform: kurbo::Affine,
+        glyph_transform: kurbo::Affine,
+        _paint: &hayro_interpret::Paint<'a>,
+        _draw_mode: &hayro_interpret::GlyphDrawMode,
+    ) {
+        let Some(c) = glyph.as_unicode().filter(|c| !c.is_control()) else {
+            return;
+        };
+        let affine = transform * glyph_transform;
+        let origin = affine * kurbo::Point::ZERO;
+        // The glyph space is normalized to the em square, so the transformed unit height is the font size
+        let font_size = (affine * kurbo::Point::new(0.0, 1.0) - origin).hypot();
+        if font_size <= 0.0 {
+            return;
+        }
+        self.push_glyph(c, na::vector![origin.x, origin.y], font_size);
+    }
+
+    fn draw_image(&mut self, _image: hayro_interpret::Image<'a, '_>, _transform: kurbo::Affine) {}
+
+    fn pop_clip_path(&mut self) {}
+
+    fn pop_transparency_group(&mut self) {}
+}
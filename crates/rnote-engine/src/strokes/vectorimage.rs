@@ -4,7 +4,7 @@ use super::resize::{ImageSizeOption, calculate_resize_ratio};
 use super::{Content, Stroke};
 use crate::Image;
 use crate::document::Format;
-use crate::engine::import::{PdfImportPageSpacing, PdfImportPrefs};
+use crate::engine::import::{PdfImportPageSpacing, PdfImportPrefs, PdfImportProgress};
 use crate::svg::USVG_FONTDB;
 use crate::{Drawable, Svg};
 use anyhow::anyhow;
@@ -211,6 +211,7 @@ impl VectorImage {
         page_range: Option<Range<usize>>,
         format: &Format,
         password: Option<String>,
+        progress: impl Fn(PdfImportProgress),
     ) -> Result<Vec<Self>, anyhow::Error> {
         // TODO: how to avoid this allocation without lifetime issues?
         let data = Arc::new(to_be_read.to_vec());
@@ -227,6 +228,7 @@ impl VectorImage {
         };
         let pages = pdf.pages();
         let page_range = page_range.unwrap_or(0..pages.len());
+        let total = page_range.len();
         let page_width = if pdf_import_prefs.adjust_document {
             format.width()
         } else {
@@ -244,7 +246,8 @@ impl VectorImage {
 
         // TODO: investigate if this can be parallelized with rayon's `par_iter()`
         let svgs = page_range
-            .filter_map(|page_i| {
+            .enumerate()
+            .filter_map(|(i, page_i)| {
                 let page = pages.get(page_i)?;
                 let (intrinsic_width, intrinsic_height) = {
                     let dimensions = page.render_dimensions();
@@ -266,6 +269,10 @@ impl VectorImage {
                 }
                 let svg_data = hayro_svg::convert(page, &interpreter_settings, &render_settings);
                 let svg = Svg { svg_data, bounds };
+                progress(PdfImportProgress {
+                    imported: i + 1,
+                    total,
+                });
 
                 Some(svg)
             })
@@ -1,15 +1,38 @@
+// Imports
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use rnote_compose::Color;
+
+/// Warnings about actions that could not be performed, to be shown to the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserWarning {
+    /// The region to fill is not enclosed by strokes within the viewport.
+    FillRegionNotEnclosed,
+}
+
 /// Flags returned to the UI widget that holds the engine.
 #[must_use]
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct WidgetFlags {
     /// Needs surface redrawing.
     pub redraw: bool,
+    /// Is Some when only the given region (in document coordinates) needs redrawing.
+    ///
+    /// `redraw` takes precedence and triggers a redraw of the entire surface.
+    pub redraw_region: Option<Aabb>,
     /// Needs surface resizing.
     pub resize: bool,
     /// Refresh the UI with the engine state.
     pub refresh_ui: bool,
     /// Indicates that the store was modified, i.e. new strokes inserted, modified, etc. .
     pub store_modified: bool,
+    /// Indicates that a stroke was completed and its rendering was regenerated.
+    ///
+    /// Not set for incremental updates while the stroke is drawn, so it can be used to trigger e.g. autosaving.
+    pub stroke_committed: bool,
+    /// Indicates that the selected strokes were modified in place, e.g. recolored.
+    pub selection_changed: bool,
+    /// Indicates that strokes were locked or unlocked.
+    pub lock_state_changed: bool,
     /// Update the current view offsets and size.
     pub view_modified: bool,
     /// Indicates that the camera has changed it's temporary zoom.
@@ -26,15 +49,23 @@ pub struct WidgetFlags {
     /// Meaning, when enabled instead of key events, text events are then emitted
     /// for regular unicode text. Used when writing text with the typewriter.
     pub enable_text_preprocessing: Option<bool>,
+    /// Is Some when a warning should be shown to the user.
+    pub warning: Option<UserWarning>,
+    /// Is Some when a color was picked from the document, to be set as the active stroke color.
+    pub picked_color: Option<Color>,
 }
 
 impl Default for WidgetFlags {
     fn default() -> Self {
         Self {
             redraw: false,
+            redraw_region: None,
             resize: false,
             refresh_ui: false,
             store_modified: false,
+            stroke_committed: false,
+            selection_changed: false,
+            lock_state_changed: false,
             view_modified: false,
             zoomed_temporarily: false,
             zoomed: false,
@@ -42,6 +73,8 @@ impl Default for WidgetFlags {
             hide_undo: None,
             hide_redo: None,
             enable_text_preprocessing: None,
+            warning: None,
+            picked_color: None,
         }
     }
 }
@@ -58,9 +91,16 @@ impl std::ops::BitOr for WidgetFlags {
 impl std::ops::BitOrAssign for WidgetFlags {
     fn bitor_assign(&mut self, rhs: Self) {
         self.redraw |= rhs.redraw;
+        self.redraw_region = match (self.redraw_region, rhs.redraw_region) {
+            (Some(region), Some(rhs_region)) => Some(region.merged(&rhs_region)),
+            (region, rhs_region) => region.or(rhs_region),
+        };
         self.resize |= rhs.resize;
         self.refresh_ui |= rhs.refresh_ui;
         self.store_modified |= rhs.store_modified;
+        self.stroke_committed |= rhs.stroke_committed;
+        self.selection_changed |= rhs.selection_changed;
+        self.lock_state_changed |= rhs.lock_state_changed;
         self.view_modified |= rhs.view_modified;
         self.zoomed_temporarily |= rhs.zoomed_temporarily;
         self.zoomed |= rhs.zoomed;
@@ -74,5 +114,11 @@ impl std::ops::BitOrAssign for WidgetFlags {
         if rhs.enable_text_preprocessing.is_some() {
             self.enable_text_preprocessing = rhs.enable_text_preprocessing;
         }
+        if rhs.warning.is_some() {
+            self.warning = rhs.warning;
+        }
+        if rhs.picked_color.is_some() {
+            self.picked_color = rhs.picked_color;
+        }
     }
 }
@@ -168,6 +168,9 @@ impl RnAppWindow {
         self.add_action(&action_devel_mode);
         let action_visual_debug = gio::PropertyAction::new("visual-debug", self, "visual-debug");
         self.add_action(&action_visual_debug);
+        let action_pressure_debug =
+            gio::PropertyAction::new("pressure-debug", self, "pressure-debug");
+        self.add_action(&action_pressure_debug);
 
         let action_pen_style = gio::SimpleAction::new_stateful(
             "pen-style",
@@ -7,6 +7,7 @@ use gtk4::{
     PadController, PositionType, gdk, gio, glib, glib::clone,
 };
 use once_cell::sync::Lazy;
+use rnote_engine::WidgetFlags;
 use rnote_engine::document::DocumentConfig;
 use rnote_engine::engine::EngineConfigShared;
 use rnote_engine::pens::PenStyle;
@@ -31,10 +32,12 @@ pub(crate) struct RnAppWindow {
     pub(crate) focus_mode: Cell<bool>,
     pub(crate) devel_mode: Cell<bool>,
     pub(crate) visual_debug: Cell<bool>,
+    pub(crate) pressure_debug: Cell<bool>,
 
     pub(crate) drawing_pad_controller: RefCell<Option<PadController>>,
     pub(crate) autosave_source_id: RefCell<Option<glib::SourceId>>,
     pub(crate) periodic_configsave_source_id: RefCell<Option<glib::SourceId>>,
+    pub(crate) crash_autosave_source_id: RefCell<Option<glib::SourceId>>,
     pub(crate) save_in_progress: Cell<bool>,
     pub(crate) save_in_progress_toast: RefCell<Option<adw::Toast>>,
     pub(crate) close_in_progress: Cell<bool>,
@@ -70,10 +73,12 @@ impl Default for RnAppWindow {
             focus_mode: Cell::new(false),
             devel_mode: Cell::new(false),
             visual_debug: Cell::new(false),
+            pressure_debug: Cell::new(false),
 
             drawing_pad_controller: RefCell::new(None),
             autosave_source_id: RefCell::new(None),
             periodic_configsave_source_id: RefCell::new(None),
+            crash_autosave_source_id: RefCell::new(None),
             save_in_progress: Cell::new(false),
             save_in_progress_toast: RefCell::new(None),
             close_in_progress: Cell::new(false),
@@ -125,6 +130,7 @@ impl ObjectImpl for RnAppWindow {
         );
 
         self.setup_input();
+        self.setup_crash_autosave();
         self.setup_overview();
         self.setup_split_view();
         self.setup_tabbar();
@@ -178,6 +184,9 @@ impl ObjectImpl for RnAppWindow {
                 glib::ParamSpecBoolean::builder("visual-debug")
                     .default_value(false)
                     .build(),
+                glib::ParamSpecBoolean::builder("pressure-debug")
+                    .default_value(false)
+                    .build(),
                 glib::ParamSpecBoolean::builder("save-in-progress")
                     .default_value(false)
                     .build(),
@@ -200,6 +209,7 @@ impl ObjectImpl for RnAppWindow {
             "focus-mode" => self.focus_mode.get().to_value(),
             "devel-mode" => self.devel_mode.get().to_value(),
             "visual-debug" => self.visual_debug.get().to_value(),
+            "pressure-debug" => self.pressure_debug.get().to_value(),
             "save-in-progress" => self.save_in_progress.get().to_value(),
             _ => unimplemented!(),
         }
@@ -315,6 +325,7 @@ impl ObjectImpl for RnAppWindow {
                 if !devel_mode {
                     debug!("Disabling developer mode, disabling visual debugging.");
                     obj.set_visual_debug(false);
+                    obj.set_pressure_debug(false);
                 }
             }
             "visual-debug" => {
@@ -327,6 +338,16 @@ impl ObjectImpl for RnAppWindow {
                     canvas.queue_draw();
                 }
             }
+            "pressure-debug" => {
+                let pressure_debug = value
+                    .get::<bool>()
+                    .expect("The value needs to be of type `bool`");
+                self.pressure_debug.replace(pressure_debug);
+                self.engine_config.write().pressure_debug = pressure_debug;
+                if let Some(canvas) = obj.active_tab_canvas() {
+                    canvas.queue_draw();
+                }
+            }
             "save-in-progress" => {
                 let save_in_progress = value
                     .get::<bool>()
@@ -436,6 +457,33 @@ impl RnAppWindow {
         }
     }
 
+    /// Periodically check the tabs for documents that are due for a crash recovery autosave,
+    /// which saves documents that were left idle after they were modified.
+    fn setup_crash_autosave(&self) {
+        let obj = self.obj();
+
+        if let Some(removed_id) = self.crash_autosave_source_id.borrow_mut().replace(
+            glib::source::timeout_add_seconds_local(
+                1,
+                clone!(
+                    #[weak(rename_to=appwindow)]
+                    obj,
+                    #[upgrade_or]
+                    glib::ControlFlow::Break,
+                    move || {
+                        for tab in appwindow.get_all_tabs() {
+                            tab.canvas().crash_autosave(&WidgetFlags::default());
+                        }
+
+                        glib::ControlFlow::Continue
+                    }
+                ),
+            ),
+        ) {
+            removed_id.remove();
+        }
+    }
+
     fn setup_input(&self) {
         let obj = self.obj();
         let drawing_pad_controller = PadController::new(&*obj, None);
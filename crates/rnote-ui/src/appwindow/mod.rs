@@ -12,6 +12,7 @@ use adw::{prelude::*, subclass::prelude::*};
 use core::cell::{Ref, RefMut};
 use gettextrs::gettext;
 use gtk4::{Application, IconTheme, Widget, gdk, gio, glib};
+use p2d::bounding_volume::BoundingVolume;
 use rnote_compose::Color;
 use rnote_engine::document::DocumentConfig;
 use rnote_engine::engine::{EngineConfig, EngineConfigShared};
@@ -19,7 +20,7 @@ use rnote_engine::ext::GdkRGBAExt;
 use rnote_engine::pens::PenStyle;
 use rnote_engine::pens::pensconfig::brushconfig::BrushStyle;
 use rnote_engine::pens::pensconfig::shaperconfig::ShaperStyle;
-use rnote_engine::{WidgetFlags, engine::EngineTask};
+use rnote_engine::{UserWarning, WidgetFlags, engine::EngineTask};
 use std::path::Path;
 use tracing::{debug, error};
 
@@ -170,6 +171,16 @@ impl RnAppWindow {
         self.set_property("visual-debug", visual_debug.to_value());
     }
 
+    #[allow(unused)]
+    pub(crate) fn pressure_debug(&self) -> bool {
+        self.property::<bool>("pressure-debug")
+    }
+
+    #[allow(unused)]
+    pub(crate) fn set_pressure_debug(&self, pressure_debug: bool) {
+        self.set_property("pressure-debug", pressure_debug.to_value());
+    }
+
     #[allow(unused)]
     pub(crate) fn save_in_progress(&self) -> bool {
         self.property::<bool>("save-in-progress")
@@ -278,7 +289,13 @@ impl RnAppWindow {
     pub(crate) fn handle_widget_flags(&self, widget_flags: WidgetFlags, canvas: &RnCanvas) {
         //debug!("handling widget flags: '{widget_flags:?}'");
 
-        if widget_flags.redraw {
+        // Gtk4 does not support invalidating only a region of a widget, so a requested region that is visible
+        // redraws the canvas. The stroke render nodes outside of the region are cached and not regenerated.
+        if widget_flags.redraw
+            || widget_flags
+                .redraw_region
+                .is_some_and(|region| region.intersects(&canvas.engine_ref().camera.viewport()))
+        {
             canvas.queue_draw();
         }
         if widget_flags.resize {
@@ -290,6 +307,7 @@ impl RnAppWindow {
         if widget_flags.store_modified {
             canvas.set_unsaved_changes(true);
             canvas.set_empty(false);
+            canvas.crash_autosave(&widget_flags);
         }
         if widget_flags.view_modified {
             canvas.queue_allocate();
@@ -330,6 +348,20 @@ impl RnAppWindow {
         if let Some(enable_text_preprocessing) = widget_flags.enable_text_preprocessing {
             canvas.set_text_preprocessing(enable_text_preprocessing);
         }
+        if let Some(picked_color) = widget_flags.picked_color {
+            self.overlays()
+                .colorpicker()
+                .set_stroke_color(gdk::RGBA::from_compose_color(picked_color));
+        }
+        if let Some(warning) = widget_flags.warning {
+            let text = match warning {
+                UserWarning::FillRegionNotEnclosed => {
+                    gettext("The area to fill is not enclosed by strokes")
+                }
+            };
+            self.overlays()
+                .dispatch_toast_text(&text, crate::overlays::TEXT_TOAST_TIMEOUT_DEFAULT);
+        }
     }
 
     /// Get the active (selected) tab page.
@@ -621,6 +653,7 @@ impl RnAppWindow {
                         self.append_wrapper_new_tab(&wrapper);
                     }
                     self.handle_widget_flags(widget_flags, &wrapper.canvas());
+                    wrapper.canvas().regenerate_all_rendering_w_progress(self);
                     true
                 }
             }
@@ -720,6 +753,7 @@ impl RnAppWindow {
             let can_undo = canvas.engine_ref().can_undo();
             let can_redo = canvas.engine_ref().can_redo();
             let visual_debug = self.engine_config().read().visual_debug;
+            let pressure_debug = self.engine_config().read().pressure_debug;
 
             self.overlays()
                 .penpicker()
@@ -736,6 +770,7 @@ impl RnAppWindow {
             self.set_pen_sounds(pen_sounds);
             self.set_snap_positions(snap_positions);
             self.set_visual_debug(visual_debug);
+            self.set_pressure_debug(pressure_debug);
 
             // Current pen
             match pen_style {
@@ -809,6 +844,30 @@ impl RnAppWindow {
                                 .colorpicker()
                                 .set_stroke_color(gdk::RGBA::from_compose_color(stroke_color));
                         }
+                        BrushStyle::Highlighter => {
+                            let stroke_color = self
+                                .engine_config()
+                                .read()
+                                .pens_config
+                                .brush_config
+                                .highlighter_options
+                                .stroke_color
+                                .unwrap_or(Color::TRANSPARENT);
+                            let fill_color = self
+                                .engine_config()
+                                .read()
+                                .pens_config
+                                .brush_config
+                                .highlighter_options
+                                .fill_color
+                                .unwrap_or(Color::TRANSPARENT);
+                            self.overlays()
+                                .colorpicker()
+                                .set_stroke_color(gdk::RGBA::from_compose_color(stroke_color));
+                            self.overlays()
+                                .colorpicker()
+                                .set_fill_color(gdk::RGBA::from_compose_color(fill_color));
+                        }
                     }
                 }
                 PenStyle::Shaper => {
@@ -2,7 +2,7 @@
 use gtk4::glib;
 use rnote_engine::WidgetFlags;
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, glib::Boxed)]
+#[derive(Clone, Debug, PartialEq, glib::Boxed)]
 #[boxed_type(name = "WidgetFlagsBoxed")]
 pub(crate) struct WidgetFlagsBoxed(WidgetFlags);
 
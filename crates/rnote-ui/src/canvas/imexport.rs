@@ -1,8 +1,10 @@
 // Imports
 use super::RnCanvas;
 use crate::RnAppWindow;
-use futures::channel::oneshot;
-use gtk4::{gio, prelude::*};
+use futures::StreamExt;
+use futures::channel::{mpsc, oneshot};
+use gettextrs::gettext;
+use gtk4::{gio, glib, glib::clone, prelude::*, subclass::prelude::*};
 use rnote_compose::ext::Vector2Ext;
 use rnote_engine::WidgetFlags;
 use rnote_engine::engine::export::{DocExportPrefs, DocPagesExportPrefs, SelectionExportPrefs};
@@ -42,6 +44,46 @@ impl RnCanvas {
         Ok(widget_flags)
     }
 
+    /// Regenerate the rendering of all strokes in the background after a large document was loaded.
+    ///
+    /// The progress is shown in the progress bar, and a toast offers to cancel it.
+    pub(crate) fn regenerate_all_rendering_w_progress(&self, appwindow: &RnAppWindow) {
+        if self.engine_ref().store.n_strokes() < Self::REGENERATE_ALL_RENDERING_STROKES_MIN {
+            return;
+        }
+        let (progress_tx, mut progress_rx) = mpsc::unbounded::<(usize, usize)>();
+        let render_batch_handle =
+            self.engine_mut()
+                .regenerate_all_rendering(Box::new(move |rendered, total| {
+                    // Fails only when the receiver was already dropped and the progress is not needed anymore
+                    let _ = progress_tx.unbounded_send((rendered, total));
+                }));
+        let cancel_toast = appwindow.overlays().dispatch_toast_w_button(
+            &gettext("Rendering document"),
+            &gettext("Cancel"),
+            move |_cancel_toast| render_batch_handle.cancel(),
+            None,
+        );
+
+        glib::spawn_future_local(clone!(
+            #[weak]
+            appwindow,
+            async move {
+                // Finishes when the batch rendering drops the progress callback
+                while let Some((rendered, total)) = progress_rx.next().await {
+                    appwindow
+                        .overlays()
+                        .progressbar()
+                        .set_fraction(rendered as f64 / total.max(1) as f64);
+                }
+                appwindow.overlays().progressbar().set_fraction(0.);
+                if let Some(cancel_toast) = cancel_toast.upgrade() {
+                    cancel_toast.dismiss();
+                }
+            }
+        ));
+    }
+
     /// Reload the engine from the file that is set as origin file.
     ///
     /// If the origin file is set to None, this does nothing and returns an error.
@@ -140,20 +182,31 @@ impl RnCanvas {
         password: Option<String>,
     ) -> anyhow::Result<()> {
         let pos = self.determine_stroke_import_pos(target_pos);
-        let adjust_document = appwindow
+        let pdf_import_prefs = appwindow
             .engine_config()
             .read()
             .import_prefs
-            .pdf_import_prefs
-            .adjust_document;
-
+            .pdf_import_prefs;
+
+        let text_layer_receiver = pdf_import_prefs.text_layer.then(|| {
+            self.engine_ref().generate_pdf_text_layer_from_bytes(
+                bytes.clone(),
+                pos,
+                page_range.clone(),
+                password.clone(),
+            )
+        });
         let strokes_receiver = self
             .engine_mut()
             .generate_pdf_pages_from_bytes(bytes, pos, page_range, password);
         let strokes = strokes_receiver.await??;
-        let widget_flags = self
+        let mut widget_flags = self
             .engine_mut()
-            .import_generated_content(strokes, adjust_document);
+            .import_generated_content(strokes, pdf_import_prefs.adjust_document);
+        if let Some(text_layer_receiver) = text_layer_receiver {
+            let textstrokes = text_layer_receiver.await??;
+            widget_flags |= self.engine_mut().import_pdf_text_layer(textstrokes);
+        }
 
         self.emit_handle_widget_flags(widget_flags);
         Ok(())
@@ -274,6 +327,32 @@ impl RnCanvas {
         Ok(true)
     }
 
+    /// Save the document for crash recovery when it is due. See [rnote_engine::Engine::maybe_autosave].
+    ///
+    /// Is skipped when the engine is currently borrowed, the modification is then registered with the next call.
+    pub(crate) fn crash_autosave(&self, widget_flags: &WidgetFlags) {
+        let Ok(mut engine) = self.imp().engine.try_borrow_mut() else {
+            return;
+        };
+        let Some(autosave_receiver) = engine.maybe_autosave(widget_flags) else {
+            return;
+        };
+        drop(engine);
+
+        glib::spawn_future_local(async move {
+            match autosave_receiver.await {
+                Ok(Ok(target_path)) => {
+                    debug!(
+                        "Crash recovery autosave to `{}` finished",
+                        target_path.display()
+                    )
+                }
+                Ok(Err(e)) => error!("Crash recovery autosave failed, Err: {e:?}"),
+                Err(e) => error!("Receiving crash recovery autosave result failed, Err: {e:?}"),
+            }
+        });
+    }
+
     pub(crate) async fn export_doc(
         &self,
         file: &gio::File,
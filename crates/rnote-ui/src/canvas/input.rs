@@ -2,7 +2,7 @@
 use super::RnCanvas;
 use gtk4::{Native, gdk, glib, graphene, prelude::*};
 use rnote_compose::penevent::{KeyboardKey, ModifierKey, PenEvent, PenState, ShortcutKey};
-use rnote_compose::penpath::Element;
+use rnote_compose::penpath::{Element, InputSource};
 use rnote_engine::WidgetFlags;
 use rnote_engine::ext::EventPropagationExt;
 use rnote_engine::ext::GraphenePointExt;
@@ -324,6 +324,19 @@ fn event_is_stylus(event: &gdk::Event) -> bool {
     event.device_tool().is_some()
 }
 
+fn event_input_source(event: &gdk::Event) -> InputSource {
+    if event_is_stylus(event) {
+        InputSource::Pen
+    } else if event
+        .device()
+        .is_some_and(|device| device.source() == gdk::InputSource::Touchscreen)
+    {
+        InputSource::Touch
+    } else {
+        InputSource::Mouse
+    }
+}
+
 fn retrieve_pointer_elements(
     canvas: &RnCanvas,
     now: Instant,
@@ -336,6 +349,7 @@ fn retrieve_pointer_elements(
     // retrieving the pressure only works when the event has a device tool (== is a stylus),
     // else we get SIGSEGV when trying to access (TODO: report this to gtk-rs)
     let is_stylus = event_is_stylus(event);
+    let source = event_input_source(event);
     let event_time = event.time();
 
     let mut elements = Vec::with_capacity(1);
@@ -389,13 +403,30 @@ fn retrieve_pointer_elements(
                 axes[crate::utils::axis_use_idx(gdk::AxisUse::X)],
                 axes[crate::utils::axis_use_idx(gdk::AxisUse::Y)]
             ]);
-            let pressure = if is_stylus {
+            // Styluses without pressure support don't report the axis
+            let pressure_available = is_stylus && available_axes.contains(gdk::AxisFlags::PRESSURE);
+            let pressure = if pressure_available {
                 axes[crate::utils::axis_use_idx(gdk::AxisUse::Pressure)]
             } else {
                 Element::PRESSURE_DEFAULT
             };
-
-            entries.push((Element::new(pos, pressure), entry_time));
+            let tilt = (is_stylus
+                && available_axes.contains(gdk::AxisFlags::XTILT)
+                && available_axes.contains(gdk::AxisFlags::YTILT))
+            .then(|| {
+                na::vector![
+                    axes[crate::utils::axis_use_idx(gdk::AxisUse::Xtilt)],
+                    axes[crate::utils::axis_use_idx(gdk::AxisUse::Ytilt)]
+                ]
+            });
+
+            entries.push((
+                Element::new(pos, pressure)
+                    .with_tilt(tilt)
+                    .with_pressure_insensitive(!pressure_available)
+                    .with_source(source),
+                entry_time,
+            ));
         }
 
         elements.extend(entries.into_iter().rev());
@@ -405,13 +436,28 @@ fn retrieve_pointer_elements(
         .position()
         .map(|(x, y)| transform_pos(na::vector![x, y]))?;
 
+    // Styluses without pressure support don't report the axis
     let pressure = if is_stylus {
-        event.axis(gdk::AxisUse::Pressure).unwrap()
+        event.axis(gdk::AxisUse::Pressure)
+    } else {
+        None
+    };
+    let tilt = if is_stylus {
+        event
+            .axis(gdk::AxisUse::Xtilt)
+            .zip(event.axis(gdk::AxisUse::Ytilt))
+            .map(|(x_tilt, y_tilt)| na::vector![x_tilt, y_tilt])
     } else {
-        Element::PRESSURE_DEFAULT
+        None
     };
 
-    elements.push((Element::new(pos, pressure), now));
+    elements.push((
+        Element::new(pos, pressure.unwrap_or(Element::PRESSURE_DEFAULT))
+            .with_tilt(tilt)
+            .with_pressure_insensitive(pressure.is_none())
+            .with_source(source),
+        now,
+    ));
 
     Some(elements)
 }
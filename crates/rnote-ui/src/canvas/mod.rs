@@ -733,6 +733,9 @@ pub(crate) static OUTPUT_FILE_NEW_SUBTITLE: once_cell::sync::Lazy<String> =
 impl RnCanvas {
     // Sets the canvas zoom scroll step in % for one unit of the event controller delta
     pub(crate) const ZOOM_SCROLL_STEP: f64 = 0.1;
+    /// The number of strokes from which the rendering of all strokes is regenerated in the background after loading
+    /// a document. The strokes of smaller documents are left to the viewport rendering.
+    pub(crate) const REGENERATE_ALL_RENDERING_STROKES_MIN: usize = 2000;
 
     pub(crate) fn new() -> Self {
         glib::Object::new()
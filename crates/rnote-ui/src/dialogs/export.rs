@@ -103,6 +103,9 @@ pub(crate) async fn dialog_export_doc_w_prefs(appwindow: &RnAppWindow, canvas: &
     let with_pattern_row: adw::SwitchRow = builder.object("export_doc_with_pattern_row").unwrap();
     let optimize_printing_row: adw::SwitchRow =
         builder.object("export_doc_optimize_printing_row").unwrap();
+    let with_pdf_text_layer_row: adw::SwitchRow = builder
+        .object("export_doc_with_pdf_text_layer_row")
+        .unwrap();
     let export_format_row: adw::ComboRow = builder.object("export_doc_export_format_row").unwrap();
     let page_order_row: adw::ComboRow = builder.object("export_doc_page_order_row").unwrap();
     let export_file_label: Label = builder.object("export_doc_export_file_label").unwrap();
@@ -123,6 +126,7 @@ pub(crate) async fn dialog_export_doc_w_prefs(appwindow: &RnAppWindow, canvas: &
     with_background_row.set_active(initial_doc_export_prefs.with_background);
     with_pattern_row.set_active(initial_doc_export_prefs.with_pattern);
     optimize_printing_row.set_active(initial_doc_export_prefs.optimize_printing);
+    with_pdf_text_layer_row.set_active(initial_doc_export_prefs.with_pdf_text_layer);
     preview.set_draw_background(initial_doc_export_prefs.with_background);
     preview.set_draw_pattern(initial_doc_export_prefs.with_pattern);
     preview.set_optimize_printing(initial_doc_export_prefs.optimize_printing);
@@ -229,6 +233,19 @@ pub(crate) async fn dialog_export_doc_w_prefs(appwindow: &RnAppWindow, canvas: &
         }
     ));
 
+    with_pdf_text_layer_row.connect_active_notify(clone!(
+        #[weak]
+        appwindow,
+        move |with_pdf_text_layer_row| {
+            appwindow
+                .engine_config()
+                .write()
+                .export_prefs
+                .doc_export_prefs
+                .with_pdf_text_layer = with_pdf_text_layer_row.is_active();
+        }
+    ));
+
     export_format_row.connect_selected_notify(clone!(
         #[strong]
         selected_file,
@@ -267,6 +267,8 @@ pub(crate) async fn dialog_import_pdf_w_prefs(
         builder.object("pdf_import_bitmap_scalefactor_row").unwrap();
     let pdf_import_adjust_document_row: adw::SwitchRow =
         builder.object("pdf_import_adjust_document_row").unwrap();
+    let pdf_import_text_layer_row: adw::SwitchRow =
+        builder.object("pdf_import_text_layer_row").unwrap();
     let import_pdf_button_cancel: Button = builder.object("import_pdf_button_cancel").unwrap();
     let import_pdf_button_confirm: Button = builder.object("import_pdf_button_confirm").unwrap();
 
@@ -302,6 +304,7 @@ pub(crate) async fn dialog_import_pdf_w_prefs(
     pdf_import_page_spacing_row.set_selected(pdf_import_prefs.page_spacing.to_u32().unwrap());
     pdf_import_bitmap_scalefactor_row.set_value(pdf_import_prefs.bitmap_scalefactor);
     pdf_import_adjust_document_row.set_active(pdf_import_prefs.adjust_document);
+    pdf_import_text_layer_row.set_active(pdf_import_prefs.text_layer);
 
     pdf_page_start_row
         .bind_property("value", &pdf_page_end_row.adjustment(), "lower")
@@ -404,6 +407,19 @@ pub(crate) async fn dialog_import_pdf_w_prefs(
         }
     ));
 
+    pdf_import_text_layer_row.connect_active_notify(clone!(
+        #[weak]
+        appwindow,
+        move |row| {
+            appwindow
+                .engine_config()
+                .write()
+                .import_prefs
+                .pdf_import_prefs
+                .text_layer = row.is_active();
+        }
+    ));
+
     let pdf_data = Arc::new(input_file.load_bytes_future().await?.0.to_vec());
     let pdf = if let Some(password) = password.as_ref() {
         hayro_syntax::Pdf::new_with_password(pdf_data, password)
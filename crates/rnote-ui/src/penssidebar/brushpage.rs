@@ -33,6 +33,8 @@ mod imp {
         #[template_child]
         pub(crate) brushstyle_textured_row: TemplateChild<adw::ActionRow>,
         #[template_child]
+        pub(crate) brushstyle_highlighter_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
         pub(crate) brushconfig_menubutton: TemplateChild<MenuButton>,
         #[template_child]
         pub(crate) brushconfig_popover: TemplateChild<Popover>,
@@ -130,6 +132,10 @@ impl RnBrushPage {
                 .imp()
                 .brushstyle_listbox
                 .select_row(Some(&*self.imp().brushstyle_textured_row)),
+            BrushStyle::Highlighter => self
+                .imp()
+                .brushstyle_listbox
+                .select_row(Some(&*self.imp().brushstyle_highlighter_row)),
         }
     }
 
@@ -162,7 +168,10 @@ impl RnBrushPage {
     }
 
     pub(crate) fn set_solidstyle_pressure_curve(&self, pressure_curve: PressureCurve) {
-        let position = pressure_curve.to_u32().unwrap();
+        // Custom pressure curves can't be selected in the UI
+        let Some(position) = pressure_curve.to_u32() else {
+            return;
+        };
 
         self.imp()
             .solidstyle_pressure_curves_row
@@ -264,6 +273,15 @@ impl RnBrushPage {
                                 .textured_options
                                 .stroke_width = stroke_width;
                         }
+                        BrushStyle::Highlighter => {
+                            appwindow
+                                .engine_config()
+                                .write()
+                                .pens_config
+                                .brush_config
+                                .highlighter_options
+                                .stroke_width = stroke_width;
+                        }
                     }
                 }
             ),
@@ -340,6 +358,23 @@ impl RnBrushPage {
                             .brushstyle_menubutton
                             .set_icon_name("pen-brush-style-textured-symbolic");
                     }
+                    BrushStyle::Highlighter => {
+                        let stroke_width = appwindow
+                            .engine_config()
+                            .read()
+                            .pens_config
+                            .brush_config
+                            .highlighter_options
+                            .stroke_width;
+                        brushpage
+                            .imp()
+                            .stroke_width_picker
+                            .set_stroke_width(stroke_width);
+                        brushpage
+                            .imp()
+                            .brushstyle_menubutton
+                            .set_icon_name("pen-brush-style-marker-symbolic");
+                    }
                 }
             }
         ));
@@ -436,7 +471,7 @@ impl RnBrushPage {
             .brush_config
             .clone();
 
-        self.set_solidstyle_pressure_curve(brush_config.solid_options.pressure_curve);
+        self.set_solidstyle_pressure_curve(brush_config.solid_options.pressure_curve.clone());
         imp.texturedstyle_density_row
             .set_value(brush_config.textured_options.density);
         self.set_texturedstyle_distribution_variant(brush_config.textured_options.distribution);
@@ -457,6 +492,10 @@ impl RnBrushPage {
                 imp.stroke_width_picker
                     .set_stroke_width(brush_config.textured_options.stroke_width);
             }
+            BrushStyle::Highlighter => {
+                imp.stroke_width_picker
+                    .set_stroke_width(brush_config.highlighter_options.stroke_width);
+            }
         }
     }
 }
@@ -20,6 +20,8 @@ mod imp {
         #[template_child]
         pub(crate) selectorstyle_intersectingpath_toggle: TemplateChild<ToggleButton>,
         #[template_child]
+        pub(crate) selectorstyle_color_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
         pub(crate) resize_lock_aspectratio_togglebutton: TemplateChild<ToggleButton>,
     }
 
@@ -81,6 +83,8 @@ impl RnSelectorPage {
             Some(SelectorStyle::Single)
         } else if self.imp().selectorstyle_intersectingpath_toggle.is_active() {
             Some(SelectorStyle::IntersectingPath)
+        } else if self.imp().selectorstyle_color_toggle.is_active() {
+            Some(SelectorStyle::Color)
         } else {
             None
         }
@@ -96,6 +100,7 @@ impl RnSelectorPage {
                 .imp()
                 .selectorstyle_intersectingpath_toggle
                 .set_active(true),
+            SelectorStyle::Color => self.imp().selectorstyle_color_toggle.set_active(true),
         }
     }
 
@@ -167,6 +172,22 @@ impl RnSelectorPage {
                 }
             ));
 
+        imp.selectorstyle_color_toggle.connect_toggled(clone!(
+            #[weak]
+            appwindow,
+            move |toggle| {
+                if !toggle.is_active() {
+                    return;
+                }
+                appwindow
+                    .engine_config()
+                    .write()
+                    .pens_config
+                    .selector_config
+                    .style = SelectorStyle::Color;
+            }
+        ));
+
         imp.resize_lock_aspectratio_togglebutton
             .connect_toggled(clone!(
                 #[weak]
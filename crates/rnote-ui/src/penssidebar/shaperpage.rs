@@ -12,7 +12,7 @@ use num_traits::cast::ToPrimitive;
 use rnote_compose::builders::ShapeBuilderType;
 use rnote_compose::constraints::ConstraintRatio;
 use rnote_compose::style::rough::roughoptions::FillStyle;
-use rnote_compose::style::smooth::{LineCap, LineStyle, SmoothOptions};
+use rnote_compose::style::smooth::{LineCap, LineJoin, LineStyle, SmoothOptions};
 use rnote_engine::pens::pensconfig::ShaperConfig;
 use rnote_engine::pens::pensconfig::shaperconfig::ShaperStyle;
 
@@ -52,6 +52,8 @@ mod imp {
         #[template_child]
         pub(crate) smoothstyle_line_cap_row: TemplateChild<adw::ComboRow>,
         #[template_child]
+        pub(crate) smoothstyle_line_join_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
         pub(crate) smoothstyle_line_style_row: TemplateChild<adw::ComboRow>,
         #[template_child]
         pub(crate) roughstyle_group: TemplateChild<adw::PreferencesGroup>,
@@ -160,6 +162,10 @@ impl RnShaperPage {
         LineCap::try_from(self.imp().smoothstyle_line_cap_row.get().selected()).unwrap()
     }
 
+    pub(crate) fn smoothstyle_line_join(&self) -> LineJoin {
+        LineJoin::try_from(self.imp().smoothstyle_line_join_row.get().selected()).unwrap()
+    }
+
     pub(crate) fn smoothstyle_line_style(&self) -> LineStyle {
         LineStyle::try_from(self.imp().smoothstyle_line_style_row.get().selected()).unwrap()
     }
@@ -340,8 +346,8 @@ impl RnShaperPage {
                 move |_| {
                     let line_cap = shaperpage.smoothstyle_line_cap();
 
-                    // If the user has selected a straight line cap while the line style was dotted, then we update the line style to be straight
-                    if line_cap == LineCap::Straight
+                    // If the user has selected a non-rounded line cap while the line style was dotted, then we update the line style to be straight
+                    if line_cap != LineCap::Rounded
                         && shaperpage.smoothstyle_line_style().is_dotted()
                     {
                         shaperpage
@@ -359,6 +365,25 @@ impl RnShaperPage {
                 }
             ));
 
+        // Line join
+        imp.smoothstyle_line_join_row
+            .get()
+            .connect_selected_notify(clone!(
+                #[weak(rename_to=shaperpage)]
+                self,
+                #[weak]
+                appwindow,
+                move |_| {
+                    appwindow
+                        .engine_config()
+                        .write()
+                        .pens_config
+                        .shaper_config
+                        .smooth_options
+                        .update_line_join(shaperpage.smoothstyle_line_join());
+                }
+            ));
+
         // Line style
         imp.smoothstyle_line_style_row
             .get()
@@ -591,6 +616,8 @@ impl RnShaperPage {
         // Smooth style
         imp.smoothstyle_line_cap_row
             .set_selected(shaper_config.smooth_options.line_cap.to_u32().unwrap());
+        imp.smoothstyle_line_join_row
+            .set_selected(shaper_config.smooth_options.line_join.to_u32().unwrap());
         imp.smoothstyle_line_style_row
             .set_selected(shaper_config.smooth_options.line_style.to_u32().unwrap());
 
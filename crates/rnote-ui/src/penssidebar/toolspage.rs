@@ -21,6 +21,14 @@ mod imp {
         #[template_child]
         pub(crate) toolstyle_laser_toggle: TemplateChild<ToggleButton>,
         #[template_child]
+        pub(crate) toolstyle_fill_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub(crate) toolstyle_colorpicker_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub(crate) toolstyle_smudge_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub(crate) toolstyle_nodeedit_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
         pub(crate) verticalspace_menubutton: TemplateChild<MenuButton>,
         #[template_child]
         pub(crate) verticalspace_popover: TemplateChild<Popover>,
@@ -93,6 +101,14 @@ impl RnToolsPage {
             Some(ToolStyle::Zoom)
         } else if imp.toolstyle_laser_toggle.is_active() {
             Some(ToolStyle::Laser)
+        } else if imp.toolstyle_fill_toggle.is_active() {
+            Some(ToolStyle::Fill)
+        } else if imp.toolstyle_colorpicker_toggle.is_active() {
+            Some(ToolStyle::ColorPicker)
+        } else if imp.toolstyle_smudge_toggle.is_active() {
+            Some(ToolStyle::Smudge)
+        } else if imp.toolstyle_nodeedit_toggle.is_active() {
+            Some(ToolStyle::NodeEdit)
         } else {
             None
         }
@@ -112,6 +128,10 @@ impl RnToolsPage {
             ToolStyle::OffsetCamera => imp.toolstyle_offsetcamera_toggle.set_active(true),
             ToolStyle::Zoom => imp.toolstyle_zoom_toggle.set_active(true),
             ToolStyle::Laser => imp.toolstyle_laser_toggle.set_active(true),
+            ToolStyle::Fill => imp.toolstyle_fill_toggle.set_active(true),
+            ToolStyle::ColorPicker => imp.toolstyle_colorpicker_toggle.set_active(true),
+            ToolStyle::Smudge => imp.toolstyle_smudge_toggle.set_active(true),
+            ToolStyle::NodeEdit => imp.toolstyle_nodeedit_toggle.set_active(true),
         }
     }
 
@@ -204,6 +224,90 @@ impl RnToolsPage {
             }
         ));
 
+        imp.toolstyle_fill_toggle.connect_toggled(clone!(
+            #[weak]
+            appwindow,
+            move |toggle| {
+                if !toggle.is_active() {
+                    return;
+                }
+                appwindow
+                    .engine_config()
+                    .write()
+                    .pens_config
+                    .tools_config
+                    .style = ToolStyle::Fill;
+
+                if let Some(canvas) = appwindow.active_tab_canvas() {
+                    let widget_flags = canvas.engine_mut().reinstall_pen_current_style();
+                    canvas.emit_handle_widget_flags(widget_flags);
+                };
+            }
+        ));
+
+        imp.toolstyle_colorpicker_toggle.connect_toggled(clone!(
+            #[weak]
+            appwindow,
+            move |toggle| {
+                if !toggle.is_active() {
+                    return;
+                }
+                appwindow
+                    .engine_config()
+                    .write()
+                    .pens_config
+                    .tools_config
+                    .style = ToolStyle::ColorPicker;
+
+                if let Some(canvas) = appwindow.active_tab_canvas() {
+                    let widget_flags = canvas.engine_mut().reinstall_pen_current_style();
+                    canvas.emit_handle_widget_flags(widget_flags);
+                };
+            }
+        ));
+
+        imp.toolstyle_smudge_toggle.connect_toggled(clone!(
+            #[weak]
+            appwindow,
+            move |toggle| {
+                if !toggle.is_active() {
+                    return;
+                }
+                appwindow
+                    .engine_config()
+                    .write()
+                    .pens_config
+                    .tools_config
+                    .style = ToolStyle::Smudge;
+
+                if let Some(canvas) = appwindow.active_tab_canvas() {
+                    let widget_flags = canvas.engine_mut().reinstall_pen_current_style();
+                    canvas.emit_handle_widget_flags(widget_flags);
+                };
+            }
+        ));
+
+        imp.toolstyle_nodeedit_toggle.connect_toggled(clone!(
+            #[weak]
+            appwindow,
+            move |toggle| {
+                if !toggle.is_active() {
+                    return;
+                }
+                appwindow
+                    .engine_config()
+                    .write()
+                    .pens_config
+                    .tools_config
+                    .style = ToolStyle::NodeEdit;
+
+                if let Some(canvas) = appwindow.active_tab_canvas() {
+                    let widget_flags = canvas.engine_mut().reinstall_pen_current_style();
+                    canvas.emit_handle_widget_flags(widget_flags);
+                };
+            }
+        ));
+
         imp.verticalspace_menubutton.connect_active_notify(clone!(
             #[weak(rename_to=toolspage)]
             self,
@@ -109,6 +109,10 @@ mod imp {
         #[template_child]
         pub(crate) doc_show_origin_indicator_row: TemplateChild<adw::SwitchRow>,
         #[template_child]
+        pub(crate) doc_simplify_strokes_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub(crate) doc_simplification_tolerance_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
         pub(crate) background_pattern_invert_color_button: TemplateChild<Button>,
         #[template_child]
         pub(crate) penshortcut_stylus_button_primary_row: TemplateChild<RnPenShortcutRow>,
@@ -465,6 +469,8 @@ impl RnSettingsPanel {
                 .config
                 .format
                 .show_origin_indicator;
+            let simplification_tolerance =
+                canvas.engine_ref().document.config.simplification_tolerance;
 
             imp.doc_show_format_borders_row
                 .set_active(show_format_borders);
@@ -484,6 +490,11 @@ impl RnSettingsPanel {
             self.set_document_layout(&document_layout);
             imp.doc_show_origin_indicator_row
                 .set_active(show_origin_indicator);
+            if let Some(tolerance) = simplification_tolerance {
+                imp.doc_simplification_tolerance_row.set_value(tolerance);
+            }
+            imp.doc_simplify_strokes_row
+                .set_active(simplification_tolerance.is_some());
         }
     }
 
@@ -792,6 +803,9 @@ impl RnSettingsPanel {
                     .document_config_preset_mut()
                     .format
                     .show_origin_indicator = doc_config.format.show_origin_indicator;
+                appwindow
+                    .document_config_preset_mut()
+                    .simplification_tolerance = doc_config.simplification_tolerance;
 
                 let widget_flags = WidgetFlags {
                     refresh_ui: true,
@@ -828,6 +842,8 @@ impl RnSettingsPanel {
                     .config
                     .format
                     .show_origin_indicator = doc_config.format.show_origin_indicator;
+                canvas.engine_mut().document.config.simplification_tolerance =
+                    doc_config.simplification_tolerance;
 
                 let mut widget_flags = canvas.engine_mut().doc_resize_autoexpand();
                 widget_flags |= canvas.engine_mut().background_rendering_regenerate();
@@ -1142,6 +1158,39 @@ impl RnSettingsPanel {
                 }
             ));
 
+        imp.doc_simplify_strokes_row
+            .bind_property(
+                "active",
+                &imp.doc_simplification_tolerance_row.get(),
+                "sensitive",
+            )
+            .sync_create()
+            .build();
+
+        imp.doc_simplify_strokes_row.connect_active_notify(clone!(
+            #[weak(rename_to=settingspanel)]
+            self,
+            #[weak]
+            appwindow,
+            move |_| {
+                settingspanel.apply_simplification_tolerance(&appwindow);
+            }
+        ));
+
+        imp.doc_simplification_tolerance_row
+            .connect_value_notify(clone!(
+                #[weak(rename_to=settingspanel)]
+                self,
+                #[weak]
+                appwindow,
+                move |_| {
+                    // The tolerance of a document without simplification is not touched
+                    if settingspanel.imp().doc_simplify_strokes_row.is_active() {
+                        settingspanel.apply_simplification_tolerance(&appwindow);
+                    }
+                }
+            ));
+
         imp.background_pattern_invert_color_button
             .get()
             .connect_clicked(clone!(
@@ -1432,6 +1481,27 @@ impl RnSettingsPanel {
         widget_flags.store_modified = true;
         appwindow.handle_widget_flags(widget_flags, &canvas);
     }
+
+    fn apply_simplification_tolerance(&self, appwindow: &RnAppWindow) {
+        let imp = self.imp();
+        let Some(canvas) = appwindow.active_tab_canvas() else {
+            return;
+        };
+        let simplification_tolerance = imp
+            .doc_simplify_strokes_row
+            .is_active()
+            .then(|| imp.doc_simplification_tolerance_row.value());
+
+        if canvas.engine_ref().document.config.simplification_tolerance != simplification_tolerance
+        {
+            canvas.engine_mut().document.config.simplification_tolerance = simplification_tolerance;
+            let widget_flags = WidgetFlags {
+                store_modified: true,
+                ..Default::default()
+            };
+            appwindow.handle_widget_flags(widget_flags, &canvas);
+        }
+    }
 }
 
 const CURSORS_LIST: &[&str] = &[